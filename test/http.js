@@ -156,6 +156,23 @@ async function assert_create_collective(t, { admin_address }) {
 	t.deepEqual(collective, {
 		name: 'Flower of Life Collective',
 		admin_address,
+		description: '',
+		purpose: '',
+		tags: [],
+		links: [],
+		avatar: null,
+		voice_credits_per_period: 100,
+		voice_credit_period: 0,
+		sponsor_threshold: 0,
+		parent_address: null,
+		inherit_policies: false,
+		election_tally_method: 'RankedChoice',
+		critical_action_ops: [],
+		cooling_off_period: 0,
+		veto_role_addresses: [],
+		policy_proposal_address: null,
+		archived_at: null,
+		dissolution_proposal_address: null,
 	})
 	return {
 		collective_address,
@@ -6,9 +6,16 @@ use hdk::holochain_core_types::dna::entry_types::Sharing;
 use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
 use holochain_wasm_utils::holochain_core_types::entry::Entry;
 use std::borrow::Borrow;
-use hdk::error::ZomeApiResult;
-use holochain_wasm_utils::holochain_core_types::link::LinkMatch;
 use hdk::prelude::ValidatingEntryType;
+use crate::critical_action::get_action_critical_action;
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::utils::t;
+use crate::signal::{ProtocolLoveSignal, emit_signal};
+use crate::store::{Store, HdkStore, get_links_and_load_type};
+use crate::validation::{
+	EntryValidation, ValidationContext, from_hdk_link_validation_data, from_hdk_validation_data,
+	validate_app_link,
+};
 
 /// An `Action` that updates the state in the CoGov system.
 ///
@@ -28,7 +35,7 @@ use hdk::prelude::ValidatingEntryType;
 /// }
 /// ```
 ///
-#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
 pub struct Action {
 	/// Represents each of the allowed operations
 	pub op: ActionOp,
@@ -44,15 +51,136 @@ pub struct Action {
 }
 
 /// An operation for an [Action](struct.Action.html).
-#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+///
+/// Each op documents the JSON schema of the `data` & `prev_data` of the action,
+/// also sent in the `action_committed` [ProtocolLoveSignal](enum.ProtocolLoveSignal.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
 pub enum ActionOp {
+	/// `data`: the created [Collective](struct.Collective.html). `prev_data`: `null`.
 	CreateCollective,
+	/// `data`: `{"person_address": Address}`. `prev_data`: `null`.
 	AddCollectivePerson,
+	/// `data` & `prev_data`: `{"name": String}`. `prev_data` is `null` when created.
 	SetCollectiveName,
+	/// `data` & `prev_data`: `{"description": String}`.
+	SetCollectiveDescription,
+	/// `data` & `prev_data`: `{"purpose": String}`.
+	SetCollectivePurpose,
+	/// `data` & `prev_data`: `{"tags": [String]}`.
+	SetCollectiveTags,
+	/// `data` & `prev_data`: `{"links": [String]}`.
+	SetCollectiveLinks,
+	/// `data` & `prev_data`: `{"avatar": String | null}`.
+	SetCollectiveAvatar,
+	/// `data`: `{"proposal_address": Address, "proposal": Proposal}`. `prev_data`: `null`.
+	CreateProposal,
+	/// `data` & `prev_data`: `{"proposal_address": Address, "status": ProposalStatus}`.
+	SetProposalStatus,
+	/// `data`: `{"proposal_address": Address, "person_address": Address, "ballot": Ballot}`.
+	/// `prev_data`: `null`.
+	CastVote,
+	/// `data`: `{"proposal_address": Address, "person_address": Address, "vote_address": Address}`.
+	/// `prev_data`: `null`.
+	CommitVote,
+	/// `data`: `{"proposal_address": Address, "person_address": Address, "ballot": Ballot}`.
+	/// `prev_data`: `null`.
+	RevealVote,
+	/// `data`: `{"proposal_address": Address, "tally": ProposalTally}`.
+	/// `prev_data`: `{"proposal_address": Address, "status": ProposalStatus}`.
+	CloseProposal,
+	/// `data`: `{"proposal_address": Address, "tally": ProposalTally, "outcome": ProposalOutcome}`.
+	/// `prev_data`: `{"proposal_address": Address, "status": ProposalStatus}`.
+	CloseExpiredProposal,
+	/// `data`: `{"template_address": Address, "template": ProposalTemplate}`. `prev_data`: `null`.
+	CreateProposalTemplate,
+	/// `data` & `prev_data`: `{"sponsor_threshold": u64}`.
+	SetSponsorThreshold,
+	/// `data`: the [Sponsorship](struct.Sponsorship.html). `prev_data`: `null`.
+	SponsorProposal,
+	/// `data`: the withdrawn [Sponsorship](struct.Sponsorship.html). `prev_data`: `null`.
+	UnsponsorProposal,
+	/// `data` & `prev_data`: `{"voice_credit_period": u64, "voice_credits_per_period": u64}`.
+	StartVoiceCreditPeriod,
+	/// `data`: `{"delegation_address": Address, "delegation": Delegation}`. `prev_data`: `null`.
+	DelegateVote,
+	/// `data`: `{"delegation_address": Address, "delegation": Delegation}` without a
+	/// `delegate_address`. `prev_data`: `null`.
+	RevokeDelegation,
+	/// `data` & `prev_data`: `{"ledger_address": Address, "funds": u64}`.
+	DepositLedgerFunds,
+	/// `data`: `{"proposal_address": Address, "person_address": Address, "amount": u64,
+	/// "timestamp": u64}`. `prev_data`: `null`.
+	StakeConviction,
+	/// `data` & `prev_data`: `{"ledger_address": Address, "funds": u64}`, the funds of the ledger
+	/// after & before funding a conviction proposal. Enacted from a conviction proposal with
+	/// [enact_proposal](fn.enact_proposal.html), `data`: `{"requested_funds": u64}`.
+	FundProposal,
+	/// `data`: `{"objection_address": Address, "objection": Objection}`. `prev_data`: `null`.
+	RaiseObjection,
+	/// `data`: `{"objection_address": Address, "objection": Objection}`, resolved.
+	/// `prev_data`: `null`.
+	ResolveObjection,
+	/// `data` & `prev_data`: `{"proposal_address": Address, "revision": u64, "name": String,
+	/// "content": String}`.
+	ReviseProposal,
+	/// `data`: `{"amendment_address": Address, "amendment": Amendment}`. `prev_data`: `null`.
+	ProposeAmendment,
+	/// `data`: `{"amendment_address": Address, "amendment": Amendment}`, accepted.
+	/// `prev_data`: `null`.
+	AcceptAmendment,
+	/// `data`: `{"amendment_address": Address, "amendment": Amendment}`, rejected.
+	/// `prev_data`: `null`.
+	RejectAmendment,
+	/// `data`: `{"message_address": Address, "message": Message}`. `prev_data`: `null`.
+	PostMessage,
+	/// `data` & `prev_data`: `{"message_address": Address, "message": Message}`.
+	EditMessage,
+	/// `data`: the adopted [Charter](struct.Charter.html). `prev_data`: the former
+	/// [Charter](struct.Charter.html), `null` for the first version.
+	AmendCharter,
+	/// `data`: `{"collective_address": Address, "collective": Collective}` of the child collective.
+	/// `prev_data`: `null`.
+	AddChildCollective,
+	/// `data`: the [EscalatedAction](struct.EscalatedAction.html) of a child collective.
+	/// `prev_data`: `null`.
+	EscalateAction,
+	/// `data`: `{"member_collective_address": Address}`. `prev_data`: `null`.
+	AddMemberCollective,
+	/// `data`: `{"federation_address": Address}`. `prev_data`: `null`.
+	JoinFederation,
+	/// `data` & `prev_data`: `{"election_tally_method": TallyMethod}`.
+	SetElectionTallyMethod,
+	/// `data`: `{"role_address": Address, "role": Role}`. `prev_data`: `null`.
+	CreateRole,
+	/// `data`: `{"election_address": Address, "election": Election}`. `prev_data`: `null`.
+	StartElection,
+	/// `data`: `{"election_address": Address, "nominator_address": Address,
+	/// "candidate_address": Address}`. `prev_data`: `null`.
+	NominateCandidate,
+	/// `data`: the [RoleTerm](struct.RoleTerm.html) of the elected person. `prev_data`: `null`.
+	AssignRole,
+	/// `data`: `{"sortition_address": Address, "sortition": Sortition}`. `prev_data`: `null`.
+	AnnounceSortition,
+	/// `data`: `{"seed_address": Address, "seed": SortitionSeed}`, committed. `prev_data`: `null`.
+	CommitSortitionSeed,
+	/// `data`: `{"seed_address": Address, "seed": SortitionSeed}`, revealed. `prev_data`: `null`.
+	RevealSortitionSeed,
+	/// `data`: the [SortitionDraw](struct.SortitionDraw.html) with its seed. `prev_data`: `null`.
+	DrawSortition,
+	/// `data` & `prev_data`: the [CriticalActionPolicy](struct.CriticalActionPolicy.html).
+	SetCriticalActionPolicy,
+	/// `data`: `{"critical_action_address": Address, "action_address": Address, "veto": Veto}`.
+	/// `prev_data`: `null`.
+	VetoAction,
+	/// `data`: the [Dissolution](struct.Dissolution.html) of the collective. `prev_data`: `null`.
+	DissolveCollective,
+	/// `data` & `prev_data`: `{"ledger_address": Address, "funds": u64}`, the funds of the ledger
+	/// settled by a [Dissolution](struct.Dissolution.html).
+	SettleLedgerFunds,
 }
 
 /// The lifecycle status of an [Action](struct.Action.html).
-#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
 pub enum ActionStatus {
 	/// Action is currently opened & not completed
 	Open,
@@ -60,16 +188,18 @@ pub enum ActionStatus {
 	///
 	/// TODO: Rename
 	Executed,
+	/// Critical action vetoed while cooling off, never executed
+	Vetoed,
 }
 
 /// How an [Action](struct.Action.html) is performed.
-#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
 pub enum ActionStrategy {
 	/// Performed via automation by the system
 	SystemAutomatic,
 	/// TODO: Evaluate
 	PrivilegedAction,
-	/// TODO: Evaluate
+	/// Performed by posting or editing a [Message](struct.Message.html) in a discussion thread
 	NewDiscussionMessage,
 }
 
@@ -84,47 +214,78 @@ pub struct ActionsPayload {
 pub type ActionEntry = (Address, Entry, Action);
 
 pub trait RootAction {
-	fn commit_action(self, collective_address: Address) -> ZomeApiResult<ActionEntry>;
+	fn commit_action(self, store: &dyn Store, collective_address: Address) -> ProtocolLoveResult<ActionEntry>;
 }
 
 pub trait ChildAction {
-	fn commit_action(self, collective_address: Address, parent_action_address: Address) -> ZomeApiResult<ActionEntry>;
+	fn commit_action(self, store: &dyn Store, collective_address: Address, parent_action_address: Address) -> ProtocolLoveResult<ActionEntry>;
 }
 
 impl RootAction for Action {
-	fn commit_action(self, collective_address: Address) -> ZomeApiResult<ActionEntry> {
+	fn commit_action(self, store: &dyn Store, collective_address: Address) -> ProtocolLoveResult<ActionEntry> {
 		let action_entry = Entry::App("action".into(), self.borrow().into());
-		let action_address = hdk::commit_entry(&action_entry)?;
-		hdk::link_entries(
+		let action_address =
+			t("commit_action: commit_entry: ", store.commit_entry(&action_entry))?;
+		t("commit_action: collective->action: ", store.link_entries(
 			&collective_address,
 			&action_address,
 			"collective->action",
 			"root_action",
-		)?;
+		))?;
+		t("commit_action: ", emit_signal(store, ProtocolLoveSignal::ActionCommitted {
+			collective_address,
+			action_address: action_address.clone(),
+			action: self.clone(),
+		}))?;
 		Ok((action_address, action_entry, self))
 	}
 }
 
 impl ChildAction for Action {
-	fn commit_action(self, collective_address: Address, parent_action_address: Address) -> ZomeApiResult<ActionEntry> {
+	fn commit_action(self, store: &dyn Store, collective_address: Address, parent_action_address: Address) -> ProtocolLoveResult<ActionEntry> {
 		let action_entry = Entry::App("action".into(), self.borrow().into());
-		let action_address = hdk::commit_entry(&action_entry)?;
-		hdk::link_entries(
+		let action_address =
+			t("commit_action: commit_entry: ", store.commit_entry(&action_entry))?;
+		t("commit_action: collective->action: ", store.link_entries(
 			&collective_address,
 			&action_address,
 			"collective->action",
 			"",
-		)?;
-		hdk::link_entries(
+		))?;
+		t("commit_action: child->action: ", store.link_entries(
 			&parent_action_address,
 			&action_address,
 			"child->action",
 			"",
-		)?;
+		))?;
+		t("commit_action: ", emit_signal(store, ProtocolLoveSignal::ActionCommitted {
+			collective_address,
+			action_address: action_address.clone(),
+			action: self.clone(),
+		}))?;
 		Ok((action_address, action_entry, self))
 	}
 }
 
+/// Creates & commits an executed [RootAction](trait.RootAction.html) for the `collective_address`.
+pub fn create_root_action(
+	store: &dyn Store,
+	collective_address: &Address,
+	op: ActionOp,
+	data: JsonString,
+	prev_data: JsonString,
+	tag: &str,
+) -> ProtocolLoveResult<ActionEntry> {
+	RootAction::commit_action(Action {
+		op,
+		status: ActionStatus::Executed,
+		data,
+		prev_data,
+		tag: tag.into(),
+		strategy: ActionStrategy::SystemAutomatic,
+	}, store, collective_address.clone())
+}
+
 /// Returns a Holochain entry definition for an action.
 pub fn action_def() -> ValidatingEntryType {
 	entry!(
@@ -134,8 +295,9 @@ pub fn action_def() -> ValidatingEntryType {
 		validation_package: || {
 			hdk::ValidationPackageDefinition::Entry
 		},
-		validation: | _validation_data: hdk::EntryValidationData<Action>| {
-			Ok(())
+		validation: | validation_data: hdk::EntryValidationData<Action>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_action_entry(&HdkStore, validation, &context)?)
 		},
 		links: [
 			from!(
@@ -157,11 +319,75 @@ pub fn action_def() -> ValidatingEntryType {
 				validation: |_validation_data: hdk::LinkValidationData| {
 					Ok(())
 				}
+			),
+			to!(
+				"critical_action",
+				link_type: "action->critical_action",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
 			)
 		]
 	)
 }
 
+/// Validation rules of an [Action](struct.Action.html) entry.
+///
+/// Only the status of an open [CriticalAction](struct.CriticalAction.html) changes, to executed
+/// once the critical action is executed after its cooling-off period, or to vetoed once it is
+/// vetoed.
+pub fn validate_action_entry(
+	store: &dyn Store,
+	validation: EntryValidation<Action>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { .. } => Ok(()),
+		EntryValidation::Modify { new_entry, old_entry } => {
+			if new_entry != (Action { status: new_entry.status.clone(), ..old_entry.clone() }) {
+				return Err(ProtocolLoveError::unauthorized("Only the status of an action can change"));
+			}
+			if old_entry.status != ActionStatus::Open {
+				return Err(ProtocolLoveError::conflict("Action is not open"));
+			}
+			let action_address = t("validation error: action: ",
+				store.entry_address(&Entry::App("action".into(), (&old_entry).into())))?;
+			let critical_action = match get_action_critical_action(store, &action_address)? {
+				Some(critical_action) => critical_action,
+				None => return Err(ProtocolLoveError::unauthorized(
+					"Only the status of a critical action can change")),
+			};
+			match new_entry.status {
+				ActionStatus::Executed => {
+					if critical_action.executed_at.is_none() {
+						return Err(ProtocolLoveError::conflict("Critical action is not executed"));
+					}
+					if context.timestamp < critical_action.executable_at {
+						return Err(ProtocolLoveError::conflict(&format!(
+							"Action is cooling off until {}", critical_action.executable_at)));
+					}
+					Ok(())
+				}
+				ActionStatus::Vetoed => {
+					if critical_action.veto.is_none() {
+						return Err(ProtocolLoveError::unauthorized(
+							"Action must be vetoed by its critical action"));
+					}
+					Ok(())
+				}
+				_ => Err(ProtocolLoveError::validation_failed(
+					"status", "Open actions are executed or vetoed")),
+			}
+		}
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Action cannot be deleted")),
+	}
+}
+
 /// Get an [ActionsPayload](struct.ActionsPayload.html) of all of the [Actions](struct.Action.html)
 /// linked to the [Collective](struct.Collective.html).
 ///
@@ -169,12 +395,17 @@ pub fn action_def() -> ValidatingEntryType {
 /// ```
 /// curl -X POST -H "Content-Type: application/json" -d '{"id": "0", "jsonrpc": "2.0", "method": "call", "params": {"instance_id": "test-instance", "zome": "protocol-love", "function": "get_collective", "args": { "collective_address": "addr" } }}' http://127.0.0.1:8888
 /// ```
-pub fn get_actions(collective_address: Address) -> ZomeApiResult<ActionsPayload> {
-	let mut actions = hdk::utils::get_links_and_load_type(
-		&collective_address,
-		LinkMatch::Exactly("collective->action"),
-		LinkMatch::Any,
-	)?;
+pub fn get_actions(
+	store: &dyn Store,
+	collective_address: Address,
+) -> ProtocolLoveResult<ActionsPayload> {
+	let mut actions =
+		t("get_actions: get_links_and_load_type: ", get_links_and_load_type(
+			store,
+			&collective_address,
+			"collective->action",
+			None,
+		))?;
 	actions.reverse();
 	Ok(ActionsPayload {
 		collective_address,
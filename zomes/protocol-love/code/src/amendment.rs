@@ -0,0 +1,324 @@
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use hdk::holochain_core_types::dna::entry_types::Sharing;
+use hdk::prelude::ValidatingEntryType;
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::action::{ActionOp, create_root_action};
+use crate::collective::is_collective_person;
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::person::{Person, get_agent_person};
+use crate::proposal::{Proposal, ProposalStatus};
+use crate::revision::{commit_proposal_revision, is_proposal_author};
+use crate::store::{Store, HdkStore, get_as_type};
+use crate::utils::t;
+use crate::validation::{EntryValidation, ValidationContext, from_hdk_validation_data};
+
+/// An amendment to the name or content of a [Proposal](struct.Proposal.html) in discussion,
+/// proposed by a [Person](struct.Person.html) of the [Collective](struct.Collective.html).
+///
+/// An accepted amendment is applied as a new [ProposalRevision](struct.ProposalRevision.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct Amendment {
+	pub proposal_address: Address,
+	/// Address of the [Person](struct.Person.html) proposing the amendment.
+	pub person_address: Address,
+	/// Amended name of the proposal. The name is kept when `None`.
+	pub name: Option<String>,
+	/// Amended content of the proposal. The content is kept when `None`.
+	pub content: Option<String>,
+	/// Reason of the amendment.
+	pub reason: String,
+	pub status: AmendmentStatus,
+}
+
+/// The lifecycle status of an [Amendment](struct.Amendment.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub enum AmendmentStatus {
+	/// Awaiting the decision of the authors.
+	Proposed,
+	/// Applied to the proposal.
+	Accepted,
+	Rejected,
+}
+
+/// Api params for [propose_amendment](fn.propose_amendment.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct AmendmentParams {
+	pub proposal_address: Address,
+	pub person_address: Address,
+	pub name: Option<String>,
+	pub content: Option<String>,
+	pub reason: String,
+}
+
+/// Api payload containing the `amendment_address` & [amendment](struct.Amendment.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct AmendmentPayload {
+	pub amendment_address: Address,
+	pub amendment: Amendment,
+}
+
+/// Api payload of the [Amendments](struct.Amendment.html) to a [Proposal](struct.Proposal.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ProposalAmendmentsPayload {
+	pub proposal_address: Address,
+	pub amendments: Vec<AmendmentPayload>,
+}
+
+/// Returns a Holochain entry definition for an amendment.
+pub fn amendment_def() -> ValidatingEntryType {
+	entry!(
+		name: "amendment",
+		description: "A protocol.love amendment to a proposal in discussion",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<Amendment>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_amendment_entry(&HdkStore, validation, &context)?)
+		}
+	)
+}
+
+/// Validation rules of an [Amendment](struct.Amendment.html) entry.
+///
+/// Only the authors of the proposal can modify an amendment, to accept or reject it.
+pub fn validate_amendment_entry(
+	store: &dyn Store,
+	validation: EntryValidation<Amendment>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let person: Person = t("validation error: amendment: fetch person: ",
+				get_as_type(store, &entry.person_address))?;
+			if !context.sources.contains(&person.agent_address) {
+				return Err(ProtocolLoveError::unauthorized(
+					"Amendment must be proposed by the agent of the person"));
+			}
+			let proposal = validate_amendment_proposal(store, &entry.proposal_address)?;
+			if !is_collective_person(store, &proposal.collective_address, &entry.person_address)? {
+				return Err(ProtocolLoveError::unauthorized(
+					"Only people in the collective can amend"));
+			}
+			if !proposal.discussion_window.contains(context.timestamp) {
+				return Err(ProtocolLoveError::conflict(
+					"Amendment is proposed outside the discussion window"));
+			}
+			if entry.name.is_none() && entry.content.is_none() {
+				return Err(ProtocolLoveError::validation_failed(
+					"content", "Amendment must change the name or content"));
+			}
+			if entry.reason.len() > 1000 {
+				return Err(ProtocolLoveError::validation_failed(
+					"reason", "Reason must be at most 1000 characters"));
+			}
+			if entry.status != AmendmentStatus::Proposed {
+				return Err(ProtocolLoveError::validation_failed(
+					"status", "Amendment must be proposed"));
+			}
+			Ok(())
+		}
+		EntryValidation::Modify { new_entry, old_entry } => {
+			if old_entry.status != AmendmentStatus::Proposed
+				|| new_entry.status == AmendmentStatus::Proposed
+				|| (Amendment { status: AmendmentStatus::Proposed, ..new_entry }) != old_entry {
+				return Err(ProtocolLoveError::unauthorized(
+					"Amendment can only be accepted or rejected"));
+			}
+			let proposal = validate_amendment_proposal(store, &old_entry.proposal_address)?;
+			let mut author_addresses = vec![&proposal.author_address];
+			author_addresses.extend(&proposal.co_author_addresses);
+			for author_address in author_addresses {
+				let author: Person = t("validation error: amendment: fetch author: ",
+					get_as_type(store, author_address))?;
+				if context.sources.contains(&author.agent_address) {
+					return Ok(());
+				}
+			}
+			Err(ProtocolLoveError::unauthorized("Only the authors can decide on an amendment"))
+		}
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Amendment cannot be deleted")),
+	}
+}
+
+/// Validates the proposal is in discussion.
+fn validate_amendment_proposal(
+	store: &dyn Store,
+	proposal_address: &Address,
+) -> ProtocolLoveResult<Proposal> {
+	let proposal: Proposal = t("validation error: amendment: fetch proposal: ",
+		get_as_type(store, proposal_address))?;
+	if proposal.status != ProposalStatus::Discussion {
+		return Err(ProtocolLoveError::conflict("Proposal is not in discussion"));
+	}
+	Ok(proposal)
+}
+
+/// Api for a [Person](struct.Person.html) of the [Collective](struct.Collective.html) to
+/// propose an [Amendment](struct.Amendment.html) to a [Proposal](struct.Proposal.html)
+/// in discussion.
+pub fn propose_amendment(
+	store: &dyn Store,
+	amendment_params: AmendmentParams,
+) -> ProtocolLoveResult<AmendmentPayload> {
+	let proposal: Proposal =
+		t("propose_amendment: ", get_as_type(store, &amendment_params.proposal_address))?;
+	t("propose_amendment: ", get_agent_person(store, &amendment_params.person_address))?;
+	let amendment = Amendment {
+		proposal_address: amendment_params.proposal_address,
+		person_address: amendment_params.person_address,
+		name: amendment_params.name,
+		content: amendment_params.content,
+		reason: amendment_params.reason,
+		status: AmendmentStatus::Proposed,
+	};
+	let amendment_entry = Entry::App("amendment".into(), (&amendment).into());
+	let amendment_address = t("propose_amendment: ", store.commit_entry(&amendment_entry))?;
+	t("propose_amendment: proposal->amendment: ", store.link_entries(
+		&amendment.proposal_address,
+		&amendment_address,
+		"proposal->amendment",
+		"",
+	))?;
+	let amendment_payload = AmendmentPayload {
+		amendment_address,
+		amendment,
+	};
+	t("propose_amendment: ", create_root_action(
+		store,
+		&proposal.collective_address,
+		ActionOp::ProposeAmendment,
+		(&amendment_payload).into(),
+		serde_json::value::Value::Null.into(),
+		"propose_amendment",
+	))?;
+	Ok(amendment_payload)
+}
+
+/// Api for an author or co-author to accept an [Amendment](struct.Amendment.html),
+/// applying it as a new revision of the [Proposal](struct.Proposal.html).
+pub fn accept_amendment(
+	store: &dyn Store,
+	amendment_address: Address,
+	author_address: Address,
+) -> ProtocolLoveResult<AmendmentPayload> {
+	let (proposal_address, proposal, saved_amendment) =
+		t("accept_amendment: ", get_decided_amendment(store, &amendment_address, &author_address))?;
+	let name = saved_amendment.name.clone().unwrap_or_else(|| proposal.name.clone());
+	let content = saved_amendment.content.clone().unwrap_or_else(|| proposal.content.clone());
+	t("accept_amendment: ", commit_proposal_revision(
+		store,
+		&proposal_address,
+		proposal.clone(),
+		&author_address,
+		name,
+		content,
+		Some(amendment_address.clone()),
+	))?;
+	t("accept_amendment: ", set_amendment_status(
+		store,
+		&proposal,
+		amendment_address,
+		saved_amendment,
+		AmendmentStatus::Accepted,
+		ActionOp::AcceptAmendment,
+	))
+}
+
+/// Api for an author or co-author to reject an [Amendment](struct.Amendment.html).
+pub fn reject_amendment(
+	store: &dyn Store,
+	amendment_address: Address,
+	author_address: Address,
+) -> ProtocolLoveResult<AmendmentPayload> {
+	let (_proposal_address, proposal, saved_amendment) =
+		t("reject_amendment: ", get_decided_amendment(store, &amendment_address, &author_address))?;
+	t("reject_amendment: ", set_amendment_status(
+		store,
+		&proposal,
+		amendment_address,
+		saved_amendment,
+		AmendmentStatus::Rejected,
+		ActionOp::RejectAmendment,
+	))
+}
+
+/// Api to get the [Amendments](struct.Amendment.html) to a [Proposal](struct.Proposal.html),
+/// the most recent first.
+pub fn get_proposal_amendments(
+	store: &dyn Store,
+	proposal_address: Address,
+) -> ProtocolLoveResult<ProposalAmendmentsPayload> {
+	let amendment_addresses =
+		t("get_proposal_amendments: ", store.get_links(&proposal_address, "proposal->amendment", None))?;
+	let amendments = amendment_addresses.into_iter()
+		.map(|amendment_address| {
+			let amendment = t("get_proposal_amendments: ", get_as_type(store, &amendment_address))?;
+			Ok(AmendmentPayload {
+				amendment_address,
+				amendment,
+			})
+		})
+		.collect::<ProtocolLoveResult<Vec<AmendmentPayload>>>()?;
+	Ok(ProposalAmendmentsPayload {
+		proposal_address,
+		amendments,
+	})
+}
+
+/// Gets a proposed amendment & its proposal, which must be authored by the `author_address`.
+fn get_decided_amendment(
+	store: &dyn Store,
+	amendment_address: &Address,
+	author_address: &Address,
+) -> ProtocolLoveResult<(Address, Proposal, Amendment)> {
+	let amendment: Amendment = t("get_decided_amendment: ", get_as_type(store, amendment_address))?;
+	if amendment.status != AmendmentStatus::Proposed {
+		return Err(ProtocolLoveError::conflict("Amendment is already decided"));
+	}
+	let proposal: Proposal =
+		t("get_decided_amendment: ", get_as_type(store, &amendment.proposal_address))?;
+	t("get_decided_amendment: ", get_agent_person(store, author_address))?;
+	if !is_proposal_author(&proposal, author_address) {
+		return Err(ProtocolLoveError::unauthorized("Only the authors can decide on an amendment"));
+	}
+	if proposal.status != ProposalStatus::Discussion {
+		return Err(ProtocolLoveError::conflict("Proposal is not in discussion"));
+	}
+	Ok((amendment.proposal_address.clone(), proposal, amendment))
+}
+
+fn set_amendment_status(
+	store: &dyn Store,
+	proposal: &Proposal,
+	amendment_address: Address,
+	amendment: Amendment,
+	status: AmendmentStatus,
+	op: ActionOp,
+) -> ProtocolLoveResult<AmendmentPayload> {
+	let amendment = Amendment {
+		status,
+		..amendment
+	};
+	let amendment_entry = Entry::App("amendment".into(), (&amendment).into());
+	t("set_amendment_status: ", store.update_entry(amendment_entry, &amendment_address))?;
+	let amendment_payload = AmendmentPayload {
+		amendment_address,
+		amendment,
+	};
+	t("set_amendment_status: ", create_root_action(
+		store,
+		&proposal.collective_address,
+		op,
+		(&amendment_payload).into(),
+		serde_json::value::Value::Null.into(),
+		"set_amendment_status",
+	))?;
+	Ok(amendment_payload)
+}
@@ -0,0 +1,273 @@
+use std::convert::TryFrom;
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use hdk::holochain_core_types::dna::entry_types::Sharing;
+use hdk::prelude::ValidatingEntryType;
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::action::{ActionOp, create_root_action};
+use crate::collective::{Collective, is_collective_admin, is_collective_admin_source};
+use crate::critical_action::validate_proposal_action_executed;
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::proposal::{Proposal, ProposalStatus};
+use crate::store::{Store, HdkStore, get_as_type};
+use crate::tally::ProposalOutcome;
+use crate::utils::t;
+use crate::validation::{
+	EntryValidation, ValidationContext, from_hdk_validation_data, validate_timestamp,
+};
+
+/// The governing document of a [Collective](struct.Collective.html), e.g. its bylaws.
+///
+/// Each version is adopted by a passed [Proposal](struct.Proposal.html) created from a
+/// [ProposalTemplate](struct.ProposalTemplate.html) with the
+/// [ActionOp::AmendCharter](enum.ActionOp.html) op. The content of the proposal becomes the
+/// content of the charter. Each version is an update of the charter entry & is recorded in the
+/// action log of the collective.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct Charter {
+	pub collective_address: Address,
+	/// Version of the charter, starting at 1.
+	pub version: u64,
+	pub content: String,
+	/// Address of the [Proposal](struct.Proposal.html) adopting the version.
+	pub proposal_address: Address,
+	/// Time the version was adopted in seconds since the unix epoch.
+	pub amended_at: u64,
+}
+
+/// Api payload of the [Charter](struct.Charter.html) of a [Collective](struct.Collective.html).
+/// The `charter` is `None` before the first version is adopted.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct CharterPayload {
+	pub collective_address: Address,
+	pub charter: Option<Charter>,
+}
+
+/// Api payload of the versions of the [Charter](struct.Charter.html) of a
+/// [Collective](struct.Collective.html), oldest first.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct CharterHistoryPayload {
+	pub collective_address: Address,
+	pub versions: Vec<Charter>,
+}
+
+/// Returns a Holochain entry definition for a charter.
+pub fn charter_def() -> ValidatingEntryType {
+	entry!(
+		name: "charter",
+		description: "A protocol.love charter governing a collective",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<Charter>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_charter_entry(&HdkStore, validation, &context)?)
+		}
+	)
+}
+
+/// Validation rules of a [Charter](struct.Charter.html) entry.
+///
+/// Each version follows the former & is adopted by the admin from a passed charter proposal of
+/// the collective not adopted before, once its action is executed when charter amendments are
+/// critical.
+pub fn validate_charter_entry(
+	store: &dyn Store,
+	validation: EntryValidation<Charter>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			if entry.version != 1 {
+				return Err(ProtocolLoveError::validation_failed(
+					"version", "Charter must start at version 1"));
+			}
+			validate_charter_proposal(store, &entry, context)
+		}
+		EntryValidation::Modify { new_entry, old_entry } => {
+			if new_entry.collective_address != old_entry.collective_address {
+				return Err(ProtocolLoveError::unauthorized("Charter cannot move to another collective"));
+			}
+			if new_entry.version != old_entry.version + 1 {
+				return Err(ProtocolLoveError::validation_failed(
+					"version", "Version must follow the current version"));
+			}
+			let enacted = get_charter_versions(store, &old_entry.collective_address)?
+				.into_iter()
+				.filter(|charter| charter.version < new_entry.version)
+				.any(|charter| charter.proposal_address == new_entry.proposal_address);
+			if enacted || new_entry.proposal_address == old_entry.proposal_address {
+				return Err(ProtocolLoveError::conflict("Proposal is already enacted"));
+			}
+			if new_entry.amended_at < old_entry.amended_at {
+				return Err(ProtocolLoveError::validation_failed(
+					"amended_at", "Amendment must follow the current version"));
+			}
+			validate_charter_proposal(store, &new_entry, context)
+		}
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Charter cannot be deleted")),
+	}
+}
+
+/// Validates the version is adopted by the admin from a passed charter proposal of the collective.
+fn validate_charter_proposal(
+	store: &dyn Store,
+	charter: &Charter,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let collective: Collective = t("validation error: charter: fetch collective: ",
+		get_as_type(store, &charter.collective_address))?;
+	if !is_collective_admin_source(store, &collective, context)? {
+		return Err(ProtocolLoveError::unauthorized("Only the admin can amend the charter"));
+	}
+	let proposal: Proposal = t("validation error: charter: fetch proposal: ",
+		get_as_type(store, &charter.proposal_address))?;
+	if proposal.collective_address != charter.collective_address || !is_charter_proposal(&proposal) {
+		return Err(ProtocolLoveError::validation_failed(
+			"proposal_address", "Proposal does not amend the charter of the collective"));
+	}
+	if proposal.status != ProposalStatus::Closed || proposal.outcome != Some(ProposalOutcome::Passed) {
+		return Err(ProtocolLoveError::conflict("Proposal has not passed"));
+	}
+	if charter.content != proposal.content {
+		return Err(ProtocolLoveError::validation_failed(
+			"content", "Content must be the content of the proposal"));
+	}
+	validate_proposal_action_executed(store, &proposal, &charter.proposal_address)?;
+	validate_timestamp("amended_at", charter.amended_at, context)
+}
+
+/// Is the proposal created from a template amending the charter?
+fn is_charter_proposal(proposal: &Proposal) -> bool {
+	proposal.template.as_ref()
+		.and_then(|template| template.action_op.as_ref())
+		.map_or(false, |action_op| action_op == &ActionOp::AmendCharter)
+}
+
+/// Api for the admin to enact a passed charter [Proposal](struct.Proposal.html), adopting its
+/// content as the next version of the [Charter](struct.Charter.html) of the collective at the
+/// `timestamp`.
+pub fn amend_charter(
+	store: &dyn Store,
+	proposal_address: Address,
+	timestamp: u64,
+) -> ProtocolLoveResult<CharterPayload> {
+	let proposal: Proposal = t("amend_charter: ", get_as_type(store, &proposal_address))?;
+	let collective_address = proposal.collective_address.clone();
+	let collective: Collective = t("amend_charter: ", get_as_type(store, &collective_address))?;
+	if !t("amend_charter: ", is_collective_admin(store, &collective))? {
+		return Err(ProtocolLoveError::unauthorized(
+			"amend_charter: Only the admin can amend the charter"));
+	}
+	let versions = t("amend_charter: ", get_charter_versions(store, &collective_address))?;
+	if versions.iter().any(|version| version.proposal_address == proposal_address) {
+		return Err(ProtocolLoveError::conflict("amend_charter: Proposal is already enacted"));
+	}
+	let prev_charter = versions.last().cloned();
+	let charter = Charter {
+		collective_address: collective_address.clone(),
+		version: prev_charter.as_ref().map_or(1, |charter| charter.version + 1),
+		content: proposal.content,
+		proposal_address,
+		amended_at: timestamp,
+	};
+	let charter_entry = Entry::App("charter".into(), (&charter).into());
+	match t("amend_charter: ", find_charter_address(store, &collective_address))? {
+		Some(charter_address) => {
+			t("amend_charter: ", store.update_entry(charter_entry, &charter_address))?;
+		}
+		None => {
+			let charter_address = t("amend_charter: ", store.commit_entry(&charter_entry))?;
+			t("amend_charter: collective->charter: ", store.link_entries(
+				&collective_address,
+				&charter_address,
+				"collective->charter",
+				"",
+			))?;
+		}
+	}
+	t("amend_charter: ", create_root_action(
+		store,
+		&collective_address,
+		ActionOp::AmendCharter,
+		charter.clone().into(),
+		match prev_charter {
+			Some(prev_charter) => prev_charter.into(),
+			None => serde_json::value::Value::Null.into(),
+		},
+		"amend_charter",
+	))?;
+	Ok(CharterPayload {
+		collective_address,
+		charter: Some(charter),
+	})
+}
+
+/// Api to get the [Charter](struct.Charter.html) of a [Collective](struct.Collective.html),
+/// as of the `timestamp` when given.
+pub fn get_charter(
+	store: &dyn Store,
+	collective_address: Address,
+	timestamp: Option<u64>,
+) -> ProtocolLoveResult<CharterPayload> {
+	let charter = match timestamp {
+		Some(timestamp) =>
+			t("get_charter: ", get_charter_versions(store, &collective_address))?
+				.into_iter()
+				.take_while(|charter| charter.amended_at <= timestamp)
+				.last(),
+		None => match t("get_charter: ", find_charter_address(store, &collective_address))? {
+			Some(charter_address) => Some(t("get_charter: ", get_as_type(store, &charter_address))?),
+			None => None,
+		},
+	};
+	Ok(CharterPayload {
+		collective_address,
+		charter,
+	})
+}
+
+/// Api to get the versions of the [Charter](struct.Charter.html) of a
+/// [Collective](struct.Collective.html), oldest first.
+pub fn get_charter_history(
+	store: &dyn Store,
+	collective_address: Address,
+) -> ProtocolLoveResult<CharterHistoryPayload> {
+	let versions = t("get_charter_history: ", get_charter_versions(store, &collective_address))?;
+	Ok(CharterHistoryPayload {
+		collective_address,
+		versions,
+	})
+}
+
+/// Reads the versions of the charter from the updates of the validated charter entry, oldest
+/// first.
+fn get_charter_versions(
+	store: &dyn Store,
+	collective_address: &Address,
+) -> ProtocolLoveResult<Vec<Charter>> {
+	let charter_address = match t("get_charter_versions: ", find_charter_address(store, collective_address))? {
+		Some(charter_address) => charter_address,
+		None => return Ok(vec![]),
+	};
+	t("get_charter_versions: ", store.get_entry_history(&charter_address))?
+		.into_iter()
+		.map(|entry| match entry {
+			Entry::App(_, entry_value) => Charter::try_from(entry_value).map_err(|_|
+				ProtocolLoveError::invariant_broken("get_charter_versions: Could not read the charter")),
+			_ => Err(ProtocolLoveError::invariant_broken("get_charter_versions: Charter is not an app entry")),
+		})
+		.collect()
+}
+
+fn find_charter_address(
+	store: &dyn Store,
+	collective_address: &Address,
+) -> ProtocolLoveResult<Option<Address>> {
+	Ok(store.get_links(collective_address, "collective->charter", None)?.into_iter().next())
+}
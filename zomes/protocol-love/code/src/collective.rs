@@ -1,25 +1,40 @@
 use std::borrow::Borrow;
-use hdk::EntryValidationData;
 use hdk::holochain_core_types::dna::entry_types::Sharing;
 use hdk::holochain_json_api::{
 	json::JsonString,
 	error::JsonError,
 };
-use crate::ledger::create_collective_ledger;
+use crate::charter::Charter;
+use crate::delegation::validate_collective_delegation_link;
+use crate::critical_action::validate_collective_policy;
+use crate::dissolution::validate_collective_dissolution;
+use crate::election::Role;
+use crate::federation::has_joined_federation;
+use crate::ledger::{Ledger, ensure_collective_ledger};
 use holochain_wasm_utils::holochain_core_types::entry::Entry;
-use hdk::prelude::{ZomeApiResult, ValidatingEntryType};
+use hdk::prelude::ValidatingEntryType;
 use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
 use crate::action::{Action, ActionStatus, ActionStrategy, ActionOp, ActionEntry};
-use crate::utils::{get_as_type_ref, t};
-use crate::person::{Person, create_person, PersonParams, PersonPayload};
-use holochain_wasm_utils::holochain_core_types::link::LinkMatch;
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::utils::t;
+use crate::person::{Person, create_person, get_agent_person, PersonParams, PersonPayload};
+use crate::proposal::TallyMethod;
+use crate::signal::{ProtocolLoveSignal, emit_signal};
+use crate::template::ProposalTemplate;
+use crate::store::{
+	Store, HdkStore, commit_entry_if_missing, get_as_type, get_initial_as_type,
+	get_links_and_load_type,
+};
+use crate::validation::{
+	EntryValidation, LinkValidation, ValidationContext, from_hdk_link_validation_data,
+	from_hdk_validation_data, validate_app_link,
+};
 use std::fmt;
-use holochain_wasm_utils::api_serialization::get_links::{GetLinksOptions};
 
 /// A collective.
 ///
-/// Has a name & an optional admin_address.
-#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+/// Has a name, an optional admin_address, & descriptive metadata.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
 pub struct Collective {
 	/// Name of the Collective
 	pub name: String,
@@ -29,6 +44,74 @@ pub struct Collective {
 	///
 	/// TODO: Move to a link or a [CollectivePersonTag](enum.CollectivePersonTag.html)
 	pub admin_address: Option<Address>,
+	/// Description of the Collective
+	#[serde(default)]
+	pub description: String,
+	/// Mission or purpose statement of the Collective
+	#[serde(default)]
+	pub purpose: String,
+	/// Tags used to categorize the Collective
+	#[serde(default)]
+	pub tags: Vec<String>,
+	/// Website links of the Collective
+	#[serde(default)]
+	pub links: Vec<String>,
+	/// Reference to the avatar of the Collective, either an url or an entry address.
+	#[serde(default)]
+	pub avatar: Option<String>,
+	/// Voice credits each person receives per period for quadratic voting.
+	#[serde(default = "default_voice_credits_per_period")]
+	pub voice_credits_per_period: u64,
+	/// Current voice credit period, started with
+	/// [start_voice_credit_period](fn.start_voice_credit_period.html).
+	#[serde(default)]
+	pub voice_credit_period: u64,
+	/// Number of [Sponsorships](struct.Sponsorship.html) a proposal needs before voting opens.
+	/// Proposals need no sponsors when `0`.
+	#[serde(default)]
+	pub sponsor_threshold: u64,
+	/// Parent of a child collective, created with
+	/// [create_sub_collective](fn.create_sub_collective.html).
+	#[serde(default)]
+	pub parent_address: Option<Address>,
+	/// Does the collective use the decision policies of its parent, i.e. the
+	/// `sponsor_threshold`, `voice_credits_per_period`, `election_tally_method` & the critical
+	/// action policy?
+	#[serde(default)]
+	pub inherit_policies: bool,
+	/// [TallyMethod](enum.TallyMethod.html) of the [Elections](struct.Election.html) of roles.
+	#[serde(default = "default_election_tally_method")]
+	pub election_tally_method: TallyMethod,
+	/// Ops of the [Actions](struct.Action.html) enacted from passed proposals only after the
+	/// `cooling_off_period`, see [CriticalAction](struct.CriticalAction.html).
+	#[serde(default)]
+	pub critical_action_ops: Vec<ActionOp>,
+	/// Delay in seconds between enacting & executing a critical action.
+	#[serde(default)]
+	pub cooling_off_period: u64,
+	/// Addresses of the [Roles](struct.Role.html) whose holders may veto a critical action
+	/// while it cools off.
+	#[serde(default)]
+	pub veto_role_addresses: Vec<Address>,
+	/// Address of the [Proposal](struct.Proposal.html) which set the critical action policy, see
+	/// [set_critical_action_policy](fn.set_critical_action_policy.html).
+	#[serde(default)]
+	pub policy_proposal_address: Option<Address>,
+	/// Time the collective was dissolved in seconds since the unix epoch, see
+	/// [dissolve_collective](fn.dissolve_collective.html). An archived collective is read-only.
+	#[serde(default)]
+	pub archived_at: Option<u64>,
+	/// Address of the [Proposal](struct.Proposal.html) dissolving the collective.
+	#[serde(default)]
+	pub dissolution_proposal_address: Option<Address>,
+}
+
+fn default_voice_credits_per_period() -> u64 {
+	100
+}
+
+fn default_election_tally_method() -> TallyMethod {
+	TallyMethod::RankedChoice
 }
 
 /// Api params to create a [Collective](struct.Collective.html) along with an optional `admin_address`.
@@ -36,6 +119,11 @@ pub struct Collective {
 pub struct CreateCollectiveParams {
 	pub name: String,
 	pub admin_address: Option<Address>,
+	pub description: Option<String>,
+	pub purpose: Option<String>,
+	pub tags: Option<Vec<String>>,
+	pub links: Option<Vec<String>>,
+	pub avatar: Option<String>,
 }
 
 impl Into<Collective> for CreateCollectiveParams {
@@ -43,6 +131,12 @@ impl Into<Collective> for CreateCollectiveParams {
 		Collective {
 			name: self.name,
 			admin_address: self.admin_address,
+			description: self.description.unwrap_or_default(),
+			purpose: self.purpose.unwrap_or_default(),
+			tags: self.tags.unwrap_or_default(),
+			links: self.links.unwrap_or_default(),
+			avatar: self.avatar,
+			..Collective::default()
 		}
 	}
 }
@@ -52,10 +146,41 @@ impl Default for Collective {
 		Collective {
 			name: "unnamed collective".to_string(),
 			admin_address: Default::default(),
+			description: Default::default(),
+			purpose: Default::default(),
+			tags: Default::default(),
+			links: Default::default(),
+			avatar: Default::default(),
+			voice_credits_per_period: default_voice_credits_per_period(),
+			voice_credit_period: Default::default(),
+			sponsor_threshold: Default::default(),
+			parent_address: Default::default(),
+			inherit_policies: Default::default(),
+			election_tally_method: default_election_tally_method(),
+			critical_action_ops: Default::default(),
+			cooling_off_period: Default::default(),
+			veto_role_addresses: Default::default(),
+			policy_proposal_address: Default::default(),
+			archived_at: Default::default(),
+			dissolution_proposal_address: Default::default(),
 		}
 	}
 }
 
+/// Api params for [update_collective](fn.update_collective.html).
+///
+/// Each field is optional & only the given fields are updated.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, Default)]
+pub struct UpdateCollectiveParams {
+	pub name: Option<String>,
+	pub description: Option<String>,
+	pub purpose: Option<String>,
+	pub tags: Option<Vec<String>>,
+	pub links: Option<Vec<String>>,
+	/// An empty string removes the avatar.
+	pub avatar: Option<String>,
+}
+
 /// Api payload containing a collective_address & collective.
 #[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
 pub struct CollectivePayload {
@@ -77,6 +202,8 @@ impl Default for CollectivePayload {
 pub enum CollectivePersonTag {
 	/// Creator of the [Collective](struct.Collective.html)
 	Creator,
+	/// Member added by the admin of the [Collective](struct.Collective.html)
+	Member,
 }
 
 impl fmt::Display for CollectivePersonTag {
@@ -103,49 +230,8 @@ pub fn collective_def() -> ValidatingEntryType {
 			hdk::ValidationPackageDefinition::Entry
 		},
 		validation: | validation_data: hdk::EntryValidationData<Collective>| {
-			match validation_data{
-				EntryValidationData::Create { entry, validation_data } => {
-					match entry.admin_address {
-						Some(admin_address) => {
-							let admin:Person = t("validation error: collective: fetch admin: ",
-								hdk::utils::get_as_type(admin_address), )?;
-							if !validation_data.sources().contains(&admin.agent_address) {
-								return Err(
-									"Collective must be created with same agent as the given person".into()
-								);
-							}
-						},
-						None => {
-							return Err("Collective being created must have an admin".into())
-						}
-					}
-					Ok(())
-				}
-				EntryValidationData::Modify { old_entry, validation_data, .. } => {
-					let admin_address = old_entry.admin_address;
-					match admin_address {
-						Some(admin_address) => {
-							let admin:Person = hdk::utils::get_as_type(admin_address)?;
-							if !validation_data.sources().contains(&admin.agent_address) {
-								return Err(
-									"Collective can only be modified by the admin".into()
-								);
-							};
-						},
-						None => {
-							// TODO: Logic for a Proposal to update this collective
-							return Err(
-								"Collective can only be modified with an executed proposal".into()
-							);
-						}
-					}
-
-					Ok(())
-				}
-				EntryValidationData::Delete { .. } => {
-					return Err("Collective cannot be deleted".into());
-				}
-			}
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_collective_entry(&HdkStore, validation, &context)?)
 		},
 		links: [
 			to!(
@@ -154,8 +240,9 @@ pub fn collective_def() -> ValidatingEntryType {
 				validation_package: || {
 					hdk::ValidationPackageDefinition::Entry
 				},
-				validation: |_validation_data: hdk::LinkValidationData| {
-					Ok(())
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
 				}
 			),
 			to!(
@@ -164,8 +251,31 @@ pub fn collective_def() -> ValidatingEntryType {
 				validation_package: || {
 					hdk::ValidationPackageDefinition::Entry
 				},
-				validation: |_validation_data: hdk::LinkValidationData| {
-					Ok(())
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"delegation",
+				link_type: "collective->delegation",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"proposal",
+				link_type: "collective->proposal",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
 				}
 			),
 			to!(
@@ -174,84 +284,383 @@ pub fn collective_def() -> ValidatingEntryType {
 				validation_package: || {
 					hdk::ValidationPackageDefinition::Entry
 				},
-				validation: |_validation_data: hdk::LinkValidationData| {
-					Ok(())
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"message",
+				link_type: "collective->message",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"proposal_template",
+				link_type: "collective->proposal_template",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"collective",
+				link_type: "collective->child_collective",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"collective",
+				link_type: "collective->member_collective",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"collective",
+				link_type: "collective->federation",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"role",
+				link_type: "collective->role",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"critical_action",
+				link_type: "collective->critical_action",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"charter",
+				link_type: "collective->charter",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
 				}
 			)
 		]
 	)
 }
 
+/// Validation rules of a [Collective](struct.Collective.html) entry.
+pub fn validate_collective_entry(
+	store: &dyn Store,
+	validation: EntryValidation<Collective>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			validate_collective(&entry)?;
+			if entry.archived_at.is_some() || entry.dissolution_proposal_address.is_some() {
+				return Err(ProtocolLoveError::validation_failed(
+					"archived_at", "Collective cannot be created archived"));
+			}
+			if entry.policy_proposal_address.is_some()
+				|| !entry.critical_action_ops.is_empty()
+				|| !entry.veto_role_addresses.is_empty() {
+				return Err(ProtocolLoveError::validation_failed(
+					"critical_action_ops", "Critical action policy must be set by a proposal"));
+			}
+			match entry.admin_address {
+				Some(admin_address) => {
+					let admin: Person = t("validation error: collective: fetch admin: ",
+						get_as_type(store, &admin_address), )?;
+					if !context.sources.contains(&admin.agent_address) {
+						return Err(ProtocolLoveError::unauthorized(
+							"Collective must be created with same agent as the given person"
+						));
+					}
+					if let Some(parent_address) = &entry.parent_address {
+						validate_child_collective_admin(store, parent_address, &admin_address, context)?;
+					}
+				}
+				None => {
+					return Err(ProtocolLoveError::validation_failed(
+						"admin_address", "Collective being created must have an admin"
+					));
+				}
+			}
+			Ok(())
+		}
+		EntryValidation::Modify { new_entry, old_entry } => {
+			validate_collective(&new_entry)?;
+			if new_entry.parent_address != old_entry.parent_address {
+				return Err(ProtocolLoveError::unauthorized("Collective cannot move to another parent"));
+			}
+			if old_entry.archived_at.is_some() {
+				return Err(ProtocolLoveError::conflict("Collective is archived"));
+			}
+			if new_entry.archived_at.is_some() {
+				validate_collective_dissolution(store, &new_entry, &old_entry, context)?;
+			}
+			if new_entry.policy_proposal_address != old_entry.policy_proposal_address
+				|| new_entry.critical_action_ops != old_entry.critical_action_ops
+				|| new_entry.cooling_off_period != old_entry.cooling_off_period
+				|| new_entry.veto_role_addresses != old_entry.veto_role_addresses {
+				validate_collective_policy(store, &new_entry, &old_entry)?;
+			}
+			match old_entry.admin_address {
+				Some(admin_address) => {
+					let admin: Person = t("validation error: collective: fetch admin: ",
+						get_as_type(store, &admin_address), )?;
+					if !context.sources.contains(&admin.agent_address) {
+						return Err(ProtocolLoveError::unauthorized(
+							"Collective can only be modified by the admin"
+						));
+					};
+				}
+				None => {
+					// TODO: Logic for a Proposal to update this collective
+					return Err(ProtocolLoveError::unauthorized(
+						"Collective can only be modified with an executed proposal"
+					));
+				}
+			}
+			Ok(())
+		}
+		EntryValidation::Delete { .. } => {
+			Err(ProtocolLoveError::conflict("Collective cannot be deleted"))
+		}
+	}
+}
+
+/// A child collective is created by the admin of the parent & administered by a person
+/// in the parent.
+fn validate_child_collective_admin(
+	store: &dyn Store,
+	parent_address: &Address,
+	admin_address: &Address,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let parent: Collective = t("validation error: collective: fetch parent: ",
+		get_as_type(store, parent_address))?;
+	let parent_admin: Person = match &parent.admin_address {
+		Some(parent_admin_address) => t("validation error: collective: fetch parent admin: ",
+			get_as_type(store, parent_admin_address))?,
+		None => return Err(ProtocolLoveError::unauthorized(
+			"Only the admin of the parent can create child collectives")),
+	};
+	if !context.sources.contains(&parent_admin.agent_address) {
+		return Err(ProtocolLoveError::unauthorized(
+			"Only the admin of the parent can create child collectives"));
+	}
+	if !is_collective_person(store, parent_address, admin_address)? {
+		return Err(ProtocolLoveError::validation_failed(
+			"admin_address", "Admin must be a person in the parent collective"));
+	}
+	Ok(())
+}
+
+fn validate_collective(collective: &Collective) -> ProtocolLoveResult<()> {
+	if collective.description.len() > 4096 {
+		return Err(ProtocolLoveError::validation_failed(
+			"description", "Collective description is too long"));
+	}
+	if collective.purpose.len() > 4096 {
+		return Err(ProtocolLoveError::validation_failed(
+			"purpose", "Collective purpose is too long"));
+	}
+	if collective.tags.len() > 32 {
+		return Err(ProtocolLoveError::validation_failed(
+			"tags", "Collective has too many tags"));
+	}
+	if collective.tags.iter().any(|tag| tag.is_empty() || tag.len() > 64) {
+		return Err(ProtocolLoveError::validation_failed(
+			"tags", "Collective tag must be between 1 & 64 characters"));
+	}
+	if collective.links.len() > 16 {
+		return Err(ProtocolLoveError::validation_failed(
+			"links", "Collective has too many links"));
+	}
+	if collective.links.iter().any(|link| link.is_empty() || link.len() > 2048) {
+		return Err(ProtocolLoveError::validation_failed(
+			"links", "Collective link must be between 1 & 2048 characters"));
+	}
+	match &collective.avatar {
+		Some(avatar) if avatar.is_empty() || avatar.len() > 2048 =>
+			Err(ProtocolLoveError::validation_failed(
+				"avatar", "Collective avatar must be between 1 & 2048 characters")),
+		_ => Ok(())
+	}
+}
+
 /// Api function to create & commit a [Collective](struct.Collective.html) along with the admin.
 ///
-/// The optional admin_address defaults to the `hdk::AGENT_ADDRESS`.
+/// The optional admin_address defaults to a new [Person](struct.Person.html) for the `store` agent.
+///
+/// Safe to retry with the same params. The entries are content addressed, so a retry
+/// resolves to the same collective & completes the steps missing from a failed call
+/// with [initialise_collective](fn.initialise_collective.html).
 ///
 /// # Test:
 ///
 /// curl -X POST -H "Content-Type: application/json" -d '{"id": "0", "jsonrpc": "2.0", "method": "call", "params": {"instance_id": "test-instance", "zome": "protocol-love", "function": "create_collective", "args": { "collective": { "name": "Collective 0" } } }}' http://127.0.0.1:8888
 pub fn create_collective(
-	collective_params: CreateCollectiveParams
-) -> ZomeApiResult<CollectivePayload> {
+	store: &dyn Store,
+	collective_params: CreateCollectiveParams,
+) -> ProtocolLoveResult<CollectivePayload> {
 	// TODO: Set name when answered: https://forum.holochain.org/t/writing-a-validation-rule-that-checks-the-entry-author-against-the-data-being-added-the-entry/1545/14?u=btakita
-	let PersonPayload {
-		person: _admin,
-		person_address: admin_address,
-	} = match collective_params.admin_address {
+	let admin_address = match collective_params.admin_address.clone() {
 		Some(admin_address) => {
-			PersonPayload {
-				person_address: admin_address.clone(),
-				person: (
-					t("create_collective: get_as_type: ",
-						hdk::utils::get_as_type(
-							admin_address.clone()
-						),
-					)?),
-			}
+			let _admin: Person =
+				t("create_collective: get_as_type: ",
+					get_as_type(store, &admin_address),
+				)?;
+			admin_address
 		}
 		None => {
-			t("create_collective: create_person: ",
-				create_person(PersonParams {
-					agent_address: (
-						match collective_params.admin_address {
-							Some(admin_address) => admin_address,
-							None => PersonParams::default().agent_address
-						}
-					),
-					..PersonParams::default()
-				}))?
+			let PersonPayload {
+				person_address,
+				..
+			} = t("create_collective: create_person: ",
+				create_person(store, PersonParams::for_agent(store.agent_address())))?;
+			person_address
 		}
 	};
+	let collective_draft: Collective = collective_params.into();
 	let CommitCollectiveResponse(
 		collective_address,
 		_collective_entry,
-		collective,
+		_collective,
 	) =
 		t("create_collective: ", commit_collective(
+			store,
 			Collective {
-				name: collective_params.name,
-				admin_address: Some(admin_address.clone()),
+				admin_address: Some(admin_address),
+				..collective_draft
 			}))?;
-	t("create_collective: ", create_create_collective_action(
-		&collective_address,
+	t("create_collective: ", initialise_collective(store, collective_address))
+}
+
+/// Api for the admin to complete the initialisation of a [Collective](struct.Collective.html).
+///
+/// Creates the actions, primary ledger & admin link missing after a partially failed
+/// [create_collective](fn.create_collective.html). Steps already performed are left unchanged.
+pub fn ensure_collective_initialised(
+	store: &dyn Store,
+	collective_address: Address,
+) -> ProtocolLoveResult<CollectivePayload> {
+	let collective: Collective =
+		t("ensure_collective_initialised: ", get_as_type(store, &collective_address))?;
+	if !t("ensure_collective_initialised: ", is_collective_admin(store, &collective))? {
+		return Err(ProtocolLoveError::unauthorized(
+			"ensure_collective_initialised: Only the admin can initialise the collective"));
+	}
+	initialise_collective(store, collective_address)
+}
+
+/// Creates the initialisation steps missing from the [Collective](struct.Collective.html),
+/// recording the collective as it was created rather than its latest version.
+pub fn initialise_collective(
+	store: &dyn Store,
+	collective_address: Address,
+) -> ProtocolLoveResult<CollectivePayload> {
+	let collective: Collective =
+		t("initialise_collective: ", get_initial_as_type(store, &collective_address))?;
+	let admin_address = match &collective.admin_address {
+		Some(admin_address) => admin_address.clone(),
+		None => return Err(ProtocolLoveError::invariant_broken(
+			"initialise_collective: Collective has no admin")),
+	};
+	let actions: Vec<Action> =
+		t("initialise_collective: ", get_links_and_load_type(
+			store,
+			&collective_address,
+			"collective->action",
+			None,
+		))?;
+	if !has_action_op(&actions, &ActionOp::CreateCollective) {
+		t("initialise_collective: ", create_create_collective_action(
+			store,
+			&collective_address,
+			&collective,
+		))?;
+	}
+	t("initialise_collective: ", ensure_collective_ledger(
+		store,
 		&collective,
-	))?;
-	t("create_collective: ", create_collective_ledger(
-		&collective.borrow(),
 		&collective_address,
 	))?;
-	t("create_collective: ", create_set_collective_name_action(
-		&collective_address,
-		&collective.name,
-		None,
-	))?;
-	t("create_collective: ", add_collective_person(
+	if !has_action_op(&actions, &ActionOp::SetCollectiveName) {
+		t("initialise_collective: ", create_set_collective_name_action(
+			store,
+			&collective_address,
+			&collective.name,
+			None,
+		))?;
+	}
+	t("initialise_collective: ", create_set_collective_metadata_actions(
+		store,
 		&collective_address,
-		&admin_address,
+		&collective,
+		&Collective::default(),
+		&actions,
 	))?;
-	Ok(CollectivePayload {
-		collective_address,
-		collective,
-	})
+	let person_addresses =
+		t("initialise_collective: ", store.get_links(
+			&collective_address,
+			"collective->person",
+			None,
+		))?;
+	if !person_addresses.contains(&admin_address) {
+		t("initialise_collective: ", link_collective_person(
+			store,
+			&collective_address,
+			&admin_address,
+			CollectivePersonTag::Creator,
+		))?;
+	}
+	let admin_action_data: JsonString = AddCollectivePersonActionData {
+		person_address: admin_address.clone(),
+	}.into();
+	if !actions.iter().any(|action|
+		action.op == ActionOp::AddCollectivePerson && action.data == admin_action_data) {
+		t("initialise_collective: ", create_add_collective_person_action(
+			store,
+			&collective_address,
+			&admin_address,
+		))?;
+	}
+	get_collective(store, collective_address)
 }
 
 /// Api to get the [Collective](struct.Collective.html).
@@ -259,10 +668,12 @@ pub fn create_collective(
 /// # Test:
 ///
 /// curl -X POST -H "Content-Type: application/json" -d '{"id": "0", "jsonrpc": "2.0", "method": "call", "params": {"instance_id": "test-instance", "zome": "protocol-love", "function": "get_collective", "args": { "collective_address": "addr" } }}' http://127.0.0.1:8888
-pub fn get_collective(collective_address: Address) -> ZomeApiResult<CollectivePayload> {
-	let collective_address__ = collective_address.clone();
+pub fn get_collective(
+	store: &dyn Store,
+	collective_address: Address,
+) -> ProtocolLoveResult<CollectivePayload> {
 	let collective =
-		hdk::utils::get_as_type(collective_address__)?;
+		t("get_collective: ", get_as_type(store, &collective_address))?;
 	Ok(CollectivePayload {
 		collective_address,
 		collective,
@@ -272,20 +683,69 @@ pub fn get_collective(collective_address: Address) -> ZomeApiResult<CollectivePa
 
 /// Api to set the [Collective](struct.Collective.html) name.
 pub fn set_collective_name(
+	store: &dyn Store,
 	collective_address: Address,
 	name: String,
-) -> ZomeApiResult<CollectivePayload> {
-	let saved_collective = get_as_type_ref(&collective_address)?;
-	let collective = Collective {
+) -> ProtocolLoveResult<CollectivePayload> {
+	update_collective(store, collective_address, UpdateCollectiveParams {
+		name: Some(name),
+		..UpdateCollectiveParams::default()
+	})
+}
+
+/// Api to update the given fields of the [Collective](struct.Collective.html).
+///
+/// Records an [Action](struct.Action.html) with the `prev_data` for each changed field.
+pub fn update_collective(
+	store: &dyn Store,
+	collective_address: Address,
+	collective_params: UpdateCollectiveParams,
+) -> ProtocolLoveResult<CollectivePayload> {
+	let saved_collective: Collective =
+		t("update_collective: ", get_as_type(store, &collective_address))?;
+	let UpdateCollectiveParams {
 		name,
-		..saved_collective
+		description,
+		purpose,
+		tags,
+		links,
+		avatar,
+	} = collective_params;
+	let collective = Collective {
+		name: name.unwrap_or_else(|| saved_collective.name.clone()),
+		description: description.unwrap_or_else(|| saved_collective.description.clone()),
+		purpose: purpose.unwrap_or_else(|| saved_collective.purpose.clone()),
+		tags: tags.unwrap_or_else(|| saved_collective.tags.clone()),
+		links: links.unwrap_or_else(|| saved_collective.links.clone()),
+		avatar: match avatar {
+			Some(avatar) => if avatar.is_empty() { None } else { Some(avatar) },
+			None => saved_collective.avatar.clone(),
+		},
+		..saved_collective.clone()
 	};
-	update_collective(&collective_address, &collective)?;
-	create_set_collective_name_action(
+	if collective.name == saved_collective.name
+		&& !has_collective_metadata_changes(&collective, &saved_collective) {
+		return Ok(CollectivePayload {
+			collective_address,
+			collective,
+		});
+	}
+	t("update_collective: ", update_collective_entry(store, &collective_address, &collective))?;
+	if collective.name != saved_collective.name {
+		t("update_collective: ", create_set_collective_name_action(
+			store,
+			&collective_address,
+			&collective.name,
+			Some(&saved_collective.name),
+		))?;
+	}
+	t("update_collective: ", create_set_collective_metadata_actions(
+		store,
 		&collective_address,
-		&collective.name,
-		Some(&saved_collective.name),
-	)?;
+		&collective,
+		&saved_collective,
+		&[],
+	))?;
 	Ok(CollectivePayload {
 		collective_address,
 		collective,
@@ -293,22 +753,252 @@ pub fn set_collective_name(
 	})
 }
 
+/// Is the agent of the `store` the admin of the [Collective](struct.Collective.html)?
+pub fn is_collective_admin(store: &dyn Store, collective: &Collective) -> ProtocolLoveResult<bool> {
+	match &collective.admin_address {
+		Some(admin_address) => match get_agent_person(store, admin_address) {
+			Ok(_) => Ok(true),
+			Err(ProtocolLoveError::Unauthorized { .. }) => Ok(false),
+			Err(error) => Err(error),
+		},
+		None => Ok(false),
+	}
+}
+
+/// Is the admin of the [Collective](struct.Collective.html) among the agents who signed the entry
+/// or link being validated?
+pub fn is_collective_admin_source(
+	store: &dyn Store,
+	collective: &Collective,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<bool> {
+	match &collective.admin_address {
+		Some(admin_address) => {
+			let admin: Person = t("validation error: fetch admin: ", get_as_type(store, admin_address))?;
+			Ok(context.sources.contains(&admin.agent_address))
+		}
+		None => Ok(false),
+	}
+}
+
+/// Is the `collective` a version of the [Collective](struct.Collective.html) committed at
+/// `collective_address`?
+pub fn is_collective_version(
+	store: &dyn Store,
+	collective_address: &Address,
+	collective: &Collective,
+) -> ProtocolLoveResult<bool> {
+	let version_address = store.entry_address(&Entry::App("collective".into(), collective.into()))?;
+	for entry in store.get_entry_history(collective_address)? {
+		if store.entry_address(&entry)? == version_address {
+			return Ok(true);
+		}
+	}
+	Ok(false)
+}
+
+/// Gets the [Collective](struct.Collective.html) whose decision policies apply to the
+/// `collective`, i.e. the nearest ancestor which does not inherit its policies.
+pub fn get_policy_collective(store: &dyn Store, collective: &Collective) -> ProtocolLoveResult<Collective> {
+	let mut policy_collective = collective.clone();
+	while policy_collective.inherit_policies {
+		match policy_collective.parent_address.clone() {
+			Some(parent_address) =>
+				policy_collective = t("get_policy_collective: ", get_as_type(store, &parent_address))?,
+			None => break,
+		}
+	}
+	Ok(policy_collective)
+}
+
+/// Validates the [Collective](struct.Collective.html) at `collective_address` is not archived
+/// by its dissolution.
+pub fn validate_collective_not_archived(
+	store: &dyn Store,
+	collective_address: &Address,
+) -> ProtocolLoveResult<()> {
+	let collective: Collective = t("validation error: fetch collective: ",
+		get_as_type(store, collective_address))?;
+	if collective.archived_at.is_some() {
+		return Err(ProtocolLoveError::conflict("Collective is archived"));
+	}
+	Ok(())
+}
+
+/// Validation rules of a link from a [Collective](struct.Collective.html).
+///
+/// Links from an archived collective are rejected. The people, child collectives, ledgers, roles
+/// & templates of a collective are linked by its admin, or the admin of an ancestor while it
+/// creates a child collective, & must belong to the collective. People of a child collective
+/// must be people in the parent. The admin links federations joined & member collectives which
+/// joined the collective.
+pub fn validate_collective_link(
+	store: &dyn Store,
+	link: &LinkValidation,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	validate_collective_not_archived(store, &link.base)?;
+	let belongs_to_collective = match link.link_type.as_str() {
+		"collective->person" => {
+			let collective = validate_collective_link_admin(store, &link.base, context)?;
+			let _person: Person = t("validation error: collective->person: fetch person: ",
+				get_as_type(store, &link.target))?;
+			match &collective.parent_address {
+				Some(parent_address) => is_collective_person(store, parent_address, &link.target)?,
+				None => true,
+			}
+		}
+		"collective->child_collective" => {
+			validate_collective_link_admin(store, &link.base, context)?;
+			let child: Collective = t("validation error: collective->child_collective: fetch child: ",
+				get_as_type(store, &link.target))?;
+			child.parent_address.as_ref() == Some(&link.base)
+		}
+		"collective->ledger" => {
+			validate_collective_link_admin(store, &link.base, context)?;
+			let ledger: Ledger = t("validation error: collective->ledger: fetch ledger: ",
+				get_as_type(store, &link.target))?;
+			ledger.collective_address.as_ref() == Some(&link.base)
+		}
+		"collective->proposal_template" => {
+			validate_collective_link_admin(store, &link.base, context)?;
+			let template: ProposalTemplate = t(
+				"validation error: collective->proposal_template: fetch template: ",
+				get_as_type(store, &link.target),
+			)?;
+			template.collective_address == link.base
+		}
+		"collective->federation" => {
+			validate_collective_link_admin(store, &link.base, context)?;
+			let _federation: Collective = t("validation error: collective->federation: fetch federation: ",
+				get_as_type(store, &link.target))?;
+			link.target != link.base
+		}
+		"collective->member_collective" => {
+			validate_collective_link_admin(store, &link.base, context)?;
+			if !has_joined_federation(store, &link.target, &link.base)? {
+				return Err(ProtocolLoveError::unauthorized(
+					"Member collective has not joined the federation"));
+			}
+			link.target != link.base
+		}
+		"collective->role" => {
+			validate_collective_link_admin(store, &link.base, context)?;
+			let role: Role = t("validation error: collective->role: fetch role: ",
+				get_as_type(store, &link.target))?;
+			role.collective_address == link.base
+		}
+		"collective->charter" => {
+			validate_collective_link_admin(store, &link.base, context)?;
+			let charter: Charter = t("validation error: collective->charter: fetch charter: ",
+				get_as_type(store, &link.target))?;
+			if store.get_links(&link.base, "collective->charter", None)?
+				.iter()
+				.any(|charter_address| charter_address != &link.target) {
+				return Err(ProtocolLoveError::conflict("Collective already has a charter"));
+			}
+			charter.collective_address == link.base
+		}
+		"collective->delegation" => validate_collective_delegation_link(store, link, context)?,
+		_ => true,
+	};
+	if !belongs_to_collective {
+		return Err(ProtocolLoveError::validation_failed(
+			"target", &format!("Target of the {} link does not belong to the collective", link.link_type)));
+	}
+	Ok(())
+}
+
+/// Validates the link is signed by the admin of the collective at `collective_address` or of one
+/// of its ancestors.
+fn validate_collective_link_admin(
+	store: &dyn Store,
+	collective_address: &Address,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<Collective> {
+	let collective: Collective = t("validation error: fetch collective: ",
+		get_as_type(store, collective_address))?;
+	let mut ancestor = collective.clone();
+	while !is_collective_admin_source(store, &ancestor, context)? {
+		ancestor = match &ancestor.parent_address {
+			Some(parent_address) =>
+				t("validation error: fetch parent: ", get_as_type(store, parent_address))?,
+			None => return Err(ProtocolLoveError::unauthorized(
+				"Only the admin can link to the collective")),
+		};
+	}
+	Ok(collective)
+}
+
+/// Is the [Person](struct.Person.html) at `person_address` in the [Collective](struct.Collective.html)?
+pub fn is_collective_person(
+	store: &dyn Store,
+	collective_address: &Address,
+	person_address: &Address,
+) -> ProtocolLoveResult<bool> {
+	let person_addresses =
+		t("is_collective_person: ", store.get_links(
+			collective_address,
+			"collective->person",
+			None,
+		))?;
+	Ok(person_addresses.contains(person_address))
+}
+
+/// Api for the admin to add the [Person](struct.Person.html) at `person_address`
+/// to the [Collective](struct.Collective.html).
+///
+/// Adding a person already in the collective leaves it unchanged.
+pub fn add_collective_person(
+	store: &dyn Store,
+	collective_address: Address,
+	person_address: Address,
+) -> ProtocolLoveResult<CollectivePeoplePayload> {
+	let collective: Collective =
+		t("add_collective_person: ", get_as_type(store, &collective_address))?;
+	if !t("add_collective_person: ", is_collective_admin(store, &collective))? {
+		return Err(ProtocolLoveError::unauthorized(
+			"add_collective_person: Only the admin can add people to the collective"));
+	}
+	let _person: Person = t("add_collective_person: ", get_as_type(store, &person_address))?;
+	if let Some(parent_address) = &collective.parent_address {
+		if !t("add_collective_person: ", is_collective_person(store, parent_address, &person_address))? {
+			return Err(ProtocolLoveError::validation_failed(
+				"person_address", "add_collective_person: Person must be in the parent collective"));
+		}
+	}
+	if !t("add_collective_person: ", is_collective_person(
+		store,
+		&collective_address,
+		&person_address,
+	))? {
+		t("add_collective_person: ", link_collective_person(
+			store,
+			&collective_address,
+			&person_address,
+			CollectivePersonTag::Member,
+		))?;
+		t("add_collective_person: ", create_add_collective_person_action(
+			store,
+			&collective_address,
+			&person_address,
+		))?;
+	}
+	get_collective_people(store, collective_address)
+}
+
 /// Api to get the [People](struct.Person.html) in the [Collective](struct.Collective.html).
 pub fn get_collective_people(
-	collective_address: Address
-) -> ZomeApiResult<CollectivePeoplePayload> {
-	hdk::get_links_with_options(
-		&collective_address,
-		LinkMatch::Exactly("collective->person"),
-		LinkMatch::Any,
-		GetLinksOptions::default(),
-	)?;
+	store: &dyn Store,
+	collective_address: Address,
+) -> ProtocolLoveResult<CollectivePeoplePayload> {
 	let collective_people =
 		t("get_collective_people: get_links_and_load_type: ",
-			hdk::utils::get_links_and_load_type(
+			get_links_and_load_type(
+				store,
 				&collective_address,
-				LinkMatch::Exactly("collective->person"),
-				LinkMatch::Any,
+				"collective->person",
+				None,
 			),
 		)?;
 	Ok(CollectivePeoplePayload {
@@ -317,28 +1007,35 @@ pub fn get_collective_people(
 	})
 }
 
-fn update_collective(
+/// Updates the [Collective](struct.Collective.html) entry at `collective_address`.
+pub fn update_collective_entry(
+	store: &dyn Store,
 	collective_address: &Address,
 	collective: &Collective,
-) -> ZomeApiResult<Address> {
+) -> ProtocolLoveResult<Address> {
 	let collective_entry = Entry::App("collective".into(), collective.into());
-	hdk::update_entry(collective_entry, &collective_address)
+	t("update_collective_entry: ", store.update_entry(collective_entry, &collective_address))
 }
 
 struct CommitCollectiveResponse(Address, Entry, Collective);
 
-fn commit_collective(collective: Collective) -> ZomeApiResult<CommitCollectiveResponse> {
+fn commit_collective(
+	store: &dyn Store,
+	collective: Collective,
+) -> ProtocolLoveResult<CommitCollectiveResponse> {
 	let collective_entry = Entry::App("collective".into(), collective.borrow().into());
 	let collective_address =
-		t("commit_collective: ", hdk::commit_entry(&collective_entry))?;
+		t("commit_collective: ", commit_entry_if_missing(store, &collective_entry))?;
 	Ok(CommitCollectiveResponse(collective_address, collective_entry, collective))
 }
 
 fn create_create_collective_action(
+	store: &dyn Store,
 	collective_address: &Address,
 	collective: &Collective,
-) -> ZomeApiResult<ActionEntry> {
+) -> ProtocolLoveResult<ActionEntry> {
 	create_collective_action(
+		store,
 		collective_address,
 		ActionOp::CreateCollective,
 		collective.into(),
@@ -348,19 +1045,22 @@ fn create_create_collective_action(
 	)
 }
 
-fn add_collective_person(
+fn link_collective_person(
+	store: &dyn Store,
 	collective_address: &Address,
 	person_address: &Address,
-) -> ZomeApiResult<Address> {
-	let collective_person_address =
-		t("add_collective_person: ", hdk::link_entries(
-			collective_address,
-			person_address,
-			"collective->person",
-			&CollectivePersonTag::Creator.to_string(),
-		))?;
-	create_add_collective_person_action(collective_address, person_address)?;
-	Ok(collective_person_address)
+	tag: CollectivePersonTag,
+) -> ProtocolLoveResult<Address> {
+	t("link_collective_person: ", store.link_entries(
+		collective_address,
+		person_address,
+		"collective->person",
+		&tag.to_string(),
+	))
+}
+
+fn has_action_op(actions: &[Action], op: &ActionOp) -> bool {
+	actions.iter().any(|action| &action.op == op)
 }
 
 #[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
@@ -369,10 +1069,12 @@ struct AddCollectivePersonActionData {
 }
 
 fn create_add_collective_person_action(
+	store: &dyn Store,
 	collective_address: &Address,
 	person_address: &Address,
-) -> ZomeApiResult<ActionEntry> {
-	create_collective_action(
+) -> ProtocolLoveResult<ActionEntry> {
+	let action_entry = create_collective_action(
+		store,
 		collective_address,
 		ActionOp::AddCollectivePerson,
 		AddCollectivePersonActionData {
@@ -381,7 +1083,12 @@ fn create_add_collective_person_action(
 		serde_json::value::Value::Null.into(),
 		&"add_collective_person".into(),
 		ActionStrategy::SystemAutomatic,
-	)
+	)?;
+	emit_signal(store, ProtocolLoveSignal::PersonJoined {
+		collective_address: collective_address.clone(),
+		person_address: person_address.clone(),
+	})?;
+	Ok(action_entry)
 }
 
 #[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
@@ -390,11 +1097,13 @@ struct SetCollectiveNameActionData {
 }
 
 fn create_set_collective_name_action(
+	store: &dyn Store,
 	collective_address: &Address,
 	name: &String,
 	prev_name_opt: Option<&String>,
-) -> ZomeApiResult<ActionEntry> {
+) -> ProtocolLoveResult<ActionEntry> {
 	create_collective_action(
+		store,
 		collective_address,
 		ActionOp::SetCollectiveName,
 		SetCollectiveNameActionData {
@@ -412,18 +1121,90 @@ fn create_set_collective_name_action(
 }
 
 #[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
-struct SetTotalSharesActionData {
-	total_shares: i64,
+struct SetCollectiveDescriptionActionData {
+	description: String
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct SetCollectivePurposeActionData {
+	purpose: String
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct SetCollectiveTagsActionData {
+	tags: Vec<String>
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct SetCollectiveLinksActionData {
+	links: Vec<String>
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct SetCollectiveAvatarActionData {
+	avatar: Option<String>
+}
+
+/// The set action op, tag & data of each metadata field of the `collective`.
+fn collective_metadata_action_data(collective: &Collective) -> Vec<(ActionOp, &'static str, JsonString)> {
+	vec![
+		(ActionOp::SetCollectiveDescription, "set_collective_description",
+			SetCollectiveDescriptionActionData { description: collective.description.clone() }.into()),
+		(ActionOp::SetCollectivePurpose, "set_collective_purpose",
+			SetCollectivePurposeActionData { purpose: collective.purpose.clone() }.into()),
+		(ActionOp::SetCollectiveTags, "set_collective_tags",
+			SetCollectiveTagsActionData { tags: collective.tags.clone() }.into()),
+		(ActionOp::SetCollectiveLinks, "set_collective_links",
+			SetCollectiveLinksActionData { links: collective.links.clone() }.into()),
+		(ActionOp::SetCollectiveAvatar, "set_collective_avatar",
+			SetCollectiveAvatarActionData { avatar: collective.avatar.clone() }.into()),
+	]
+}
+
+fn has_collective_metadata_changes(collective: &Collective, prev_collective: &Collective) -> bool {
+	collective_metadata_action_data(collective) != collective_metadata_action_data(prev_collective)
+}
+
+/// Creates a set action for each metadata field that differs from `prev_collective`,
+/// skipping the ops already in the `recorded_actions`.
+///
+/// The `prev_data` holds the value of the field in `prev_collective`.
+fn create_set_collective_metadata_actions(
+	store: &dyn Store,
+	collective_address: &Address,
+	collective: &Collective,
+	prev_collective: &Collective,
+	recorded_actions: &[Action],
+) -> ProtocolLoveResult<Vec<ActionEntry>> {
+	let mut action_entries = Vec::new();
+	let field_action_data = collective_metadata_action_data(collective).into_iter()
+		.zip(collective_metadata_action_data(prev_collective));
+	for ((op, tag, data), (_, _, prev_data)) in field_action_data {
+		if data == prev_data || has_action_op(recorded_actions, &op) {
+			continue;
+		}
+		action_entries.push(create_collective_action(
+			store,
+			collective_address,
+			op,
+			data,
+			prev_data,
+			&tag.into(),
+			ActionStrategy::SystemAutomatic,
+		)?);
+	}
+	Ok(action_entries)
 }
 
 fn create_collective_action(
+	store: &dyn Store,
 	collective_address: &Address,
 	op: ActionOp,
 	data: JsonString,
 	prev_data: JsonString,
 	tag: &String,
 	strategy: ActionStrategy,
-) -> ZomeApiResult<ActionEntry> {
+) -> ProtocolLoveResult<ActionEntry> {
 	let collective_action = Action {
 		op,
 		status: ActionStatus::Executed,
@@ -437,13 +1218,184 @@ fn create_collective_action(
 		collective_action.borrow().into());
 	let action_address =
 		t("create_collective_action: commit_entry: ",
-			hdk::commit_entry(&action_entry))?;
+			store.commit_entry(&action_entry))?;
 	t("create_collective_action: collective->action: ",
-		hdk::link_entries(
+		store.link_entries(
 			&collective_address,
 			&action_address,
 			"collective->action",
 			tag,
 		))?;
+	t("create_collective_action: ", emit_signal(store, ProtocolLoveSignal::ActionCommitted {
+		collective_address: collective_address.clone(),
+		action_address: action_address.clone(),
+		action: collective_action.clone(),
+	}))?;
 	Ok((action_address, action_entry, collective_action))
 }
+
+#[cfg(test)]
+mod tests {
+	use hdk::holochain_json_api::json::JsonString;
+	use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+	use crate::action::{ActionOp, get_actions};
+	use crate::memory_store::MemoryStore;
+	use crate::person::{PersonParams, create_person};
+	use crate::store::Store;
+	use super::{
+		Collective, CreateCollectiveParams, create_collective, ensure_collective_initialised,
+		get_collective, get_collective_people, set_collective_name, commit_collective,
+		SetCollectiveNameActionData, AddCollectivePersonActionData, SetCollectiveDescriptionActionData,
+		UpdateCollectiveParams, update_collective, update_collective_entry,
+	};
+
+	fn create_collective_params(admin_address: Option<Address>) -> CreateCollectiveParams {
+		CreateCollectiveParams {
+			name: "Flower of Life Collective".into(),
+			admin_address,
+			description: None,
+			purpose: None,
+			tags: None,
+			links: None,
+			avatar: None,
+		}
+	}
+
+	#[test]
+	fn create_collective_records_actions() {
+		let store = MemoryStore::new(Address::from("alice"));
+		let admin = create_person(&store, PersonParams {
+			name: "Jane".into(),
+			..PersonParams::for_agent(Address::from("alice"))
+		}).unwrap();
+		let created =
+			create_collective(&store, create_collective_params(Some(admin.person_address.clone())))
+				.unwrap();
+		assert_eq!(created.collective.admin_address, Some(admin.person_address.clone()));
+		let fetched = get_collective(&store, created.collective_address.clone()).unwrap();
+		assert_eq!(fetched.collective.name, "Flower of Life Collective");
+		let people = get_collective_people(&store, created.collective_address.clone()).unwrap();
+		assert_eq!(people.collective_people.len(), 1);
+		assert_eq!(people.collective_people[0].name, "Jane");
+		let actions = get_actions(&store, created.collective_address.clone()).unwrap().actions;
+		assert_eq!(
+			actions.iter().map(|action| action.op.clone()).collect::<Vec<_>>(),
+			vec![ActionOp::CreateCollective, ActionOp::SetCollectiveName, ActionOp::AddCollectivePerson]);
+		assert_eq!(actions[0].data, JsonString::from(&created.collective));
+		assert_eq!(actions[1].data, JsonString::from(SetCollectiveNameActionData {
+			name: "Flower of Life Collective".into(),
+		}));
+		assert_eq!(actions[2].data, JsonString::from(AddCollectivePersonActionData {
+			person_address: admin.person_address,
+		}));
+	}
+
+	#[test]
+	fn create_collective_without_admin_creates_a_person_for_the_agent() {
+		let store = MemoryStore::new(Address::from("alice"));
+		let created = create_collective(&store, create_collective_params(None)).unwrap();
+		let people = get_collective_people(&store, created.collective_address).unwrap();
+		assert_eq!(people.collective_people.len(), 1);
+		assert_eq!(people.collective_people[0].agent_address, Address::from("alice"));
+	}
+
+	#[test]
+	fn set_collective_name_updates_the_collective_at_the_same_address() {
+		let store = MemoryStore::new(Address::from("alice"));
+		let created = create_collective(&store, create_collective_params(None)).unwrap();
+		let renamed = set_collective_name(
+			&store,
+			created.collective_address.clone(),
+			"Renamed Collective".into(),
+		).unwrap();
+		assert_eq!(renamed.collective_address, created.collective_address);
+		let fetched = get_collective(&store, created.collective_address.clone()).unwrap();
+		assert_eq!(fetched.collective.name, "Renamed Collective");
+		let actions = get_actions(&store, created.collective_address).unwrap().actions;
+		assert_eq!(actions.len(), 4);
+		let rename_action = &actions[3];
+		assert_eq!(rename_action.op, ActionOp::SetCollectiveName);
+		assert_eq!(rename_action.data, JsonString::from(SetCollectiveNameActionData {
+			name: "Renamed Collective".into(),
+		}));
+		assert_eq!(rename_action.prev_data, JsonString::from(SetCollectiveNameActionData {
+			name: "Flower of Life Collective".into(),
+		}));
+	}
+
+	#[test]
+	fn update_collective_records_the_previous_metadata() {
+		let store = MemoryStore::new(Address::from("alice"));
+		let created = create_collective(&store, create_collective_params(None)).unwrap();
+		for description in &["Sacred geometry", "Seed of life"] {
+			update_collective(&store, created.collective_address.clone(), UpdateCollectiveParams {
+				description: Some(description.to_string()),
+				..UpdateCollectiveParams::default()
+			}).unwrap();
+		}
+		let actions = get_actions(&store, created.collective_address).unwrap().actions;
+		assert_eq!(actions.len(), 5);
+		assert_eq!(actions[3].op, ActionOp::SetCollectiveDescription);
+		assert_eq!(actions[3].prev_data, JsonString::from(SetCollectiveDescriptionActionData {
+			description: "".into(),
+		}));
+		assert_eq!(actions[4].prev_data, JsonString::from(SetCollectiveDescriptionActionData {
+			description: "Sacred geometry".into(),
+		}));
+	}
+
+	#[test]
+	fn create_collective_is_safe_to_retry() {
+		let store = MemoryStore::new(Address::from("alice"));
+		let created = create_collective(&store, create_collective_params(None)).unwrap();
+		let retried = create_collective(&store, create_collective_params(None)).unwrap();
+		assert_eq!(retried.collective_address, created.collective_address);
+		assert_eq!(get_actions(&store, created.collective_address.clone()).unwrap().actions.len(), 3);
+		assert_eq!(get_collective_people(&store, created.collective_address).unwrap().collective_people.len(), 1);
+	}
+
+	#[test]
+	fn ensure_collective_initialised_completes_a_partial_collective() {
+		let store = MemoryStore::new(Address::from("alice"));
+		let admin = create_person(&store, PersonParams::for_agent(Address::from("alice"))).unwrap();
+		let super::CommitCollectiveResponse(collective_address, _, collective) =
+			commit_collective(&store, Collective {
+				admin_address: Some(admin.person_address.clone()),
+				description: "Sacred geometry".into(),
+				..Collective::default()
+			}).unwrap();
+		update_collective_entry(&store, &collective_address, &Collective {
+			name: "Renamed Collective".into(),
+			..collective.clone()
+		}).unwrap();
+		assert!(get_actions(&store, collective_address.clone()).unwrap().actions.is_empty());
+		let error = ensure_collective_initialised(
+			&store.for_agent(Address::from("bob")),
+			collective_address.clone(),
+		).unwrap_err();
+		assert_eq!(error.code(), "UNAUTHORIZED");
+		ensure_collective_initialised(&store, collective_address.clone()).unwrap();
+		let actions = get_actions(&store, collective_address.clone()).unwrap().actions;
+		assert_eq!(actions[0].data, JsonString::from(&collective));
+		assert_eq!(
+			actions.iter().map(|action| action.op.clone()).collect::<Vec<_>>(),
+			vec![
+				ActionOp::CreateCollective,
+				ActionOp::SetCollectiveName,
+				ActionOp::SetCollectiveDescription,
+				ActionOp::AddCollectivePerson,
+			]);
+		assert_eq!(store.get_links(&collective_address, "collective->ledger", None).unwrap().len(), 1);
+		ensure_collective_initialised(&store, collective_address.clone()).unwrap();
+		assert_eq!(get_actions(&store, collective_address.clone()).unwrap().actions.len(), 4);
+		assert_eq!(get_collective_people(&store, collective_address).unwrap().collective_people.len(), 1);
+	}
+
+	#[test]
+	fn get_collective_returns_not_found_for_a_missing_address() {
+		let store = MemoryStore::new(Address::from("alice"));
+		let error = get_collective(&store, Address::from("missing")).unwrap_err();
+		assert_eq!(error.code(), "NOT_FOUND");
+		assert!(error.is_transient());
+	}
+}
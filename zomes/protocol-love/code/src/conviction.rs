@@ -0,0 +1,477 @@
+use std::collections::HashMap;
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use hdk::holochain_core_types::dna::entry_types::Sharing;
+use hdk::prelude::ValidatingEntryType;
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::action::{ActionOp, create_root_action};
+use crate::collective::is_collective_person;
+use crate::critical_action::validate_proposal_action_executed;
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::ledger::{Ledger, get_collective_ledger, set_ledger_funds};
+use crate::person::{Person, get_agent_person};
+use crate::proposal::{Proposal, ProposalStatus, TallyMethod, update_proposal_entry};
+use crate::store::{Store, HdkStore, get_as_type, get_links_and_load_type};
+use crate::tally::ProposalOutcome;
+use crate::utils::t;
+use crate::validation::{
+	EntryValidation, LinkValidation, ValidationContext, from_hdk_validation_data, validate_timestamp,
+};
+
+/// Duration in seconds of a step of conviction accrual.
+pub const CONVICTION_STEP_SECS: u64 = 3600;
+/// Share of the conviction kept at each step, in millionths. Conviction halves in about 69 steps.
+pub const CONVICTION_DECAY_PER_MILLION: u64 = 990_000;
+/// Stake each person can spread across the conviction proposals in voting.
+pub const CONVICTION_STAKE_PER_PERSON: u64 = 100;
+/// Maximum share of the ledger funds a conviction proposal can request.
+pub const CONVICTION_MAX_RATIO: f64 = 0.2;
+/// Weight of the total stake in the conviction threshold.
+pub const CONVICTION_WEIGHT: f64 = 0.0025;
+
+/// Stake of a [Person](struct.Person.html) supporting a conviction [Proposal](struct.Proposal.html)
+/// from the `timestamp`.
+///
+/// The latest stake of a person on a proposal replaces the former. An `amount` of 0 withdraws
+/// the support.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ConvictionStake {
+	pub proposal_address: Address,
+	/// Address of the staking [Person](struct.Person.html).
+	pub person_address: Address,
+	pub amount: u64,
+	/// Time of the stake in seconds since the unix epoch.
+	pub timestamp: u64,
+}
+
+/// Api payload containing the `conviction_stake_address` & [conviction_stake](struct.ConvictionStake.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ConvictionStakePayload {
+	pub conviction_stake_address: Address,
+	pub conviction_stake: ConvictionStake,
+}
+
+/// Api payload of the conviction of a [Proposal](struct.Proposal.html) at the `timestamp`.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ConvictionPayload {
+	pub proposal_address: Address,
+	pub timestamp: u64,
+	/// Total stake supporting the proposal.
+	pub staked: u64,
+	pub conviction: u64,
+	/// Conviction required to fund the proposal. `None` when the ledger cannot fund the proposal.
+	pub threshold: Option<u64>,
+}
+
+/// Returns a Holochain entry definition for a conviction stake.
+pub fn conviction_stake_def() -> ValidatingEntryType {
+	entry!(
+		name: "conviction_stake",
+		description: "A protocol.love stake supporting a conviction proposal",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<ConvictionStake>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_conviction_stake_entry(&HdkStore, validation, &context)?)
+		}
+	)
+}
+
+/// Validation rules of a [ConvictionStake](struct.ConvictionStake.html) entry.
+pub fn validate_conviction_stake_entry(
+	store: &dyn Store,
+	validation: EntryValidation<ConvictionStake>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			validate_timestamp("timestamp", entry.timestamp, context)?;
+			let person: Person = t("validation error: conviction_stake: fetch person: ",
+				get_as_type(store, &entry.person_address))?;
+			if !context.sources.contains(&person.agent_address) {
+				return Err(ProtocolLoveError::unauthorized(
+					"Stake must be made by the agent of the person"));
+			}
+			let proposal: Proposal = t("validation error: conviction_stake: fetch proposal: ",
+				get_as_type(store, &entry.proposal_address))?;
+			if !is_collective_person(store, &proposal.collective_address, &entry.person_address)? {
+				return Err(ProtocolLoveError::unauthorized("Only people in the collective can stake"));
+			}
+			if proposal.tally_method != TallyMethod::Conviction {
+				return Err(ProtocolLoveError::conflict("Proposal does not use conviction voting"));
+			}
+			if proposal.status != ProposalStatus::Voting {
+				return Err(ProtocolLoveError::conflict("Proposal is not in voting"));
+			}
+			let staked = get_person_stake(
+				store,
+				&proposal.collective_address,
+				&entry.person_address,
+				&entry.proposal_address,
+			)?;
+			if staked.saturating_add(entry.amount) > CONVICTION_STAKE_PER_PERSON {
+				return Err(ProtocolLoveError::validation_failed("amount", "Not enough stake"));
+			}
+			Ok(())
+		}
+		EntryValidation::Modify { .. } =>
+			Err(ProtocolLoveError::unauthorized("Conviction stake cannot be modified")),
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Conviction stake cannot be deleted")),
+	}
+}
+
+/// Validation rules of a `proposal->conviction_stake` link: the stake is on the base proposal &
+/// is linked by the agent of the person staking.
+pub fn validate_proposal_conviction_stake_link(
+	store: &dyn Store,
+	link: &LinkValidation,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let stake: ConvictionStake = t("validation error: proposal->conviction_stake: fetch stake: ",
+		get_as_type(store, &link.target))?;
+	if stake.proposal_address != link.base {
+		return Err(ProtocolLoveError::validation_failed("target", "Stake is not on the proposal"));
+	}
+	let person: Person = t("validation error: proposal->conviction_stake: fetch person: ",
+		get_as_type(store, &stake.person_address))?;
+	if !context.sources.contains(&person.agent_address) {
+		return Err(ProtocolLoveError::unauthorized(
+			"Stake must be linked by the agent of the person"));
+	}
+	Ok(())
+}
+
+/// Validates the conviction of the proposal at `proposal_address` reached the threshold at the
+/// `funded_at` time of the commit.
+pub fn validate_conviction_funding(
+	store: &dyn Store,
+	proposal_address: &Address,
+	proposal: &Proposal,
+	funded_at: u64,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	validate_timestamp("funded_at", funded_at, context)?;
+	if proposal.tally_method != TallyMethod::Conviction {
+		return Err(ProtocolLoveError::conflict("Proposal does not use conviction voting"));
+	}
+	let ConvictionPayload { conviction, threshold, .. } =
+		compute_proposal_conviction(store, proposal_address, proposal, funded_at)?;
+	match threshold {
+		Some(threshold) if conviction >= threshold => Ok(()),
+		Some(_) => Err(ProtocolLoveError::conflict("Conviction is below the threshold")),
+		None => Err(ProtocolLoveError::conflict("Ledger cannot fund the proposal")),
+	}
+}
+
+/// Api for a [Person](struct.Person.html) to stake support on a conviction
+/// [Proposal](struct.Proposal.html) from the `timestamp`, replacing their former stake.
+pub fn stake_conviction(
+	store: &dyn Store,
+	conviction_stake: ConvictionStake,
+) -> ProtocolLoveResult<ConvictionStakePayload> {
+	let proposal: Proposal =
+		t("stake_conviction: ", get_as_type(store, &conviction_stake.proposal_address))?;
+	t("stake_conviction: ", get_agent_person(store, &conviction_stake.person_address))?;
+	let conviction_stake_entry =
+		Entry::App("conviction_stake".into(), (&conviction_stake).into());
+	let conviction_stake_address =
+		t("stake_conviction: ", store.commit_entry(&conviction_stake_entry))?;
+	t("stake_conviction: proposal->conviction_stake: ", store.link_entries(
+		&conviction_stake.proposal_address,
+		&conviction_stake_address,
+		"proposal->conviction_stake",
+		"",
+	))?;
+	t("stake_conviction: ", create_root_action(
+		store,
+		&proposal.collective_address,
+		ActionOp::StakeConviction,
+		(&conviction_stake).into(),
+		serde_json::value::Value::Null.into(),
+		"stake_conviction",
+	))?;
+	Ok(ConvictionStakePayload {
+		conviction_stake_address,
+		conviction_stake,
+	})
+}
+
+/// Api to get the conviction of a [Proposal](struct.Proposal.html) at the `timestamp`.
+pub fn get_proposal_conviction(
+	store: &dyn Store,
+	proposal_address: Address,
+	timestamp: u64,
+) -> ProtocolLoveResult<ConvictionPayload> {
+	let proposal: Proposal =
+		t("get_proposal_conviction: ", get_as_type(store, &proposal_address))?;
+	t("get_proposal_conviction: ", compute_proposal_conviction(
+		store,
+		&proposal_address,
+		&proposal,
+		timestamp,
+	))
+}
+
+/// Api to fund a conviction [Proposal](struct.Proposal.html) whose conviction reached the
+/// threshold at the `timestamp`. The requested funds are debited from the
+/// [Ledger](struct.Ledger.html) & the proposal is closed.
+///
+/// When [ActionOp::FundProposal](enum.ActionOp.html) is critical, the proposal is first enacted
+/// with [enact_proposal](fn.enact_proposal.html) & funded once its critical action is executed.
+pub fn fund_conviction_proposal(
+	store: &dyn Store,
+	proposal_address: Address,
+	timestamp: u64,
+) -> ProtocolLoveResult<ConvictionPayload> {
+	let proposal: Proposal =
+		t("fund_conviction_proposal: ", get_as_type(store, &proposal_address))?;
+	if proposal.tally_method != TallyMethod::Conviction {
+		return Err(ProtocolLoveError::conflict(
+			"fund_conviction_proposal: Proposal does not use conviction voting"));
+	}
+	if proposal.status != ProposalStatus::Voting {
+		return Err(ProtocolLoveError::conflict("fund_conviction_proposal: Proposal is not in voting"));
+	}
+	let conviction_payload = t("fund_conviction_proposal: ", compute_proposal_conviction(
+		store,
+		&proposal_address,
+		&proposal,
+		timestamp,
+	))?;
+	match conviction_payload.threshold {
+		Some(threshold) if conviction_payload.conviction >= threshold => {}
+		Some(_) => return Err(ProtocolLoveError::conflict(
+			"fund_conviction_proposal: Conviction is below the threshold")),
+		None => return Err(ProtocolLoveError::conflict(
+			"fund_conviction_proposal: Ledger cannot fund the proposal")),
+	}
+	t("fund_conviction_proposal: ", validate_proposal_action_executed(store, &proposal, &proposal_address))?;
+	let ledger_address =
+		t("fund_conviction_proposal: ", get_collective_ledger(store, &proposal.collective_address))?;
+	let ledger: Ledger = t("fund_conviction_proposal: ", get_as_type(store, &ledger_address))?;
+	let funds = ledger.funds - proposal.requested_funds;
+	t("fund_conviction_proposal: ", update_proposal_entry(store, &proposal_address, &Proposal {
+		status: ProposalStatus::Closed,
+		funded_at: Some(timestamp),
+		outcome: Some(ProposalOutcome::Passed),
+		..proposal.clone()
+	}))?;
+	t("fund_conviction_proposal: ", set_ledger_funds(
+		store,
+		&proposal.collective_address,
+		&ledger_address,
+		ledger,
+		funds,
+		Some(proposal_address),
+		ActionOp::FundProposal,
+	))?;
+	Ok(conviction_payload)
+}
+
+/// Has the conviction of the proposal reached the threshold at the `timestamp`?
+pub fn has_reached_conviction_threshold(
+	store: &dyn Store,
+	proposal_address: &Address,
+	proposal: &Proposal,
+	timestamp: u64,
+) -> ProtocolLoveResult<bool> {
+	let conviction_payload = compute_proposal_conviction(store, proposal_address, proposal, timestamp)?;
+	Ok(conviction_payload.threshold.map_or(false, |threshold| conviction_payload.conviction >= threshold))
+}
+
+/// Computes the conviction of the proposal from its stakes & the threshold from the ledger funds.
+fn compute_proposal_conviction(
+	store: &dyn Store,
+	proposal_address: &Address,
+	proposal: &Proposal,
+	timestamp: u64,
+) -> ProtocolLoveResult<ConvictionPayload> {
+	let stakes: Vec<ConvictionStake> =
+		t("compute_proposal_conviction: ", get_links_and_load_type(
+			store,
+			proposal_address,
+			"proposal->conviction_stake",
+			None,
+		))?;
+	let stakes: Vec<ConvictionStake> = stakes.into_iter()
+		.filter(|stake| &stake.proposal_address == proposal_address)
+		.collect();
+	let ledger_address =
+		t("compute_proposal_conviction: ", get_collective_ledger(store, &proposal.collective_address))?;
+	let ledger: Ledger = t("compute_proposal_conviction: ", get_as_type(store, &ledger_address))?;
+	let person_count =
+		t("compute_proposal_conviction: ", store.get_links(
+			&proposal.collective_address,
+			"collective->person",
+			None,
+		))?.len() as u64;
+	Ok(ConvictionPayload {
+		proposal_address: proposal_address.clone(),
+		timestamp,
+		staked: latest_stakes(&stakes, timestamp).values().sum(),
+		conviction: compute_conviction(&stakes, timestamp),
+		threshold: conviction_threshold(
+			proposal.requested_funds,
+			ledger.funds,
+			person_count * CONVICTION_STAKE_PER_PERSON,
+		),
+	})
+}
+
+/// Computes the conviction at the `timestamp` from the `stakes`.
+///
+/// At each step of [CONVICTION_STEP_SECS](constant.CONVICTION_STEP_SECS.html),
+/// the conviction decays by [CONVICTION_DECAY_PER_MILLION](constant.CONVICTION_DECAY_PER_MILLION.html)
+/// & grows by the total stake. Integer arithmetic keeps the computation deterministic.
+pub fn compute_conviction(stakes: &[ConvictionStake], timestamp: u64) -> u64 {
+	let mut stakes: Vec<&ConvictionStake> = stakes.iter()
+		.filter(|stake| stake.timestamp <= timestamp)
+		.collect();
+	stakes.sort_by(|a, b| (a.timestamp, &a.person_address, a.amount)
+		.cmp(&(b.timestamp, &b.person_address, b.amount)));
+	let mut amounts: HashMap<&Address, u64> = HashMap::new();
+	let mut conviction = 0;
+	let mut step = match stakes.first() {
+		Some(stake) => stake.timestamp / CONVICTION_STEP_SECS,
+		None => return 0,
+	};
+	for stake in stakes {
+		let stake_step = stake.timestamp / CONVICTION_STEP_SECS;
+		conviction = accrue_conviction(conviction, amounts.values().sum(), stake_step - step);
+		step = stake_step;
+		amounts.insert(&stake.person_address, stake.amount);
+	}
+	accrue_conviction(conviction, amounts.values().sum(), timestamp / CONVICTION_STEP_SECS - step)
+}
+
+/// The conviction required to fund `requested_funds` from the ledger `funds`,
+/// given the `total_stake` of the collective.
+///
+/// Grows as the requested share of the funds approaches
+/// [CONVICTION_MAX_RATIO](constant.CONVICTION_MAX_RATIO.html). `None` beyond it.
+pub fn conviction_threshold(requested_funds: u64, funds: u64, total_stake: u64) -> Option<u64> {
+	if funds == 0 {
+		return None;
+	}
+	let ratio = requested_funds as f64 / funds as f64;
+	if ratio >= CONVICTION_MAX_RATIO {
+		return None;
+	}
+	let decay = CONVICTION_DECAY_PER_MILLION as f64 / 1_000_000.0;
+	let distance = CONVICTION_MAX_RATIO - ratio;
+	Some((CONVICTION_WEIGHT * total_stake as f64 / (1.0 - decay) / (distance * distance)).ceil() as u64)
+}
+
+fn accrue_conviction(conviction: u64, staked: u64, steps: u64) -> u64 {
+	let mut conviction = conviction;
+	for _ in 0..steps {
+		let next = conviction * CONVICTION_DECAY_PER_MILLION / 1_000_000 + staked;
+		if next == conviction {
+			break;
+		}
+		conviction = next;
+	}
+	conviction
+}
+
+/// The latest stake of each person at the `timestamp`.
+fn latest_stakes(stakes: &[ConvictionStake], timestamp: u64) -> HashMap<&Address, u64> {
+	let mut latest: HashMap<&Address, &ConvictionStake> = HashMap::new();
+	for stake in stakes.iter().filter(|stake| stake.timestamp <= timestamp) {
+		let is_later = match latest.get(&stake.person_address) {
+			Some(latest_stake) => stake.timestamp > latest_stake.timestamp,
+			None => true,
+		};
+		if is_later {
+			latest.insert(&stake.person_address, stake);
+		}
+	}
+	latest.into_iter().map(|(person_address, stake)| (person_address, stake.amount)).collect()
+}
+
+/// Sums the latest stake of the person on the other conviction proposals in voting.
+fn get_person_stake(
+	store: &dyn Store,
+	collective_address: &Address,
+	person_address: &Address,
+	excluded_proposal_address: &Address,
+) -> ProtocolLoveResult<u64> {
+	let proposal_addresses =
+		t("get_person_stake: ", store.get_links(collective_address, "collective->proposal", None))?;
+	let mut staked = 0;
+	for proposal_address in proposal_addresses.iter()
+		.filter(|proposal_address| *proposal_address != excluded_proposal_address) {
+		let proposal: Proposal = t("get_person_stake: ", get_as_type(store, proposal_address))?;
+		if proposal.tally_method != TallyMethod::Conviction
+			|| proposal.status != ProposalStatus::Voting {
+			continue;
+		}
+		let stakes: Vec<ConvictionStake> =
+			t("get_person_stake: ", get_links_and_load_type(
+				store,
+				proposal_address,
+				"proposal->conviction_stake",
+				None,
+			))?;
+		staked += latest_stakes(&stakes, u64::max_value())
+			.get(person_address)
+			.cloned()
+			.unwrap_or_default();
+	}
+	Ok(staked)
+}
+
+#[cfg(test)]
+mod tests {
+	use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+	use super::{CONVICTION_STEP_SECS, ConvictionStake, compute_conviction, conviction_threshold};
+
+	fn stake(person: &str, amount: u64, step: u64) -> ConvictionStake {
+		ConvictionStake {
+			proposal_address: Address::from("proposal"),
+			person_address: Address::from(person),
+			amount,
+			timestamp: step * CONVICTION_STEP_SECS,
+		}
+	}
+
+	#[test]
+	fn conviction_accrues_with_decay() {
+		let stakes = vec![stake("alice", 100, 0), stake("bob", 100, 0)];
+		assert_eq!(compute_conviction(&stakes, 0), 0);
+		assert_eq!(compute_conviction(&stakes, CONVICTION_STEP_SECS), 200);
+		assert_eq!(compute_conviction(&stakes, 2 * CONVICTION_STEP_SECS), 398);
+		assert_eq!(compute_conviction(&stakes, 29 * CONVICTION_STEP_SECS), 5047);
+	}
+
+	#[test]
+	fn conviction_decays_after_the_stake_is_withdrawn() {
+		let stakes = vec![stake("alice", 100, 0), stake("alice", 0, 2)];
+		assert_eq!(compute_conviction(&stakes, 2 * CONVICTION_STEP_SECS), 199);
+		assert_eq!(compute_conviction(&stakes, 3 * CONVICTION_STEP_SECS), 197);
+	}
+
+	#[test]
+	fn conviction_does_not_depend_on_the_order_of_the_stakes() {
+		let stakes = vec![stake("bob", 50, 3), stake("alice", 100, 0), stake("alice", 20, 5)];
+		let mut reversed = stakes.clone();
+		reversed.reverse();
+		assert_eq!(
+			compute_conviction(&stakes, 10 * CONVICTION_STEP_SECS),
+			compute_conviction(&reversed, 10 * CONVICTION_STEP_SECS));
+	}
+
+	#[test]
+	fn threshold_grows_with_the_requested_share_of_the_funds() {
+		assert_eq!(conviction_threshold(100, 1000, 200), Some(5000));
+		assert!(conviction_threshold(150, 1000, 200) > Some(5000));
+		assert_eq!(conviction_threshold(200, 1000, 200), None);
+		assert_eq!(conviction_threshold(100, 0, 200), None);
+	}
+}
@@ -0,0 +1,795 @@
+use std::convert::TryFrom;
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use hdk::holochain_core_types::dna::entry_types::Sharing;
+use hdk::prelude::ValidatingEntryType;
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use serde_json::Value;
+use crate::action::{
+	Action, ActionOp, ActionStatus, ActionStrategy, RootAction, create_root_action,
+};
+use crate::collective::{
+	Collective, CollectivePayload, get_policy_collective, is_collective_admin, is_collective_version,
+	update_collective_entry,
+};
+use crate::conviction::has_reached_conviction_threshold;
+use crate::election::{Role, get_role};
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::person::{Person, get_agent_person};
+use crate::proposal::{Proposal, ProposalStatus, TallyMethod};
+use crate::store::{Store, HdkStore, get_as_type, get_initial_as_type, get_links_and_load_type};
+use crate::tally::ProposalOutcome;
+use crate::utils::t;
+use crate::validation::{
+	EntryValidation, LinkValidation, ValidationContext, from_hdk_validation_data, validate_timestamp,
+};
+
+/// An [Action](struct.Action.html) enacted from a passed [Proposal](struct.Proposal.html) whose
+/// op is critical in the decision policy of the [Collective](struct.Collective.html), e.g.
+/// removing a member or spending above a limit.
+///
+/// The action stays [ActionStatus::Open](enum.ActionStatus.html) during the cooling-off period,
+/// while the holders of the veto roles of the policy it was enacted under may veto it. Once the period is over,
+/// the action is executed with [execute_critical_action](fn.execute_critical_action.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct CriticalAction {
+	pub collective_address: Address,
+	pub proposal_address: Address,
+	pub action_address: Address,
+	/// Time the action was enacted in seconds since the unix epoch.
+	pub enacted_at: u64,
+	/// End of the cooling-off period in seconds since the unix epoch.
+	pub executable_at: u64,
+	pub veto: Option<Veto>,
+	/// Time the action was executed in seconds since the unix epoch.
+	#[serde(default)]
+	pub executed_at: Option<u64>,
+	/// [CriticalActionPolicy](struct.CriticalActionPolicy.html) of the collective when the action
+	/// was enacted. Later changes of the policy do not apply to the action.
+	#[serde(default)]
+	pub policy: CriticalActionPolicy,
+}
+
+/// A veto of a [CriticalAction](struct.CriticalAction.html) by the holder of a veto
+/// [Role](struct.Role.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct Veto {
+	/// Address of the [Person](struct.Person.html) vetoing the action.
+	pub person_address: Address,
+	/// Address of the veto [Role](struct.Role.html) held by the person.
+	pub role_address: Address,
+	pub reason: String,
+	/// Time of the veto in seconds since the unix epoch.
+	pub vetoed_at: u64,
+}
+
+/// The critical action policy of a [Collective](struct.Collective.html), set from the values of
+/// a passed [ActionOp::SetCriticalActionPolicy](enum.ActionOp.html) proposal with
+/// [set_critical_action_policy](fn.set_critical_action_policy.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq, Default)]
+pub struct CriticalActionPolicy {
+	pub critical_action_ops: Vec<ActionOp>,
+	/// Cooling-off period in seconds.
+	pub cooling_off_period: u64,
+	pub veto_role_addresses: Vec<Address>,
+}
+
+/// Api params for [veto_critical_action](fn.veto_critical_action.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct VetoParams {
+	pub critical_action_address: Address,
+	/// Address of the [Person](struct.Person.html) vetoing the action.
+	pub person_address: Address,
+	/// Address of the veto [Role](struct.Role.html) held by the person.
+	pub role_address: Address,
+	pub reason: Option<String>,
+	pub timestamp: u64,
+}
+
+/// Api payload of the [Action](struct.Action.html) enacted from a
+/// [Proposal](struct.Proposal.html), with its [CriticalAction](struct.CriticalAction.html)
+/// when the op is critical.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ProposalActionPayload {
+	pub proposal_address: Address,
+	pub action_address: Address,
+	pub action: Action,
+	pub critical_action_address: Option<Address>,
+	pub critical_action: Option<CriticalAction>,
+}
+
+/// Api payload of a [CriticalAction](struct.CriticalAction.html) & its
+/// [Action](struct.Action.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct CriticalActionPayload {
+	pub critical_action_address: Address,
+	pub critical_action: CriticalAction,
+	pub action: Action,
+}
+
+/// Api payload of the [CriticalActions](struct.CriticalAction.html) of a
+/// [Collective](struct.Collective.html), the most recent first.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct CollectiveCriticalActionsPayload {
+	pub collective_address: Address,
+	pub critical_actions: Vec<CriticalActionPayload>,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct VetoActionData {
+	critical_action_address: Address,
+	action_address: Address,
+	veto: Veto,
+}
+
+/// Returns a Holochain entry definition for a critical action.
+pub fn critical_action_def() -> ValidatingEntryType {
+	entry!(
+		name: "critical_action",
+		description: "A protocol.love critical action cooling off before its execution",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<CriticalAction>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_critical_action_entry(&HdkStore, validation, &context)?)
+		}
+	)
+}
+
+/// Validation rules of a [CriticalAction](struct.CriticalAction.html) entry.
+///
+/// A critical action is enacted from a passed proposal with a critical op & cools off for the
+/// period of the collective. It is only modified by a veto of the holder of a veto role before
+/// the end of the period, or by its execution after.
+pub fn validate_critical_action_entry(
+	store: &dyn Store,
+	validation: EntryValidation<CriticalAction>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let proposal: Proposal = t("validation error: critical_action: fetch proposal: ",
+				get_as_type(store, &entry.proposal_address))?;
+			if proposal.collective_address != entry.collective_address {
+				return Err(ProtocolLoveError::validation_failed(
+					"proposal_address", "Proposal must be a proposal of the collective"));
+			}
+			if !is_proposal_passed(store, &entry.proposal_address, &proposal, entry.enacted_at)? {
+				return Err(ProtocolLoveError::conflict("Proposal has not passed"));
+			}
+			let policy_collective = load_policy_collective(store, &entry.collective_address)?;
+			if entry.policy != collective_policy(&policy_collective) {
+				return Err(ProtocolLoveError::validation_failed(
+					"policy", "Action must be enacted under the policy of the collective"));
+			}
+			if !proposal_action_op(&proposal)
+				.map_or(false, |action_op| entry.policy.critical_action_ops.contains(&action_op)) {
+				return Err(ProtocolLoveError::validation_failed(
+					"proposal_address", "Proposal does not enact a critical action"));
+			}
+			let action_addresses = t("validation error: critical_action: fetch actions: ",
+				store.get_links(&entry.proposal_address, "proposal->action", None))?;
+			if !action_addresses.contains(&entry.action_address) {
+				return Err(ProtocolLoveError::validation_failed(
+					"action_address", "Action must be enacted from the proposal"));
+			}
+			if get_action_critical_action(store, &entry.action_address)?.is_some() {
+				return Err(ProtocolLoveError::conflict("Critical action is already enacted"));
+			}
+			if entry.executable_at != entry.enacted_at + entry.policy.cooling_off_period {
+				return Err(ProtocolLoveError::validation_failed(
+					"executable_at", "Action must cool off for the period of the collective"));
+			}
+			if entry.veto.is_some() || entry.executed_at.is_some() {
+				return Err(ProtocolLoveError::validation_failed(
+					"veto", "Action cannot be vetoed or executed when enacted"));
+			}
+			validate_timestamp("enacted_at", entry.enacted_at, context)
+		}
+		EntryValidation::Modify { new_entry, old_entry } => {
+			if old_entry.veto.is_some() {
+				return Err(ProtocolLoveError::conflict("Action is already vetoed"));
+			}
+			if old_entry.executed_at.is_some() {
+				return Err(ProtocolLoveError::conflict("Action is already executed"));
+			}
+			match (&new_entry.veto, new_entry.executed_at) {
+				(Some(veto), None) => {
+					if new_entry != (CriticalAction { veto: Some(veto.clone()), ..old_entry.clone() }) {
+						return Err(ProtocolLoveError::unauthorized(
+							"Critical action can only be modified by a veto or its execution"));
+					}
+					if veto.vetoed_at >= old_entry.executable_at {
+						return Err(ProtocolLoveError::conflict("Cooling-off period is over"));
+					}
+					let person: Person = t("validation error: critical_action: fetch person: ",
+						get_as_type(store, &veto.person_address))?;
+					if !context.sources.contains(&person.agent_address) {
+						return Err(ProtocolLoveError::unauthorized("Veto must be cast by the person"));
+					}
+					validate_veto_role(store, &old_entry.policy, veto)?;
+					validate_timestamp("vetoed_at", veto.vetoed_at, context)
+				}
+				(None, Some(executed_at)) => {
+					if new_entry != (CriticalAction { executed_at: Some(executed_at), ..old_entry.clone() }) {
+						return Err(ProtocolLoveError::unauthorized(
+							"Critical action can only be modified by a veto or its execution"));
+					}
+					if executed_at < old_entry.executable_at {
+						return Err(ProtocolLoveError::conflict(&format!(
+							"Action is cooling off until {}", old_entry.executable_at)));
+					}
+					validate_timestamp("executed_at", executed_at, context)
+				}
+				_ => Err(ProtocolLoveError::unauthorized(
+					"Critical action can only be modified by a veto or its execution")),
+			}
+		}
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Critical action cannot be deleted")),
+	}
+}
+
+/// Validation rules of a "proposal->action" link.
+///
+/// The action is enacted once from the passed proposal, open when its op is critical & executed
+/// otherwise.
+pub fn validate_proposal_action_link(
+	store: &dyn Store,
+	link: &LinkValidation,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let proposal: Proposal = t("validation error: proposal->action: fetch proposal: ",
+		get_as_type(store, &link.base))?;
+	if !is_proposal_passed(store, &link.base, &proposal, context.timestamp)? {
+		return Err(ProtocolLoveError::conflict("Proposal has not passed"));
+	}
+	let action: Action = t("validation error: proposal->action: fetch action: ",
+		get_initial_as_type(store, &link.target))?;
+	let policy_collective = load_policy_collective(store, &proposal.collective_address)?;
+	if proposal_action(&proposal, &policy_collective).as_ref() != Some(&action) {
+		return Err(ProtocolLoveError::validation_failed(
+			"target", "Action is not enacted from the proposal"));
+	}
+	let action_addresses = t("validation error: proposal->action: fetch actions: ",
+		store.get_links(&link.base, "proposal->action", None))?;
+	if action_addresses.iter().any(|action_address| action_address != &link.target) {
+		return Err(ProtocolLoveError::conflict("Proposal is already enacted"));
+	}
+	Ok(())
+}
+
+/// Validation rules of an "action->critical_action" link, linking the action to its only
+/// critical action.
+pub fn validate_action_critical_action_link(
+	store: &dyn Store,
+	link: &LinkValidation,
+	_context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let critical_action: CriticalAction = t("validation error: action->critical_action: fetch critical action: ",
+		get_initial_as_type(store, &link.target))?;
+	if critical_action.action_address != link.base {
+		return Err(ProtocolLoveError::validation_failed(
+			"target", "Critical action is not of the action"));
+	}
+	let critical_action_addresses = t("validation error: action->critical_action: fetch critical actions: ",
+		store.get_links(&link.base, "action->critical_action", None))?;
+	if critical_action_addresses.iter().any(|address| address != &link.target) {
+		return Err(ProtocolLoveError::conflict("Critical action is already enacted"));
+	}
+	Ok(())
+}
+
+/// Validates the person of the veto holds a veto role of the policy at the time of the veto.
+fn validate_veto_role(
+	store: &dyn Store,
+	policy: &CriticalActionPolicy,
+	veto: &Veto,
+) -> ProtocolLoveResult<()> {
+	if !policy.veto_role_addresses.contains(&veto.role_address) {
+		return Err(ProtocolLoveError::validation_failed(
+			"role_address", "Role cannot veto critical actions"));
+	}
+	let holder = get_role(store, veto.role_address.clone(), veto.vetoed_at)?.holder;
+	if !holder.map_or(false, |role_term| role_term.person_address == veto.person_address) {
+		return Err(ProtocolLoveError::unauthorized("Only the holder of the role can veto"));
+	}
+	Ok(())
+}
+
+/// Gets the collective whose policies apply to the collective at `collective_address`.
+fn load_policy_collective(store: &dyn Store, collective_address: &Address) -> ProtocolLoveResult<Collective> {
+	let collective: Collective = t("load_policy_collective: ", get_as_type(store, collective_address))?;
+	get_policy_collective(store, &collective)
+}
+
+/// The [CriticalActionPolicy](struct.CriticalActionPolicy.html) of the collective.
+fn collective_policy(collective: &Collective) -> CriticalActionPolicy {
+	CriticalActionPolicy {
+		critical_action_ops: collective.critical_action_ops.clone(),
+		cooling_off_period: collective.cooling_off_period,
+		veto_role_addresses: collective.veto_role_addresses.clone(),
+	}
+}
+
+/// Has the proposal passed at the `timestamp`? A conviction proposal passes while in voting,
+/// once its conviction reaches the threshold.
+fn is_proposal_passed(
+	store: &dyn Store,
+	proposal_address: &Address,
+	proposal: &Proposal,
+	timestamp: u64,
+) -> ProtocolLoveResult<bool> {
+	match proposal.status {
+		ProposalStatus::Closed => Ok(proposal.outcome == Some(ProposalOutcome::Passed)),
+		ProposalStatus::Voting if proposal.tally_method == TallyMethod::Conviction =>
+			has_reached_conviction_threshold(store, proposal_address, proposal, timestamp),
+		_ => Ok(false),
+	}
+}
+
+/// Op of the action the proposal decides on, from its template. Conviction proposals without
+/// an op fund their requested funds.
+fn proposal_action_op(proposal: &Proposal) -> Option<ActionOp> {
+	match proposal.template.as_ref().and_then(|template| template.action_op.clone()) {
+		Some(action_op) => Some(action_op),
+		None if proposal.tally_method == TallyMethod::Conviction => Some(ActionOp::FundProposal),
+		None => None,
+	}
+}
+
+/// The [Action](struct.Action.html) enacted from the proposal, open when its op is critical in
+/// the policy of the collective.
+///
+/// The values of the template are the `data` of the action, or the requested funds of a
+/// conviction proposal.
+fn proposal_action(proposal: &Proposal, policy_collective: &Collective) -> Option<Action> {
+	let action_op = proposal_action_op(proposal)?;
+	let data = match &proposal.template {
+		Some(template) if template.action_op.is_some() =>
+			Value::Object(template.values.clone().into_iter().collect()),
+		_ => Value::Object(vec![("requested_funds".to_string(), Value::from(proposal.requested_funds))]
+			.into_iter()
+			.collect()),
+	};
+	let is_critical = is_critical_action_op(policy_collective, &action_op);
+	Some(Action {
+		op: action_op,
+		status: if is_critical { ActionStatus::Open } else { ActionStatus::Executed },
+		data: data.into(),
+		prev_data: Value::Null.into(),
+		tag: "enact_proposal".into(),
+		strategy: ActionStrategy::SystemAutomatic,
+	})
+}
+
+/// Is the op critical in the policy of the collective?
+pub fn is_critical_action_op(policy_collective: &Collective, action_op: &ActionOp) -> bool {
+	policy_collective.critical_action_ops.contains(action_op)
+}
+
+/// Api for the admin to set the [CriticalActionPolicy](struct.CriticalActionPolicy.html) of the
+/// [Collective](struct.Collective.html) from the values of a passed proposal with the
+/// [ActionOp::SetCriticalActionPolicy](enum.ActionOp.html) op. The veto roles must be roles of
+/// the collective & each proposal sets the policy once.
+pub fn set_critical_action_policy(
+	store: &dyn Store,
+	proposal_address: Address,
+) -> ProtocolLoveResult<CollectivePayload> {
+	let proposal: Proposal = t("set_critical_action_policy: ", get_as_type(store, &proposal_address))?;
+	if proposal_action_op(&proposal) != Some(ActionOp::SetCriticalActionPolicy) {
+		return Err(ProtocolLoveError::validation_failed(
+			"proposal_address", "set_critical_action_policy: Proposal does not set the critical action policy"));
+	}
+	if proposal.status != ProposalStatus::Closed || proposal.outcome != Some(ProposalOutcome::Passed) {
+		return Err(ProtocolLoveError::conflict("set_critical_action_policy: Proposal has not passed"));
+	}
+	let collective_address = proposal.collective_address.clone();
+	let saved_collective: Collective =
+		t("set_critical_action_policy: ", get_as_type(store, &collective_address))?;
+	if !t("set_critical_action_policy: ", is_collective_admin(store, &saved_collective))? {
+		return Err(ProtocolLoveError::unauthorized(
+			"set_critical_action_policy: Only the admin can set the critical action policy"));
+	}
+	if saved_collective.inherit_policies {
+		return Err(ProtocolLoveError::conflict(
+			"set_critical_action_policy: Collective inherits the policies of its parent"));
+	}
+	if t("set_critical_action_policy: ",
+		is_policy_proposal_enacted(store, &collective_address, &saved_collective, &proposal_address))? {
+		return Err(ProtocolLoveError::conflict("set_critical_action_policy: Policy is already set by the proposal"));
+	}
+	let critical_action_policy = t("set_critical_action_policy: ", proposal_policy(&proposal))?;
+	t("set_critical_action_policy: ",
+		validate_veto_roles(store, &collective_address, &critical_action_policy))?;
+	t("set_critical_action_policy: ", validate_proposal_action_executed(store, &proposal, &proposal_address))?;
+	let CriticalActionPolicy {
+		critical_action_ops,
+		cooling_off_period,
+		veto_role_addresses,
+	} = critical_action_policy.clone();
+	let collective = Collective {
+		critical_action_ops,
+		cooling_off_period,
+		veto_role_addresses,
+		policy_proposal_address: Some(proposal_address),
+		..saved_collective.clone()
+	};
+	t("set_critical_action_policy: ", update_collective_entry(store, &collective_address, &collective))?;
+	t("set_critical_action_policy: ", create_root_action(
+		store,
+		&collective_address,
+		ActionOp::SetCriticalActionPolicy,
+		critical_action_policy.into(),
+		collective_policy(&saved_collective).into(),
+		"set_critical_action_policy",
+	))?;
+	Ok(CollectivePayload {
+		collective_address,
+		collective,
+	})
+}
+
+/// Validates the critical action policy of the `collective` is set from a passed proposal of the
+/// collective, which did not already set it.
+pub fn validate_collective_policy(
+	store: &dyn Store,
+	collective: &Collective,
+	prev_collective: &Collective,
+) -> ProtocolLoveResult<()> {
+	let proposal_address = match &collective.policy_proposal_address {
+		Some(proposal_address) => proposal_address,
+		None => return Err(ProtocolLoveError::validation_failed(
+			"policy_proposal_address", "Policy must be set by a proposal")),
+	};
+	let proposal: Proposal = t("validation error: collective: fetch policy proposal: ",
+		get_as_type(store, proposal_address))?;
+	if proposal_action_op(&proposal) != Some(ActionOp::SetCriticalActionPolicy)
+		|| !is_collective_version(store, &proposal.collective_address, prev_collective)? {
+		return Err(ProtocolLoveError::validation_failed(
+			"policy_proposal_address", "Proposal does not set the critical action policy"));
+	}
+	if proposal.status != ProposalStatus::Closed || proposal.outcome != Some(ProposalOutcome::Passed) {
+		return Err(ProtocolLoveError::conflict("Proposal has not passed"));
+	}
+	if prev_collective.inherit_policies {
+		return Err(ProtocolLoveError::conflict("Collective inherits the policies of its parent"));
+	}
+	if collective_policy(collective) != proposal_policy(&proposal)? {
+		return Err(ProtocolLoveError::validation_failed(
+			"critical_action_ops", "Policy must be the policy of the proposal"));
+	}
+	validate_veto_roles(store, &proposal.collective_address, &collective_policy(collective))?;
+	validate_proposal_action_executed(store, &proposal, proposal_address)?;
+	if is_policy_proposal_enacted(store, &proposal.collective_address, prev_collective, proposal_address)? {
+		return Err(ProtocolLoveError::conflict("Policy is already set by the proposal"));
+	}
+	Ok(())
+}
+
+/// The [CriticalActionPolicy](struct.CriticalActionPolicy.html) in the template values of the
+/// proposal.
+fn proposal_policy(proposal: &Proposal) -> ProtocolLoveResult<CriticalActionPolicy> {
+	let values = proposal.template.as_ref()
+		.map(|template| template.values.clone())
+		.unwrap_or_default();
+	serde_json::from_value(Value::Object(values.into_iter().collect())).map_err(|_|
+		ProtocolLoveError::validation_failed("template_values", "Proposal values are not a critical action policy"))
+}
+
+/// Validates the veto roles of the policy are roles of the collective.
+fn validate_veto_roles(
+	store: &dyn Store,
+	collective_address: &Address,
+	policy: &CriticalActionPolicy,
+) -> ProtocolLoveResult<()> {
+	for role_address in &policy.veto_role_addresses {
+		let role: Role = t("validate_veto_roles: ", get_as_type(store, role_address))?;
+		if &role.collective_address != collective_address {
+			return Err(ProtocolLoveError::validation_failed(
+				"veto_role_addresses", "Veto roles must be roles of the collective"));
+		}
+	}
+	Ok(())
+}
+
+/// Did the proposal set the policy of the collective at `collective_address`, up to its version
+/// `collective`?
+fn is_policy_proposal_enacted(
+	store: &dyn Store,
+	collective_address: &Address,
+	collective: &Collective,
+	proposal_address: &Address,
+) -> ProtocolLoveResult<bool> {
+	for entry in store.get_entry_history(collective_address)? {
+		let version = match entry {
+			Entry::App(_, entry_value) => Collective::try_from(entry_value).map_err(|_|
+				ProtocolLoveError::invariant_broken("is_policy_proposal_enacted: Could not read the collective"))?,
+			_ => return Err(ProtocolLoveError::invariant_broken(
+				"is_policy_proposal_enacted: Collective is not an app entry")),
+		};
+		if version.policy_proposal_address.as_ref() == Some(proposal_address) {
+			return Ok(true);
+		}
+		if &version == collective {
+			break;
+		}
+	}
+	Ok(false)
+}
+
+/// Api to enact the action of a passed [Proposal](struct.Proposal.html) created from a
+/// [ProposalTemplate](struct.ProposalTemplate.html) with an `action_op`, or of a conviction
+/// proposal whose conviction reached the threshold, at the `timestamp`.
+///
+/// An action with a critical op is left open for the cooling-off period of the collective,
+/// otherwise it is executed.
+pub fn enact_proposal(
+	store: &dyn Store,
+	proposal_address: Address,
+	timestamp: u64,
+) -> ProtocolLoveResult<ProposalActionPayload> {
+	let proposal: Proposal = t("enact_proposal: ", get_as_type(store, &proposal_address))?;
+	if !t("enact_proposal: ", is_proposal_passed(store, &proposal_address, &proposal, timestamp))? {
+		return Err(ProtocolLoveError::conflict("enact_proposal: Proposal has not passed"));
+	}
+	let collective_address = proposal.collective_address.clone();
+	let policy_collective = t("enact_proposal: ", load_policy_collective(store, &collective_address))?;
+	let action = match proposal_action(&proposal, &policy_collective) {
+		Some(action) => action,
+		None => return Err(ProtocolLoveError::validation_failed(
+			"proposal_address", "enact_proposal: Proposal has no action to enact")),
+	};
+	if !t("enact_proposal: ", store.get_links(&proposal_address, "proposal->action", None))?.is_empty() {
+		return Err(ProtocolLoveError::conflict("enact_proposal: Proposal is already enacted"));
+	}
+	let is_critical = action.status == ActionStatus::Open;
+	let (action_address, _action_entry, action) =
+		t("enact_proposal: ", action.commit_action(store, collective_address.clone()))?;
+	t("enact_proposal: proposal->action: ", store.link_entries(
+		&proposal_address,
+		&action_address,
+		"proposal->action",
+		"",
+	))?;
+	let (critical_action_address, critical_action) = if is_critical {
+		let critical_action = CriticalAction {
+			collective_address: collective_address.clone(),
+			proposal_address: proposal_address.clone(),
+			action_address: action_address.clone(),
+			enacted_at: timestamp,
+			executable_at: timestamp + policy_collective.cooling_off_period,
+			veto: None,
+			executed_at: None,
+			policy: collective_policy(&policy_collective),
+		};
+		let critical_action_entry = Entry::App("critical_action".into(), (&critical_action).into());
+		let critical_action_address = t("enact_proposal: ", store.commit_entry(&critical_action_entry))?;
+		t("enact_proposal: collective->critical_action: ", store.link_entries(
+			&collective_address,
+			&critical_action_address,
+			"collective->critical_action",
+			"",
+		))?;
+		t("enact_proposal: action->critical_action: ", store.link_entries(
+			&action_address,
+			&critical_action_address,
+			"action->critical_action",
+			"",
+		))?;
+		(Some(critical_action_address), Some(critical_action))
+	} else {
+		(None, None)
+	};
+	Ok(ProposalActionPayload {
+		proposal_address,
+		action_address,
+		action,
+		critical_action_address,
+		critical_action,
+	})
+}
+
+/// Api for the holder of a veto [Role](struct.Role.html) to veto a
+/// [CriticalAction](struct.CriticalAction.html) during its cooling-off period.
+///
+/// The [Action](struct.Action.html) moves to [ActionStatus::Vetoed](enum.ActionStatus.html) &
+/// the veto is recorded as an [ActionOp::VetoAction](enum.ActionOp.html).
+pub fn veto_critical_action(
+	store: &dyn Store,
+	veto_params: VetoParams,
+) -> ProtocolLoveResult<CriticalActionPayload> {
+	let VetoParams {
+		critical_action_address,
+		person_address,
+		role_address,
+		reason,
+		timestamp,
+	} = veto_params;
+	let _person = t("veto_critical_action: ", get_agent_person(store, &person_address))?;
+	let critical_action: CriticalAction =
+		t("veto_critical_action: ", get_as_type(store, &critical_action_address))?;
+	if critical_action.veto.is_some() {
+		return Err(ProtocolLoveError::conflict("veto_critical_action: Action is already vetoed"));
+	}
+	if timestamp >= critical_action.executable_at {
+		return Err(ProtocolLoveError::conflict("veto_critical_action: Cooling-off period is over"));
+	}
+	let veto = Veto {
+		person_address,
+		role_address,
+		reason: reason.unwrap_or_default(),
+		vetoed_at: timestamp,
+	};
+	t("veto_critical_action: ", validate_veto_role(store, &critical_action.policy, &veto))?;
+	let critical_action = CriticalAction {
+		veto: Some(veto.clone()),
+		..critical_action
+	};
+	t("veto_critical_action: ", store.update_entry(
+		Entry::App("critical_action".into(), (&critical_action).into()),
+		&critical_action_address,
+	))?;
+	let action = t("veto_critical_action: ",
+		update_action_status(store, &critical_action.action_address, ActionStatus::Vetoed))?;
+	t("veto_critical_action: ", create_root_action(
+		store,
+		&critical_action.collective_address,
+		ActionOp::VetoAction,
+		VetoActionData {
+			critical_action_address: critical_action_address.clone(),
+			action_address: critical_action.action_address.clone(),
+			veto,
+		}.into(),
+		Value::Null.into(),
+		"veto_critical_action",
+	))?;
+	Ok(CriticalActionPayload {
+		critical_action_address,
+		critical_action,
+		action,
+	})
+}
+
+/// Api to execute a [CriticalAction](struct.CriticalAction.html) once its cooling-off period is
+/// over at the `timestamp`, recording the time of its execution & moving its
+/// [Action](struct.Action.html) to [ActionStatus::Executed](enum.ActionStatus.html).
+pub fn execute_critical_action(
+	store: &dyn Store,
+	critical_action_address: Address,
+	timestamp: u64,
+) -> ProtocolLoveResult<CriticalActionPayload> {
+	let saved_critical_action: CriticalAction =
+		t("execute_critical_action: ", get_as_type(store, &critical_action_address))?;
+	if saved_critical_action.veto.is_some() {
+		return Err(ProtocolLoveError::conflict("execute_critical_action: Action is vetoed"));
+	}
+	if saved_critical_action.executed_at.is_some() {
+		return Err(ProtocolLoveError::conflict("execute_critical_action: Action is already executed"));
+	}
+	if timestamp < saved_critical_action.executable_at {
+		return Err(ProtocolLoveError::conflict(&format!(
+			"execute_critical_action: Action is cooling off until {}", saved_critical_action.executable_at)));
+	}
+	let critical_action = CriticalAction {
+		executed_at: Some(timestamp),
+		..saved_critical_action
+	};
+	t("execute_critical_action: ", store.update_entry(
+		Entry::App("critical_action".into(), (&critical_action).into()),
+		&critical_action_address,
+	))?;
+	let action = t("execute_critical_action: ",
+		update_action_status(store, &critical_action.action_address, ActionStatus::Executed))?;
+	Ok(CriticalActionPayload {
+		critical_action_address,
+		critical_action,
+		action,
+	})
+}
+
+/// Api to get a [CriticalAction](struct.CriticalAction.html) & its [Action](struct.Action.html).
+pub fn get_critical_action(
+	store: &dyn Store,
+	critical_action_address: Address,
+) -> ProtocolLoveResult<CriticalActionPayload> {
+	let critical_action: CriticalAction =
+		t("get_critical_action: ", get_as_type(store, &critical_action_address))?;
+	let action = t("get_critical_action: ", get_as_type(store, &critical_action.action_address))?;
+	Ok(CriticalActionPayload {
+		critical_action_address,
+		critical_action,
+		action,
+	})
+}
+
+/// Api to get the [CriticalActions](struct.CriticalAction.html) of a
+/// [Collective](struct.Collective.html).
+pub fn get_critical_actions(
+	store: &dyn Store,
+	collective_address: Address,
+) -> ProtocolLoveResult<CollectiveCriticalActionsPayload> {
+	let critical_actions = t("get_critical_actions: ", store.get_links(
+		&collective_address,
+		"collective->critical_action",
+		None,
+	))?
+		.into_iter()
+		.map(|critical_action_address| get_critical_action(store, critical_action_address))
+		.collect::<ProtocolLoveResult<Vec<CriticalActionPayload>>>()?;
+	Ok(CollectiveCriticalActionsPayload {
+		collective_address,
+		critical_actions,
+	})
+}
+
+/// Validates the [CriticalAction](struct.CriticalAction.html) of the passed proposal is
+/// executed when its op is critical, before the proposal takes effect.
+///
+/// Once the action is enacted, its op is critical when it was under the policy of the collective
+/// at the time, otherwise under the current policy.
+pub fn validate_proposal_action_executed(
+	store: &dyn Store,
+	proposal: &Proposal,
+	proposal_address: &Address,
+) -> ProtocolLoveResult<()> {
+	let action_address = store.get_links(proposal_address, "proposal->action", None)?.into_iter().next();
+	let is_critical = match &action_address {
+		Some(action_address) => {
+			let action: Action = t("validate_proposal_action_executed: ",
+				get_initial_as_type(store, action_address))?;
+			action.status == ActionStatus::Open
+		}
+		None => {
+			let policy_collective = load_policy_collective(store, &proposal.collective_address)?;
+			proposal_action_op(proposal)
+				.map_or(false, |action_op| is_critical_action_op(&policy_collective, &action_op))
+		}
+	};
+	if !is_critical {
+		return Ok(());
+	}
+	let critical_action = match &action_address {
+		Some(action_address) => get_action_critical_action(store, action_address)?,
+		None => None,
+	};
+	match critical_action {
+		Some(CriticalAction { veto: Some(_), .. }) =>
+			Err(ProtocolLoveError::conflict("Critical action is vetoed")),
+		Some(CriticalAction { executed_at: Some(_), .. }) => Ok(()),
+		_ => Err(ProtocolLoveError::conflict("Critical action is not executed")),
+	}
+}
+
+/// Gets the [CriticalAction](struct.CriticalAction.html) of the action, when its op was critical
+/// when enacted.
+pub fn get_action_critical_action(
+	store: &dyn Store,
+	action_address: &Address,
+) -> ProtocolLoveResult<Option<CriticalAction>> {
+	let critical_actions: Vec<CriticalAction> = t("get_action_critical_action: ",
+		get_links_and_load_type(store, action_address, "action->critical_action", None))?;
+	Ok(critical_actions.into_iter().next())
+}
+
+fn update_action_status(
+	store: &dyn Store,
+	action_address: &Address,
+	status: ActionStatus,
+) -> ProtocolLoveResult<Action> {
+	let saved_action: Action = t("update_action_status: ", get_as_type(store, action_address))?;
+	let action = Action {
+		status,
+		..saved_action
+	};
+	t("update_action_status: ", store.update_entry(
+		Entry::App("action".into(), (&action).into()),
+		action_address,
+	))?;
+	Ok(action)
+}
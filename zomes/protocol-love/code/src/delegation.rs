@@ -0,0 +1,372 @@
+use std::collections::{HashMap, HashSet};
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use hdk::holochain_core_types::dna::entry_types::Sharing;
+use hdk::prelude::ValidatingEntryType;
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::action::{ActionOp, create_root_action};
+use crate::collective::is_collective_person;
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::person::{Person, get_agent_person};
+use crate::proposal::Proposal;
+use crate::store::{Store, HdkStore, get_as_type, get_links_and_load_type};
+use crate::utils::t;
+use crate::validation::{EntryValidation, LinkValidation, ValidationContext, from_hdk_validation_data};
+use crate::vote::{Ballot, Vote};
+
+/// A [Person](struct.Person.html) delegating their vote to another person of the
+/// [Collective](struct.Collective.html).
+///
+/// The latest delegation of a delegator for a `tag` replaces the former.
+/// A delegation without a `delegate_address` revokes the delegation.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct Delegation {
+	pub collective_address: Address,
+	/// Address of the [Person](struct.Person.html) delegating their vote.
+	pub delegator_address: Address,
+	/// Address of the [Person](struct.Person.html) voting on behalf of the delegator.
+	pub delegate_address: Option<Address>,
+	/// Proposal tag the delegation applies to. Applies to every proposal when `None`.
+	pub tag: Option<String>,
+	/// Number of the delegations of the delegator on the tag, including this one. A delegation
+	/// repeating an earlier one gets its own address, so it is ordered by its own commit.
+	#[serde(default)]
+	pub version: u64,
+}
+
+/// Api params for [delegate_vote](fn.delegate_vote.html) &
+/// [revoke_delegation](fn.revoke_delegation.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct DelegationParams {
+	pub collective_address: Address,
+	pub delegator_address: Address,
+	/// Ignored by [revoke_delegation](fn.revoke_delegation.html).
+	pub delegate_address: Option<Address>,
+	pub tag: Option<String>,
+}
+
+/// Api payload containing the `delegation_address` & [delegation](struct.Delegation.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct DelegationPayload {
+	pub delegation_address: Address,
+	pub delegation: Delegation,
+}
+
+/// Api payload of the active [Delegations](struct.Delegation.html) in a
+/// [Collective](struct.Collective.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct DelegationsPayload {
+	pub collective_address: Address,
+	pub delegations: Vec<Delegation>,
+}
+
+/// Returns a Holochain entry definition for a delegation.
+pub fn delegation_def() -> ValidatingEntryType {
+	entry!(
+		name: "delegation",
+		description: "A protocol.love vote delegation between people of a collective",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<Delegation>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_delegation_entry(&HdkStore, validation, &context)?)
+		}
+	)
+}
+
+/// Validation rules of a [Delegation](struct.Delegation.html) entry.
+pub fn validate_delegation_entry(
+	store: &dyn Store,
+	validation: EntryValidation<Delegation>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let delegator: Person = t("validation error: delegation: fetch delegator: ",
+				get_as_type(store, &entry.delegator_address))?;
+			if !context.sources.contains(&delegator.agent_address) {
+				return Err(ProtocolLoveError::unauthorized(
+					"Delegation must be made by the agent of the delegator"));
+			}
+			if !is_collective_person(store, &entry.collective_address, &entry.delegator_address)? {
+				return Err(ProtocolLoveError::unauthorized(
+					"Only people in the collective can delegate"));
+			}
+			if let Some(delegate_address) = &entry.delegate_address {
+				if delegate_address == &entry.delegator_address {
+					return Err(ProtocolLoveError::validation_failed(
+						"delegate_address", "Vote cannot be delegated to oneself"));
+				}
+				if !is_collective_person(store, &entry.collective_address, delegate_address)? {
+					return Err(ProtocolLoveError::validation_failed(
+						"delegate_address", "Vote can only be delegated to people in the collective"));
+				}
+				let delegations = t("validation error: delegation: fetch delegations: ",
+					get_active_delegations(store, &entry.collective_address))?;
+				validate_delegation_chain(&delegations, &entry)?;
+			}
+			if let Some(tag) = &entry.tag {
+				if tag.is_empty() || tag.len() > 64 {
+					return Err(ProtocolLoveError::validation_failed(
+						"tag", "Delegation tag must be between 1 & 64 characters"));
+				}
+			}
+			Ok(())
+		}
+		EntryValidation::Modify { .. } =>
+			Err(ProtocolLoveError::unauthorized("Delegation cannot be modified")),
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Delegation cannot be deleted")),
+	}
+}
+
+/// Api for a [Person](struct.Person.html) to delegate their vote to the `delegate_address`,
+/// on every proposal or on the proposals with the `tag`.
+///
+/// A delegation on a tag takes precedence over a delegation on every proposal.
+/// Delegations are transitive & the delegator voting directly overrides the delegation.
+pub fn delegate_vote(
+	store: &dyn Store,
+	delegation_params: DelegationParams,
+) -> ProtocolLoveResult<DelegationPayload> {
+	t("delegate_vote: ", get_agent_person(store, &delegation_params.delegator_address))?;
+	let delegate_address = match &delegation_params.delegate_address {
+		Some(delegate_address) => delegate_address.clone(),
+		None => return Err(ProtocolLoveError::validation_failed(
+			"delegate_address", "delegate_vote: Delegate is required")),
+	};
+	let delegation = Delegation {
+		collective_address: delegation_params.collective_address,
+		delegator_address: delegation_params.delegator_address,
+		delegate_address: Some(delegate_address),
+		tag: delegation_params.tag,
+		version: 0,
+	};
+	let delegations =
+		t("delegate_vote: ", get_active_delegations(store, &delegation.collective_address))?;
+	t("delegate_vote: ", validate_delegation_chain(&delegations, &delegation))?;
+	commit_delegation(store, delegation, ActionOp::DelegateVote)
+}
+
+/// Validates the `delegation` does not lead back to its delegator through the active `delegations`.
+fn validate_delegation_chain(
+	delegations: &[Delegation],
+	delegation: &Delegation,
+) -> ProtocolLoveResult<()> {
+	let tags: Vec<String> = delegation.tag.iter().cloned().collect();
+	let mut current_address = match &delegation.delegate_address {
+		Some(delegate_address) => delegate_address,
+		None => return Ok(()),
+	};
+	let mut visited = HashSet::new();
+	while visited.insert(current_address) {
+		if current_address == &delegation.delegator_address {
+			return Err(ProtocolLoveError::conflict("Delegation would create a cycle"));
+		}
+		match find_delegate(delegations, current_address, &tags) {
+			Some(next_address) => current_address = next_address,
+			None => break,
+		}
+	}
+	Ok(())
+}
+
+/// Validation rules of a collective->delegation link.
+pub fn validate_collective_delegation_link(
+	store: &dyn Store,
+	link: &LinkValidation,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<bool> {
+	let delegation: Delegation = t("validation error: collective->delegation: fetch delegation: ",
+		get_as_type(store, &link.target))?;
+	let delegator: Person = t("validation error: collective->delegation: fetch delegator: ",
+		get_as_type(store, &delegation.delegator_address))?;
+	if !context.sources.contains(&delegator.agent_address) {
+		return Err(ProtocolLoveError::unauthorized(
+			"Delegation must be linked by the agent of the delegator"));
+	}
+	if link.tag != delegation.delegator_address.to_string() {
+		return Err(ProtocolLoveError::validation_failed(
+			"tag", "Delegation must be tagged with its delegator"));
+	}
+	Ok(delegation.collective_address == link.base)
+}
+
+/// Api for a [Person](struct.Person.html) to revoke their delegation on the `tag`.
+pub fn revoke_delegation(
+	store: &dyn Store,
+	delegation_params: DelegationParams,
+) -> ProtocolLoveResult<DelegationPayload> {
+	t("revoke_delegation: ", get_agent_person(store, &delegation_params.delegator_address))?;
+	let delegations =
+		t("revoke_delegation: ", get_active_delegations(store, &delegation_params.collective_address))?;
+	if !delegations.iter().any(|delegation|
+		delegation.delegator_address == delegation_params.delegator_address
+			&& delegation.tag == delegation_params.tag) {
+		return Err(ProtocolLoveError::conflict("revoke_delegation: No delegation to revoke"));
+	}
+	commit_delegation(store, Delegation {
+		collective_address: delegation_params.collective_address,
+		delegator_address: delegation_params.delegator_address,
+		delegate_address: None,
+		tag: delegation_params.tag,
+		version: 0,
+	}, ActionOp::RevokeDelegation)
+}
+
+/// Api to get the active [Delegations](struct.Delegation.html) in a
+/// [Collective](struct.Collective.html).
+pub fn get_delegations(
+	store: &dyn Store,
+	collective_address: Address,
+) -> ProtocolLoveResult<DelegationsPayload> {
+	let delegations = t("get_delegations: ", get_active_delegations(store, &collective_address))?;
+	Ok(DelegationsPayload {
+		collective_address,
+		delegations,
+	})
+}
+
+/// Gets the latest delegation of each delegator & tag, skipping the revoked delegations.
+/// The most recent delegation is returned first.
+pub fn get_active_delegations(
+	store: &dyn Store,
+	collective_address: &Address,
+) -> ProtocolLoveResult<Vec<Delegation>> {
+	let delegations: Vec<Delegation> =
+		t("get_active_delegations: ", get_links_and_load_type(
+			store,
+			collective_address,
+			"collective->delegation",
+			None,
+		))?;
+	let mut scopes = HashSet::new();
+	Ok(delegations.into_iter()
+		.filter(|delegation| &delegation.collective_address == collective_address)
+		.filter(|delegation|
+			scopes.insert((delegation.delegator_address.clone(), delegation.tag.clone())))
+		.filter(|delegation| delegation.delegate_address.is_some())
+		.collect())
+}
+
+/// Gets the ballots cast by delegates on behalf of the people of the collective who did not vote.
+///
+/// The delegation chain of each person is followed until a delegate who voted.
+/// A chain ending in a cycle or without a vote is not counted.
+pub fn get_delegated_ballots(
+	store: &dyn Store,
+	proposal: &Proposal,
+	votes: &[Vote],
+) -> ProtocolLoveResult<Vec<Ballot>> {
+	let delegations =
+		t("get_delegated_ballots: ", get_active_delegations(store, &proposal.collective_address))?;
+	let person_addresses =
+		t("get_delegated_ballots: ", store.get_links(
+			&proposal.collective_address,
+			"collective->person",
+			None,
+		))?;
+	let ballots: HashMap<&Address, &Ballot> = votes.iter()
+		.map(|vote| (&vote.person_address, &vote.ballot))
+		.collect();
+	let mut delegated_ballots = vec![];
+	for person_address in person_addresses.iter() {
+		if ballots.contains_key(person_address) {
+			continue;
+		}
+		let mut current_address = person_address;
+		let mut visited = HashSet::new();
+		visited.insert(person_address);
+		while let Some(delegate_address) =
+			find_delegate(&delegations, current_address, &proposal.tags) {
+			if !visited.insert(delegate_address) {
+				break;
+			}
+			if let Some(ballot) = ballots.get(delegate_address) {
+				delegated_ballots.push((*ballot).clone());
+				break;
+			}
+			current_address = delegate_address;
+		}
+	}
+	Ok(delegated_ballots)
+}
+
+/// Finds the delegate of the `person_address` for a proposal with the `tags`.
+fn find_delegate<'a>(
+	delegations: &'a [Delegation],
+	person_address: &Address,
+	tags: &[String],
+) -> Option<&'a Address> {
+	let person_delegations: Vec<&Delegation> = delegations.iter()
+		.filter(|delegation| &delegation.delegator_address == person_address)
+		.collect();
+	person_delegations.iter()
+		.find(|delegation| match &delegation.tag {
+			Some(tag) => tags.contains(tag),
+			None => false,
+		})
+		.or_else(|| person_delegations.iter().find(|delegation| delegation.tag.is_none()))
+		.and_then(|delegation| delegation.delegate_address.as_ref())
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct DelegationActionData {
+	delegation_address: Address,
+	delegation: Delegation,
+}
+
+/// Commits & links the next version of the `delegation` of the delegator on the tag.
+fn commit_delegation(
+	store: &dyn Store,
+	delegation: Delegation,
+	op: ActionOp,
+) -> ProtocolLoveResult<DelegationPayload> {
+	let delegations: Vec<Delegation> =
+		t("commit_delegation: ", get_links_and_load_type(
+			store,
+			&delegation.collective_address,
+			"collective->delegation",
+			Some(&delegation.delegator_address.to_string()),
+		))?;
+	let version = delegations.into_iter()
+		.filter(|prev_delegation|
+			prev_delegation.collective_address == delegation.collective_address
+				&& prev_delegation.delegator_address == delegation.delegator_address
+				&& prev_delegation.tag == delegation.tag)
+		.map(|prev_delegation| prev_delegation.version)
+		.max()
+		.unwrap_or(0) + 1;
+	let delegation = Delegation {
+		version,
+		..delegation
+	};
+	let delegation_entry = Entry::App("delegation".into(), (&delegation).into());
+	let delegation_address = t("commit_delegation: ", store.commit_entry(&delegation_entry))?;
+	t("commit_delegation: collective->delegation: ", store.link_entries(
+		&delegation.collective_address,
+		&delegation_address,
+		"collective->delegation",
+		&delegation.delegator_address.to_string(),
+	))?;
+	t("commit_delegation: ", create_root_action(
+		store,
+		&delegation.collective_address,
+		op,
+		DelegationActionData {
+			delegation_address: delegation_address.clone(),
+			delegation: delegation.clone(),
+		}.into(),
+		serde_json::value::Value::Null.into(),
+		"delegation",
+	))?;
+	Ok(DelegationPayload {
+		delegation_address,
+		delegation,
+	})
+}
@@ -0,0 +1,331 @@
+use std::convert::TryFrom;
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::action::{ActionOp, create_root_action, get_actions};
+use crate::collective::{
+	Collective, is_collective_admin, is_collective_version, update_collective_entry,
+};
+use crate::critical_action::validate_proposal_action_executed;
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::ledger::{Ledger, get_collective_ledger, set_ledger_funds};
+use crate::person::{Person, PersonStatus};
+use crate::proposal::{Proposal, ProposalStatus};
+use crate::store::{Store, get_as_type};
+use crate::tally::ProposalOutcome;
+use crate::utils::t;
+use crate::validation::{ValidationContext, validate_timestamp};
+
+/// The end of a [Collective](struct.Collective.html), decided by a passed
+/// [Proposal](struct.Proposal.html) created from a
+/// [ProposalTemplate](struct.ProposalTemplate.html) with the
+/// [ActionOp::DissolveCollective](enum.ActionOp.html) op.
+///
+/// The funds of the primary [Ledger](struct.Ledger.html) are settled & the collective is
+/// archived. Its entries & action log stay available but no longer change.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct Dissolution {
+	pub collective_address: Address,
+	pub proposal_address: Address,
+	/// Time the collective was archived in seconds since the unix epoch.
+	pub archived_at: u64,
+	/// Funds paid out from the ledger of the collective.
+	pub settlements: Vec<LedgerSettlement>,
+}
+
+/// Funds of a dissolved [Collective](struct.Collective.html) paid out to a recipient.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct LedgerSettlement {
+	/// Address of the parent [Collective](struct.Collective.html), or of an active
+	/// [Person](struct.Person.html) of the dissolved collective.
+	pub recipient_address: Address,
+	pub amount: u64,
+}
+
+/// Api payload of the [Dissolution](struct.Dissolution.html) of a
+/// [Collective](struct.Collective.html). The `dissolution` is `None` while the collective is
+/// active.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct DissolutionPayload {
+	pub collective_address: Address,
+	pub collective: Collective,
+	pub dissolution: Option<Dissolution>,
+}
+
+/// Validates the collective is archived by a passed dissolution proposal.
+pub fn validate_collective_dissolution(
+	store: &dyn Store,
+	collective: &Collective,
+	prev_collective: &Collective,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let (archived_at, proposal_address) =
+		match (&collective.archived_at, &collective.dissolution_proposal_address) {
+			(Some(archived_at), Some(proposal_address)) => (*archived_at, proposal_address),
+			_ => return Err(ProtocolLoveError::validation_failed(
+				"dissolution_proposal_address", "Collective must be dissolved by a proposal")),
+		};
+	let proposal = validate_dissolution_proposal(store, proposal_address)?;
+	if !is_collective_version(store, &proposal.collective_address, prev_collective)? {
+		return Err(ProtocolLoveError::validation_failed(
+			"dissolution_proposal_address", "Proposal does not dissolve the collective"));
+	}
+	validate_timestamp("archived_at", archived_at, context)
+}
+
+/// Validates the proposal at `proposal_address` is a passed dissolution proposal whose action
+/// is executed.
+pub fn validate_dissolution_proposal(
+	store: &dyn Store,
+	proposal_address: &Address,
+) -> ProtocolLoveResult<Proposal> {
+	let proposal: Proposal = t("validation error: fetch dissolution proposal: ",
+		get_as_type(store, proposal_address))?;
+	if !is_dissolution_proposal(&proposal) {
+		return Err(ProtocolLoveError::validation_failed(
+			"dissolution_proposal_address", "Proposal does not dissolve the collective"));
+	}
+	if proposal.status != ProposalStatus::Closed || proposal.outcome != Some(ProposalOutcome::Passed) {
+		return Err(ProtocolLoveError::conflict("Proposal has not passed"));
+	}
+	validate_proposal_action_executed(store, &proposal, proposal_address)?;
+	Ok(proposal)
+}
+
+/// Is the proposal created from a template dissolving the collective?
+fn is_dissolution_proposal(proposal: &Proposal) -> bool {
+	proposal.template.as_ref()
+		.and_then(|template| template.action_op.as_ref())
+		.map_or(false, |action_op| action_op == &ActionOp::DissolveCollective)
+}
+
+/// Api for the admin to enact a passed dissolution [Proposal](struct.Proposal.html), archiving
+/// the collective at the `timestamp`.
+///
+/// The funds of the primary ledger go to the ledger of the parent collective, or are split
+/// evenly between the active people of the collective, the remainder going to the lowest
+/// addresses. The settlements are recorded with the
+/// [ActionOp::DissolveCollective](enum.ActionOp.html) action.
+pub fn dissolve_collective(
+	store: &dyn Store,
+	proposal_address: Address,
+	timestamp: u64,
+) -> ProtocolLoveResult<DissolutionPayload> {
+	let proposal: Proposal = t("dissolve_collective: ", get_as_type(store, &proposal_address))?;
+	if !is_dissolution_proposal(&proposal) {
+		return Err(ProtocolLoveError::validation_failed(
+			"proposal_address", "dissolve_collective: Proposal does not dissolve the collective"));
+	}
+	if proposal.status != ProposalStatus::Closed || proposal.outcome != Some(ProposalOutcome::Passed) {
+		return Err(ProtocolLoveError::conflict("dissolve_collective: Proposal has not passed"));
+	}
+	let collective_address = proposal.collective_address;
+	let saved_collective: Collective = t("dissolve_collective: ", get_as_type(store, &collective_address))?;
+	if saved_collective.archived_at.is_some() {
+		return Err(ProtocolLoveError::conflict("dissolve_collective: Collective is already dissolved"));
+	}
+	if !t("dissolve_collective: ", is_collective_admin(store, &saved_collective))? {
+		return Err(ProtocolLoveError::unauthorized(
+			"dissolve_collective: Only the admin can dissolve the collective"));
+	}
+	t("dissolve_collective: ", validate_proposal_action_executed(store, &proposal, &proposal_address))?;
+	let settlements = t("dissolve_collective: ", settle_ledger(
+		store,
+		&collective_address,
+		&saved_collective,
+		&proposal_address,
+	))?;
+	let dissolution = Dissolution {
+		collective_address: collective_address.clone(),
+		proposal_address: proposal_address.clone(),
+		archived_at: timestamp,
+		settlements,
+	};
+	t("dissolve_collective: ", create_root_action(
+		store,
+		&collective_address,
+		ActionOp::DissolveCollective,
+		dissolution.clone().into(),
+		serde_json::value::Value::Null.into(),
+		"dissolve_collective",
+	))?;
+	let collective = Collective {
+		archived_at: Some(timestamp),
+		dissolution_proposal_address: Some(proposal_address),
+		..saved_collective
+	};
+	t("dissolve_collective: ", update_collective_entry(store, &collective_address, &collective))?;
+	Ok(DissolutionPayload {
+		collective_address,
+		collective,
+		dissolution: Some(dissolution),
+	})
+}
+
+/// Api to get a [Collective](struct.Collective.html) & its
+/// [Dissolution](struct.Dissolution.html), read from the archived collective.
+pub fn get_dissolution(
+	store: &dyn Store,
+	collective_address: Address,
+) -> ProtocolLoveResult<DissolutionPayload> {
+	let collective: Collective = t("get_dissolution: ", get_as_type(store, &collective_address))?;
+	let dissolution = match (&collective.dissolution_proposal_address, collective.archived_at) {
+		(Some(proposal_address), Some(archived_at)) => Some(Dissolution {
+			collective_address: collective_address.clone(),
+			proposal_address: proposal_address.clone(),
+			archived_at,
+			settlements: t("get_dissolution: ", get_dissolution_settlements(
+				store,
+				&collective_address,
+				proposal_address,
+				archived_at,
+			))?,
+		}),
+		_ => None,
+	};
+	Ok(DissolutionPayload {
+		collective_address,
+		collective,
+		dissolution,
+	})
+}
+
+/// Reads the settlements of the dissolution from the
+/// [ActionOp::DissolveCollective](enum.ActionOp.html) action recorded by
+/// [dissolve_collective](fn.dissolve_collective.html), paying out the funds settled from the
+/// primary ledger by the proposal. The action is the latest matching record, as the collective
+/// is archived right after it.
+fn get_dissolution_settlements(
+	store: &dyn Store,
+	collective_address: &Address,
+	proposal_address: &Address,
+	archived_at: u64,
+) -> ProtocolLoveResult<Vec<LedgerSettlement>> {
+	let settled_funds = t("get_dissolution_settlements: ",
+		get_settled_funds(store, collective_address, proposal_address))?;
+	if settled_funds == 0 {
+		return Ok(vec![]);
+	}
+	t("get_dissolution_settlements: ", get_actions(store, collective_address.clone()))?
+		.actions
+		.into_iter()
+		.filter(|action| action.op == ActionOp::DissolveCollective)
+		.filter_map(|action| Dissolution::try_from(action.data).ok())
+		.filter(|dissolution| &dissolution.collective_address == collective_address
+			&& &dissolution.proposal_address == proposal_address
+			&& dissolution.archived_at == archived_at
+			&& dissolution.settlements.iter()
+				.try_fold(0u64, |total, settlement| total.checked_add(settlement.amount))
+				== Some(settled_funds))
+		.last()
+		.map(|dissolution| dissolution.settlements)
+		.ok_or_else(|| ProtocolLoveError::invariant_broken(
+			"get_dissolution_settlements: Settlements of the dissolution are not recorded"))
+}
+
+/// Funds debited from the primary ledger of the collective by the dissolution proposal.
+fn get_settled_funds(
+	store: &dyn Store,
+	collective_address: &Address,
+	proposal_address: &Address,
+) -> ProtocolLoveResult<u64> {
+	let ledger_address = t("get_settled_funds: ", get_collective_ledger(store, collective_address))?;
+	let ledgers = t("get_settled_funds: ", store.get_entry_history(&ledger_address))?
+		.into_iter()
+		.filter_map(|entry| match entry {
+			Entry::App(_, entry_value) => Ledger::try_from(entry_value).ok(),
+			_ => None,
+		})
+		.collect::<Vec<Ledger>>();
+	Ok(ledgers.windows(2)
+		.find(|versions| versions[1].proposal_address.as_ref() == Some(proposal_address))
+		.map_or(0, |versions| versions[0].funds))
+}
+
+/// Pays out the funds of the primary ledger of the collective dissolved by the proposal at
+/// `proposal_address`, returning the settlements.
+fn settle_ledger(
+	store: &dyn Store,
+	collective_address: &Address,
+	collective: &Collective,
+	proposal_address: &Address,
+) -> ProtocolLoveResult<Vec<LedgerSettlement>> {
+	let ledger_address = t("settle_ledger: ", get_collective_ledger(store, collective_address))?;
+	let ledger: Ledger = t("settle_ledger: ", get_as_type(store, &ledger_address))?;
+	if ledger.funds == 0 {
+		return Ok(vec![]);
+	}
+	let funds = ledger.funds;
+	let parent_address = match &collective.parent_address {
+		Some(parent_address) => {
+			let parent: Collective = t("settle_ledger: ", get_as_type(store, parent_address))?;
+			if parent.archived_at.is_none() { Some(parent_address.clone()) } else { None }
+		}
+		None => None,
+	};
+	let settlements = match parent_address {
+		Some(parent_address) => {
+			let parent_ledger_address = t("settle_ledger: ", get_collective_ledger(store, &parent_address))?;
+			let parent_ledger: Ledger = t("settle_ledger: ", get_as_type(store, &parent_ledger_address))?;
+			let parent_funds = match parent_ledger.funds.checked_add(funds) {
+				Some(parent_funds) => parent_funds,
+				None => return Err(ProtocolLoveError::conflict(
+					"settle_ledger: Funds exceed the ledger of the parent collective")),
+			};
+			t("settle_ledger: ", set_ledger_funds(
+				store,
+				&parent_address,
+				&parent_ledger_address,
+				parent_ledger,
+				parent_funds,
+				Some(proposal_address.clone()),
+				ActionOp::SettleLedgerFunds,
+			))?;
+			vec![LedgerSettlement {
+				recipient_address: parent_address,
+				amount: funds,
+			}]
+		}
+		None => {
+			let mut person_addresses = vec![];
+			for person_address in t("settle_ledger: ", store.get_links(
+				collective_address,
+				"collective->person",
+				None,
+			))? {
+				let person: Person = t("settle_ledger: ", get_as_type(store, &person_address))?;
+				if let PersonStatus::Active = person.status {
+					person_addresses.push(person_address);
+				}
+			}
+			if person_addresses.is_empty() {
+				return Err(ProtocolLoveError::conflict(
+					"settle_ledger: Collective has no active people to settle its ledger with"));
+			}
+			person_addresses.sort();
+			let share = funds / person_addresses.len() as u64;
+			let remainder = (funds % person_addresses.len() as u64) as usize;
+			person_addresses.into_iter()
+				.enumerate()
+				.map(|(index, recipient_address)| LedgerSettlement {
+					recipient_address,
+					amount: if index < remainder { share + 1 } else { share },
+				})
+				.filter(|settlement| settlement.amount > 0)
+				.collect()
+		}
+	};
+	t("settle_ledger: ", set_ledger_funds(
+		store,
+		collective_address,
+		&ledger_address,
+		ledger,
+		0,
+		Some(proposal_address.clone()),
+		ActionOp::SettleLedgerFunds,
+	))?;
+	Ok(settlements)
+}
@@ -0,0 +1,1054 @@
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use hdk::holochain_core_types::dna::entry_types::Sharing;
+use hdk::prelude::ValidatingEntryType;
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::action::{ActionOp, create_root_action};
+use crate::collective::{
+	Collective, CollectivePayload, get_policy_collective, is_collective_admin, is_collective_person,
+	update_collective_entry,
+};
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::person::{Person, get_agent_person};
+use crate::proposal::{
+	Proposal, ProposalParams, ProposalPayload, ProposalStatus, TallyMethod, close_proposal,
+	create_proposal,
+};
+use crate::store::{Store, HdkStore, get_as_type, get_links_and_load_type};
+use crate::tally::ProposalOutcome;
+use crate::utils::t;
+use crate::validation::{
+	EntryValidation, LinkValidation, ValidationContext, from_hdk_link_validation_data,
+	from_hdk_validation_data, validate_app_link, validate_timestamp,
+};
+use crate::vote::tally_proposal;
+
+/// A role of a [Collective](struct.Collective.html), e.g. treasurer or facilitator.
+///
+/// The role is held for a term by the winner of an [Election](struct.Election.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct Role {
+	pub collective_address: Address,
+	pub name: String,
+	pub description: String,
+	/// Length of a term in seconds.
+	pub term_length: u64,
+	/// Maximum number of consecutive terms a person holds the role. `0` sets no limit.
+	pub term_limit: u64,
+}
+
+/// An election filling the next term of a [Role](struct.Role.html).
+///
+/// Candidates are nominated until voting opens on a [Proposal](struct.Proposal.html) with an
+/// option for each candidate, tallied with the `election_tally_method` of the collective.
+/// Nominations of fewer than two candidates close without voting, electing a single candidate
+/// uncontested.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct Election {
+	pub collective_address: Address,
+	pub role_address: Address,
+	/// Term filled by the election, starting at 1.
+	pub term: u64,
+	/// Addresses of the candidate [People](struct.Person.html), in the order of the options of
+	/// the proposal.
+	pub candidate_addresses: Vec<Address>,
+	/// Address of the [Proposal](struct.Proposal.html) voting on the candidates, once
+	/// nominations close.
+	pub proposal_address: Option<Address>,
+	/// Time the election started in seconds since the unix epoch.
+	pub started_at: u64,
+	/// Time the nominations closed without voting in seconds since the unix epoch.
+	#[serde(default)]
+	pub closed_at: Option<u64>,
+}
+
+/// A term of a [Role](struct.Role.html) held by the winner of its [Election](struct.Election.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct RoleTerm {
+	pub role_address: Address,
+	pub election_address: Address,
+	/// Address of the [Person](struct.Person.html) holding the role.
+	pub person_address: Address,
+	pub term: u64,
+	/// Start of the term in seconds since the unix epoch.
+	pub started_at: u64,
+	/// End of the term in seconds since the unix epoch, exclusive.
+	pub ends_at: u64,
+}
+
+impl RoleTerm {
+	/// Is the term running at the `timestamp`?
+	pub fn is_current(&self, timestamp: u64) -> bool {
+		self.started_at <= timestamp && timestamp < self.ends_at
+	}
+}
+
+/// Api params for [create_role](fn.create_role.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct RoleParams {
+	pub collective_address: Address,
+	pub name: String,
+	pub description: Option<String>,
+	/// Length of a term in seconds.
+	pub term_length: u64,
+	/// Optional maximum number of consecutive terms, defaults to no limit.
+	pub term_limit: Option<u64>,
+}
+
+/// Api params for [start_election](fn.start_election.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ElectionParams {
+	pub role_address: Address,
+	/// Address of the [Person](struct.Person.html) starting the election.
+	pub person_address: Address,
+	pub timestamp: u64,
+}
+
+/// Api params for [nominate_candidate](fn.nominate_candidate.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct NominationParams {
+	pub election_address: Address,
+	/// Address of the [Person](struct.Person.html) nominating the candidate.
+	pub nominator_address: Address,
+	pub candidate_address: Address,
+}
+
+/// Api payload of a [Role](struct.Role.html) with its current holder & election.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct RolePayload {
+	pub role_address: Address,
+	pub role: Role,
+	/// The [RoleTerm](struct.RoleTerm.html) running at the requested time.
+	pub holder: Option<RoleTerm>,
+	/// Address of the [Election](struct.Election.html) in progress.
+	pub election_address: Option<Address>,
+}
+
+/// Api payload of the [Roles](struct.Role.html) of a [Collective](struct.Collective.html),
+/// oldest first.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct CollectiveRolesPayload {
+	pub collective_address: Address,
+	pub roles: Vec<RolePayload>,
+}
+
+/// Api payload containing the `election_address` & [election](struct.Election.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ElectionPayload {
+	pub election_address: Address,
+	pub election: Election,
+}
+
+/// Api payload of the [Elections](struct.Election.html) started by
+/// [start_expired_elections](fn.start_expired_elections.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct CollectiveElectionsPayload {
+	pub collective_address: Address,
+	pub elections: Vec<ElectionPayload>,
+}
+
+/// Returns a Holochain entry definition for a role.
+pub fn role_def() -> ValidatingEntryType {
+	entry!(
+		name: "role",
+		description: "A protocol.love role of a collective",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<Role>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_role_entry(&HdkStore, validation, &context)?)
+		},
+		links: [
+			to!(
+				"election",
+				link_type: "role->election",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"role_term",
+				link_type: "role->role_term",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			)
+		]
+	)
+}
+
+/// Returns a Holochain entry definition for an election.
+pub fn election_def() -> ValidatingEntryType {
+	entry!(
+		name: "election",
+		description: "A protocol.love election of a role",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<Election>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_election_entry(&HdkStore, validation, &context)?)
+		}
+	)
+}
+
+/// Returns a Holochain entry definition for a role term.
+pub fn role_term_def() -> ValidatingEntryType {
+	entry!(
+		name: "role_term",
+		description: "A protocol.love term of an elected role",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<RoleTerm>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_role_term_entry(&HdkStore, validation, &context)?)
+		}
+	)
+}
+
+/// Validation rules of a [Role](struct.Role.html) entry.
+pub fn validate_role_entry(
+	store: &dyn Store,
+	validation: EntryValidation<Role>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let collective: Collective = t("validation error: role: fetch collective: ",
+				get_as_type(store, &entry.collective_address))?;
+			let admin_agent_address = match &collective.admin_address {
+				Some(admin_address) => {
+					let admin: Person = t("validation error: role: fetch admin: ",
+						get_as_type(store, admin_address))?;
+					Some(admin.agent_address)
+				}
+				None => None,
+			};
+			match admin_agent_address {
+				Some(agent_address) if context.sources.contains(&agent_address) => {}
+				_ => return Err(ProtocolLoveError::unauthorized("Only the admin can create roles")),
+			}
+			if entry.name.trim().is_empty() || entry.name.len() > 64 {
+				return Err(ProtocolLoveError::validation_failed(
+					"name", "Name must be between 1 & 64 characters"));
+			}
+			if entry.term_length == 0 {
+				return Err(ProtocolLoveError::validation_failed(
+					"term_length", "Term length must be positive"));
+			}
+			Ok(())
+		}
+		EntryValidation::Modify { .. } =>
+			Err(ProtocolLoveError::unauthorized("Role cannot be modified")),
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Role cannot be deleted")),
+	}
+}
+
+/// Validation rules of an [Election](struct.Election.html) entry.
+///
+/// An election starts once the term of the role has expired. Candidates are nominated one at
+/// a time, within the term limit of the role, until voting opens.
+pub fn validate_election_entry(
+	store: &dyn Store,
+	validation: EntryValidation<Election>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let role: Role = t("validation error: election: fetch role: ",
+				get_as_type(store, &entry.role_address))?;
+			if role.collective_address != entry.collective_address {
+				return Err(ProtocolLoveError::validation_failed(
+					"role_address", "Role must be in the collective"));
+			}
+			validate_timestamp("started_at", entry.started_at, context)?;
+			if !entry.candidate_addresses.is_empty()
+				|| entry.proposal_address.is_some()
+				|| entry.closed_at.is_some() {
+				return Err(ProtocolLoveError::validation_failed(
+					"candidate_addresses", "Election must start without candidates"));
+			}
+			if let Some(role_term) = get_latest_role_term(store, &entry.role_address)? {
+				if entry.started_at < role_term.ends_at {
+					return Err(ProtocolLoveError::conflict("Role term has not expired"));
+				}
+			}
+			if entry.term != get_next_term(store, &entry.role_address)? {
+				return Err(ProtocolLoveError::validation_failed(
+					"term", "Election must fill the next term"));
+			}
+			if get_election_in_progress(store, &entry.role_address)?.is_some() {
+				return Err(ProtocolLoveError::conflict("Election is already in progress"));
+			}
+			if let Some(latest_election) = get_latest_election(store, &entry.role_address)? {
+				if entry.started_at <= latest_election.election.started_at {
+					return Err(ProtocolLoveError::validation_failed(
+						"started_at", "Election must start after the previous election"));
+				}
+			}
+			Ok(())
+		}
+		EntryValidation::Modify { new_entry, old_entry } => {
+			let unchanged = Election {
+				candidate_addresses: old_entry.candidate_addresses.clone(),
+				proposal_address: old_entry.proposal_address.clone(),
+				closed_at: old_entry.closed_at,
+				..new_entry.clone()
+			};
+			if unchanged != old_entry {
+				return Err(ProtocolLoveError::unauthorized(
+					"Only the candidates & proposal of an election can change"));
+			}
+			if old_entry.proposal_address.is_some() || old_entry.closed_at.is_some() {
+				return Err(ProtocolLoveError::conflict("Nominations are closed"));
+			}
+			if let Some(closed_at) = new_entry.closed_at {
+				if new_entry.candidate_addresses != old_entry.candidate_addresses
+					|| new_entry.proposal_address.is_some() {
+					return Err(ProtocolLoveError::validation_failed(
+						"closed_at", "Nominations close without voting"));
+				}
+				if new_entry.candidate_addresses.len() >= 2 {
+					return Err(ProtocolLoveError::validation_failed(
+						"closed_at", "Election of two or more candidates must be voted on"));
+				}
+				if closed_at < new_entry.started_at {
+					return Err(ProtocolLoveError::validation_failed(
+						"closed_at", "Nominations cannot close before the election starts"));
+				}
+				return validate_timestamp("closed_at", closed_at, context);
+			}
+			let role: Role = t("validation error: election: fetch role: ",
+				get_as_type(store, &new_entry.role_address))?;
+			match &new_entry.proposal_address {
+				None => {
+					let candidate_count = old_entry.candidate_addresses.len();
+					if new_entry.candidate_addresses.len() != candidate_count + 1
+						|| !new_entry.candidate_addresses.starts_with(&old_entry.candidate_addresses) {
+						return Err(ProtocolLoveError::validation_failed(
+							"candidate_addresses", "Candidates are nominated one at a time"));
+					}
+					validate_candidate(
+						store,
+						&role,
+						&new_entry.role_address,
+						&new_entry.candidate_addresses[candidate_count],
+						&old_entry.candidate_addresses,
+					)
+				}
+				Some(proposal_address) => {
+					if new_entry.candidate_addresses != old_entry.candidate_addresses {
+						return Err(ProtocolLoveError::validation_failed(
+							"candidate_addresses", "Candidates cannot change when voting opens"));
+					}
+					if new_entry.candidate_addresses.len() < 2 {
+						return Err(ProtocolLoveError::validation_failed(
+							"candidate_addresses", "Election needs at least two candidates"));
+					}
+					let proposal: Proposal = t("validation error: election: fetch proposal: ",
+						get_as_type(store, proposal_address))?;
+					let collective: Collective = t("validation error: election: fetch collective: ",
+						get_as_type(store, &new_entry.collective_address))?;
+					let election_tally_method =
+						get_policy_collective(store, &collective)?.election_tally_method;
+					if proposal.collective_address != new_entry.collective_address
+						|| proposal.options.len() != new_entry.candidate_addresses.len()
+						|| proposal.tally_method != election_tally_method {
+						return Err(ProtocolLoveError::validation_failed(
+							"proposal_address",
+							"Proposal must vote on the candidates with the election tally method"));
+					}
+					Ok(())
+				}
+			}
+		}
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Election cannot be deleted")),
+	}
+}
+
+/// Validation rules of a [RoleTerm](struct.RoleTerm.html) entry.
+///
+/// The role is assigned to the winner of the election for the term length, within the term
+/// limit of the role.
+pub fn validate_role_term_entry(
+	store: &dyn Store,
+	validation: EntryValidation<RoleTerm>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let role: Role = t("validation error: role_term: fetch role: ",
+				get_as_type(store, &entry.role_address))?;
+			let election: Election = t("validation error: role_term: fetch election: ",
+				get_as_type(store, &entry.election_address))?;
+			if election.role_address != entry.role_address || election.term != entry.term {
+				return Err(ProtocolLoveError::validation_failed(
+					"election_address", "Term must be filled by its election"));
+			}
+			if entry.term != get_next_term(store, &entry.role_address)? {
+				return Err(ProtocolLoveError::conflict("Term is already assigned"));
+			}
+			let winner_address = match (&election.proposal_address, election.closed_at) {
+				(Some(proposal_address), _) => {
+					let proposal: Proposal = t("validation error: role_term: fetch proposal: ",
+						get_as_type(store, proposal_address))?;
+					if proposal.status != ProposalStatus::Closed
+						|| proposal.outcome != Some(ProposalOutcome::Passed) {
+						return Err(ProtocolLoveError::conflict("Election has no winner"));
+					}
+					let tally = tally_proposal(store, proposal_address, &proposal)?;
+					tally.winners.first()
+						.and_then(|winner| election.candidate_addresses.get(*winner))
+						.cloned()
+				}
+				(None, Some(_)) => match election.candidate_addresses.as_slice() {
+					[candidate_address] => Some(candidate_address.clone()),
+					_ => return Err(ProtocolLoveError::conflict("Election has no winner")),
+				},
+				(None, None) => return Err(ProtocolLoveError::conflict("Election voting is not open")),
+			};
+			if winner_address.as_ref() != Some(&entry.person_address) {
+				return Err(ProtocolLoveError::validation_failed(
+					"person_address", "Role must be assigned to the winner of the election"));
+			}
+			if has_reached_term_limit(store, &role, &entry.role_address, &entry.person_address)? {
+				return Err(ProtocolLoveError::validation_failed(
+					"person_address", "Person has reached the term limit of the role"));
+			}
+			if entry.ends_at != entry.started_at.saturating_add(role.term_length) {
+				return Err(ProtocolLoveError::validation_failed(
+					"ends_at", "Term must last the term length of the role"));
+			}
+			validate_timestamp("started_at", entry.started_at, context)
+		}
+		EntryValidation::Modify { .. } =>
+			Err(ProtocolLoveError::unauthorized("Role term cannot be modified")),
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Role term cannot be deleted")),
+	}
+}
+
+/// Validation rules of a `role->election` link: the election fills a term of the base role.
+pub fn validate_role_election_link(
+	store: &dyn Store,
+	link: &LinkValidation,
+	_context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let election: Election =
+		t("validation error: role->election: fetch election: ", get_as_type(store, &link.target))?;
+	if election.role_address != link.base {
+		return Err(ProtocolLoveError::validation_failed("target", "Election is not of the role"));
+	}
+	Ok(())
+}
+
+/// Validation rules of a `role->role_term` link: the term is of the base role.
+pub fn validate_role_role_term_link(
+	store: &dyn Store,
+	link: &LinkValidation,
+	_context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let role_term: RoleTerm =
+		t("validation error: role->role_term: fetch role term: ", get_as_type(store, &link.target))?;
+	if role_term.role_address != link.base {
+		return Err(ProtocolLoveError::validation_failed("target", "Term is not of the role"));
+	}
+	Ok(())
+}
+
+/// Validates the candidate is a person in the collective, not yet nominated & within the term
+/// limit of the role.
+fn validate_candidate(
+	store: &dyn Store,
+	role: &Role,
+	role_address: &Address,
+	candidate_address: &Address,
+	candidate_addresses: &[Address],
+) -> ProtocolLoveResult<()> {
+	if !is_collective_person(store, &role.collective_address, candidate_address)? {
+		return Err(ProtocolLoveError::validation_failed(
+			"candidate_addresses", "Candidates must be people in the collective"));
+	}
+	if candidate_addresses.contains(candidate_address) {
+		return Err(ProtocolLoveError::conflict("Person is already a candidate"));
+	}
+	if has_reached_term_limit(store, role, role_address, candidate_address)? {
+		return Err(ProtocolLoveError::validation_failed(
+			"candidate_addresses", "Person has reached the term limit of the role"));
+	}
+	Ok(())
+}
+
+/// Has the person held the role for the consecutive terms allowed by its `term_limit`?
+fn has_reached_term_limit(
+	store: &dyn Store,
+	role: &Role,
+	role_address: &Address,
+	person_address: &Address,
+) -> ProtocolLoveResult<bool> {
+	if role.term_limit == 0 {
+		return Ok(false);
+	}
+	let role_terms = t("has_reached_term_limit: ", get_role_terms(store, role_address))?;
+	let consecutive_terms = role_terms.iter()
+		.take_while(|role_term| &role_term.person_address == person_address)
+		.count() as u64;
+	Ok(consecutive_terms >= role.term_limit)
+}
+
+/// Api for the admin to create a [Role](struct.Role.html) of the collective.
+pub fn create_role(store: &dyn Store, role_params: RoleParams) -> ProtocolLoveResult<RolePayload> {
+	let collective: Collective =
+		t("create_role: ", get_as_type(store, &role_params.collective_address))?;
+	if !t("create_role: ", is_collective_admin(store, &collective))? {
+		return Err(ProtocolLoveError::unauthorized("create_role: Only the admin can create roles"));
+	}
+	let role = Role {
+		collective_address: role_params.collective_address,
+		name: role_params.name,
+		description: role_params.description.unwrap_or_default(),
+		term_length: role_params.term_length,
+		term_limit: role_params.term_limit.unwrap_or_default(),
+	};
+	let role_entry = Entry::App("role".into(), (&role).into());
+	let role_address = t("create_role: ", store.commit_entry(&role_entry))?;
+	t("create_role: collective->role: ", store.link_entries(
+		&role.collective_address,
+		&role_address,
+		"collective->role",
+		"",
+	))?;
+	t("create_role: ", create_root_action(
+		store,
+		&role.collective_address,
+		ActionOp::CreateRole,
+		RoleActionData {
+			role_address: role_address.clone(),
+			role: role.clone(),
+		}.into(),
+		serde_json::value::Value::Null.into(),
+		"create_role",
+	))?;
+	Ok(RolePayload {
+		role_address,
+		role,
+		holder: None,
+		election_address: None,
+	})
+}
+
+/// Api to get a [Role](struct.Role.html) with its holder at the `timestamp`.
+pub fn get_role(
+	store: &dyn Store,
+	role_address: Address,
+	timestamp: u64,
+) -> ProtocolLoveResult<RolePayload> {
+	let role: Role = t("get_role: ", get_as_type(store, &role_address))?;
+	let holder = t("get_role: ", get_latest_role_term(store, &role_address))?
+		.filter(|role_term| role_term.is_current(timestamp));
+	let election_address = t("get_role: ", get_election_in_progress(store, &role_address))?
+		.map(|election_payload| election_payload.election_address);
+	Ok(RolePayload {
+		role_address,
+		role,
+		holder,
+		election_address,
+	})
+}
+
+/// Api to get the [Roles](struct.Role.html) of a [Collective](struct.Collective.html) with
+/// their holders at the `timestamp`.
+pub fn get_collective_roles(
+	store: &dyn Store,
+	collective_address: Address,
+	timestamp: u64,
+) -> ProtocolLoveResult<CollectiveRolesPayload> {
+	let role_addresses =
+		t("get_collective_roles: ", store.get_links(&collective_address, "collective->role", None))?;
+	let roles = role_addresses.into_iter()
+		.rev()
+		.map(|role_address| get_role(store, role_address, timestamp))
+		.collect::<ProtocolLoveResult<Vec<RolePayload>>>()?;
+	Ok(CollectiveRolesPayload {
+		collective_address,
+		roles,
+	})
+}
+
+/// Api for a person in the collective to start the [Election](struct.Election.html) of the
+/// next term of a vacant [Role](struct.Role.html).
+pub fn start_election(
+	store: &dyn Store,
+	election_params: ElectionParams,
+) -> ProtocolLoveResult<ElectionPayload> {
+	let ElectionParams {
+		role_address,
+		person_address,
+		timestamp,
+	} = election_params;
+	let role: Role = t("start_election: ", get_as_type(store, &role_address))?;
+	t("start_election: ", validate_elector(store, &role.collective_address, &person_address))?;
+	if t("start_election: ", get_election_in_progress(store, &role_address))?.is_some() {
+		return Err(ProtocolLoveError::conflict("start_election: Election is already in progress"));
+	}
+	if let Some(role_term) = t("start_election: ", get_latest_role_term(store, &role_address))? {
+		if timestamp < role_term.ends_at {
+			return Err(ProtocolLoveError::conflict("start_election: Role term has not expired"));
+		}
+	}
+	t("start_election: ", commit_election(store, &role, role_address, timestamp))
+}
+
+/// Api for a person in the collective to start the [Elections](struct.Election.html) of the
+/// [Roles](struct.Role.html) whose term has expired at the `timestamp`.
+pub fn start_expired_elections(
+	store: &dyn Store,
+	collective_address: Address,
+	person_address: Address,
+	timestamp: u64,
+) -> ProtocolLoveResult<CollectiveElectionsPayload> {
+	t("start_expired_elections: ", validate_elector(store, &collective_address, &person_address))?;
+	let role_addresses =
+		t("start_expired_elections: ", store.get_links(&collective_address, "collective->role", None))?;
+	let mut elections: Vec<ElectionPayload> = vec![];
+	for role_address in role_addresses.into_iter().rev() {
+		let has_expired = t("start_expired_elections: ", get_latest_role_term(store, &role_address))?
+			.map_or(false, |role_term| role_term.ends_at <= timestamp);
+		if !has_expired
+			|| t("start_expired_elections: ", get_election_in_progress(store, &role_address))?.is_some() {
+			continue;
+		}
+		let role: Role = t("start_expired_elections: ", get_as_type(store, &role_address))?;
+		elections.push(t("start_expired_elections: ", commit_election(
+			store,
+			&role,
+			role_address,
+			timestamp,
+		))?);
+	}
+	Ok(CollectiveElectionsPayload {
+		collective_address,
+		elections,
+	})
+}
+
+/// Api for a person in the collective to nominate a candidate in an
+/// [Election](struct.Election.html), until voting opens.
+pub fn nominate_candidate(
+	store: &dyn Store,
+	nomination_params: NominationParams,
+) -> ProtocolLoveResult<ElectionPayload> {
+	let NominationParams {
+		election_address,
+		nominator_address,
+		candidate_address,
+	} = nomination_params;
+	let saved_election: Election = t("nominate_candidate: ", get_as_type(store, &election_address))?;
+	t("nominate_candidate: ", validate_elector(store, &saved_election.collective_address, &nominator_address))?;
+	if saved_election.proposal_address.is_some() || saved_election.closed_at.is_some() {
+		return Err(ProtocolLoveError::conflict("nominate_candidate: Nominations are closed"));
+	}
+	let mut candidate_addresses = saved_election.candidate_addresses.clone();
+	candidate_addresses.push(candidate_address.clone());
+	let election = Election {
+		candidate_addresses,
+		..saved_election.clone()
+	};
+	t("nominate_candidate: ", update_election_entry(store, &election_address, &election))?;
+	t("nominate_candidate: ", create_root_action(
+		store,
+		&election.collective_address,
+		ActionOp::NominateCandidate,
+		NominationActionData {
+			election_address: election_address.clone(),
+			nominator_address,
+			candidate_address,
+		}.into(),
+		serde_json::value::Value::Null.into(),
+		"nominate_candidate",
+	))?;
+	Ok(ElectionPayload {
+		election_address,
+		election,
+	})
+}
+
+/// Api to close the nominations of an [Election](struct.Election.html) & open voting on a
+/// [Proposal](struct.Proposal.html) authored by the person at `author_address`.
+///
+/// The proposal has an option for each candidate & is tallied with the `election_tally_method`
+/// of the collective.
+pub fn open_election_voting(
+	store: &dyn Store,
+	election_address: Address,
+	author_address: Address,
+) -> ProtocolLoveResult<ElectionPayload> {
+	let saved_election: Election = t("open_election_voting: ", get_as_type(store, &election_address))?;
+	if saved_election.proposal_address.is_some() || saved_election.closed_at.is_some() {
+		return Err(ProtocolLoveError::conflict("open_election_voting: Nominations are closed"));
+	}
+	if saved_election.candidate_addresses.len() < 2 {
+		return Err(ProtocolLoveError::validation_failed(
+			"candidate_addresses", "open_election_voting: Election needs at least two candidates"));
+	}
+	let role: Role = t("open_election_voting: ", get_as_type(store, &saved_election.role_address))?;
+	let collective: Collective =
+		t("open_election_voting: ", get_as_type(store, &saved_election.collective_address))?;
+	let election_tally_method =
+		t("open_election_voting: ", get_policy_collective(store, &collective))?.election_tally_method;
+	let options = saved_election.candidate_addresses.iter()
+		.map(|candidate_address| {
+			let candidate: Person = t("open_election_voting: ", get_as_type(store, candidate_address))?;
+			Ok(candidate.name)
+		})
+		.collect::<ProtocolLoveResult<Vec<String>>>()?;
+	let ProposalPayload { proposal_address, .. } =
+		t("open_election_voting: ", create_proposal(store, ProposalParams {
+			collective_address: saved_election.collective_address.clone(),
+			author_address,
+			name: format!("Elect the {}", role.name),
+			content: format!("Elect the {} for term {}", role.name, saved_election.term),
+			ballot_mode: None,
+			tally_method: Some(election_tally_method),
+			options: Some(options),
+			tags: None,
+			requested_funds: None,
+			status: None,
+			co_author_addresses: None,
+			discussion_window: None,
+			voting_window: None,
+			federated_proposal_address: None,
+			template_address: None,
+			template_values: None,
+		}))?;
+	let election = Election {
+		proposal_address: Some(proposal_address),
+		..saved_election
+	};
+	t("open_election_voting: ", update_election_entry(store, &election_address, &election))?;
+	Ok(ElectionPayload {
+		election_address,
+		election,
+	})
+}
+
+/// Api for the author of the election proposal to close an [Election](struct.Election.html)
+/// & assign the [Role](struct.Role.html) to the winner for a term starting at the `timestamp`.
+///
+/// An election of fewer than two candidates closes its nominations without voting & assigns the
+/// role to a single candidate uncontested. The role stays vacant when the election is tied, has
+/// no winner or no candidates, & a new election of the term can start.
+pub fn close_election(
+	store: &dyn Store,
+	election_address: Address,
+	timestamp: u64,
+) -> ProtocolLoveResult<RolePayload> {
+	let saved_election: Election = t("close_election: ", get_as_type(store, &election_address))?;
+	if t("close_election: ", get_next_term(store, &saved_election.role_address))? != saved_election.term {
+		return Err(ProtocolLoveError::conflict("close_election: Term is already assigned"));
+	}
+	let proposal_address = match saved_election.proposal_address.clone() {
+		Some(proposal_address) => proposal_address,
+		None => {
+			if saved_election.candidate_addresses.len() >= 2 {
+				return Err(ProtocolLoveError::conflict("close_election: Election voting is not open"));
+			}
+			let election = match saved_election.closed_at {
+				Some(_) => saved_election,
+				None => {
+					let election = Election {
+						closed_at: Some(timestamp),
+						..saved_election
+					};
+					t("close_election: ", update_election_entry(store, &election_address, &election))?;
+					election
+				}
+			};
+			if let Some(person_address) = election.candidate_addresses.first() {
+				t("close_election: ", assign_role(
+					store,
+					&election_address,
+					&election,
+					person_address.clone(),
+					timestamp,
+				))?;
+			}
+			return get_role(store, election.role_address, timestamp);
+		}
+	};
+	let election = saved_election;
+	let saved_proposal: Proposal = t("close_election: ", get_as_type(store, &proposal_address))?;
+	if saved_proposal.status != ProposalStatus::Closed {
+		t("close_election: ", close_proposal(store, proposal_address.clone(), timestamp))?;
+	}
+	let proposal: Proposal = t("close_election: ", get_as_type(store, &proposal_address))?;
+	if proposal.outcome == Some(ProposalOutcome::Passed) {
+		let tally = t("close_election: ", tally_proposal(store, &proposal_address, &proposal))?;
+		let person_address = match tally.winners.first()
+			.and_then(|winner| election.candidate_addresses.get(*winner)) {
+			Some(person_address) => person_address.clone(),
+			None => return Err(ProtocolLoveError::invariant_broken(
+				"close_election: Election has no winner")),
+		};
+		t("close_election: ", assign_role(store, &election_address, &election, person_address, timestamp))?;
+	}
+	get_role(store, election.role_address, timestamp)
+}
+
+/// Api for the admin to set the [TallyMethod](enum.TallyMethod.html) of the elections of the
+/// [Collective](struct.Collective.html).
+pub fn set_election_tally_method(
+	store: &dyn Store,
+	collective_address: Address,
+	election_tally_method: TallyMethod,
+) -> ProtocolLoveResult<CollectivePayload> {
+	let saved_collective: Collective =
+		t("set_election_tally_method: ", get_as_type(store, &collective_address))?;
+	if !t("set_election_tally_method: ", is_collective_admin(store, &saved_collective))? {
+		return Err(ProtocolLoveError::unauthorized(
+			"set_election_tally_method: Only the admin can set the election tally method"));
+	}
+	if saved_collective.inherit_policies {
+		return Err(ProtocolLoveError::conflict(
+			"set_election_tally_method: Collective inherits the policies of its parent"));
+	}
+	match election_tally_method {
+		TallyMethod::Approval | TallyMethod::RankedChoice | TallyMethod::Borda | TallyMethod::Schulze => {}
+		_ => return Err(ProtocolLoveError::validation_failed(
+			"election_tally_method",
+			"set_election_tally_method: Elections are tallied by Approval, RankedChoice, Borda or Schulze")),
+	}
+	let collective = Collective {
+		election_tally_method: election_tally_method.clone(),
+		..saved_collective.clone()
+	};
+	t("set_election_tally_method: ", update_collective_entry(store, &collective_address, &collective))?;
+	t("set_election_tally_method: ", create_root_action(
+		store,
+		&collective_address,
+		ActionOp::SetElectionTallyMethod,
+		ElectionTallyMethodActionData {
+			election_tally_method,
+		}.into(),
+		ElectionTallyMethodActionData {
+			election_tally_method: saved_collective.election_tally_method,
+		}.into(),
+		"set_election_tally_method",
+	))?;
+	Ok(CollectivePayload {
+		collective_address,
+		collective,
+	})
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct RoleActionData {
+	role_address: Address,
+	role: Role,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct NominationActionData {
+	election_address: Address,
+	nominator_address: Address,
+	candidate_address: Address,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct ElectionTallyMethodActionData {
+	election_tally_method: TallyMethod,
+}
+
+/// Validates the person represents the agent & is in the collective.
+fn validate_elector(
+	store: &dyn Store,
+	collective_address: &Address,
+	person_address: &Address,
+) -> ProtocolLoveResult<()> {
+	t("validate_elector: ", get_agent_person(store, person_address))?;
+	if !t("validate_elector: ", is_collective_person(store, collective_address, person_address))? {
+		return Err(ProtocolLoveError::unauthorized("Only people in the collective take part in elections"));
+	}
+	Ok(())
+}
+
+fn commit_election(
+	store: &dyn Store,
+	role: &Role,
+	role_address: Address,
+	timestamp: u64,
+) -> ProtocolLoveResult<ElectionPayload> {
+	let term = t("commit_election: ", get_next_term(store, &role_address))?;
+	let election = Election {
+		collective_address: role.collective_address.clone(),
+		role_address,
+		term,
+		candidate_addresses: vec![],
+		proposal_address: None,
+		started_at: timestamp,
+		closed_at: None,
+	};
+	let election_entry = Entry::App("election".into(), (&election).into());
+	let election_address = t("commit_election: ", store.commit_entry(&election_entry))?;
+	t("commit_election: role->election: ", store.link_entries(
+		&election.role_address,
+		&election_address,
+		"role->election",
+		"",
+	))?;
+	t("commit_election: ", create_root_action(
+		store,
+		&election.collective_address,
+		ActionOp::StartElection,
+		ElectionPayload {
+			election_address: election_address.clone(),
+			election: election.clone(),
+		}.into(),
+		serde_json::value::Value::Null.into(),
+		"start_election",
+	))?;
+	Ok(ElectionPayload {
+		election_address,
+		election,
+	})
+}
+
+fn update_election_entry(
+	store: &dyn Store,
+	election_address: &Address,
+	election: &Election,
+) -> ProtocolLoveResult<Address> {
+	let election_entry = Entry::App("election".into(), election.into());
+	t("update_election_entry: ", store.update_entry(election_entry, election_address))
+}
+
+/// Assigns the role to the winner of the election for a term starting at the `timestamp`.
+fn assign_role(
+	store: &dyn Store,
+	election_address: &Address,
+	election: &Election,
+	person_address: Address,
+	timestamp: u64,
+) -> ProtocolLoveResult<RoleTerm> {
+	let role: Role = t("assign_role: ", get_as_type(store, &election.role_address))?;
+	let role_term = RoleTerm {
+		role_address: election.role_address.clone(),
+		election_address: election_address.clone(),
+		person_address,
+		term: election.term,
+		started_at: timestamp,
+		ends_at: timestamp.saturating_add(role.term_length),
+	};
+	let role_term_entry = Entry::App("role_term".into(), (&role_term).into());
+	let role_term_address = t("assign_role: ", store.commit_entry(&role_term_entry))?;
+	t("assign_role: role->role_term: ", store.link_entries(
+		&role_term.role_address,
+		&role_term_address,
+		"role->role_term",
+		"",
+	))?;
+	t("assign_role: ", create_root_action(
+		store,
+		&election.collective_address,
+		ActionOp::AssignRole,
+		role_term.clone().into(),
+		serde_json::value::Value::Null.into(),
+		"close_election",
+	))?;
+	Ok(role_term)
+}
+
+/// Gets the [RoleTerms](struct.RoleTerm.html) of the role, latest term first.
+fn get_role_terms(store: &dyn Store, role_address: &Address) -> ProtocolLoveResult<Vec<RoleTerm>> {
+	let role_terms: Vec<RoleTerm> =
+		t("get_role_terms: ", get_links_and_load_type(store, role_address, "role->role_term", None))?;
+	let mut role_terms: Vec<RoleTerm> = role_terms.into_iter()
+		.filter(|role_term| &role_term.role_address == role_address)
+		.collect();
+	role_terms.sort_by(|a, b| b.term.cmp(&a.term));
+	Ok(role_terms)
+}
+
+/// Gets the latest [RoleTerm](struct.RoleTerm.html) of the role.
+fn get_latest_role_term(store: &dyn Store, role_address: &Address) -> ProtocolLoveResult<Option<RoleTerm>> {
+	Ok(get_role_terms(store, role_address)?.into_iter().next())
+}
+
+/// The term following the latest [RoleTerm](struct.RoleTerm.html) of the role.
+fn get_next_term(store: &dyn Store, role_address: &Address) -> ProtocolLoveResult<u64> {
+	Ok(get_latest_role_term(store, role_address)?.map_or(1, |role_term| role_term.term + 1))
+}
+
+/// Gets the latest started [Election](struct.Election.html) of the role.
+fn get_latest_election(
+	store: &dyn Store,
+	role_address: &Address,
+) -> ProtocolLoveResult<Option<ElectionPayload>> {
+	let election_addresses =
+		t("get_latest_election: ", store.get_links(role_address, "role->election", None))?;
+	let elections = election_addresses.into_iter()
+		.map(|election_address| {
+			let election: Election = t("get_latest_election: ", get_as_type(store, &election_address))?;
+			Ok(ElectionPayload {
+				election_address,
+				election,
+			})
+		})
+		.collect::<ProtocolLoveResult<Vec<ElectionPayload>>>()?;
+	Ok(elections.into_iter()
+		.filter(|election_payload| &election_payload.election.role_address == role_address)
+		.max_by_key(|election_payload| (election_payload.election.term, election_payload.election.started_at)))
+}
+
+/// Gets the latest [Election](struct.Election.html) of the role when it is still nominating,
+/// voting or awaiting the assignment of the winner.
+fn get_election_in_progress(
+	store: &dyn Store,
+	role_address: &Address,
+) -> ProtocolLoveResult<Option<ElectionPayload>> {
+	let ElectionPayload { election_address, election } =
+		match t("get_election_in_progress: ", get_latest_election(store, role_address))? {
+			Some(election_payload) => election_payload,
+			None => return Ok(None),
+		};
+	let in_progress = match &election.proposal_address {
+		None => match election.closed_at {
+			None => true,
+			Some(_) => election.candidate_addresses.len() == 1
+				&& get_next_term(store, role_address)? == election.term,
+		},
+		Some(proposal_address) => {
+			let proposal: Proposal =
+				t("get_election_in_progress: ", get_as_type(store, proposal_address))?;
+			proposal.status != ProposalStatus::Closed
+				|| (proposal.outcome == Some(ProposalOutcome::Passed)
+					&& get_next_term(store, role_address)? == election.term)
+		}
+	};
+	Ok(if in_progress {
+		Some(ElectionPayload {
+			election_address,
+			election,
+		})
+	} else {
+		None
+	})
+}
@@ -0,0 +1,231 @@
+use std::fmt;
+use hdk::prelude::ZomeApiError;
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+
+/// Prefix of a [ProtocolLoveError](enum.ProtocolLoveError.html) serialized into a message,
+/// followed by the JSON payload of the error.
+const MESSAGE_PAYLOAD_PREFIX: &str = "ProtocolLoveError:";
+
+/// Result of the protocol.love api functions.
+pub type ProtocolLoveResult<T> = Result<T, ProtocolLoveError>;
+
+/// A typed error returned by the protocol.love api.
+///
+/// Serialized with a stable `code`, so the client does not need to parse the message.
+///
+/// # Examples
+///
+/// ```
+/// {"code": "NOT_FOUND", "address": "Qm...", "message": "get_collective: No entry at this address"}
+/// {"code": "VALIDATION_FAILED", "field": "name", "message": "Name is too long"}
+/// ```
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+#[serde(tag = "code", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ProtocolLoveError {
+	/// No entry was found at the `address`.
+	///
+	/// Can be a transient DHT miss, so the call may be retried.
+	NotFound {
+		address: Option<Address>,
+		message: String,
+	},
+	/// The agent is not permitted to perform the operation.
+	Unauthorized {
+		message: String,
+	},
+	/// The data is invalid, optionally for a given `field`.
+	ValidationFailed {
+		field: Option<String>,
+		message: String,
+	},
+	/// The operation conflicts with the current state.
+	Conflict {
+		message: String,
+	},
+	/// The stored data is inconsistent, e.g. a partially created entry graph.
+	InvariantBroken {
+		message: String,
+	},
+	/// The Holochain zome api timed out. The call may be retried.
+	Timeout {
+		message: String,
+	},
+	/// Any other error returned by the Holochain zome api.
+	Internal {
+		message: String,
+	},
+}
+
+impl ProtocolLoveError {
+	pub fn not_found(address: &Address) -> Self {
+		ProtocolLoveError::NotFound {
+			address: Some(address.clone()),
+			message: "No entry at this address".into(),
+		}
+	}
+
+	pub fn unauthorized(message: &str) -> Self {
+		ProtocolLoveError::Unauthorized {
+			message: message.into(),
+		}
+	}
+
+	pub fn validation_failed(field: &str, message: &str) -> Self {
+		ProtocolLoveError::ValidationFailed {
+			field: Some(field.into()),
+			message: message.into(),
+		}
+	}
+
+	pub fn conflict(message: &str) -> Self {
+		ProtocolLoveError::Conflict {
+			message: message.into(),
+		}
+	}
+
+	pub fn invariant_broken(message: &str) -> Self {
+		ProtocolLoveError::InvariantBroken {
+			message: message.into(),
+		}
+	}
+
+	/// The stable code of the error.
+	pub fn code(&self) -> &'static str {
+		match self {
+			ProtocolLoveError::NotFound { .. } => "NOT_FOUND",
+			ProtocolLoveError::Unauthorized { .. } => "UNAUTHORIZED",
+			ProtocolLoveError::ValidationFailed { .. } => "VALIDATION_FAILED",
+			ProtocolLoveError::Conflict { .. } => "CONFLICT",
+			ProtocolLoveError::InvariantBroken { .. } => "INVARIANT_BROKEN",
+			ProtocolLoveError::Timeout { .. } => "TIMEOUT",
+			ProtocolLoveError::Internal { .. } => "INTERNAL",
+		}
+	}
+
+	pub fn message(&self) -> &str {
+		match self {
+			ProtocolLoveError::NotFound { message, .. } => message,
+			ProtocolLoveError::Unauthorized { message } => message,
+			ProtocolLoveError::ValidationFailed { message, .. } => message,
+			ProtocolLoveError::Conflict { message } => message,
+			ProtocolLoveError::InvariantBroken { message } => message,
+			ProtocolLoveError::Timeout { message } => message,
+			ProtocolLoveError::Internal { message } => message,
+		}
+	}
+
+	/// Is the error possibly resolved by retrying, i.e. a DHT miss or a timeout?
+	pub fn is_transient(&self) -> bool {
+		match self {
+			ProtocolLoveError::NotFound { .. } | ProtocolLoveError::Timeout { .. } => true,
+			_ => false,
+		}
+	}
+
+	/// Prepends `tag` to the message, keeping the code.
+	pub fn tagged(mut self, tag: &str) -> Self {
+		let message = match &mut self {
+			ProtocolLoveError::NotFound { message, .. } => message,
+			ProtocolLoveError::Unauthorized { message } => message,
+			ProtocolLoveError::ValidationFailed { message, .. } => message,
+			ProtocolLoveError::Conflict { message } => message,
+			ProtocolLoveError::InvariantBroken { message } => message,
+			ProtocolLoveError::Timeout { message } => message,
+			ProtocolLoveError::Internal { message } => message,
+		};
+		message.insert_str(0, tag);
+		self
+	}
+
+	/// Serializes the error into a message, parsed back with
+	/// [from_message](enum.ProtocolLoveError.html#method.from_message).
+	fn into_message(self) -> String {
+		format!("{}{}", MESSAGE_PAYLOAD_PREFIX, JsonString::from(self))
+	}
+
+	/// Parses an error serialized into a message, e.g. by a validation callback.
+	///
+	/// The message may be wrapped by the conductor, so only the JSON payload following the
+	/// prefix is read.
+	fn from_message(message: &str) -> Option<Self> {
+		let start = message.find(MESSAGE_PAYLOAD_PREFIX)? + MESSAGE_PAYLOAD_PREFIX.len();
+		serde_json::Deserializer::from_str(&message[start..])
+			.into_iter::<ProtocolLoveError>()
+			.next()?
+			.ok()
+	}
+}
+
+impl fmt::Display for ProtocolLoveError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}: {}", self.code(), self.message())
+	}
+}
+
+impl From<ZomeApiError> for ProtocolLoveError {
+	fn from(error: ZomeApiError) -> Self {
+		match error {
+			ZomeApiError::HashNotFound => ProtocolLoveError::NotFound {
+				address: None,
+				message: "Hash not found".into(),
+			},
+			ZomeApiError::Timeout => ProtocolLoveError::Timeout {
+				message: "Timeout".into(),
+			},
+			ZomeApiError::ValidationFailed(message) =>
+				ProtocolLoveError::from_message(&message).unwrap_or(
+					ProtocolLoveError::ValidationFailed {
+						field: None,
+						message,
+					}),
+			ZomeApiError::Internal(message) =>
+				ProtocolLoveError::from_message(&message).unwrap_or_else(||
+					if message.contains("No entry at this address") {
+						ProtocolLoveError::NotFound {
+							address: None,
+							message,
+						}
+					} else {
+						ProtocolLoveError::Internal { message }
+					}),
+			error => ProtocolLoveError::Internal {
+				message: error.to_string(),
+			},
+		}
+	}
+}
+
+impl From<ProtocolLoveError> for ZomeApiError {
+	fn from(error: ProtocolLoveError) -> Self {
+		ZomeApiError::Internal(error.into_message())
+	}
+}
+
+/// Serializes the error for the validation callbacks, which return a `String`.
+impl From<ProtocolLoveError> for String {
+	fn from(error: ProtocolLoveError) -> Self {
+		error.into_message()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use hdk::prelude::ZomeApiError;
+	use super::ProtocolLoveError;
+
+	#[test]
+	fn validation_message_is_parsed_back_into_the_error() {
+		let error = ProtocolLoveError::validation_failed("content", "Use {name} } in the template {");
+		let message: String = error.clone().into();
+		assert_eq!(
+			ProtocolLoveError::from(ZomeApiError::ValidationFailed(format!("Validation failed: {} (entry)", message))),
+			error);
+		assert_eq!(
+			ProtocolLoveError::from(ZomeApiError::ValidationFailed("Invalid {entry}".into())),
+			ProtocolLoveError::ValidationFailed { field: None, message: "Invalid {entry}".into() });
+	}
+}
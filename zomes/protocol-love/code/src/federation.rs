@@ -0,0 +1,245 @@
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::action::{ActionOp, create_root_action};
+use crate::collective::{Collective, CollectivePayload, is_collective_admin};
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::person::Person;
+use crate::proposal::{BallotMode, Proposal, ProposalStatus};
+use crate::store::{Store, get_as_type};
+use crate::tally::ProposalOutcome;
+use crate::utils::t;
+use crate::validation::ValidationContext;
+use crate::vote::{Ballot, Vote};
+
+/// Api payload of the member [Collectives](struct.Collective.html) of a federation, oldest first.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct MemberCollectivesPayload {
+	pub collective_address: Address,
+	pub member_collectives: Vec<CollectivePayload>,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct AddMemberCollectiveActionData {
+	member_collective_address: Address,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct JoinFederationActionData {
+	federation_address: Address,
+}
+
+/// Api for the admin to consent to the collective joining the federation at
+/// `federation_address`, whose admin then adds it with
+/// [add_member_collective](fn.add_member_collective.html).
+///
+/// Joining a federation already joined leaves it unchanged.
+pub fn join_federation(
+	store: &dyn Store,
+	collective_address: Address,
+	federation_address: Address,
+) -> ProtocolLoveResult<CollectivePayload> {
+	let collective: Collective = t("join_federation: ", get_as_type(store, &collective_address))?;
+	if !t("join_federation: ", is_collective_admin(store, &collective))? {
+		return Err(ProtocolLoveError::unauthorized(
+			"join_federation: Only the admin can join a federation"));
+	}
+	if federation_address == collective_address {
+		return Err(ProtocolLoveError::validation_failed(
+			"federation_address", "join_federation: Collective cannot be its own member"));
+	}
+	let _federation: Collective = t("join_federation: ", get_as_type(store, &federation_address))?;
+	if !t("join_federation: ", has_joined_federation(store, &collective_address, &federation_address))? {
+		t("join_federation: collective->federation: ", store.link_entries(
+			&collective_address,
+			&federation_address,
+			"collective->federation",
+			"",
+		))?;
+		t("join_federation: ", create_root_action(
+			store,
+			&collective_address,
+			ActionOp::JoinFederation,
+			JoinFederationActionData {
+				federation_address,
+			}.into(),
+			serde_json::value::Value::Null.into(),
+			"join_federation",
+		))?;
+	}
+	Ok(CollectivePayload {
+		collective_address,
+		collective,
+		..CollectivePayload::default()
+	})
+}
+
+/// Api for the admin to add the [Collective](struct.Collective.html) at
+/// `member_collective_address` as a member of the collective, forming a federation.
+///
+/// The member collective must have joined the federation with
+/// [join_federation](fn.join_federation.html).
+///
+/// A member collective votes with [cast_collective_vote](fn.cast_collective_vote.html)
+/// according to the outcome of its own proposal.
+pub fn add_member_collective(
+	store: &dyn Store,
+	collective_address: Address,
+	member_collective_address: Address,
+) -> ProtocolLoveResult<MemberCollectivesPayload> {
+	let collective: Collective = t("add_member_collective: ", get_as_type(store, &collective_address))?;
+	if !t("add_member_collective: ", is_collective_admin(store, &collective))? {
+		return Err(ProtocolLoveError::unauthorized(
+			"add_member_collective: Only the admin can add member collectives"));
+	}
+	if member_collective_address == collective_address {
+		return Err(ProtocolLoveError::validation_failed(
+			"member_collective_address", "add_member_collective: Collective cannot be its own member"));
+	}
+	let _member_collective: Collective =
+		t("add_member_collective: ", get_as_type(store, &member_collective_address))?;
+	if !t("add_member_collective: ", has_joined_federation(
+		store,
+		&member_collective_address,
+		&collective_address,
+	))? {
+		return Err(ProtocolLoveError::conflict(
+			"add_member_collective: Member collective has not joined the federation"));
+	}
+	if !t("add_member_collective: ", is_member_collective(
+		store,
+		&collective_address,
+		&member_collective_address,
+	))? {
+		t("add_member_collective: collective->member_collective: ", store.link_entries(
+			&collective_address,
+			&member_collective_address,
+			"collective->member_collective",
+			"",
+		))?;
+		t("add_member_collective: ", create_root_action(
+			store,
+			&collective_address,
+			ActionOp::AddMemberCollective,
+			AddMemberCollectiveActionData {
+				member_collective_address,
+			}.into(),
+			serde_json::value::Value::Null.into(),
+			"add_member_collective",
+		))?;
+	}
+	get_member_collectives(store, collective_address)
+}
+
+/// Api to get the member [Collectives](struct.Collective.html) of a federation.
+pub fn get_member_collectives(
+	store: &dyn Store,
+	collective_address: Address,
+) -> ProtocolLoveResult<MemberCollectivesPayload> {
+	let mut member_collective_addresses = t("get_member_collectives: ", store.get_links(
+		&collective_address,
+		"collective->member_collective",
+		None,
+	))?;
+	member_collective_addresses.reverse();
+	let member_collectives = member_collective_addresses.into_iter()
+		.map(|member_collective_address| Ok(CollectivePayload {
+			collective: t("get_member_collectives: ", get_as_type(store, &member_collective_address))?,
+			collective_address: member_collective_address,
+		}))
+		.collect::<ProtocolLoveResult<Vec<CollectivePayload>>>()?;
+	Ok(MemberCollectivesPayload {
+		collective_address,
+		member_collectives,
+	})
+}
+
+/// Is the [Collective](struct.Collective.html) at `member_collective_address` a member of the
+/// collective?
+pub fn is_member_collective(
+	store: &dyn Store,
+	collective_address: &Address,
+	member_collective_address: &Address,
+) -> ProtocolLoveResult<bool> {
+	let member_collective_addresses = t("is_member_collective: ", store.get_links(
+		collective_address,
+		"collective->member_collective",
+		None,
+	))?;
+	Ok(member_collective_addresses.contains(member_collective_address))
+}
+
+/// Has the admin of the [Collective](struct.Collective.html) at `collective_address` consented to
+/// joining the federation at `federation_address`?
+pub fn has_joined_federation(
+	store: &dyn Store,
+	collective_address: &Address,
+	federation_address: &Address,
+) -> ProtocolLoveResult<bool> {
+	let federation_addresses = t("has_joined_federation: ", store.get_links(
+		collective_address,
+		"collective->federation",
+		None,
+	))?;
+	Ok(federation_addresses.contains(federation_address))
+}
+
+/// The [Ballot](enum.Ballot.html) a member collective casts for the outcome of its decision.
+pub fn decision_ballot(outcome: &ProposalOutcome) -> Ballot {
+	match outcome {
+		ProposalOutcome::Passed => Ballot::Yes,
+		ProposalOutcome::Rejected => Ballot::No,
+		ProposalOutcome::Tied => Ballot::Abstain,
+	}
+}
+
+/// Validates the vote of a member collective is cast by its admin & follows its closed
+/// decision at `decision_address` on the proposal of the federation.
+pub fn validate_collective_voter(
+	store: &dyn Store,
+	vote: &Vote,
+	decision_address: &Address,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<Proposal> {
+	let proposal: Proposal =
+		t("validation error: vote: fetch proposal: ", get_as_type(store, &vote.proposal_address))?;
+	if !is_member_collective(store, &proposal.collective_address, &vote.person_address)? {
+		return Err(ProtocolLoveError::unauthorized("Only member collectives can vote"));
+	}
+	let member_collective: Collective = t("validation error: vote: fetch member collective: ",
+		get_as_type(store, &vote.person_address))?;
+	let admin: Option<Person> = match &member_collective.admin_address {
+		Some(admin_address) =>
+			Some(t("validation error: vote: fetch admin: ", get_as_type(store, admin_address))?),
+		None => None,
+	};
+	if !admin.map_or(false, |admin| context.sources.contains(&admin.agent_address)) {
+		return Err(ProtocolLoveError::unauthorized(
+			"Vote of a member collective must be cast by its admin"));
+	}
+	if proposal.ballot_mode != BallotMode::Open {
+		return Err(ProtocolLoveError::conflict("Member collectives vote with open ballots"));
+	}
+	let decision: Proposal =
+		t("validation error: vote: fetch decision: ", get_as_type(store, decision_address))?;
+	if decision.collective_address != vote.person_address {
+		return Err(ProtocolLoveError::validation_failed(
+			"decision_address", "Decision must be a proposal of the member collective"));
+	}
+	if decision.federated_proposal_address.as_ref() != Some(&vote.proposal_address) {
+		return Err(ProtocolLoveError::validation_failed(
+			"decision_address", "Decision is not on the proposal of the federation"));
+	}
+	match (&decision.status, &decision.outcome) {
+		(ProposalStatus::Closed, Some(outcome)) => {
+			if vote.ballot != decision_ballot(outcome) {
+				return Err(ProtocolLoveError::validation_failed(
+					"ballot", "Ballot does not follow the decision of the member collective"));
+			}
+		}
+		_ => return Err(ProtocolLoveError::conflict("Decision of the member collective is not closed")),
+	}
+	Ok(proposal)
+}
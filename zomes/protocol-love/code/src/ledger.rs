@@ -1,28 +1,61 @@
+use std::convert::TryFrom;
 use hdk::holochain_json_api::{
 	json::JsonString,
 	error::JsonError,
 };
 use hdk::holochain_core_types::dna::entry_types::Sharing;
-use crate::collective::Collective;
+use crate::action::{ActionOp, create_root_action};
+use crate::collective::{
+	Collective, is_collective_admin, is_collective_admin_source, validate_collective_not_archived,
+};
+use crate::critical_action::validate_proposal_action_executed;
+use crate::dissolution::validate_dissolution_proposal;
 use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
-use hdk::prelude::{ZomeApiResult, ValidatingEntryType};
+use hdk::prelude::ValidatingEntryType;
 use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::proposal::{Proposal, ProposalStatus};
 use crate::utils::t;
+use crate::store::{Store, HdkStore, get_as_type};
+use crate::validation::{
+	EntryValidation, ValidationContext, from_hdk_link_validation_data, from_hdk_validation_data,
+	validate_app_link,
+};
 
 /// A ledger to account for transactions relating to a [Collective](struct.Collective.html).
 #[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
 pub struct Ledger {
 	pub name: String,
+	/// Funds held by the collective, allocated by conviction voting.
+	#[serde(default)]
+	pub funds: u64,
+	/// Address of the [Collective](struct.Collective.html) of the ledger.
+	#[serde(default)]
+	pub collective_address: Option<Address>,
+	/// Address of the funded or dissolution [Proposal](struct.Proposal.html) the latest change
+	/// of the `funds` is made for. `None` for the deposits of the admin.
+	#[serde(default)]
+	pub proposal_address: Option<Address>,
 }
 
 impl Default for Ledger {
 	fn default() -> Self {
 		Ledger {
 			name: "unnamed ledger".to_string(),
+			funds: 0,
+			collective_address: None,
+			proposal_address: None,
 		}
 	}
 }
 
+/// Api payload containing the `ledger_address` & [ledger](struct.Ledger.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct LedgerPayload {
+	pub ledger_address: Address,
+	pub ledger: Ledger,
+}
+
 /// Returns a Holochain entry definition for a ledger.
 pub fn ledger_def() -> ValidatingEntryType {
 	entry!(
@@ -32,23 +65,338 @@ pub fn ledger_def() -> ValidatingEntryType {
 		validation_package: || {
 			hdk::ValidationPackageDefinition::Entry
 		},
-		validation: | _validation_data: hdk::EntryValidationData<Ledger>| {
+		validation: | validation_data: hdk::EntryValidationData<Ledger>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_ledger_entry(&HdkStore, validation, &context)?)
+		},
+		links: [
+			to!(
+				"voice_credit_spend",
+				link_type: "ledger->voice_credit_spend",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			)
+		]
+	)
+}
+
+/// Validation rules of a [Ledger](struct.Ledger.html) entry.
+///
+/// A ledger belongs to a collective & is created empty. Only the admin deposits funds. Funds
+/// are debited by a funded conviction proposal or by the dissolution of the collective, whose
+/// settlement may credit the ledger of the parent. The ledger of an archived collective is
+/// read-only.
+pub fn validate_ledger_entry(
+	store: &dyn Store,
+	validation: EntryValidation<Ledger>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			validate_ledger_collective(store, &entry)?;
+			if entry.funds != 0 || entry.proposal_address.is_some() {
+				return Err(ProtocolLoveError::validation_failed(
+					"funds", "Ledger must be created without funds"));
+			}
 			Ok(())
 		}
-	)
+		EntryValidation::Modify { new_entry, old_entry } => {
+			if new_entry.collective_address != old_entry.collective_address {
+				return Err(ProtocolLoveError::unauthorized("Ledger cannot move to another collective"));
+			}
+			if new_entry.name != old_entry.name {
+				return Err(ProtocolLoveError::unauthorized("Ledger cannot be renamed"));
+			}
+			let collective_address = validate_ledger_collective(store, &old_entry)?;
+			match &new_entry.proposal_address {
+				None => {
+					if new_entry.funds < old_entry.funds {
+						return Err(ProtocolLoveError::unauthorized(
+							"Funds can only be debited by a proposal"));
+					}
+					let collective: Collective = t("validation error: ledger: fetch collective: ",
+						get_as_type(store, &collective_address))?;
+					if !is_collective_admin_source(store, &collective, context)? {
+						return Err(ProtocolLoveError::unauthorized("Only the admin can deposit funds"));
+					}
+					Ok(())
+				}
+				Some(proposal_address) if new_entry.funds < old_entry.funds => validate_ledger_debit(
+					store,
+					&collective_address,
+					proposal_address,
+					&old_entry,
+					&new_entry,
+				),
+				Some(proposal_address) => validate_ledger_settlement(
+					store,
+					&collective_address,
+					proposal_address,
+					&old_entry,
+					&new_entry,
+					context,
+				),
+			}
+		}
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Ledger cannot be deleted")),
+	}
+}
+
+/// Validates the ledger belongs to a collective which is not archived.
+fn validate_ledger_collective(store: &dyn Store, ledger: &Ledger) -> ProtocolLoveResult<Address> {
+	match &ledger.collective_address {
+		Some(collective_address) => {
+			validate_collective_not_archived(store, collective_address)?;
+			Ok(collective_address.clone())
+		}
+		None => Err(ProtocolLoveError::validation_failed(
+			"collective_address", "Ledger must belong to a collective")),
+	}
+}
+
+/// Validates the debit is the requested funds of a conviction proposal funded once, or the
+/// whole funds of a collective being dissolved.
+fn validate_ledger_debit(
+	store: &dyn Store,
+	collective_address: &Address,
+	proposal_address: &Address,
+	old_ledger: &Ledger,
+	new_ledger: &Ledger,
+) -> ProtocolLoveResult<()> {
+	let proposal: Proposal = t("validation error: ledger: fetch proposal: ",
+		get_as_type(store, proposal_address))?;
+	if &proposal.collective_address != collective_address {
+		return Err(ProtocolLoveError::validation_failed(
+			"proposal_address", "Proposal is not of the collective of the ledger"));
+	}
+	if proposal.funded_at.is_none() {
+		validate_dissolution_proposal(store, proposal_address)?;
+		if new_ledger.funds != 0 {
+			return Err(ProtocolLoveError::validation_failed(
+				"funds", "Dissolution must settle the whole funds"));
+		}
+		return Ok(());
+	}
+	if proposal.status != ProposalStatus::Closed
+		|| old_ledger.funds - new_ledger.funds != proposal.requested_funds {
+		return Err(ProtocolLoveError::validation_failed(
+			"funds", "Debit must be the requested funds of the proposal"));
+	}
+	if is_ledger_proposal_recorded(store, collective_address, proposal_address)? {
+		return Err(ProtocolLoveError::conflict("Proposal is already funded"));
+	}
+	validate_proposal_action_executed(store, &proposal, proposal_address)
+}
+
+/// Validates the credit is the whole funds of a child collective being dissolved, settled once
+/// by the admin of the child.
+fn validate_ledger_settlement(
+	store: &dyn Store,
+	collective_address: &Address,
+	proposal_address: &Address,
+	old_ledger: &Ledger,
+	new_ledger: &Ledger,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let proposal = validate_dissolution_proposal(store, proposal_address)?;
+	let child: Collective = t("validation error: ledger: fetch dissolved collective: ",
+		get_as_type(store, &proposal.collective_address))?;
+	if child.parent_address.as_ref() != Some(collective_address) {
+		return Err(ProtocolLoveError::validation_failed(
+			"proposal_address", "Proposal does not dissolve a child collective"));
+	}
+	if !is_collective_admin_source(store, &child, context)? {
+		return Err(ProtocolLoveError::unauthorized(
+			"Only the admin of the dissolved collective settles its funds"));
+	}
+	if is_ledger_proposal_recorded(store, collective_address, proposal_address)? {
+		return Err(ProtocolLoveError::conflict("Collective is already settled"));
+	}
+	validate_collective_not_archived(store, &proposal.collective_address)?;
+	let child_ledger_address = get_collective_ledger(store, &proposal.collective_address)?;
+	let child_ledger: Ledger = t("validation error: ledger: fetch dissolved ledger: ",
+		get_as_type(store, &child_ledger_address))?;
+	if child_ledger.funds == 0 || new_ledger.funds - old_ledger.funds != child_ledger.funds {
+		return Err(ProtocolLoveError::validation_failed(
+			"funds", "Settlement must be the whole funds of the dissolved collective"));
+	}
+	Ok(())
+}
+
+/// Is a change of the funds of the primary ledger of the collective already made for the
+/// `proposal_address`?
+fn is_ledger_proposal_recorded(
+	store: &dyn Store,
+	collective_address: &Address,
+	proposal_address: &Address,
+) -> ProtocolLoveResult<bool> {
+	let ledger_address = get_collective_ledger(store, collective_address)?;
+	Ok(store.get_entry_history(&ledger_address)?
+		.into_iter()
+		.filter_map(|entry| match entry {
+			Entry::App(_, entry_value) => Ledger::try_from(entry_value).ok(),
+			_ => None,
+		})
+		.any(|ledger| ledger.proposal_address.as_ref() == Some(proposal_address)))
+}
+
+/// Creates the primary [Ledger](struct.Ledger.html) for a [Collective](struct.Collective.html)
+/// unless it is already linked.
+pub fn ensure_collective_ledger(
+	store: &dyn Store,
+	collective: &Collective,
+	collective_address: &Address,
+) -> ProtocolLoveResult<Address> {
+	match t("ensure_collective_ledger: ", find_collective_ledger(store, collective_address))? {
+		Some(ledger_address) => Ok(ledger_address),
+		None => create_collective_ledger(store, collective, collective_address),
+	}
+}
+
+/// Gets the address of the primary [Ledger](struct.Ledger.html) of a
+/// [Collective](struct.Collective.html).
+pub fn get_collective_ledger(
+	store: &dyn Store,
+	collective_address: &Address,
+) -> ProtocolLoveResult<Address> {
+	match t("get_collective_ledger: ", find_collective_ledger(store, collective_address))? {
+		Some(ledger_address) => Ok(ledger_address),
+		None => Err(ProtocolLoveError::invariant_broken(
+			"get_collective_ledger: Collective has no primary ledger")),
+	}
+}
+
+/// Gets the earliest primary [Ledger](struct.Ledger.html) belonging to the collective.
+fn find_collective_ledger(
+	store: &dyn Store,
+	collective_address: &Address,
+) -> ProtocolLoveResult<Option<Address>> {
+	let ledger_addresses =
+		t("find_collective_ledger: ", store.get_links(
+			collective_address,
+			"collective->ledger",
+			Some("ledger_primary"),
+		))?;
+	for ledger_address in ledger_addresses.into_iter().rev() {
+		let ledger: Ledger = t("find_collective_ledger: ", get_as_type(store, &ledger_address))?;
+		if ledger.collective_address.as_ref() == Some(collective_address) {
+			return Ok(Some(ledger_address));
+		}
+	}
+	Ok(None)
+}
+
+/// Api to get the primary [Ledger](struct.Ledger.html) of a [Collective](struct.Collective.html).
+pub fn get_ledger(
+	store: &dyn Store,
+	collective_address: Address,
+) -> ProtocolLoveResult<LedgerPayload> {
+	let ledger_address = t("get_ledger: ", get_collective_ledger(store, &collective_address))?;
+	let ledger = t("get_ledger: ", get_as_type(store, &ledger_address))?;
+	Ok(LedgerPayload {
+		ledger_address,
+		ledger,
+	})
+}
+
+/// Api for the admin to deposit funds on the primary [Ledger](struct.Ledger.html)
+/// of a [Collective](struct.Collective.html).
+pub fn deposit_ledger_funds(
+	store: &dyn Store,
+	collective_address: Address,
+	amount: u64,
+) -> ProtocolLoveResult<LedgerPayload> {
+	let collective: Collective =
+		t("deposit_ledger_funds: ", get_as_type(store, &collective_address))?;
+	if !t("deposit_ledger_funds: ", is_collective_admin(store, &collective))? {
+		return Err(ProtocolLoveError::unauthorized(
+			"deposit_ledger_funds: Only the admin can deposit funds"));
+	}
+	let LedgerPayload { ledger_address, ledger } =
+		t("deposit_ledger_funds: ", get_ledger(store, collective_address.clone()))?;
+	let funds = match ledger.funds.checked_add(amount) {
+		Some(funds) => funds,
+		None => return Err(ProtocolLoveError::validation_failed(
+			"amount", "deposit_ledger_funds: Amount is too large")),
+	};
+	t("deposit_ledger_funds: ", set_ledger_funds(
+		store,
+		&collective_address,
+		&ledger_address,
+		ledger,
+		funds,
+		None,
+		ActionOp::DepositLedgerFunds,
+	))
+}
+
+/// Updates the `funds` of the [Ledger](struct.Ledger.html) for the `proposal_address` & records
+/// the `op` action.
+pub fn set_ledger_funds(
+	store: &dyn Store,
+	collective_address: &Address,
+	ledger_address: &Address,
+	ledger: Ledger,
+	funds: u64,
+	proposal_address: Option<Address>,
+	op: ActionOp,
+) -> ProtocolLoveResult<LedgerPayload> {
+	let prev_funds = ledger.funds;
+	let ledger = Ledger {
+		funds,
+		proposal_address,
+		..ledger
+	};
+	let ledger_entry = Entry::App("ledger".into(), (&ledger).into());
+	t("set_ledger_funds: ", store.update_entry(ledger_entry, ledger_address))?;
+	t("set_ledger_funds: ", create_root_action(
+		store,
+		collective_address,
+		op,
+		LedgerFundsActionData {
+			ledger_address: ledger_address.clone(),
+			funds,
+		}.into(),
+		LedgerFundsActionData {
+			ledger_address: ledger_address.clone(),
+			funds: prev_funds,
+		}.into(),
+		"set_ledger_funds",
+	))?;
+	Ok(LedgerPayload {
+		ledger_address: ledger_address.clone(),
+		ledger,
+	})
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct LedgerFundsActionData {
+	ledger_address: Address,
+	funds: u64,
 }
 
 /// Create & commit a [Ledger](struct.Ledger.html) for a [Collective](struct.Collective.html).
-pub fn create_collective_ledger(collective: &Collective, collective_address: &Address) -> ZomeApiResult<Address> {
+pub fn create_collective_ledger(
+	store: &dyn Store,
+	collective: &Collective,
+	collective_address: &Address,
+) -> ProtocolLoveResult<Address> {
 	let ledger_name =
 		format!("Primary Ledger for {}", collective.name).to_string();
 	let ledger = Ledger {
 		name: ledger_name,
+		collective_address: Some(collective_address.clone()),
 		..Default::default()
 	};
 	let ledger_address =
-		t("create_collective_ledger: ", commit_ledger(ledger))?;
-	t("create_collective_ledger: collective->ledger: ", hdk::link_entries(
+		t("create_collective_ledger: ", commit_ledger(store, ledger))?;
+	t("create_collective_ledger: collective->ledger: ", store.link_entries(
 		&collective_address,
 		&ledger_address,
 		"collective->ledger",
@@ -57,9 +405,9 @@ pub fn create_collective_ledger(collective: &Collective, collective_address: &Ad
 	Ok(ledger_address)
 }
 
-fn commit_ledger(ledger: Ledger) -> ZomeApiResult<Address> {
+fn commit_ledger(store: &dyn Store, ledger: Ledger) -> ProtocolLoveResult<Address> {
 	let ledger_entry = Entry::App("ledger".into(), ledger.into());
 	let ledger_address =
-		t("commit_ledger: ", hdk::commit_entry(&ledger_entry))?;
+		t("commit_ledger: ", store.commit_entry(&ledger_entry))?;
 	Ok(ledger_address)
 }
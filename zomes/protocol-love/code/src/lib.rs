@@ -3,6 +3,7 @@
 #[macro_use]
 extern crate hdk;
 extern crate hdk_proc_macros;
+extern crate chrono;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
@@ -15,10 +16,35 @@ extern crate holochain_json_derive;
 #[macro_use]
 pub mod utils;
 pub mod action;
+pub mod amendment;
+pub mod charter;
 pub mod collective;
+pub mod conviction;
+pub mod critical_action;
+pub mod delegation;
+pub mod dissolution;
+pub mod election;
+pub mod error;
+pub mod federation;
 pub mod ledger;
+pub mod memory_store;
+pub mod message;
+pub mod objection;
 pub mod person;
 pub mod proposal;
+pub mod revision;
+pub mod signal;
+pub mod sortition;
+pub mod sponsor;
+pub mod store;
+pub mod sub_collective;
+pub mod tally;
+pub mod template;
+pub mod validation;
+pub mod voice_credit;
+pub mod vote;
+#[cfg(test)]
+mod scenario;
 
 use hdk_proc_macros::zome;
 //use std::borrow::Borrow;
@@ -31,10 +57,47 @@ mod protocol_love {
 	};
 	use hdk::prelude::{ValidatingEntryType, ZomeApiResult};
 
-	use crate::collective::{CollectivePayload, CreateCollectiveParams, CollectivePeoplePayload};
-	use crate::proposal::{ProposalParams, ProposalPayload};
+	use crate::collective::{
+		CollectivePayload, CreateCollectiveParams, CollectivePeoplePayload, UpdateCollectiveParams
+	};
+	use crate::proposal::{
+		CollectiveProposalsPayload, ExpiredProposalsPayload, ProposalParams, ProposalPayload, TallyMethod,
+	};
+	use crate::voice_credit::VoiceCreditsPayload;
+	use crate::vote::{
+		CollectiveVoteParams, ProposalTallyPayload, VoteCommitmentPayload, VoteParams, VotePayload,
+	};
 	use crate::action::ActionsPayload;
+	use crate::amendment::{AmendmentParams, AmendmentPayload, ProposalAmendmentsPayload};
+	use crate::charter::{CharterHistoryPayload, CharterPayload};
+	use crate::conviction::{ConvictionPayload, ConvictionStake, ConvictionStakePayload};
+	use crate::critical_action::{
+		CollectiveCriticalActionsPayload, CriticalActionPayload, ProposalActionPayload, VetoParams,
+	};
+	use crate::delegation::{DelegationParams, DelegationPayload, DelegationsPayload};
+	use crate::dissolution::DissolutionPayload;
+	use crate::ledger::LedgerPayload;
+	use crate::message::{MessageHistoryPayload, MessageParams, MessagePayload, ThreadParams, ThreadPayload};
+	use crate::objection::{
+		ObjectionParams, ObjectionPayload, ProposalObjectionsPayload, ResolveObjectionParams,
+	};
+	use crate::election::{
+		CollectiveElectionsPayload, CollectiveRolesPayload, ElectionParams, ElectionPayload,
+		NominationParams, RoleParams, RolePayload,
+	};
+	use crate::error::ProtocolLoveResult;
+	use crate::federation::MemberCollectivesPayload;
 	use crate::person::{OptionalPersonParams, PersonPayload};
+	use crate::revision::{ProposalDiffPayload, ProposalRevisionsPayload, ReviseProposalParams};
+	use crate::sortition::{
+		SortitionParams, SortitionPayload, SortitionSeedParams, SortitionSeedPayload, SortitionVerificationPayload,
+	};
+	use crate::sponsor::{ProposalSponsorsPayload, SponsorshipParams};
+	use crate::store::HdkStore;
+	use crate::sub_collective::{CollectiveTreePayload, EscalatedActionsPayload, SubCollectiveParams};
+	use crate::template::{
+		CollectiveTemplatesPayload, ProposalTemplateParams, ProposalTemplatePayload,
+	};
 
 	// collective
 	#[entry_def]
@@ -52,6 +115,51 @@ mod protocol_love {
 		crate::ledger::ledger_def()
 	}
 
+	#[entry_def]
+	fn amendment_def() -> ValidatingEntryType {
+		crate::amendment::amendment_def()
+	}
+
+	#[entry_def]
+	fn charter_def() -> ValidatingEntryType {
+		crate::charter::charter_def()
+	}
+
+	#[entry_def]
+	fn conviction_stake_def() -> ValidatingEntryType {
+		crate::conviction::conviction_stake_def()
+	}
+
+	#[entry_def]
+	fn critical_action_def() -> ValidatingEntryType {
+		crate::critical_action::critical_action_def()
+	}
+
+	#[entry_def]
+	fn delegation_def() -> ValidatingEntryType {
+		crate::delegation::delegation_def()
+	}
+
+	#[entry_def]
+	fn election_def() -> ValidatingEntryType {
+		crate::election::election_def()
+	}
+
+	#[entry_def]
+	fn message_def() -> ValidatingEntryType {
+		crate::message::message_def()
+	}
+
+	#[entry_def]
+	fn message_edit_def() -> ValidatingEntryType {
+		crate::message::message_edit_def()
+	}
+
+	#[entry_def]
+	fn objection_def() -> ValidatingEntryType {
+		crate::objection::objection_def()
+	}
+
 	#[entry_def]
 	fn person_def() -> ValidatingEntryType {
 		crate::person::person_def()
@@ -62,6 +170,61 @@ mod protocol_love {
 		crate::proposal::proposal_def()
 	}
 
+	#[entry_def]
+	fn proposal_revision_def() -> ValidatingEntryType {
+		crate::revision::proposal_revision_def()
+	}
+
+	#[entry_def]
+	fn proposal_template_def() -> ValidatingEntryType {
+		crate::template::proposal_template_def()
+	}
+
+	#[entry_def]
+	fn role_def() -> ValidatingEntryType {
+		crate::election::role_def()
+	}
+
+	#[entry_def]
+	fn role_term_def() -> ValidatingEntryType {
+		crate::election::role_term_def()
+	}
+
+	#[entry_def]
+	fn sortition_def() -> ValidatingEntryType {
+		crate::sortition::sortition_def()
+	}
+
+	#[entry_def]
+	fn sortition_draw_def() -> ValidatingEntryType {
+		crate::sortition::sortition_draw_def()
+	}
+
+	#[entry_def]
+	fn sortition_seed_def() -> ValidatingEntryType {
+		crate::sortition::sortition_seed_def()
+	}
+
+	#[entry_def]
+	fn sponsorship_def() -> ValidatingEntryType {
+		crate::sponsor::sponsorship_def()
+	}
+
+	#[entry_def]
+	fn vote_def() -> ValidatingEntryType {
+		crate::vote::vote_def()
+	}
+
+	#[entry_def]
+	fn vote_commitment_def() -> ValidatingEntryType {
+		crate::vote::vote_commitment_def()
+	}
+
+	#[entry_def]
+	fn voice_credit_spend_def() -> ValidatingEntryType {
+		crate::voice_credit::voice_credit_spend_def()
+	}
+
 	#[init]
 	fn init() -> ZomeApiResult<()> {
 		Ok(())
@@ -74,59 +237,561 @@ mod protocol_love {
 		Ok(())
 	}
 
+	#[receive]
+	pub fn receive(from: Address, msg_json: String) -> String {
+		let payload = hdk::holochain_json_api::json::JsonString::from_json(&msg_json);
+		match crate::signal::receive_signal(&HdkStore, &from, payload) {
+			Ok(()) => "Ok".into(),
+			Err(error) => error.into(),
+		}
+	}
+
 	#[zome_fn("hc_public")]
-	pub fn get_entry(address: Address) -> ZomeApiResult<Option<Entry>> {
-		hdk::get_entry(&address)
+	pub fn get_entry(address: Address) -> ProtocolLoveResult<Option<Entry>> {
+		Ok(hdk::get_entry(&address)?)
 	}
 
 	#[zome_fn("hc_public")]
-	pub fn create_person(person: OptionalPersonParams) -> ZomeApiResult<PersonPayload> {
-		crate::person::create_person(person.into())
+	pub fn create_person(person: OptionalPersonParams) -> ProtocolLoveResult<PersonPayload> {
+		crate::person::create_person(&HdkStore, person.into_person_params(&HdkStore))
 	}
 
 	#[zome_fn("hc_public")]
-	pub fn get_person(person_address: Address) -> ZomeApiResult<PersonPayload> {
-		crate::person::get_person(person_address)
+	pub fn get_person(person_address: Address) -> ProtocolLoveResult<PersonPayload> {
+		crate::person::get_person(&HdkStore, person_address)
 	}
 
 	#[zome_fn("hc_public")]
 	pub fn create_collective(
 		collective: CreateCollectiveParams
-	) -> ZomeApiResult<CollectivePayload> {
-		crate::collective::create_collective(collective)
+	) -> ProtocolLoveResult<CollectivePayload> {
+		crate::collective::create_collective(&HdkStore, collective)
 	}
 
 	// curl -X POST -H "Content-Type: application/json" -d '{"id": "0", "jsonrpc": "2.0", "method": "call", "params": {"instance_id": "test-instance", "zome": "protocol-love", "function": "get_collective", "args": { "collective_address": "addr" } }}' http://127.0.0.1:8888
 	#[zome_fn("hc_public")]
 	pub fn get_collective(
 		collective_address: Address
-	) -> ZomeApiResult<CollectivePayload> {
-		crate::collective::get_collective(collective_address)
+	) -> ProtocolLoveResult<CollectivePayload> {
+		crate::collective::get_collective(&HdkStore, collective_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn ensure_collective_initialised(
+		collective_address: Address
+	) -> ProtocolLoveResult<CollectivePayload> {
+		crate::collective::ensure_collective_initialised(&HdkStore, collective_address)
 	}
 
 	#[zome_fn("hc_public")]
 	pub fn set_collective_name(
 		collective_address: Address,
 		name: String
-	) -> ZomeApiResult<CollectivePayload> {
-		crate::collective::set_collective_name(collective_address, name)
+	) -> ProtocolLoveResult<CollectivePayload> {
+		crate::collective::set_collective_name(&HdkStore, collective_address, name)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn update_collective(
+		collective_address: Address,
+		collective: UpdateCollectiveParams
+	) -> ProtocolLoveResult<CollectivePayload> {
+		crate::collective::update_collective(&HdkStore, collective_address, collective)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn add_collective_person(
+		collective_address: Address,
+		person_address: Address
+	) -> ProtocolLoveResult<CollectivePeoplePayload> {
+		crate::collective::add_collective_person(&HdkStore, collective_address, person_address)
 	}
 
 	#[zome_fn("hc_public")]
 	pub fn get_collective_people(
 		collective_address: Address
-	) -> ZomeApiResult<CollectivePeoplePayload> {
-		crate::collective::get_collective_people(collective_address)
+	) -> ProtocolLoveResult<CollectivePeoplePayload> {
+		crate::collective::get_collective_people(&HdkStore, collective_address)
 	}
 
 	// curl -X POST -H "Content-Type: application/json" -d '{"id": "0", "jsonrpc": "2.0", "method": "call", "params": {"instance_id": "test-instance", "zome": "protocol-love", "function": "get_collective", "args": { "collective_address": "addr" } }}' http://127.0.0.1:8888
 	#[zome_fn("hc_public")]
-	pub fn get_actions(collective_address: Address) -> ZomeApiResult<ActionsPayload> {
-		crate::action::get_actions(collective_address)
+	pub fn get_actions(collective_address: Address) -> ProtocolLoveResult<ActionsPayload> {
+		crate::action::get_actions(&HdkStore, collective_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn create_proposal(proposal: ProposalParams) -> ProtocolLoveResult<ProposalPayload> {
+		crate::proposal::create_proposal(&HdkStore, proposal)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_proposal(proposal_address: Address) -> ProtocolLoveResult<ProposalPayload> {
+		crate::proposal::get_proposal(&HdkStore, proposal_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_collective_proposals(
+		collective_address: Address
+	) -> ProtocolLoveResult<CollectiveProposalsPayload> {
+		crate::proposal::get_collective_proposals(&HdkStore, collective_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn start_proposal_reveal(
+		proposal_address: Address,
+		timestamp: u64
+	) -> ProtocolLoveResult<ProposalPayload> {
+		crate::proposal::start_proposal_reveal(&HdkStore, proposal_address, timestamp)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn close_proposal(
+		proposal_address: Address,
+		timestamp: u64
+	) -> ProtocolLoveResult<ProposalTallyPayload> {
+		crate::proposal::close_proposal(&HdkStore, proposal_address, timestamp)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn close_expired_proposals(
+		collective_address: Address,
+		person_address: Address,
+		timestamp: u64
+	) -> ProtocolLoveResult<ExpiredProposalsPayload> {
+		crate::proposal::close_expired_proposals(&HdkStore, collective_address, person_address, timestamp)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn cast_vote(vote: VoteParams) -> ProtocolLoveResult<VotePayload> {
+		crate::vote::cast_vote(&HdkStore, vote)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn commit_vote(vote: VoteParams) -> ProtocolLoveResult<VoteCommitmentPayload> {
+		crate::vote::commit_vote(&HdkStore, vote)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn reveal_vote(vote: VoteParams) -> ProtocolLoveResult<VotePayload> {
+		crate::vote::reveal_vote(&HdkStore, vote)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_proposal_tally(proposal_address: Address) -> ProtocolLoveResult<ProposalTallyPayload> {
+		crate::vote::get_proposal_tally(&HdkStore, proposal_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_voice_credits(
+		collective_address: Address,
+		person_address: Address
+	) -> ProtocolLoveResult<VoiceCreditsPayload> {
+		crate::voice_credit::get_voice_credits(&HdkStore, collective_address, person_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn start_voice_credit_period(
+		collective_address: Address,
+		voice_credits_per_period: Option<u64>
+	) -> ProtocolLoveResult<CollectivePayload> {
+		crate::voice_credit::start_voice_credit_period(
+			&HdkStore,
+			collective_address,
+			voice_credits_per_period,
+		)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn delegate_vote(delegation: DelegationParams) -> ProtocolLoveResult<DelegationPayload> {
+		crate::delegation::delegate_vote(&HdkStore, delegation)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn revoke_delegation(delegation: DelegationParams) -> ProtocolLoveResult<DelegationPayload> {
+		crate::delegation::revoke_delegation(&HdkStore, delegation)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_delegations(collective_address: Address) -> ProtocolLoveResult<DelegationsPayload> {
+		crate::delegation::get_delegations(&HdkStore, collective_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_ledger(collective_address: Address) -> ProtocolLoveResult<LedgerPayload> {
+		crate::ledger::get_ledger(&HdkStore, collective_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn deposit_ledger_funds(
+		collective_address: Address,
+		amount: u64
+	) -> ProtocolLoveResult<LedgerPayload> {
+		crate::ledger::deposit_ledger_funds(&HdkStore, collective_address, amount)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn stake_conviction(
+		conviction_stake: ConvictionStake
+	) -> ProtocolLoveResult<ConvictionStakePayload> {
+		crate::conviction::stake_conviction(&HdkStore, conviction_stake)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_proposal_conviction(
+		proposal_address: Address,
+		timestamp: u64
+	) -> ProtocolLoveResult<ConvictionPayload> {
+		crate::conviction::get_proposal_conviction(&HdkStore, proposal_address, timestamp)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn fund_conviction_proposal(
+		proposal_address: Address,
+		timestamp: u64
+	) -> ProtocolLoveResult<ConvictionPayload> {
+		crate::conviction::fund_conviction_proposal(&HdkStore, proposal_address, timestamp)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn raise_objection(objection: ObjectionParams) -> ProtocolLoveResult<ObjectionPayload> {
+		crate::objection::raise_objection(&HdkStore, objection)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn resolve_objection(
+		resolution: ResolveObjectionParams
+	) -> ProtocolLoveResult<ObjectionPayload> {
+		crate::objection::resolve_objection(&HdkStore, resolution)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_proposal_objections(
+		proposal_address: Address
+	) -> ProtocolLoveResult<ProposalObjectionsPayload> {
+		crate::objection::get_proposal_objections(&HdkStore, proposal_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn start_proposal_discussion(proposal_address: Address) -> ProtocolLoveResult<ProposalPayload> {
+		crate::proposal::start_proposal_discussion(&HdkStore, proposal_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn open_proposal_voting(
+		proposal_address: Address,
+		timestamp: u64
+	) -> ProtocolLoveResult<ProposalPayload> {
+		crate::proposal::open_proposal_voting(&HdkStore, proposal_address, timestamp)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn revise_proposal(revision: ReviseProposalParams) -> ProtocolLoveResult<ProposalPayload> {
+		crate::revision::revise_proposal(&HdkStore, revision)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_proposal_revisions(
+		proposal_address: Address
+	) -> ProtocolLoveResult<ProposalRevisionsPayload> {
+		crate::revision::get_proposal_revisions(&HdkStore, proposal_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_proposal_diff(
+		proposal_address: Address,
+		from_revision: u64,
+		to_revision: u64
+	) -> ProtocolLoveResult<ProposalDiffPayload> {
+		crate::revision::get_proposal_diff(&HdkStore, proposal_address, from_revision, to_revision)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn propose_amendment(amendment: AmendmentParams) -> ProtocolLoveResult<AmendmentPayload> {
+		crate::amendment::propose_amendment(&HdkStore, amendment)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn accept_amendment(
+		amendment_address: Address,
+		author_address: Address
+	) -> ProtocolLoveResult<AmendmentPayload> {
+		crate::amendment::accept_amendment(&HdkStore, amendment_address, author_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn reject_amendment(
+		amendment_address: Address,
+		author_address: Address
+	) -> ProtocolLoveResult<AmendmentPayload> {
+		crate::amendment::reject_amendment(&HdkStore, amendment_address, author_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_proposal_amendments(
+		proposal_address: Address
+	) -> ProtocolLoveResult<ProposalAmendmentsPayload> {
+		crate::amendment::get_proposal_amendments(&HdkStore, proposal_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn post_message(message: MessageParams) -> ProtocolLoveResult<MessagePayload> {
+		crate::message::post_message(&HdkStore, message)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn edit_message(
+		message_address: Address,
+		content: String,
+		timestamp: u64
+	) -> ProtocolLoveResult<MessagePayload> {
+		crate::message::edit_message(&HdkStore, message_address, content, timestamp)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_thread(thread: ThreadParams) -> ProtocolLoveResult<ThreadPayload> {
+		crate::message::get_thread(&HdkStore, thread)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_message_history(message_address: Address) -> ProtocolLoveResult<MessageHistoryPayload> {
+		crate::message::get_message_history(&HdkStore, message_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn create_proposal_template(
+		template: ProposalTemplateParams
+	) -> ProtocolLoveResult<ProposalTemplatePayload> {
+		crate::template::create_proposal_template(&HdkStore, template)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_collective_templates(
+		collective_address: Address
+	) -> ProtocolLoveResult<CollectiveTemplatesPayload> {
+		crate::template::get_collective_templates(&HdkStore, collective_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn set_sponsor_threshold(
+		collective_address: Address,
+		sponsor_threshold: u64
+	) -> ProtocolLoveResult<CollectivePayload> {
+		crate::sponsor::set_sponsor_threshold(&HdkStore, collective_address, sponsor_threshold)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn sponsor_proposal(sponsorship: SponsorshipParams) -> ProtocolLoveResult<ProposalSponsorsPayload> {
+		crate::sponsor::sponsor_proposal(&HdkStore, sponsorship)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn unsponsor_proposal(
+		sponsorship: SponsorshipParams
+	) -> ProtocolLoveResult<ProposalSponsorsPayload> {
+		crate::sponsor::unsponsor_proposal(&HdkStore, sponsorship)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_proposal_sponsors(proposal_address: Address) -> ProtocolLoveResult<ProposalSponsorsPayload> {
+		crate::sponsor::get_proposal_sponsors(&HdkStore, proposal_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn amend_charter(proposal_address: Address, timestamp: u64) -> ProtocolLoveResult<CharterPayload> {
+		crate::charter::amend_charter(&HdkStore, proposal_address, timestamp)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_charter(
+		collective_address: Address,
+		timestamp: Option<u64>
+	) -> ProtocolLoveResult<CharterPayload> {
+		crate::charter::get_charter(&HdkStore, collective_address, timestamp)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_charter_history(collective_address: Address) -> ProtocolLoveResult<CharterHistoryPayload> {
+		crate::charter::get_charter_history(&HdkStore, collective_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn create_sub_collective(sub_collective: SubCollectiveParams) -> ProtocolLoveResult<CollectivePayload> {
+		crate::sub_collective::create_sub_collective(&HdkStore, sub_collective)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_collective_tree(collective_address: Address) -> ProtocolLoveResult<CollectiveTreePayload> {
+		crate::sub_collective::get_collective_tree(&HdkStore, collective_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn escalate_action(
+		collective_address: Address,
+		action_address: Address
+	) -> ProtocolLoveResult<EscalatedActionsPayload> {
+		crate::sub_collective::escalate_action(&HdkStore, collective_address, action_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_escalated_actions(collective_address: Address) -> ProtocolLoveResult<EscalatedActionsPayload> {
+		crate::sub_collective::get_escalated_actions(&HdkStore, collective_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn add_member_collective(
+		collective_address: Address,
+		member_collective_address: Address
+	) -> ProtocolLoveResult<MemberCollectivesPayload> {
+		crate::federation::add_member_collective(&HdkStore, collective_address, member_collective_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn join_federation(
+		collective_address: Address,
+		federation_address: Address
+	) -> ProtocolLoveResult<CollectivePayload> {
+		crate::federation::join_federation(&HdkStore, collective_address, federation_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_member_collectives(collective_address: Address) -> ProtocolLoveResult<MemberCollectivesPayload> {
+		crate::federation::get_member_collectives(&HdkStore, collective_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn cast_collective_vote(vote: CollectiveVoteParams) -> ProtocolLoveResult<VotePayload> {
+		crate::vote::cast_collective_vote(&HdkStore, vote)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn set_election_tally_method(
+		collective_address: Address,
+		election_tally_method: TallyMethod
+	) -> ProtocolLoveResult<CollectivePayload> {
+		crate::election::set_election_tally_method(&HdkStore, collective_address, election_tally_method)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn create_role(role: RoleParams) -> ProtocolLoveResult<RolePayload> {
+		crate::election::create_role(&HdkStore, role)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_role(role_address: Address, timestamp: u64) -> ProtocolLoveResult<RolePayload> {
+		crate::election::get_role(&HdkStore, role_address, timestamp)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_collective_roles(
+		collective_address: Address,
+		timestamp: u64
+	) -> ProtocolLoveResult<CollectiveRolesPayload> {
+		crate::election::get_collective_roles(&HdkStore, collective_address, timestamp)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn start_election(election: ElectionParams) -> ProtocolLoveResult<ElectionPayload> {
+		crate::election::start_election(&HdkStore, election)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn start_expired_elections(
+		collective_address: Address,
+		person_address: Address,
+		timestamp: u64
+	) -> ProtocolLoveResult<CollectiveElectionsPayload> {
+		crate::election::start_expired_elections(&HdkStore, collective_address, person_address, timestamp)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn nominate_candidate(nomination: NominationParams) -> ProtocolLoveResult<ElectionPayload> {
+		crate::election::nominate_candidate(&HdkStore, nomination)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn open_election_voting(
+		election_address: Address,
+		author_address: Address
+	) -> ProtocolLoveResult<ElectionPayload> {
+		crate::election::open_election_voting(&HdkStore, election_address, author_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn close_election(election_address: Address, timestamp: u64) -> ProtocolLoveResult<RolePayload> {
+		crate::election::close_election(&HdkStore, election_address, timestamp)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn announce_sortition(sortition: SortitionParams) -> ProtocolLoveResult<SortitionPayload> {
+		crate::sortition::announce_sortition(&HdkStore, sortition)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn commit_sortition_seed(seed: SortitionSeedParams) -> ProtocolLoveResult<SortitionSeedPayload> {
+		crate::sortition::commit_sortition_seed(&HdkStore, seed)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn reveal_sortition_seed(seed: SortitionSeedParams) -> ProtocolLoveResult<SortitionSeedPayload> {
+		crate::sortition::reveal_sortition_seed(&HdkStore, seed)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn draw_sortition(sortition_address: Address, timestamp: u64) -> ProtocolLoveResult<SortitionPayload> {
+		crate::sortition::draw_sortition(&HdkStore, sortition_address, timestamp)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_sortition(sortition_address: Address) -> ProtocolLoveResult<SortitionPayload> {
+		crate::sortition::get_sortition(&HdkStore, sortition_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn verify_sortition(sortition_address: Address) -> ProtocolLoveResult<SortitionVerificationPayload> {
+		crate::sortition::verify_sortition(&HdkStore, sortition_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn set_critical_action_policy(proposal_address: Address) -> ProtocolLoveResult<CollectivePayload> {
+		crate::critical_action::set_critical_action_policy(&HdkStore, proposal_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn enact_proposal(proposal_address: Address, timestamp: u64) -> ProtocolLoveResult<ProposalActionPayload> {
+		crate::critical_action::enact_proposal(&HdkStore, proposal_address, timestamp)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn veto_critical_action(veto: VetoParams) -> ProtocolLoveResult<CriticalActionPayload> {
+		crate::critical_action::veto_critical_action(&HdkStore, veto)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn execute_critical_action(
+		critical_action_address: Address,
+		timestamp: u64
+	) -> ProtocolLoveResult<CriticalActionPayload> {
+		crate::critical_action::execute_critical_action(&HdkStore, critical_action_address, timestamp)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_critical_action(critical_action_address: Address) -> ProtocolLoveResult<CriticalActionPayload> {
+		crate::critical_action::get_critical_action(&HdkStore, critical_action_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn get_critical_actions(collective_address: Address) -> ProtocolLoveResult<CollectiveCriticalActionsPayload> {
+		crate::critical_action::get_critical_actions(&HdkStore, collective_address)
+	}
+
+	#[zome_fn("hc_public")]
+	pub fn dissolve_collective(proposal_address: Address, timestamp: u64) -> ProtocolLoveResult<DissolutionPayload> {
+		crate::dissolution::dissolve_collective(&HdkStore, proposal_address, timestamp)
 	}
 
 	#[zome_fn("hc_public")]
-	pub fn create_proposal(proposal: ProposalParams) -> ZomeApiResult<ProposalPayload> {
-		crate::proposal::create_proposal(proposal)
+	pub fn get_dissolution(collective_address: Address) -> ProtocolLoveResult<DissolutionPayload> {
+		crate::dissolution::get_dissolution(&HdkStore, collective_address)
 	}
 }
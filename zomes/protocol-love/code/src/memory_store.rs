@@ -0,0 +1,286 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use hdk::holochain_json_api::json::JsonString;
+use holochain_persistence_api::cas::content::AddressableContent;
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::signal::receive_signal;
+use crate::store::Store;
+use crate::validation::{
+	EntryValidation, LinkValidation, ValidationContext, validate_app_entry, validate_app_link,
+};
+
+/// In-memory [Store](trait.Store.html) to run the domain logic without a conductor.
+///
+/// Entries & links are validated with the zome validation rules, signed by the `agent_address`.
+/// Links are returned in the reverse order of their commit.
+/// Stores returned by [for_agent](struct.MemoryStore.html#method.for_agent) share the same
+/// entries & links, simulating several agents on the same DHT.
+#[derive(Clone)]
+pub struct MemoryStore {
+	agent_address: Address,
+	dht: Rc<RefCell<MemoryDht>>,
+}
+
+#[derive(Default)]
+struct MemoryDht {
+	entries: HashMap<Address, Entry>,
+	/// Maps the address of an updated entry to the address of its replacement.
+	updates: HashMap<Address, Address>,
+	links: Vec<MemoryLink>,
+	signals: Vec<MemorySignal>,
+	/// Time of the commits in seconds since the unix epoch.
+	now: u64,
+}
+
+struct MemoryLink {
+	base: Address,
+	target: Address,
+	link_type: String,
+	tag: String,
+}
+
+struct MemorySignal {
+	agent_address: Address,
+	name: String,
+	payload: JsonString,
+}
+
+impl MemoryDht {
+	fn latest_address(&self, address: &Address) -> Address {
+		let mut latest_address = address.clone();
+		while let Some(update_address) = self.updates.get(&latest_address) {
+			latest_address = update_address.clone();
+		}
+		latest_address
+	}
+}
+
+impl MemoryStore {
+	pub fn new(agent_address: Address) -> Self {
+		MemoryStore {
+			agent_address,
+			dht: Default::default(),
+		}
+	}
+
+	/// Returns a store for the agent with the `agent_address`, sharing the entries & links.
+	pub fn for_agent(&self, agent_address: Address) -> Self {
+		MemoryStore {
+			agent_address,
+			dht: self.dht.clone(),
+		}
+	}
+
+	/// Sets the time of the following commits, in seconds since the unix epoch.
+	pub fn set_now(&self, now: u64) {
+		self.dht.borrow_mut().now = now;
+	}
+
+	/// Time of the following commits, in seconds since the unix epoch.
+	pub fn now(&self) -> u64 {
+		self.dht.borrow().now
+	}
+
+	/// Signals emitted to the agent, as `(name, payload)`, oldest first.
+	pub fn signals(&self) -> Vec<(String, JsonString)> {
+		self.dht.borrow().signals.iter()
+			.filter(|signal| signal.agent_address == self.agent_address)
+			.map(|signal| (signal.name.clone(), signal.payload.clone()))
+			.collect()
+	}
+
+	fn validate(&self, entry: &Entry, validation: EntryValidation<Entry>) -> ProtocolLoveResult<()> {
+		let entry_type = match entry {
+			Entry::App(entry_type, _) => String::from(entry_type.clone()),
+			_ => return Ok(()),
+		};
+		let app_validation = validation.try_map_with(|entry| match entry {
+			Entry::App(_, entry_value) => Ok(entry_value),
+			_ => Err(ProtocolLoveError::invariant_broken("Entry is not an app entry")),
+		})?;
+		validate_app_entry(self, &entry_type, app_validation, &self.validation_context())
+	}
+
+	fn validation_context(&self) -> ValidationContext {
+		ValidationContext {
+			sources: vec![self.agent_address.clone()],
+			timestamp: self.dht.borrow().now,
+		}
+	}
+}
+
+impl Store for MemoryStore {
+	fn agent_address(&self) -> Address {
+		self.agent_address.clone()
+	}
+
+	fn entry_address(&self, entry: &Entry) -> ProtocolLoveResult<Address> {
+		Ok(entry.address())
+	}
+
+	fn commit_entry(&self, entry: &Entry) -> ProtocolLoveResult<Address> {
+		self.validate(entry, EntryValidation::Create {
+			entry: entry.clone(),
+		})?;
+		let address = entry.address();
+		self.dht.borrow_mut().entries.insert(address.clone(), entry.clone());
+		Ok(address)
+	}
+
+	fn update_entry(&self, entry: Entry, address: &Address) -> ProtocolLoveResult<Address> {
+		let old_entry = match self.get_entry(address)? {
+			Some(old_entry) => old_entry,
+			None => return Err(ProtocolLoveError::not_found(address)),
+		};
+		self.validate(&entry, EntryValidation::Modify {
+			new_entry: entry.clone(),
+			old_entry,
+		})?;
+		let mut dht = self.dht.borrow_mut();
+		let latest_address = dht.latest_address(address);
+		let update_address = entry.address();
+		if update_address == latest_address {
+			return Ok(update_address);
+		}
+		dht.entries.insert(update_address.clone(), entry);
+		dht.updates.insert(latest_address, update_address.clone());
+		Ok(update_address)
+	}
+
+	fn link_entries(
+		&self,
+		base: &Address,
+		target: &Address,
+		link_type: &str,
+		tag: &str,
+	) -> ProtocolLoveResult<Address> {
+		for address in &[base, target] {
+			if !self.dht.borrow().entries.contains_key(*address) {
+				return Err(ProtocolLoveError::not_found(address));
+			}
+		}
+		validate_app_link(self, &LinkValidation {
+			base: base.clone(),
+			target: target.clone(),
+			link_type: link_type.into(),
+			tag: tag.into(),
+		}, &self.validation_context())?;
+		let mut dht = self.dht.borrow_mut();
+		dht.links.push(MemoryLink {
+			base: base.clone(),
+			target: target.clone(),
+			link_type: link_type.into(),
+			tag: tag.into(),
+		});
+		Ok(Address::from(format!("{}:{}:{}:{}", base, link_type, tag, target)))
+	}
+
+	fn get_entry(&self, address: &Address) -> ProtocolLoveResult<Option<Entry>> {
+		let dht = self.dht.borrow();
+		Ok(dht.entries.get(&dht.latest_address(address)).cloned())
+	}
+
+	fn get_entry_history(&self, address: &Address) -> ProtocolLoveResult<Vec<Entry>> {
+		let dht = self.dht.borrow();
+		let mut history = vec![];
+		let mut version_address = Some(address.clone());
+		while let Some(address) = version_address {
+			match dht.entries.get(&address) {
+				Some(entry) => history.push(entry.clone()),
+				None => break,
+			}
+			version_address = dht.updates.get(&address).cloned();
+		}
+		Ok(history)
+	}
+
+	fn get_initial_address(&self, address: &Address) -> ProtocolLoveResult<Address> {
+		let dht = self.dht.borrow();
+		let mut version_address = address.clone();
+		for _ in 0..dht.updates.len() {
+			match dht.updates.iter().find(|(_, update_address)| *update_address == &version_address) {
+				Some((replaced_address, _)) => version_address = replaced_address.clone(),
+				None => break,
+			}
+		}
+		Ok(version_address)
+	}
+
+	fn emit_signal(&self, name: &str, payload: JsonString) -> ProtocolLoveResult<()> {
+		self.dht.borrow_mut().signals.push(MemorySignal {
+			agent_address: self.agent_address.clone(),
+			name: name.into(),
+			payload,
+		});
+		Ok(())
+	}
+
+	fn send_signal(&self, agent_address: &Address, payload: JsonString) -> ProtocolLoveResult<()> {
+		receive_signal(&self.for_agent(agent_address.clone()), &self.agent_address, payload)
+	}
+
+	fn get_links(
+		&self,
+		base: &Address,
+		link_type: &str,
+		tag: Option<&str>,
+	) -> ProtocolLoveResult<Vec<Address>> {
+		let dht = self.dht.borrow();
+		Ok(dht.links.iter()
+			.rev()
+			.filter(|link|
+				&link.base == base
+					&& link.link_type == link_type
+					&& tag.map_or(true, |tag| link.tag == tag))
+			.map(|link| link.target.clone())
+			.collect())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use holochain_wasm_utils::holochain_core_types::entry::Entry;
+	use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+	use crate::ledger::Ledger;
+	use crate::store::{Store, get_as_type, get_initial_as_type};
+	use super::MemoryStore;
+
+	/// A ledger entry under a type without validation rules, as it belongs to no collective.
+	fn ledger_entry(name: &str) -> Entry {
+		Entry::App("test_ledger".into(), Ledger { name: name.into(), ..Ledger::default() }.into())
+	}
+
+	#[test]
+	fn update_entry_is_returned_from_the_original_address() {
+		let store = MemoryStore::new(Address::from("agent"));
+		let address = store.commit_entry(&ledger_entry("first")).unwrap();
+		store.update_entry(ledger_entry("second"), &address).unwrap();
+		let third_address = store.update_entry(ledger_entry("third"), &address).unwrap();
+		let ledger: Ledger = get_as_type(&store, &address).unwrap();
+		assert_eq!(ledger.name, "third");
+		let initial_ledger: Ledger = get_initial_as_type(&store, &address).unwrap();
+		assert_eq!(initial_ledger.name, "first");
+		assert_eq!(store.get_entry_history(&address).unwrap().len(), 3);
+		assert_eq!(store.get_initial_address(&third_address).unwrap(), address);
+	}
+
+	#[test]
+	fn get_links_returns_the_most_recent_link_first() {
+		let store = MemoryStore::new(Address::from("agent"));
+		let base = store.commit_entry(&ledger_entry("base")).unwrap();
+		let first = store.commit_entry(&ledger_entry("first")).unwrap();
+		let second = store.commit_entry(&ledger_entry("second")).unwrap();
+		store.link_entries(&base, &first, "ledger->ledger", "a").unwrap();
+		store.link_entries(&base, &second, "ledger->ledger", "b").unwrap();
+		assert_eq!(
+			store.get_links(&base, "ledger->ledger", None).unwrap(),
+			vec![second.clone(), first.clone()]);
+		assert_eq!(
+			store.get_links(&base, "ledger->ledger", Some("a")).unwrap(),
+			vec![first]);
+		assert!(store.get_links(&base, "other", None).unwrap().is_empty());
+	}
+}
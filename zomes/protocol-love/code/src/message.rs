@@ -0,0 +1,480 @@
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use hdk::holochain_core_types::dna::entry_types::Sharing;
+use hdk::prelude::ValidatingEntryType;
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::action::{Action, ActionOp, ActionStatus, ActionStrategy, RootAction};
+use crate::collective::is_collective_person;
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::person::{Person, get_agent_person};
+use crate::proposal::Proposal;
+use crate::signal::{ProtocolLoveSignal, emit_signal};
+use crate::store::{Store, HdkStore, get_as_type, get_links_and_load_type};
+use crate::utils::t;
+use crate::validation::{
+	EntryValidation, ValidationContext, from_hdk_validation_data, validate_timestamp,
+};
+
+/// Default number of messages returned by [get_thread](fn.get_thread.html).
+pub const THREAD_PAGE_SIZE: u64 = 20;
+/// Maximum number of messages returned by [get_thread](fn.get_thread.html).
+pub const MAX_THREAD_PAGE_SIZE: u64 = 100;
+
+/// A message of a discussion thread attached to a [Proposal](struct.Proposal.html) or
+/// a [Collective](struct.Collective.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct Message {
+	pub collective_address: Address,
+	/// Address of the proposal or collective the thread is attached to.
+	pub thread_address: Address,
+	/// Address of the message replied to. `None` for a message at the top of the thread.
+	pub parent_address: Option<Address>,
+	/// Address of the [Person](struct.Person.html) posting the message.
+	pub author_address: Address,
+	pub content: String,
+	/// Time the message is posted in seconds since the unix epoch.
+	pub timestamp: u64,
+	/// Time of the latest edit in seconds since the unix epoch.
+	pub edited_at: Option<u64>,
+}
+
+/// An edit of a [Message](struct.Message.html), keeping the history of its content.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct MessageEdit {
+	pub message_address: Address,
+	pub prev_content: String,
+	pub content: String,
+	/// Time of the edit in seconds since the unix epoch.
+	pub timestamp: u64,
+}
+
+/// Api params for [post_message](fn.post_message.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct MessageParams {
+	pub thread_address: Address,
+	pub parent_address: Option<Address>,
+	pub author_address: Address,
+	pub content: String,
+	pub timestamp: u64,
+}
+
+/// Api params for [get_thread](fn.get_thread.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ThreadParams {
+	pub thread_address: Address,
+	/// Gets the replies to the message at the `parent_address` instead of the top of the thread.
+	pub parent_address: Option<Address>,
+	/// Number of messages skipped, oldest first. Defaults to 0.
+	pub offset: Option<u64>,
+	/// Optional number of messages defaults to [THREAD_PAGE_SIZE](constant.THREAD_PAGE_SIZE.html).
+	pub limit: Option<u64>,
+}
+
+/// Api payload containing the `message_address`, [message](struct.Message.html) &
+/// the count of its replies.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct MessagePayload {
+	pub message_address: Address,
+	pub message: Message,
+	pub reply_count: u64,
+}
+
+/// Api payload of a page of the messages in a thread, oldest first.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ThreadPayload {
+	pub thread_address: Address,
+	pub parent_address: Option<Address>,
+	pub messages: Vec<MessagePayload>,
+	pub offset: u64,
+	/// Count of the messages in the thread or replies to the parent.
+	pub total: u64,
+}
+
+/// Api payload of the [MessageEdits](struct.MessageEdit.html) of a message, oldest first.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct MessageHistoryPayload {
+	pub message_address: Address,
+	pub edits: Vec<MessageEdit>,
+}
+
+/// Returns a Holochain entry definition for a message.
+pub fn message_def() -> ValidatingEntryType {
+	entry!(
+		name: "message",
+		description: "A protocol.love message in a discussion thread",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<Message>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_message_entry(&HdkStore, validation, &context)?)
+		},
+		links: [
+			to!(
+				"message",
+				link_type: "message->reply",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |_validation_data: hdk::LinkValidationData| {
+					Ok(())
+				}
+			),
+			to!(
+				"message_edit",
+				link_type: "message->message_edit",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |_validation_data: hdk::LinkValidationData| {
+					Ok(())
+				}
+			)
+		]
+	)
+}
+
+/// Returns a Holochain entry definition for a message edit.
+pub fn message_edit_def() -> ValidatingEntryType {
+	entry!(
+		name: "message_edit",
+		description: "A protocol.love edit of a message",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<MessageEdit>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_message_edit_entry(&HdkStore, validation, &context)?)
+		}
+	)
+}
+
+/// Validation rules of a [Message](struct.Message.html) entry.
+pub fn validate_message_entry(
+	store: &dyn Store,
+	validation: EntryValidation<Message>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			validate_message_author(store, &entry, context)?;
+			if !is_collective_person(store, &entry.collective_address, &entry.author_address)? {
+				return Err(ProtocolLoveError::unauthorized(
+					"Only people in the collective can post messages"));
+			}
+			if get_thread_collective_address(store, &entry.thread_address)? != entry.collective_address {
+				return Err(ProtocolLoveError::validation_failed(
+					"thread_address", "Thread must be attached to the collective"));
+			}
+			if let Some(parent_address) = &entry.parent_address {
+				let parent: Message = t("validation error: message: fetch parent: ",
+					get_as_type(store, parent_address))?;
+				if parent.thread_address != entry.thread_address {
+					return Err(ProtocolLoveError::validation_failed(
+						"parent_address", "Reply must be in the thread of the parent"));
+				}
+			}
+			validate_message_content(&entry.content)?;
+			validate_timestamp("timestamp", entry.timestamp, context)?;
+			if entry.edited_at.is_some() {
+				return Err(ProtocolLoveError::validation_failed(
+					"edited_at", "Message cannot be posted edited"));
+			}
+			Ok(())
+		}
+		EntryValidation::Modify { new_entry, old_entry } => {
+			if (Message { content: old_entry.content.clone(), edited_at: None, ..new_entry.clone() })
+				!= (Message { edited_at: None, ..old_entry.clone() }) {
+				return Err(ProtocolLoveError::unauthorized("Only the content of a message can be edited"));
+			}
+			validate_message_author(store, &old_entry, context)?;
+			validate_message_content(&new_entry.content)?;
+			match new_entry.edited_at {
+				Some(edited_at) => validate_timestamp("edited_at", edited_at, context),
+				None => Err(ProtocolLoveError::validation_failed(
+					"edited_at", "Edited message must have an edit time")),
+			}
+		}
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Message cannot be deleted")),
+	}
+}
+
+/// Validation rules of a [MessageEdit](struct.MessageEdit.html) entry.
+pub fn validate_message_edit_entry(
+	store: &dyn Store,
+	validation: EntryValidation<MessageEdit>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let message: Message = t("validation error: message_edit: fetch message: ",
+				get_as_type(store, &entry.message_address))?;
+			validate_message_author(store, &message, context)?;
+			validate_message_content(&entry.content)?;
+			validate_timestamp("timestamp", entry.timestamp, context)
+		}
+		EntryValidation::Modify { .. } =>
+			Err(ProtocolLoveError::unauthorized("Message edit cannot be modified")),
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Message edit cannot be deleted")),
+	}
+}
+
+fn validate_message_author(
+	store: &dyn Store,
+	message: &Message,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let author: Person = t("validation error: message: fetch author: ",
+		get_as_type(store, &message.author_address))?;
+	if !context.sources.contains(&author.agent_address) {
+		return Err(ProtocolLoveError::unauthorized("Message must be written by the agent of the author"));
+	}
+	Ok(())
+}
+
+fn validate_message_content(content: &str) -> ProtocolLoveResult<()> {
+	if content.trim().is_empty() || content.len() > 5000 {
+		return Err(ProtocolLoveError::validation_failed(
+			"content", "Content must be between 1 & 5000 characters"));
+	}
+	Ok(())
+}
+
+/// Api for a [Person](struct.Person.html) of the [Collective](struct.Collective.html) to post a
+/// [Message](struct.Message.html) in the thread of a proposal or collective, or reply to a message.
+pub fn post_message(
+	store: &dyn Store,
+	message_params: MessageParams,
+) -> ProtocolLoveResult<MessagePayload> {
+	t("post_message: ", get_agent_person(store, &message_params.author_address))?;
+	let collective_address =
+		t("post_message: ", get_thread_collective_address(store, &message_params.thread_address))?;
+	let message = Message {
+		collective_address,
+		thread_address: message_params.thread_address,
+		parent_address: message_params.parent_address,
+		author_address: message_params.author_address,
+		content: message_params.content,
+		timestamp: message_params.timestamp,
+		edited_at: None,
+	};
+	let message_entry = Entry::App("message".into(), (&message).into());
+	let message_address = t("post_message: ", store.commit_entry(&message_entry))?;
+	let (base, link_type) = match &message.parent_address {
+		Some(parent_address) => (parent_address.clone(), "message->reply".to_string()),
+		None => (
+			message.thread_address.clone(),
+			t("post_message: ", thread_link_type(store, &message.thread_address))?,
+		),
+	};
+	t("post_message: ", store.link_entries(&base, &message_address, &link_type, ""))?;
+	t("post_message: ", commit_message_action(
+		store,
+		&message.collective_address,
+		ActionOp::PostMessage,
+		MessageActionData {
+			message_address: message_address.clone(),
+			message: message.clone(),
+		}.into(),
+		serde_json::value::Value::Null.into(),
+	))?;
+	t("post_message: ", emit_signal(store, ProtocolLoveSignal::MessagePosted {
+		collective_address: message.collective_address.clone(),
+		thread_address: message.thread_address.clone(),
+		message_address: message_address.clone(),
+		message: message.clone(),
+	}))?;
+	Ok(MessagePayload {
+		message_address,
+		message,
+		reply_count: 0,
+	})
+}
+
+/// Api for the author to edit the content of a [Message](struct.Message.html).
+/// The former content is kept in the history of the message.
+pub fn edit_message(
+	store: &dyn Store,
+	message_address: Address,
+	content: String,
+	timestamp: u64,
+) -> ProtocolLoveResult<MessagePayload> {
+	let saved_message: Message = t("edit_message: ", get_as_type(store, &message_address))?;
+	match get_agent_person(store, &saved_message.author_address) {
+		Ok(_) => {}
+		Err(ProtocolLoveError::Unauthorized { .. }) =>
+			return Err(ProtocolLoveError::unauthorized(
+				"edit_message: Only the author can edit the message")),
+		Err(error) => return Err(error),
+	}
+	let message_edit = MessageEdit {
+		message_address: message_address.clone(),
+		prev_content: saved_message.content.clone(),
+		content: content.clone(),
+		timestamp,
+	};
+	let message_edit_entry = Entry::App("message_edit".into(), (&message_edit).into());
+	let message_edit_address = t("edit_message: ", store.commit_entry(&message_edit_entry))?;
+	t("edit_message: message->message_edit: ", store.link_entries(
+		&message_address,
+		&message_edit_address,
+		"message->message_edit",
+		"",
+	))?;
+	let message = Message {
+		content,
+		edited_at: Some(timestamp),
+		..saved_message.clone()
+	};
+	let message_entry = Entry::App("message".into(), (&message).into());
+	t("edit_message: ", store.update_entry(message_entry, &message_address))?;
+	t("edit_message: ", commit_message_action(
+		store,
+		&message.collective_address,
+		ActionOp::EditMessage,
+		MessageActionData {
+			message_address: message_address.clone(),
+			message: message.clone(),
+		}.into(),
+		MessageActionData {
+			message_address: message_address.clone(),
+			message: saved_message,
+		}.into(),
+	))?;
+	let reply_count = t("edit_message: ", count_replies(store, &message_address))?;
+	Ok(MessagePayload {
+		message_address,
+		message,
+		reply_count,
+	})
+}
+
+/// Api to get a page of the messages at the top of a thread, or of the replies to a message,
+/// oldest first.
+pub fn get_thread(
+	store: &dyn Store,
+	thread_params: ThreadParams,
+) -> ProtocolLoveResult<ThreadPayload> {
+	let offset = thread_params.offset.unwrap_or(0);
+	let limit = thread_params.limit.unwrap_or(THREAD_PAGE_SIZE);
+	if limit == 0 || limit > MAX_THREAD_PAGE_SIZE {
+		return Err(ProtocolLoveError::validation_failed(
+			"limit", &format!("get_thread: Limit must be between 1 & {}", MAX_THREAD_PAGE_SIZE)));
+	}
+	let mut message_addresses = match &thread_params.parent_address {
+		Some(parent_address) =>
+			t("get_thread: ", store.get_links(parent_address, "message->reply", None))?,
+		None => {
+			let link_type = t("get_thread: ", thread_link_type(store, &thread_params.thread_address))?;
+			t("get_thread: ", store.get_links(&thread_params.thread_address, &link_type, None))?
+		}
+	};
+	message_addresses.reverse();
+	let total = message_addresses.len() as u64;
+	let messages = message_addresses.into_iter()
+		.skip(offset as usize)
+		.take(limit as usize)
+		.map(|message_address| {
+			let message = t("get_thread: ", get_as_type(store, &message_address))?;
+			let reply_count = t("get_thread: ", count_replies(store, &message_address))?;
+			Ok(MessagePayload {
+				message_address,
+				message,
+				reply_count,
+			})
+		})
+		.collect::<ProtocolLoveResult<Vec<MessagePayload>>>()?;
+	Ok(ThreadPayload {
+		thread_address: thread_params.thread_address,
+		parent_address: thread_params.parent_address,
+		messages,
+		offset,
+		total,
+	})
+}
+
+/// Api to get the [MessageEdits](struct.MessageEdit.html) of a [Message](struct.Message.html),
+/// oldest first.
+pub fn get_message_history(
+	store: &dyn Store,
+	message_address: Address,
+) -> ProtocolLoveResult<MessageHistoryPayload> {
+	let mut edits: Vec<MessageEdit> =
+		t("get_message_history: ", get_links_and_load_type(
+			store,
+			&message_address,
+			"message->message_edit",
+			None,
+		))?;
+	edits.reverse();
+	Ok(MessageHistoryPayload {
+		message_address,
+		edits,
+	})
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct MessageActionData {
+	message_address: Address,
+	message: Message,
+}
+
+/// Commits an executed action with the
+/// [ActionStrategy::NewDiscussionMessage](enum.ActionStrategy.html) strategy.
+fn commit_message_action(
+	store: &dyn Store,
+	collective_address: &Address,
+	op: ActionOp,
+	data: JsonString,
+	prev_data: JsonString,
+) -> ProtocolLoveResult<Address> {
+	let (action_address, _, _) = RootAction::commit_action(Action {
+		op,
+		status: ActionStatus::Executed,
+		data,
+		prev_data,
+		tag: "message".into(),
+		strategy: ActionStrategy::NewDiscussionMessage,
+	}, store, collective_address.clone())?;
+	Ok(action_address)
+}
+
+/// Gets the address of the collective of the proposal or collective at the `thread_address`.
+fn get_thread_collective_address(
+	store: &dyn Store,
+	thread_address: &Address,
+) -> ProtocolLoveResult<Address> {
+	match thread_link_type(store, thread_address)?.as_str() {
+		"proposal->message" => {
+			let proposal: Proposal = get_as_type(store, thread_address)?;
+			Ok(proposal.collective_address)
+		}
+		_ => Ok(thread_address.clone()),
+	}
+}
+
+/// The type of the links from the proposal or collective at the `thread_address` to its messages.
+fn thread_link_type(store: &dyn Store, thread_address: &Address) -> ProtocolLoveResult<String> {
+	match store.get_entry(thread_address)? {
+		Some(Entry::App(entry_type, _)) => match String::from(entry_type).as_str() {
+			"collective" => Ok("collective->message".into()),
+			"proposal" => Ok("proposal->message".into()),
+			_ => Err(ProtocolLoveError::validation_failed(
+				"thread_address", "Threads are attached to a proposal or a collective")),
+		},
+		Some(_) => Err(ProtocolLoveError::validation_failed(
+			"thread_address", "Threads are attached to a proposal or a collective")),
+		None => Err(ProtocolLoveError::not_found(thread_address)),
+	}
+}
+
+fn count_replies(store: &dyn Store, message_address: &Address) -> ProtocolLoveResult<u64> {
+	Ok(store.get_links(message_address, "message->reply", None)?.len() as u64)
+}
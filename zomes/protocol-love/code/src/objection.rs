@@ -0,0 +1,310 @@
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use hdk::holochain_core_types::dna::entry_types::Sharing;
+use hdk::prelude::ValidatingEntryType;
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::action::{ActionOp, create_root_action};
+use crate::collective::is_collective_person;
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::person::{Person, get_agent_person};
+use crate::proposal::{Proposal, ProposalStatus, TallyMethod};
+use crate::revision::commit_proposal_revision;
+use crate::store::{Store, HdkStore, get_as_type};
+use crate::utils::t;
+use crate::validation::{
+	EntryValidation, LinkValidation, ValidationContext, from_hdk_validation_data,
+};
+
+/// A reasoned objection or concern raised by a [Person](struct.Person.html) on a
+/// [TallyMethod::Consent](enum.TallyMethod.html) [Proposal](struct.Proposal.html).
+///
+/// The proposal cannot pass while an [ObjectionKind::Objection](enum.ObjectionKind.html)
+/// is unresolved. The author of the proposal resolves it by integrating the objection.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct Objection {
+	pub proposal_address: Address,
+	/// Address of the [Person](struct.Person.html) raising the objection.
+	pub person_address: Address,
+	pub kind: ObjectionKind,
+	/// Reason of the objection.
+	pub reason: String,
+	/// Whether the author of the proposal integrated the objection.
+	pub resolved: bool,
+}
+
+/// How an [Objection](struct.Objection.html) weighs on the consent.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub enum ObjectionKind {
+	/// Blocks the consent until resolved.
+	Objection,
+	/// Recorded with the decision without blocking the consent.
+	Concern,
+}
+
+/// Api params for [raise_objection](fn.raise_objection.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ObjectionParams {
+	pub proposal_address: Address,
+	pub person_address: Address,
+	pub kind: ObjectionKind,
+	pub reason: String,
+}
+
+/// Api params for [resolve_objection](fn.resolve_objection.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ResolveObjectionParams {
+	pub objection_address: Address,
+	/// Amended content of the proposal integrating the objection. The content is kept when `None`.
+	pub content: Option<String>,
+}
+
+/// Api payload containing the `objection_address` & [objection](struct.Objection.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ObjectionPayload {
+	pub objection_address: Address,
+	pub objection: Objection,
+}
+
+/// Api payload of the [Objections](struct.Objection.html) on a [Proposal](struct.Proposal.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ProposalObjectionsPayload {
+	pub proposal_address: Address,
+	pub objections: Vec<ObjectionPayload>,
+}
+
+/// Returns a Holochain entry definition for an objection.
+pub fn objection_def() -> ValidatingEntryType {
+	entry!(
+		name: "objection",
+		description: "A protocol.love objection or concern on a consent proposal",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<Objection>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_objection_entry(&HdkStore, validation, &context)?)
+		}
+	)
+}
+
+/// Validation rules of an [Objection](struct.Objection.html) entry.
+///
+/// Only the author of the proposal can modify an objection, to mark it resolved.
+pub fn validate_objection_entry(
+	store: &dyn Store,
+	validation: EntryValidation<Objection>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let person: Person = t("validation error: objection: fetch person: ",
+				get_as_type(store, &entry.person_address))?;
+			if !context.sources.contains(&person.agent_address) {
+				return Err(ProtocolLoveError::unauthorized(
+					"Objection must be raised by the agent of the person"));
+			}
+			let proposal = validate_objection_proposal(store, &entry.proposal_address)?;
+			if !is_collective_person(store, &proposal.collective_address, &entry.person_address)? {
+				return Err(ProtocolLoveError::unauthorized(
+					"Only people in the collective can object"));
+			}
+			if entry.reason.trim().is_empty() || entry.reason.len() > 1000 {
+				return Err(ProtocolLoveError::validation_failed(
+					"reason", "Reason must be between 1 & 1000 characters"));
+			}
+			if entry.resolved {
+				return Err(ProtocolLoveError::validation_failed(
+					"resolved", "Objection cannot be raised resolved"));
+			}
+			Ok(())
+		}
+		EntryValidation::Modify { new_entry, old_entry } => {
+			if old_entry.resolved
+				|| !new_entry.resolved
+				|| (Objection { resolved: false, ..new_entry }) != old_entry {
+				return Err(ProtocolLoveError::unauthorized("Objection can only be marked resolved"));
+			}
+			let proposal = validate_objection_proposal(store, &old_entry.proposal_address)?;
+			let author: Person = t("validation error: objection: fetch author: ",
+				get_as_type(store, &proposal.author_address))?;
+			if !context.sources.contains(&author.agent_address) {
+				return Err(ProtocolLoveError::unauthorized(
+					"Only the author of the proposal can resolve an objection"));
+			}
+			Ok(())
+		}
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Objection cannot be deleted")),
+	}
+}
+
+/// Validation rules of a `proposal->objection` link: the objection is on the base proposal & is
+/// linked by the agent of the person objecting.
+pub fn validate_proposal_objection_link(
+	store: &dyn Store,
+	link: &LinkValidation,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let objection: Objection = t("validation error: proposal->objection: fetch objection: ",
+		get_as_type(store, &link.target))?;
+	if objection.proposal_address != link.base {
+		return Err(ProtocolLoveError::validation_failed(
+			"target", "Objection is not on the proposal"));
+	}
+	let person: Person = t("validation error: proposal->objection: fetch person: ",
+		get_as_type(store, &objection.person_address))?;
+	if !context.sources.contains(&person.agent_address) {
+		return Err(ProtocolLoveError::unauthorized(
+			"Objection must be linked by the agent of the person"));
+	}
+	Ok(())
+}
+
+/// Validates the proposal decides by consent & is in voting.
+fn validate_objection_proposal(
+	store: &dyn Store,
+	proposal_address: &Address,
+) -> ProtocolLoveResult<Proposal> {
+	let proposal: Proposal = t("validation error: objection: fetch proposal: ",
+		get_as_type(store, proposal_address))?;
+	if proposal.tally_method != TallyMethod::Consent {
+		return Err(ProtocolLoveError::conflict("Proposal does not decide by consent"));
+	}
+	if proposal.status != ProposalStatus::Voting {
+		return Err(ProtocolLoveError::conflict("Proposal is not in voting"));
+	}
+	Ok(proposal)
+}
+
+/// Api for a [Person](struct.Person.html) to raise an objection or concern on a
+/// [TallyMethod::Consent](enum.TallyMethod.html) [Proposal](struct.Proposal.html).
+pub fn raise_objection(
+	store: &dyn Store,
+	objection_params: ObjectionParams,
+) -> ProtocolLoveResult<ObjectionPayload> {
+	let proposal: Proposal =
+		t("raise_objection: ", get_as_type(store, &objection_params.proposal_address))?;
+	t("raise_objection: ", get_agent_person(store, &objection_params.person_address))?;
+	let objection = Objection {
+		proposal_address: objection_params.proposal_address,
+		person_address: objection_params.person_address,
+		kind: objection_params.kind,
+		reason: objection_params.reason,
+		resolved: false,
+	};
+	let objection_entry = Entry::App("objection".into(), (&objection).into());
+	let objection_address = t("raise_objection: ", store.commit_entry(&objection_entry))?;
+	t("raise_objection: proposal->objection: ", store.link_entries(
+		&objection.proposal_address,
+		&objection_address,
+		"proposal->objection",
+		"",
+	))?;
+	t("raise_objection: ", create_root_action(
+		store,
+		&proposal.collective_address,
+		ActionOp::RaiseObjection,
+		ObjectionPayload {
+			objection_address: objection_address.clone(),
+			objection: objection.clone(),
+		}.into(),
+		serde_json::value::Value::Null.into(),
+		"raise_objection",
+	))?;
+	Ok(ObjectionPayload {
+		objection_address,
+		objection,
+	})
+}
+
+/// Api for the author of the [Proposal](struct.Proposal.html) to integrate an
+/// [Objection](struct.Objection.html), amending the content of the proposal, & mark it resolved.
+pub fn resolve_objection(
+	store: &dyn Store,
+	resolve_objection_params: ResolveObjectionParams,
+) -> ProtocolLoveResult<ObjectionPayload> {
+	let objection_address = resolve_objection_params.objection_address;
+	let saved_objection: Objection =
+		t("resolve_objection: ", get_as_type(store, &objection_address))?;
+	if saved_objection.resolved {
+		return Err(ProtocolLoveError::conflict("resolve_objection: Objection is already resolved"));
+	}
+	let proposal_address = saved_objection.proposal_address.clone();
+	let proposal: Proposal = t("resolve_objection: ", get_as_type(store, &proposal_address))?;
+	if proposal.status != ProposalStatus::Voting {
+		return Err(ProtocolLoveError::conflict("resolve_objection: Proposal is not in voting"));
+	}
+	match get_agent_person(store, &proposal.author_address) {
+		Ok(_) => {}
+		Err(ProtocolLoveError::Unauthorized { .. }) =>
+			return Err(ProtocolLoveError::unauthorized(
+				"resolve_objection: Only the author can resolve an objection")),
+		Err(error) => return Err(error),
+	}
+	if let Some(content) = resolve_objection_params.content {
+		t("resolve_objection: ", commit_proposal_revision(
+			store,
+			&proposal_address,
+			proposal.clone(),
+			&proposal.author_address,
+			proposal.name.clone(),
+			content,
+			None,
+		))?;
+	}
+	let objection = Objection {
+		resolved: true,
+		..saved_objection
+	};
+	let objection_entry = Entry::App("objection".into(), (&objection).into());
+	t("resolve_objection: ", store.update_entry(objection_entry, &objection_address))?;
+	t("resolve_objection: ", create_root_action(
+		store,
+		&proposal.collective_address,
+		ActionOp::ResolveObjection,
+		ObjectionPayload {
+			objection_address: objection_address.clone(),
+			objection: objection.clone(),
+		}.into(),
+		serde_json::value::Value::Null.into(),
+		"resolve_objection",
+	))?;
+	Ok(ObjectionPayload {
+		objection_address,
+		objection,
+	})
+}
+
+/// Api to get the [Objections](struct.Objection.html) on a [Proposal](struct.Proposal.html).
+pub fn get_proposal_objections(
+	store: &dyn Store,
+	proposal_address: Address,
+) -> ProtocolLoveResult<ProposalObjectionsPayload> {
+	let objections = t("get_proposal_objections: ", get_objections(store, &proposal_address))?;
+	Ok(ProposalObjectionsPayload {
+		proposal_address,
+		objections,
+	})
+}
+
+/// Gets the latest version of the objections on the proposal, the most recent first.
+pub fn get_objections(
+	store: &dyn Store,
+	proposal_address: &Address,
+) -> ProtocolLoveResult<Vec<ObjectionPayload>> {
+	let objection_addresses =
+		t("get_objections: ", store.get_links(proposal_address, "proposal->objection", None))?;
+	objection_addresses.into_iter()
+		.map(|objection_address| {
+			let objection = t("get_objections: ", get_as_type(store, &objection_address))?;
+			Ok(ObjectionPayload {
+				objection_address,
+				objection,
+			})
+		})
+		.collect()
+}
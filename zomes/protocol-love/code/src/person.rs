@@ -1,39 +1,45 @@
 use std::prelude::v1::Into;
 use std::borrow::Borrow;
-use hdk::{EntryValidationData};
 use hdk::holochain_core_types::dna::entry_types::Sharing;
 use hdk::holochain_json_api::{
 	json::JsonString,
 	error::JsonError,
 };
-use hdk::prelude::{ZomeApiResult, ValidatingEntryType};
+use hdk::prelude::ValidatingEntryType;
 use holochain_persistence_api::cas::content::Address;
 use holochain_wasm_utils::holochain_core_types::entry::Entry;
-use crate::utils::{t};
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::utils::t;
+use crate::store::{Store, commit_entry_if_missing, get_as_type};
+use crate::validation::{EntryValidation, ValidationContext, from_hdk_validation_data};
 
 /// Api params with name, optional agent_address, & optional status.
 ///
-/// Convertable into [PersonParams](struct.PersonParams.html).
+/// Convertable into [PersonParams](struct.PersonParams.html) with
+/// [into_person_params](struct.OptionalPersonParams.html#method.into_person_params).
 #[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
 pub struct OptionalPersonParams {
 	pub name: String,
-	/// Optional agent_address defaults to hdk::AGENT_ADDRESS
+	/// Optional agent_address defaults to the agent making the call
 	pub agent_address: Option<Address>,
 	/// Optional status defaults to [PersonStatus::Active](enum.PersonStatus.html).
 	pub status: Option<PersonStatus>,
 }
 
-impl Into<PersonParams> for OptionalPersonParams {
-	fn into(self) -> PersonParams {
+impl OptionalPersonParams {
+	/// Converts into [PersonParams](struct.PersonParams.html),
+	/// defaulting to the agent of the `store`.
+	pub fn into_person_params(self, store: &dyn Store) -> PersonParams {
+		let default_params = PersonParams::for_agent(store.agent_address());
 		PersonParams {
 			name: self.name,
 			agent_address: match self.agent_address {
 				Some(agent_address) => agent_address,
-				None => PersonParams::default().agent_address,
+				None => default_params.agent_address,
 			},
 			status: match self.status {
 				Some(status) => status,
-				None => PersonParams::default().status,
+				None => default_params.status,
 			},
 		}
 	}
@@ -47,16 +53,23 @@ pub struct PersonParams {
 	pub status: PersonStatus,
 }
 
-impl Default for PersonParams {
-	fn default() -> Self {
+impl PersonParams {
+	/// Default params for the agent with the `agent_address`.
+	pub fn for_agent(agent_address: Address) -> Self {
 		PersonParams {
 			name: "".to_string(),
-			agent_address: hdk::AGENT_ADDRESS.clone(),
+			agent_address,
 			status: PersonStatus::Active,
 		}
 	}
 }
 
+impl Default for PersonParams {
+	fn default() -> Self {
+		PersonParams::for_agent(hdk::AGENT_ADDRESS.clone())
+	}
+}
+
 /// Is the [Person](struct.Person.html) Active or Inactive.
 #[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
 pub enum PersonStatus {
@@ -104,30 +117,8 @@ pub fn person_def() -> ValidatingEntryType {
 			hdk::ValidationPackageDefinition::Entry
 		},
 		validation: | validation_data: hdk::EntryValidationData<Person>| {
-			match validation_data{
-				EntryValidationData::Create { entry, validation_data } => {
-					if !validation_data.sources().contains(&entry.agent_address) {
-						return Err(
-							String::from("Person representing agent must be created by agent")
-						);
-					}
-					validate_name(&entry.name)?;
-					Ok(())
-				}
-				EntryValidationData::Modify { new_entry, old_entry, validation_data, .. } => {
-					if new_entry.agent_address != old_entry.agent_address {
-						return Err(String::from("agent_address cannot be updated"));
-					}
-					if !validation_data.sources().contains(&old_entry.agent_address) {
-						return Err(String::from("Person can only update by oneself"));
-					}
-					validate_name(&new_entry.name)?;
-					Ok(())
-				}
-				EntryValidationData::Delete { .. } => {
-					return Err(String::from("Person cannot be deleted"));
-				}
-			}
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_person_entry(validation, &context)?)
 		},
 		links: [
 			to!(
@@ -144,22 +135,60 @@ pub fn person_def() -> ValidatingEntryType {
 	)
 }
 
-fn validate_name(name: &str) -> Result<(), String> {
+/// Validation rules of a [Person](struct.Person.html) entry.
+pub fn validate_person_entry(
+	validation: EntryValidation<Person>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			if !context.sources.contains(&entry.agent_address) {
+				return Err(ProtocolLoveError::unauthorized(
+					"Person representing agent must be created by agent"
+				));
+			}
+			validate_name(&entry.name)?;
+			Ok(())
+		}
+		EntryValidation::Modify { new_entry, old_entry } => {
+			if new_entry.agent_address != old_entry.agent_address {
+				return Err(ProtocolLoveError::validation_failed(
+					"agent_address", "agent_address cannot be updated"
+				));
+			}
+			if !context.sources.contains(&old_entry.agent_address) {
+				return Err(ProtocolLoveError::unauthorized(
+					"Person can only update by oneself"
+				));
+			}
+			validate_name(&new_entry.name)?;
+			Ok(())
+		}
+		EntryValidation::Delete { .. } => {
+			Err(ProtocolLoveError::conflict("Person cannot be deleted"))
+		}
+	}
+}
+
+fn validate_name(name: &str) -> ProtocolLoveResult<()> {
 	if name.len() > 64 {
-		Err("Name is too long".into())
+		Err(ProtocolLoveError::validation_failed("name", "Name is too long"))
 	} else {
 		Ok(())
 	}
 }
 
 /// Api function to create & commit a [Person](struct.Person.html).
-pub fn create_person(person_params: PersonParams) -> ZomeApiResult<PersonPayload> {
+pub fn create_person(
+	store: &dyn Store,
+	person_params: PersonParams,
+) -> ProtocolLoveResult<PersonPayload> {
 	let CommitPersonResponse(
 		person_address,
 		_person_entry,
 		person,
 	) =
-		t("create_person: ", commit_person(Person {
+		t("create_person: ", commit_person(store, Person {
 			name: person_params.name,
 			agent_address: person_params.agent_address,
 			status: person_params.status,
@@ -170,10 +199,22 @@ pub fn create_person(person_params: PersonParams) -> ZomeApiResult<PersonPayload
 	})
 }
 
+/// Gets the [Person](struct.Person.html) at `person_address`, which must represent the agent
+/// of the `store`.
+pub fn get_agent_person(store: &dyn Store, person_address: &Address) -> ProtocolLoveResult<Person> {
+	let person: Person = t("get_agent_person: ", get_as_type(store, person_address))?;
+	if person.agent_address != store.agent_address() {
+		return Err(ProtocolLoveError::unauthorized("Person does not represent the agent"));
+	}
+	Ok(person)
+}
+
 /// Api function to get a [Person](struct.Person.html).
-pub fn get_person(person_address: Address) -> ZomeApiResult<PersonPayload> {
-	let person_address__ = person_address.clone();
-	let person = hdk::utils::get_as_type(person_address__)?;
+pub fn get_person(
+	store: &dyn Store,
+	person_address: Address,
+) -> ProtocolLoveResult<PersonPayload> {
+	let person = t("get_person: ", get_as_type(store, &person_address))?;
 	Ok(PersonPayload {
 		person_address,
 		person,
@@ -182,8 +223,9 @@ pub fn get_person(person_address: Address) -> ZomeApiResult<PersonPayload> {
 
 struct CommitPersonResponse(Address, Entry, Person);
 
-fn commit_person(person: Person) -> ZomeApiResult<CommitPersonResponse> {
+fn commit_person(store: &dyn Store, person: Person) -> ProtocolLoveResult<CommitPersonResponse> {
 	let person_entry = Entry::App("person".into(), person.borrow().into());
-	let person_address = hdk::commit_entry(&person_entry)?;
+	let person_address =
+		t("commit_person: ", commit_entry_if_missing(store, &person_entry))?;
 	Ok(CommitPersonResponse(person_address, person_entry, person))
 }
@@ -6,36 +6,239 @@ use hdk::holochain_core_types::dna::entry_types::Sharing;
 use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
 use holochain_wasm_utils::holochain_core_types::entry::Entry;
 use std::borrow::Borrow;
-use hdk::error::ZomeApiResult;
+use std::collections::BTreeMap;
+use serde_json::Value;
 use hdk::prelude::ValidatingEntryType;
+use crate::action::{ActionOp, create_root_action};
+use crate::collective::{
+	Collective, get_policy_collective, is_collective_person, validate_collective_not_archived,
+};
+use crate::conviction::validate_conviction_funding;
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::federation::is_member_collective;
+use crate::person::{Person, PersonStatus, get_agent_person};
+use crate::revision::{commit_initial_revision, is_proposal_revisable};
+use crate::utils::t;
+use crate::signal::{ProtocolLoveSignal, emit_signal};
+use crate::sponsor::validate_proposal_sponsors;
+use crate::store::{Store, HdkStore, get_as_type, get_links_and_load_type};
+use crate::tally::{ProposalOutcome, ProposalTally, tally_outcome};
+use crate::template::{
+	ProposalTemplate, ProposalTemplateValues, apply_proposal_template, validate_template_values,
+};
+use crate::validation::{
+	EntryValidation, ValidationContext, from_hdk_link_validation_data, from_hdk_validation_data,
+	validate_app_link, validate_timestamp,
+};
+use crate::vote::{ProposalTallyPayload, tally_proposal};
 
 /// Api params for [create_proposal](fn.create_proposal.html).
 #[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
 pub struct ProposalParams {
+	/// Address of the [Collective](struct.Collective.html) the proposal changes.
+	pub collective_address: Address,
+	/// Address of the [Person](struct.Person.html) authoring the proposal.
+	pub author_address: Address,
 	/// Name of the proposal.
 	pub name: String,
 	/// Text content of the proposal.
 	pub content: String,
+	/// Optional ballot mode defaults to [BallotMode::Open](enum.BallotMode.html).
+	pub ballot_mode: Option<BallotMode>,
+	/// Optional tally method defaults to [TallyMethod::Majority](enum.TallyMethod.html).
+	pub tally_method: Option<TallyMethod>,
+	/// Options voted on, required by the multi-option tally methods.
+	pub options: Option<Vec<String>>,
+	/// Tags categorizing the proposal, used to resolve [Delegations](struct.Delegation.html).
+	pub tags: Option<Vec<String>>,
+	/// Funds requested from the [Ledger](struct.Ledger.html), required by
+	/// [TallyMethod::Conviction](enum.TallyMethod.html).
+	pub requested_funds: Option<u64>,
+	/// Optional initial status, [ProposalStatus::Draft](enum.ProposalStatus.html),
+	/// [ProposalStatus::Discussion](enum.ProposalStatus.html) or the default
+	/// [ProposalStatus::Voting](enum.ProposalStatus.html).
+	pub status: Option<ProposalStatus>,
+	/// Addresses of the [People](struct.Person.html) co-authoring the proposal.
+	pub co_author_addresses: Option<Vec<Address>>,
+	/// Optional window in which the proposal is amended.
+	pub discussion_window: Option<ProposalWindow>,
+	/// Optional window in which the ballots are cast.
+	pub voting_window: Option<ProposalWindow>,
+	/// Address of the proposal of a federation on which the proposal decides the vote of the
+	/// collective, a member of the federation.
+	pub federated_proposal_address: Option<Address>,
+	/// Address of the [ProposalTemplate](struct.ProposalTemplate.html) the proposal is created from.
+	/// The name & content default to the ones rendered from the template.
+	pub template_address: Option<Address>,
+	/// Values of the fields of the template, by field name.
+	pub template_values: Option<BTreeMap<String, Value>>,
 }
 
 /// A proposal to change the collective.
-#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
 pub struct Proposal {
+	/// Address of the [Collective](struct.Collective.html) the proposal changes.
+	pub collective_address: Address,
+	/// Address of the [Person](struct.Person.html) authoring the proposal.
+	pub author_address: Address,
 	/// Name of the proposal
 	pub name: String,
 	/// Text content of the proposal.
 	pub content: String,
+	/// How the ballots are cast.
+	pub ballot_mode: BallotMode,
+	/// Lifecycle status of the proposal.
+	pub status: ProposalStatus,
+	/// How the ballots are counted.
+	#[serde(default)]
+	pub tally_method: TallyMethod,
+	/// Options voted on with a multi-option [TallyMethod](enum.TallyMethod.html).
+	#[serde(default)]
+	pub options: Vec<String>,
+	/// Voice credit period of the collective when the proposal is created.
+	#[serde(default)]
+	pub voice_credit_period: u64,
+	/// Tags categorizing the proposal, used to resolve [Delegations](struct.Delegation.html).
+	#[serde(default)]
+	pub tags: Vec<String>,
+	/// Funds requested from the [Ledger](struct.Ledger.html) with
+	/// [TallyMethod::Conviction](enum.TallyMethod.html).
+	#[serde(default)]
+	pub requested_funds: u64,
+	/// Time the requested funds were debited, in seconds since the unix epoch.
+	#[serde(default)]
+	pub funded_at: Option<u64>,
+	/// Addresses of the [People](struct.Person.html) co-authoring the proposal.
+	#[serde(default)]
+	pub co_author_addresses: Vec<Address>,
+	/// Number of the current [ProposalRevision](struct.ProposalRevision.html).
+	#[serde(default)]
+	pub revision: u64,
+	/// Window in which the proposal is amended.
+	#[serde(default)]
+	pub discussion_window: ProposalWindow,
+	/// Window in which the ballots are cast.
+	#[serde(default)]
+	pub voting_window: ProposalWindow,
+	/// Time the proposal was closed by [close_expired_proposals](fn.close_expired_proposals.html),
+	/// in seconds since the unix epoch.
+	#[serde(default)]
+	pub closed_at: Option<u64>,
+	/// The [ProposalTemplate](struct.ProposalTemplate.html) the proposal is created from.
+	#[serde(default)]
+	pub template: Option<ProposalTemplateValues>,
+	/// Outcome of the final tally, set when the proposal is closed.
+	#[serde(default)]
+	pub outcome: Option<ProposalOutcome>,
+	/// Address of the proposal of a federation on which the proposal decides the vote of the
+	/// collective, cast with [cast_collective_vote](fn.cast_collective_vote.html).
+	#[serde(default)]
+	pub federated_proposal_address: Option<Address>,
 }
 
 impl Default for Proposal {
 	fn default() -> Self {
 		Proposal {
+			collective_address: Default::default(),
+			author_address: Default::default(),
 			name: "unnamed proposal".to_string(),
 			content: "".to_string(),
+			ballot_mode: BallotMode::Open,
+			status: ProposalStatus::Voting,
+			tally_method: TallyMethod::Majority,
+			options: vec![],
+			voice_credit_period: 0,
+			tags: vec![],
+			requested_funds: 0,
+			funded_at: None,
+			co_author_addresses: vec![],
+			revision: 0,
+			discussion_window: ProposalWindow::default(),
+			voting_window: ProposalWindow::default(),
+			closed_at: None,
+			template: None,
+			outcome: None,
+			federated_proposal_address: None,
 		}
 	}
 }
 
+/// A time window of a phase of a [Proposal](struct.Proposal.html), in seconds since the unix epoch.
+/// An unset bound leaves the window open on that side.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq, Default)]
+pub struct ProposalWindow {
+	/// The phase starts at this time.
+	pub opens_at: Option<u64>,
+	/// The phase ends at this time.
+	pub closes_at: Option<u64>,
+}
+
+impl ProposalWindow {
+	/// Whether the `timestamp` is in the window.
+	pub fn contains(&self, timestamp: u64) -> bool {
+		self.opens_at.map_or(true, |opens_at| opens_at <= timestamp)
+			&& self.closes_at.map_or(true, |closes_at| timestamp < closes_at)
+	}
+
+	/// Whether the window is closed at the `timestamp`.
+	pub fn has_expired(&self, timestamp: u64) -> bool {
+		self.closes_at.map_or(false, |closes_at| closes_at <= timestamp)
+	}
+}
+
+/// How the ballots of a [Proposal](struct.Proposal.html) are cast.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub enum BallotMode {
+	/// Each [Vote](struct.Vote.html) is public when cast.
+	Open,
+	/// Secret ballot. A [VoteCommitment](struct.VoteCommitment.html) holding the hash of the salted
+	/// ballot is committed while voting & the [Vote](struct.Vote.html) is revealed afterwards.
+	CommitReveal,
+}
+
+/// How the ballots of a [Proposal](struct.Proposal.html) are counted.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub enum TallyMethod {
+	/// Yes, no or abstain on the proposal.
+	Majority,
+	/// Approve any number of options. The most approved options win.
+	Approval,
+	/// Instant-runoff on ranked options.
+	RankedChoice,
+	/// Borda count on ranked options.
+	Borda,
+	/// Condorcet winner on ranked options, resolved with the Schulze method.
+	Schulze,
+	/// Votes on options paid with voice credits. Casting `n` votes on an option costs `n²` credits.
+	Quadratic,
+	/// Stakes accrue conviction over time. The proposal is funded from the
+	/// [Ledger](struct.Ledger.html) once its conviction reaches the threshold.
+	Conviction,
+	/// Passes by consent, unless an [Objection](struct.Objection.html) is unresolved.
+	Consent,
+}
+
+impl Default for TallyMethod {
+	fn default() -> Self {
+		TallyMethod::Majority
+	}
+}
+
+/// The lifecycle status of a [Proposal](struct.Proposal.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub enum ProposalStatus {
+	/// Only the authors work on the proposal.
+	Draft,
+	/// People of the collective discuss & amend the proposal.
+	Discussion,
+	/// Ballots are being cast. The content of the proposal is frozen.
+	Voting,
+	/// Committed secret ballots are being revealed.
+	Revealing,
+	/// Voting is over & the tally is final.
+	Closed,
+}
+
 /// Api payload for a [Proposal](struct.Proposal.html)
 /// returned by [create_proposal](fn.create_proposal.html).
 #[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
@@ -44,36 +247,910 @@ pub struct ProposalPayload {
 	pub proposal: Proposal,
 }
 
+/// Api payload of the [Proposals](struct.Proposal.html) closed by
+/// [close_expired_proposals](fn.close_expired_proposals.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ExpiredProposalsPayload {
+	pub collective_address: Address,
+	pub proposals: Vec<ExpiredProposalPayload>,
+}
+
+/// Api payload of the final tally & outcome of an expired [Proposal](struct.Proposal.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ExpiredProposalPayload {
+	pub proposal_address: Address,
+	pub tally: ProposalTally,
+	pub outcome: ProposalOutcome,
+}
+
+/// Api payload of the [Proposals](struct.Proposal.html) in a [Collective](struct.Collective.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct CollectiveProposalsPayload {
+	pub collective_address: Address,
+	pub proposals: Vec<Proposal>,
+}
+
 /// Returns a Holochain entry definition for a proposal.
 pub fn proposal_def() -> ValidatingEntryType {
 	entry!(
 		name: "proposal",
-		description: "A pro",
+		description: "A protocol.love proposal",
 		sharing: Sharing::Public,
 		validation_package: || {
 			hdk::ValidationPackageDefinition::Entry
 		},
-		validation: | _validation_data: hdk::EntryValidationData<Proposal>| {
+		validation: | validation_data: hdk::EntryValidationData<Proposal>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_proposal_entry(&HdkStore, validation, &context)?)
+		},
+		links: [
+			to!(
+				"vote",
+				link_type: "proposal->vote",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"vote_commitment",
+				link_type: "proposal->vote_commitment",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"conviction_stake",
+				link_type: "proposal->conviction_stake",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"objection",
+				link_type: "proposal->objection",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"proposal_revision",
+				link_type: "proposal->proposal_revision",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"amendment",
+				link_type: "proposal->amendment",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"action",
+				link_type: "proposal->action",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"message",
+				link_type: "proposal->message",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"sponsorship",
+				link_type: "proposal->sponsorship",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			)
+		]
+	)
+}
+
+/// Validation rules of a [Proposal](struct.Proposal.html) entry.
+///
+/// The author must be a person in the collective. Only the name, content & lifecycle of a
+/// proposal change, & a closed proposal is final.
+/// The name & content can only be revised by the authors while revisable.
+/// The status follows the lifecycle & is changed by the author, except when funding a conviction
+/// proposal, which requires its conviction to reach the threshold, or closing an expired proposal
+/// once its voting window is over.
+pub fn validate_proposal_entry(
+	store: &dyn Store,
+	validation: EntryValidation<Proposal>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let author: Person = t("validation error: proposal: fetch author: ",
+				get_as_type(store, &entry.author_address))?;
+			if !context.sources.contains(&author.agent_address) {
+				return Err(ProtocolLoveError::unauthorized(
+					"Proposal must be created by the agent of the author"));
+			}
+			validate_collective_not_archived(store, &entry.collective_address)?;
+			if !is_collective_person(store, &entry.collective_address, &entry.author_address)? {
+				return Err(ProtocolLoveError::unauthorized("Author must be a person in the collective"));
+			}
+			if let PersonStatus::Inactive = author.status {
+				return Err(ProtocolLoveError::unauthorized("Author must be an active person"));
+			}
+			for co_author_address in &entry.co_author_addresses {
+				if !is_collective_person(store, &entry.collective_address, co_author_address)? {
+					return Err(ProtocolLoveError::validation_failed(
+						"co_author_addresses", "Co-authors must be people in the collective"));
+				}
+			}
+			validate_proposal_name(&entry.name)?;
+			validate_proposal_content(&entry.content)?;
+			validate_proposal_tally_method(&entry)?;
+			match entry.status {
+				ProposalStatus::Draft | ProposalStatus::Discussion => {}
+				ProposalStatus::Voting => {
+					let collective: Collective = t("validation error: proposal: fetch collective: ",
+						get_as_type(store, &entry.collective_address))?;
+					let policy_collective = get_policy_collective(store, &collective)?;
+					if policy_collective.sponsor_threshold > 0 {
+						return Err(ProtocolLoveError::conflict(&format!(
+							"Proposal needs {} sponsors before voting",
+							policy_collective.sponsor_threshold,
+						)));
+					}
+				}
+				_ => return Err(ProtocolLoveError::validation_failed(
+					"status", "Proposal must start in Draft, Discussion or Voting")),
+			}
+			if entry.revision != 0 || entry.funded_at.is_some() || entry.closed_at.is_some() {
+				return Err(ProtocolLoveError::validation_failed(
+					"revision", "Proposal must be created unrevised, unfunded & open"));
+			}
+			if let Some(federated_proposal_address) = &entry.federated_proposal_address {
+				let federated_proposal: Proposal = t("validation error: proposal: fetch federated proposal: ",
+					get_as_type(store, federated_proposal_address))?;
+				if !is_member_collective(
+					store,
+					&federated_proposal.collective_address,
+					&entry.collective_address,
+				)? {
+					return Err(ProtocolLoveError::validation_failed(
+						"federated_proposal_address", "Collective is not a member of the federation"));
+				}
+			}
+			if let Some(template_values) = &entry.template {
+				let template: ProposalTemplate = t("validation error: proposal: fetch template: ",
+					get_as_type(store, &template_values.template_address))?;
+				if template.collective_address != entry.collective_address
+					|| template.action_op != template_values.action_op {
+					return Err(ProtocolLoveError::validation_failed(
+						"template", "Template is not registered in the collective"));
+				}
+				validate_template_values(store, &template, &template_values.values)?;
+			}
 			Ok(())
 		}
-	)
+		EntryValidation::Modify { new_entry, old_entry } => {
+			if old_entry.status == ProposalStatus::Closed {
+				return Err(ProtocolLoveError::conflict("Closed proposal cannot be changed"));
+			}
+			validate_collective_not_archived(store, &old_entry.collective_address)?;
+			let unchanged = Proposal {
+				name: old_entry.name.clone(),
+				content: old_entry.content.clone(),
+				status: old_entry.status.clone(),
+				revision: old_entry.revision,
+				funded_at: old_entry.funded_at,
+				closed_at: old_entry.closed_at,
+				outcome: old_entry.outcome.clone(),
+				..new_entry.clone()
+			};
+			if unchanged != old_entry {
+				return Err(ProtocolLoveError::unauthorized(
+					"Only the name, content & status of a proposal can be changed"));
+			}
+			if new_entry.name != old_entry.name || new_entry.content != old_entry.content {
+				validate_proposal_name(&new_entry.name)?;
+				validate_proposal_content(&new_entry.content)?;
+				validate_proposal_revision(store, &new_entry, &old_entry, context)?;
+			} else if new_entry.revision != old_entry.revision {
+				return Err(ProtocolLoveError::validation_failed(
+					"revision", "Revision must change the name or content"));
+			}
+			let funded = old_entry.funded_at.is_none() && new_entry.funded_at.is_some();
+			let expired = old_entry.closed_at.is_none() && new_entry.closed_at.is_some();
+			if new_entry.status != old_entry.status {
+				validate_proposal_status_change(&old_entry, &new_entry.status)?;
+				if !funded && !expired {
+					validate_proposal_voting_window(&old_entry, &new_entry.status, context.timestamp)?;
+				}
+				let sponsored = new_entry.status == ProposalStatus::Voting && validate_proposal_sponsors(
+					store,
+					&find_proposal_address(store, &old_entry)?,
+					&old_entry,
+				)?;
+				if !funded && !expired && !sponsored {
+					let author: Person = t("validation error: proposal: fetch author: ",
+						get_as_type(store, &old_entry.author_address))?;
+					if !context.sources.contains(&author.agent_address) {
+						return Err(ProtocolLoveError::unauthorized(
+							"Only the author can change the status of the proposal"));
+					}
+				}
+			}
+			match (&old_entry.outcome, &new_entry.outcome) {
+				(None, Some(outcome)) => {
+					if new_entry.status != ProposalStatus::Closed {
+						return Err(ProtocolLoveError::validation_failed(
+							"outcome", "Only a closed proposal has an outcome"));
+					}
+					let expected_outcome = if funded {
+						ProposalOutcome::Passed
+					} else {
+						let proposal_address = find_proposal_address(store, &old_entry)?;
+						tally_outcome(
+							&old_entry.tally_method,
+							&tally_proposal(store, &proposal_address, &old_entry)?,
+						)
+					};
+					if outcome != &expected_outcome {
+						return Err(ProtocolLoveError::validation_failed(
+							"outcome", "Outcome does not match the tally"));
+					}
+				}
+				(old_outcome, new_outcome) if old_outcome != new_outcome =>
+					return Err(ProtocolLoveError::unauthorized("Outcome cannot be changed")),
+				_ => {}
+			}
+			match (old_entry.closed_at, new_entry.closed_at) {
+				(None, Some(closed_at)) => {
+					if new_entry.status != ProposalStatus::Closed {
+						return Err(ProtocolLoveError::validation_failed(
+							"closed_at", "Only a closed proposal has a closing time"));
+					}
+					if !old_entry.voting_window.has_expired(closed_at) {
+						return Err(ProtocolLoveError::conflict("Voting window has not expired"));
+					}
+					validate_timestamp("closed_at", closed_at, context)?;
+				}
+				(old_closed_at, new_closed_at) if old_closed_at != new_closed_at =>
+					return Err(ProtocolLoveError::unauthorized("Closing time cannot be changed")),
+				_ => {}
+			}
+			match (old_entry.funded_at, new_entry.funded_at) {
+				(None, Some(funded_at)) => {
+					if old_entry.status != ProposalStatus::Voting {
+						return Err(ProtocolLoveError::conflict("Proposal is not in voting"));
+					}
+					let proposal_address = find_proposal_address(store, &old_entry)?;
+					validate_conviction_funding(store, &proposal_address, &old_entry, funded_at, context)
+				}
+				(Some(_), _) if old_entry.funded_at != new_entry.funded_at =>
+					Err(ProtocolLoveError::unauthorized("Funded proposal cannot be funded again")),
+				_ => Ok(()),
+			}
+		}
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Proposal cannot be deleted")),
+	}
+}
+
+fn validate_proposal_name(name: &str) -> ProtocolLoveResult<()> {
+	if name.trim().is_empty() {
+		Err(ProtocolLoveError::validation_failed("name", "Name is required"))
+	} else if name.len() > 128 {
+		Err(ProtocolLoveError::validation_failed("name", "Name is too long"))
+	} else {
+		Ok(())
+	}
+}
+
+fn validate_proposal_content(content: &str) -> ProtocolLoveResult<()> {
+	if content.len() > 20000 {
+		Err(ProtocolLoveError::validation_failed("content", "Content is too long"))
+	} else {
+		Ok(())
+	}
+}
+
+/// Validates the `status` follows the current status in the lifecycle of the proposal.
+fn validate_proposal_status_change(
+	proposal: &Proposal,
+	status: &ProposalStatus,
+) -> ProtocolLoveResult<()> {
+	let allowed = match (&proposal.status, status) {
+		(ProposalStatus::Draft, ProposalStatus::Discussion)
+		| (ProposalStatus::Draft, ProposalStatus::Voting)
+		| (ProposalStatus::Discussion, ProposalStatus::Voting)
+		| (ProposalStatus::Revealing, ProposalStatus::Closed) => true,
+		(ProposalStatus::Voting, ProposalStatus::Revealing) =>
+			proposal.ballot_mode == BallotMode::CommitReveal,
+		(ProposalStatus::Voting, ProposalStatus::Closed) =>
+			proposal.ballot_mode == BallotMode::Open,
+		_ => false,
+	};
+	if !allowed {
+		return Err(ProtocolLoveError::conflict(
+			&format!("Proposal cannot move from {:?} to {:?}", proposal.status, status)));
+	}
+	Ok(())
+}
+
+/// Validates the voting of the proposal opens & ends within its voting window at the `timestamp`.
+fn validate_proposal_voting_window(
+	proposal: &Proposal,
+	status: &ProposalStatus,
+	timestamp: u64,
+) -> ProtocolLoveResult<()> {
+	let window = &proposal.voting_window;
+	if *status == ProposalStatus::Voting {
+		if let Some(opens_at) = window.opens_at.filter(|opens_at| *opens_at > timestamp) {
+			return Err(ProtocolLoveError::conflict(&format!("Voting opens at {}", opens_at)));
+		}
+		if window.has_expired(timestamp) {
+			return Err(ProtocolLoveError::conflict("Voting window is closed"));
+		}
+	} else if proposal.status == ProposalStatus::Voting && !window.has_expired(timestamp) {
+		if let Some(closes_at) = window.closes_at {
+			return Err(ProtocolLoveError::conflict(&format!("Voting is open until {}", closes_at)));
+		}
+	}
+	Ok(())
 }
 
-/// Api to create & commit a [Proposal](struct.Proposal.html).
-pub fn create_proposal(proposal_params: ProposalParams) -> ZomeApiResult<ProposalPayload> {
+/// Validates a revision of the name & content of the proposal.
+fn validate_proposal_revision(
+	store: &dyn Store,
+	new_entry: &Proposal,
+	old_entry: &Proposal,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	if !is_proposal_revisable(old_entry) {
+		return Err(ProtocolLoveError::conflict("Proposal content is frozen once voting opens"));
+	}
+	if new_entry.revision != old_entry.revision + 1 {
+		return Err(ProtocolLoveError::validation_failed(
+			"revision", "Revision must follow the current revision"));
+	}
+	let author_addresses =
+		std::iter::once(&old_entry.author_address).chain(&old_entry.co_author_addresses);
+	for author_address in author_addresses {
+		let author: Person = t("validation error: proposal: fetch author: ",
+			get_as_type(store, author_address))?;
+		if context.sources.contains(&author.agent_address) {
+			return Ok(());
+		}
+	}
+	Err(ProtocolLoveError::unauthorized("Only the authors can revise the proposal"))
+}
+
+/// Api to create & commit a [Proposal](struct.Proposal.html) in a [Collective](struct.Collective.html).
+///
+/// The author must represent the agent & be a person in the collective. Voting opens immediately,
+/// unless the proposal starts in draft or discussion. In a collective with a `sponsor_threshold`,
+/// the proposal starts in draft by default & voting opens once it is sponsored.
+/// A proposal created from a [ProposalTemplate](struct.ProposalTemplate.html) is rendered from the
+/// typed values of its fields & follows the decision policy of the template by default.
+pub fn create_proposal(
+	store: &dyn Store,
+	proposal_params: ProposalParams,
+) -> ProtocolLoveResult<ProposalPayload> {
+	t("create_proposal: ", get_agent_person(store, &proposal_params.author_address))?;
+	if !t("create_proposal: ", is_collective_person(
+		store,
+		&proposal_params.collective_address,
+		&proposal_params.author_address,
+	))? {
+		return Err(ProtocolLoveError::unauthorized(
+			"create_proposal: Author must be a person in the collective"));
+	}
+	let (proposal_params, template) =
+		t("create_proposal: ", apply_proposal_template(store, proposal_params))?;
+	let collective: Collective =
+		t("create_proposal: ", get_as_type(store, &proposal_params.collective_address))?;
+	let policy_collective = t("create_proposal: ", get_policy_collective(store, &collective))?;
+	let status = proposal_params.status.unwrap_or(if policy_collective.sponsor_threshold > 0 {
+		ProposalStatus::Draft
+	} else {
+		ProposalStatus::Voting
+	});
+	match status {
+		ProposalStatus::Draft | ProposalStatus::Discussion | ProposalStatus::Voting => {}
+		_ => return Err(ProtocolLoveError::validation_failed(
+			"status", "Proposal must start in Draft, Discussion or Voting")),
+	}
+	let mut co_author_addresses: Vec<Address> = vec![];
+	for co_author_address in proposal_params.co_author_addresses.unwrap_or_default() {
+		if co_author_address == proposal_params.author_address
+			|| co_author_addresses.contains(&co_author_address) {
+			continue;
+		}
+		if !t("create_proposal: ", is_collective_person(
+			store,
+			&proposal_params.collective_address,
+			&co_author_address,
+		))? {
+			return Err(ProtocolLoveError::validation_failed(
+				"co_author_addresses", "create_proposal: Co-authors must be people in the collective"));
+		}
+		co_author_addresses.push(co_author_address);
+	}
 	let (proposal_address, _proposal_entry, proposal2) =
-		commit_proposal(Proposal {
+		t("create_proposal: ", commit_proposal(store, Proposal {
+			collective_address: proposal_params.collective_address,
+			author_address: proposal_params.author_address,
 			name: proposal_params.name,
 			content: proposal_params.content,
-		})?;
+			ballot_mode: proposal_params.ballot_mode.unwrap_or(BallotMode::Open),
+			status,
+			tally_method: proposal_params.tally_method.unwrap_or_default(),
+			options: proposal_params.options.unwrap_or_default(),
+			voice_credit_period: collective.voice_credit_period,
+			tags: proposal_params.tags.unwrap_or_default(),
+			requested_funds: proposal_params.requested_funds.unwrap_or_default(),
+			funded_at: None,
+			co_author_addresses,
+			revision: 0,
+			discussion_window: proposal_params.discussion_window.unwrap_or_default(),
+			voting_window: proposal_params.voting_window.unwrap_or_default(),
+			closed_at: None,
+			template,
+			outcome: None,
+			federated_proposal_address: proposal_params.federated_proposal_address,
+		}))?;
+	t("create_proposal: ", commit_initial_revision(store, &proposal_address, &proposal2))?;
+	t("create_proposal: collective->proposal: ", store.link_entries(
+		&proposal2.collective_address,
+		&proposal_address,
+		"collective->proposal",
+		"",
+	))?;
+	t("create_proposal: ", create_root_action(
+		store,
+		&proposal2.collective_address,
+		ActionOp::CreateProposal,
+		CreateProposalActionData {
+			proposal_address: proposal_address.clone(),
+			proposal: proposal2.clone(),
+		}.into(),
+		serde_json::value::Value::Null.into(),
+		"create_proposal",
+	))?;
+	t("create_proposal: ", emit_signal(store, ProtocolLoveSignal::ProposalOpened {
+		proposal_address: proposal_address.clone(),
+		proposal: proposal2.clone(),
+	}))?;
 	Ok(ProposalPayload {
 		proposal_address,
 		proposal: proposal2,
 	})
 }
 
-fn commit_proposal(proposal: Proposal) -> ZomeApiResult<(Address, Entry, Proposal)> {
+/// Api to get a [Proposal](struct.Proposal.html).
+pub fn get_proposal(
+	store: &dyn Store,
+	proposal_address: Address,
+) -> ProtocolLoveResult<ProposalPayload> {
+	let proposal = t("get_proposal: ", get_as_type(store, &proposal_address))?;
+	Ok(ProposalPayload {
+		proposal_address,
+		proposal,
+	})
+}
+
+/// Api to get the [Proposals](struct.Proposal.html) in a [Collective](struct.Collective.html).
+pub fn get_collective_proposals(
+	store: &dyn Store,
+	collective_address: Address,
+) -> ProtocolLoveResult<CollectiveProposalsPayload> {
+	let proposals =
+		t("get_collective_proposals: ", get_links_and_load_type(
+			store,
+			&collective_address,
+			"collective->proposal",
+			None,
+		))?;
+	Ok(CollectiveProposalsPayload {
+		collective_address,
+		proposals,
+	})
+}
+
+/// Api for the author to open the discussion of a [Proposal](struct.Proposal.html) in draft.
+pub fn start_proposal_discussion(
+	store: &dyn Store,
+	proposal_address: Address,
+) -> ProtocolLoveResult<ProposalPayload> {
+	let proposal = t("start_proposal_discussion: ", get_authored_proposal(store, &proposal_address))?;
+	if proposal.status != ProposalStatus::Draft {
+		return Err(ProtocolLoveError::conflict(
+			"start_proposal_discussion: Proposal is not in draft"));
+	}
+	let proposal = t("start_proposal_discussion: ", set_proposal_status(
+		store,
+		&proposal_address,
+		proposal,
+		ProposalStatus::Discussion,
+	))?;
+	Ok(ProposalPayload {
+		proposal_address,
+		proposal,
+	})
+}
+
+/// Api for the author to open the voting of a [Proposal](struct.Proposal.html) in draft or
+/// discussion, freezing its content.
+pub fn open_proposal_voting(
+	store: &dyn Store,
+	proposal_address: Address,
+	timestamp: u64,
+) -> ProtocolLoveResult<ProposalPayload> {
+	let proposal = t("open_proposal_voting: ", get_authored_proposal(store, &proposal_address))?;
+	match proposal.status {
+		ProposalStatus::Draft | ProposalStatus::Discussion => {}
+		_ => return Err(ProtocolLoveError::conflict(
+			"open_proposal_voting: Proposal is not in draft or discussion")),
+	}
+	t("open_proposal_voting: ", validate_proposal_voting_window(
+		&proposal,
+		&ProposalStatus::Voting,
+		timestamp,
+	))?;
+	let proposal = t("open_proposal_voting: ", set_proposal_status(
+		store,
+		&proposal_address,
+		proposal,
+		ProposalStatus::Voting,
+	))?;
+	Ok(ProposalPayload {
+		proposal_address,
+		proposal,
+	})
+}
+
+/// Api for the author to end the voting of a [BallotMode::CommitReveal](enum.BallotMode.html)
+/// [Proposal](struct.Proposal.html) & start revealing the committed ballots once its voting window
+/// closes at the `timestamp`.
+pub fn start_proposal_reveal(
+	store: &dyn Store,
+	proposal_address: Address,
+	timestamp: u64,
+) -> ProtocolLoveResult<ProposalPayload> {
+	let proposal = t("start_proposal_reveal: ", get_authored_proposal(store, &proposal_address))?;
+	if proposal.ballot_mode != BallotMode::CommitReveal {
+		return Err(ProtocolLoveError::conflict(
+			"start_proposal_reveal: Only commit-reveal proposals are revealed"));
+	}
+	if proposal.status != ProposalStatus::Voting {
+		return Err(ProtocolLoveError::conflict(
+			"start_proposal_reveal: Proposal is not in voting"));
+	}
+	t("start_proposal_reveal: ", validate_proposal_voting_window(
+		&proposal,
+		&ProposalStatus::Revealing,
+		timestamp,
+	))?;
+	let proposal = t("start_proposal_reveal: ", set_proposal_status(
+		store,
+		&proposal_address,
+		proposal,
+		ProposalStatus::Revealing,
+	))?;
+	Ok(ProposalPayload {
+		proposal_address,
+		proposal,
+	})
+}
+
+/// Api for the author to close a [Proposal](struct.Proposal.html) & record the final tally.
+///
+/// An open ballot proposal is closed while voting, once its voting window closes at the `timestamp`.
+/// A commit-reveal proposal is closed while revealing, counting only the valid reveals.
+/// A consent proposal is closed once its objections are resolved.
+pub fn close_proposal(
+	store: &dyn Store,
+	proposal_address: Address,
+	timestamp: u64,
+) -> ProtocolLoveResult<ProposalTallyPayload> {
+	let proposal = t("close_proposal: ", get_authored_proposal(store, &proposal_address))?;
+	let closable_status = match proposal.ballot_mode {
+		BallotMode::Open => ProposalStatus::Voting,
+		BallotMode::CommitReveal => ProposalStatus::Revealing,
+	};
+	if proposal.status != closable_status {
+		return Err(ProtocolLoveError::conflict(
+			&format!("close_proposal: Proposal can only be closed while {:?}", closable_status)));
+	}
+	t("close_proposal: ", validate_proposal_voting_window(
+		&proposal,
+		&ProposalStatus::Closed,
+		timestamp,
+	))?;
+	let tally = t("close_proposal: ", tally_proposal(store, &proposal_address, &proposal))?;
+	if proposal.tally_method == TallyMethod::Consent && tally.objections > 0 {
+		return Err(ProtocolLoveError::conflict("close_proposal: Proposal has unresolved objections"));
+	}
+	let prev_status = proposal.status.clone();
+	let collective_address = proposal.collective_address.clone();
+	let outcome = tally_outcome(&proposal.tally_method, &tally);
+	t("close_proposal: ", update_proposal_entry(store, &proposal_address, &Proposal {
+		status: ProposalStatus::Closed,
+		outcome: Some(outcome),
+		..proposal
+	}))?;
+	t("close_proposal: ", create_root_action(
+		store,
+		&collective_address,
+		ActionOp::CloseProposal,
+		CloseProposalActionData {
+			proposal_address: proposal_address.clone(),
+			tally: tally.clone(),
+		}.into(),
+		SetProposalStatusActionData {
+			proposal_address: proposal_address.clone(),
+			status: prev_status,
+		}.into(),
+		"close_proposal",
+	))?;
+	Ok(ProposalTallyPayload {
+		proposal_address,
+		tally,
+	})
+}
+
+/// Api for a [Person](struct.Person.html) of the [Collective](struct.Collective.html) to close the
+/// open ballot proposals whose voting window expired at the `timestamp`, recording their final
+/// tally & [ProposalOutcome](enum.ProposalOutcome.html).
+///
+/// Commit-reveal proposals are closed by their author once the ballots are revealed.
+pub fn close_expired_proposals(
+	store: &dyn Store,
+	collective_address: Address,
+	person_address: Address,
+	timestamp: u64,
+) -> ProtocolLoveResult<ExpiredProposalsPayload> {
+	t("close_expired_proposals: ", get_agent_person(store, &person_address))?;
+	if !t("close_expired_proposals: ", is_collective_person(
+		store,
+		&collective_address,
+		&person_address,
+	))? {
+		return Err(ProtocolLoveError::unauthorized(
+			"close_expired_proposals: Only people in the collective can close expired proposals"));
+	}
+	let proposal_addresses = t("close_expired_proposals: ", store.get_links(
+		&collective_address,
+		"collective->proposal",
+		None,
+	))?;
+	let mut proposals: Vec<ExpiredProposalPayload> = vec![];
+	for proposal_address in proposal_addresses.into_iter().rev() {
+		let proposal: Proposal = t("close_expired_proposals: ", get_as_type(store, &proposal_address))?;
+		if proposal.status != ProposalStatus::Voting
+			|| proposal.ballot_mode != BallotMode::Open
+			|| !proposal.voting_window.has_expired(timestamp) {
+			continue;
+		}
+		let tally = t("close_expired_proposals: ", tally_proposal(store, &proposal_address, &proposal))?;
+		let outcome = tally_outcome(&proposal.tally_method, &tally);
+		let prev_status = proposal.status.clone();
+		t("close_expired_proposals: ", update_proposal_entry(store, &proposal_address, &Proposal {
+			status: ProposalStatus::Closed,
+			closed_at: Some(timestamp),
+			outcome: Some(outcome.clone()),
+			..proposal
+		}))?;
+		let expired_proposal = ExpiredProposalPayload {
+			proposal_address: proposal_address.clone(),
+			tally,
+			outcome,
+		};
+		t("close_expired_proposals: ", create_root_action(
+			store,
+			&collective_address,
+			ActionOp::CloseExpiredProposal,
+			expired_proposal.clone().into(),
+			SetProposalStatusActionData {
+				proposal_address,
+				status: prev_status,
+			}.into(),
+			"close_expired_proposals",
+		))?;
+		proposals.push(expired_proposal);
+	}
+	Ok(ExpiredProposalsPayload {
+		collective_address,
+		proposals,
+	})
+}
+
+/// Validates the options, ballot mode, requested funds & windows of the proposal suit its
+/// [TallyMethod](enum.TallyMethod.html).
+fn validate_proposal_tally_method(proposal: &Proposal) -> ProtocolLoveResult<()> {
+	let tally_method = &proposal.tally_method;
+	let has_options = match tally_method {
+		TallyMethod::Majority | TallyMethod::Conviction | TallyMethod::Consent => false,
+		_ => true,
+	};
+	if !has_options && !proposal.options.is_empty() {
+		return Err(ProtocolLoveError::validation_failed(
+			"options", &format!("{:?} proposals have no options", tally_method)));
+	}
+	if has_options && proposal.options.len() < 2 {
+		return Err(ProtocolLoveError::validation_failed(
+			"options", "Multi-option proposals need at least two options"));
+	}
+	let requires_open_ballots = match tally_method {
+		TallyMethod::Quadratic | TallyMethod::Conviction | TallyMethod::Consent => true,
+		_ => false,
+	};
+	if requires_open_ballots && proposal.ballot_mode != BallotMode::Open {
+		return Err(ProtocolLoveError::validation_failed(
+			"ballot_mode", &format!("{:?} voting requires open ballots", tally_method)));
+	}
+	if tally_method == &TallyMethod::Conviction && proposal.requested_funds == 0 {
+		return Err(ProtocolLoveError::validation_failed(
+			"requested_funds", "Conviction proposals must request funds"));
+	}
+	if tally_method != &TallyMethod::Conviction && proposal.requested_funds != 0 {
+		return Err(ProtocolLoveError::validation_failed(
+			"requested_funds", "Only conviction proposals request funds"));
+	}
+	validate_proposal_windows(&proposal.discussion_window, &proposal.voting_window)?;
+	if tally_method == &TallyMethod::Conviction && proposal.voting_window != ProposalWindow::default() {
+		return Err(ProtocolLoveError::validation_failed(
+			"voting_window", "Conviction proposals have no voting window"));
+	}
+	Ok(())
+}
+
+/// Validates each window opens before it closes & the discussion closes before voting opens.
+fn validate_proposal_windows(
+	discussion_window: &ProposalWindow,
+	voting_window: &ProposalWindow,
+) -> ProtocolLoveResult<()> {
+	for (field, window) in &[("discussion_window", discussion_window), ("voting_window", voting_window)] {
+		if let (Some(opens_at), Some(closes_at)) = (window.opens_at, window.closes_at) {
+			if opens_at >= closes_at {
+				return Err(ProtocolLoveError::validation_failed(
+					field, "Window must open before it closes"));
+			}
+		}
+	}
+	let discussion_end = discussion_window.closes_at.or(discussion_window.opens_at);
+	let voting_start = voting_window.opens_at.or(voting_window.closes_at);
+	if let (Some(discussion_end), Some(voting_start)) = (discussion_end, voting_start) {
+		if discussion_end > voting_start {
+			return Err(ProtocolLoveError::validation_failed(
+				"voting_window", "Voting must open after the discussion closes"));
+		}
+	}
+	Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct CreateProposalActionData {
+	proposal_address: Address,
+	proposal: Proposal,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct SetProposalStatusActionData {
+	proposal_address: Address,
+	status: ProposalStatus,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct CloseProposalActionData {
+	proposal_address: Address,
+	tally: ProposalTally,
+}
+
+/// Gets the [Proposal](struct.Proposal.html), which must be authored by the agent of the `store`.
+fn get_authored_proposal(
+	store: &dyn Store,
+	proposal_address: &Address,
+) -> ProtocolLoveResult<Proposal> {
+	let proposal: Proposal = t("get_authored_proposal: ", get_as_type(store, proposal_address))?;
+	match get_agent_person(store, &proposal.author_address) {
+		Ok(_) => Ok(proposal),
+		Err(ProtocolLoveError::Unauthorized { .. }) =>
+			Err(ProtocolLoveError::unauthorized("Only the author can change the proposal")),
+		Err(error) => Err(error),
+	}
+}
+
+/// Updates the status of the [Proposal](struct.Proposal.html) & records the status change.
+pub fn set_proposal_status(
+	store: &dyn Store,
+	proposal_address: &Address,
+	proposal: Proposal,
+	status: ProposalStatus,
+) -> ProtocolLoveResult<Proposal> {
+	let prev_status = proposal.status.clone();
+	let proposal = Proposal {
+		status,
+		..proposal
+	};
+	t("set_proposal_status: ", update_proposal_entry(store, proposal_address, &proposal))?;
+	t("set_proposal_status: ", create_root_action(
+		store,
+		&proposal.collective_address,
+		ActionOp::SetProposalStatus,
+		SetProposalStatusActionData {
+			proposal_address: proposal_address.clone(),
+			status: proposal.status.clone(),
+		}.into(),
+		SetProposalStatusActionData {
+			proposal_address: proposal_address.clone(),
+			status: prev_status,
+		}.into(),
+		"set_proposal_status",
+	))?;
+	Ok(proposal)
+}
+
+/// Finds the address the version `proposal` was created at from its entry history, as the links
+/// of a revised proposal stay on its original entry.
+pub fn find_proposal_address(store: &dyn Store, proposal: &Proposal) -> ProtocolLoveResult<Address> {
+	let proposal_entry = Entry::App("proposal".into(), proposal.into());
+	let version_address = t("find_proposal_address: ", store.entry_address(&proposal_entry))?;
+	t("find_proposal_address: ", store.get_initial_address(&version_address))
+}
+
+pub fn update_proposal_entry(
+	store: &dyn Store,
+	proposal_address: &Address,
+	proposal: &Proposal,
+) -> ProtocolLoveResult<Address> {
+	let proposal_entry = Entry::App("proposal".into(), proposal.into());
+	t("update_proposal_entry: ", store.update_entry(proposal_entry, proposal_address))
+}
+
+fn commit_proposal(
+	store: &dyn Store,
+	proposal: Proposal,
+) -> ProtocolLoveResult<(Address, Entry, Proposal)> {
 	let proposal_entry = Entry::App("proposal".into(), proposal.borrow().into());
-	let proposal_address = hdk::commit_entry(&proposal_entry)?;
+	let proposal_address =
+		t("commit_proposal: ", store.commit_entry(&proposal_entry))?;
 	Ok((proposal_address, proposal_entry, proposal))
 }
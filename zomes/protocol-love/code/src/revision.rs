@@ -0,0 +1,363 @@
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use hdk::holochain_core_types::dna::entry_types::Sharing;
+use hdk::prelude::ValidatingEntryType;
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::action::{ActionOp, create_root_action};
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::person::{Person, get_agent_person};
+use crate::proposal::{
+	Proposal, ProposalPayload, ProposalStatus, TallyMethod, update_proposal_entry,
+};
+use crate::store::{Store, HdkStore, get_as_type, get_links_and_load_type};
+use crate::utils::t;
+use crate::validation::{EntryValidation, ValidationContext, from_hdk_validation_data};
+
+/// A revision of the name & content of a [Proposal](struct.Proposal.html).
+///
+/// Revision 0 is the proposal as created. Each revision is linked from the proposal.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct ProposalRevision {
+	pub proposal_address: Address,
+	pub revision: u64,
+	/// Address of the author or co-author [Person](struct.Person.html) making the revision.
+	pub reviser_address: Address,
+	pub name: String,
+	pub content: String,
+	/// Address of the accepted [Amendment](struct.Amendment.html) the revision applies.
+	pub amendment_address: Option<Address>,
+}
+
+/// Api params for [revise_proposal](fn.revise_proposal.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ReviseProposalParams {
+	pub proposal_address: Address,
+	/// Address of the author or co-author [Person](struct.Person.html) making the revision.
+	pub reviser_address: Address,
+	/// New name of the proposal. The name is kept when `None`.
+	pub name: Option<String>,
+	/// New content of the proposal. The content is kept when `None`.
+	pub content: Option<String>,
+}
+
+/// Api payload of the [ProposalRevisions](struct.ProposalRevision.html) of a
+/// [Proposal](struct.Proposal.html), oldest first.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ProposalRevisionsPayload {
+	pub proposal_address: Address,
+	pub revisions: Vec<ProposalRevision>,
+}
+
+/// A line of a [diff_lines](fn.diff_lines.html) diff.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub enum DiffLine {
+	Same(String),
+	Added(String),
+	Removed(String),
+}
+
+/// Api payload of the line by line diff of the name & content between two
+/// [ProposalRevisions](struct.ProposalRevision.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ProposalDiffPayload {
+	pub proposal_address: Address,
+	pub from_revision: u64,
+	pub to_revision: u64,
+	pub name: Vec<DiffLine>,
+	pub content: Vec<DiffLine>,
+}
+
+/// Returns a Holochain entry definition for a proposal revision.
+pub fn proposal_revision_def() -> ValidatingEntryType {
+	entry!(
+		name: "proposal_revision",
+		description: "A protocol.love revision of the name & content of a proposal",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<ProposalRevision>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_proposal_revision_entry(&HdkStore, validation, &context)?)
+		}
+	)
+}
+
+/// Validation rules of a [ProposalRevision](struct.ProposalRevision.html) entry.
+pub fn validate_proposal_revision_entry(
+	store: &dyn Store,
+	validation: EntryValidation<ProposalRevision>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let reviser: Person = t("validation error: proposal_revision: fetch reviser: ",
+				get_as_type(store, &entry.reviser_address))?;
+			if !context.sources.contains(&reviser.agent_address) {
+				return Err(ProtocolLoveError::unauthorized(
+					"Revision must be made by the agent of the reviser"));
+			}
+			let proposal: Proposal = t("validation error: proposal_revision: fetch proposal: ",
+				get_as_type(store, &entry.proposal_address))?;
+			if !is_proposal_author(&proposal, &entry.reviser_address) {
+				return Err(ProtocolLoveError::unauthorized("Only the authors can revise the proposal"));
+			}
+			if entry.revision > 0 && !is_proposal_revisable(&proposal) {
+				return Err(ProtocolLoveError::conflict("Proposal content is frozen once voting opens"));
+			}
+			Ok(())
+		}
+		EntryValidation::Modify { .. } =>
+			Err(ProtocolLoveError::unauthorized("Proposal revision cannot be modified")),
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Proposal revision cannot be deleted")),
+	}
+}
+
+/// Whether the person at `person_address` is the author or a co-author of the proposal.
+pub fn is_proposal_author(proposal: &Proposal, person_address: &Address) -> bool {
+	&proposal.author_address == person_address || proposal.co_author_addresses.contains(person_address)
+}
+
+/// Whether the name & content of the proposal can be revised.
+///
+/// The content is frozen once voting opens, except to integrate the objections to a
+/// [TallyMethod::Consent](enum.TallyMethod.html) proposal.
+pub fn is_proposal_revisable(proposal: &Proposal) -> bool {
+	match proposal.status {
+		ProposalStatus::Draft | ProposalStatus::Discussion => true,
+		ProposalStatus::Voting => proposal.tally_method == TallyMethod::Consent,
+		_ => false,
+	}
+}
+
+/// Api for an author or co-author to revise the name & content of a
+/// [Proposal](struct.Proposal.html) in draft or discussion.
+pub fn revise_proposal(
+	store: &dyn Store,
+	revise_proposal_params: ReviseProposalParams,
+) -> ProtocolLoveResult<ProposalPayload> {
+	let proposal_address = revise_proposal_params.proposal_address;
+	let proposal: Proposal = t("revise_proposal: ", get_as_type(store, &proposal_address))?;
+	t("revise_proposal: ", get_agent_person(store, &revise_proposal_params.reviser_address))?;
+	if !is_proposal_author(&proposal, &revise_proposal_params.reviser_address) {
+		return Err(ProtocolLoveError::unauthorized(
+			"revise_proposal: Only the authors can revise the proposal"));
+	}
+	if revise_proposal_params.name.is_none() && revise_proposal_params.content.is_none() {
+		return Err(ProtocolLoveError::validation_failed(
+			"content", "revise_proposal: Revision must change the name or content"));
+	}
+	let name = revise_proposal_params.name.unwrap_or_else(|| proposal.name.clone());
+	let content = revise_proposal_params.content.unwrap_or_else(|| proposal.content.clone());
+	let proposal = t("revise_proposal: ", commit_proposal_revision(
+		store,
+		&proposal_address,
+		proposal,
+		&revise_proposal_params.reviser_address,
+		name,
+		content,
+		None,
+	))?;
+	Ok(ProposalPayload {
+		proposal_address,
+		proposal,
+	})
+}
+
+/// Api to get the [ProposalRevisions](struct.ProposalRevision.html) of a
+/// [Proposal](struct.Proposal.html), oldest first.
+pub fn get_proposal_revisions(
+	store: &dyn Store,
+	proposal_address: Address,
+) -> ProtocolLoveResult<ProposalRevisionsPayload> {
+	let revisions = t("get_proposal_revisions: ", get_revisions(store, &proposal_address))?;
+	Ok(ProposalRevisionsPayload {
+		proposal_address,
+		revisions,
+	})
+}
+
+/// Api to get the diff of the name & content of a [Proposal](struct.Proposal.html)
+/// from the `from_revision` to the `to_revision`.
+pub fn get_proposal_diff(
+	store: &dyn Store,
+	proposal_address: Address,
+	from_revision: u64,
+	to_revision: u64,
+) -> ProtocolLoveResult<ProposalDiffPayload> {
+	let revisions = t("get_proposal_diff: ", get_revisions(store, &proposal_address))?;
+	let find_revision = |field: &str, revision: u64| revisions.iter()
+		.find(|proposal_revision| proposal_revision.revision == revision)
+		.ok_or_else(|| ProtocolLoveError::validation_failed(
+			field, "get_proposal_diff: Unknown revision"));
+	let from = find_revision("from_revision", from_revision)?;
+	let to = find_revision("to_revision", to_revision)?;
+	Ok(ProposalDiffPayload {
+		proposal_address: proposal_address.clone(),
+		from_revision,
+		to_revision,
+		name: diff_lines(&from.name, &to.name),
+		content: diff_lines(&from.content, &to.content),
+	})
+}
+
+/// Commits the first revision of a newly created proposal.
+pub fn commit_initial_revision(
+	store: &dyn Store,
+	proposal_address: &Address,
+	proposal: &Proposal,
+) -> ProtocolLoveResult<Address> {
+	link_proposal_revision(store, &ProposalRevision {
+		proposal_address: proposal_address.clone(),
+		revision: proposal.revision,
+		reviser_address: proposal.author_address.clone(),
+		name: proposal.name.clone(),
+		content: proposal.content.clone(),
+		amendment_address: None,
+	})
+}
+
+/// Updates the name & content of the proposal, commits the next
+/// [ProposalRevision](struct.ProposalRevision.html) & records the `ReviseProposal` action.
+pub fn commit_proposal_revision(
+	store: &dyn Store,
+	proposal_address: &Address,
+	proposal: Proposal,
+	reviser_address: &Address,
+	name: String,
+	content: String,
+	amendment_address: Option<Address>,
+) -> ProtocolLoveResult<Proposal> {
+	let prev_revision = RevisionActionData {
+		proposal_address: proposal_address.clone(),
+		revision: proposal.revision,
+		name: proposal.name.clone(),
+		content: proposal.content.clone(),
+	};
+	let proposal = Proposal {
+		name,
+		content,
+		revision: proposal.revision + 1,
+		..proposal
+	};
+	t("commit_proposal_revision: ", link_proposal_revision(store, &ProposalRevision {
+		proposal_address: proposal_address.clone(),
+		revision: proposal.revision,
+		reviser_address: reviser_address.clone(),
+		name: proposal.name.clone(),
+		content: proposal.content.clone(),
+		amendment_address,
+	}))?;
+	t("commit_proposal_revision: ", update_proposal_entry(store, proposal_address, &proposal))?;
+	t("commit_proposal_revision: ", create_root_action(
+		store,
+		&proposal.collective_address,
+		ActionOp::ReviseProposal,
+		RevisionActionData {
+			proposal_address: proposal_address.clone(),
+			revision: proposal.revision,
+			name: proposal.name.clone(),
+			content: proposal.content.clone(),
+		}.into(),
+		prev_revision.into(),
+		"revise_proposal",
+	))?;
+	Ok(proposal)
+}
+
+/// Line by line diff from the `old` text to the `new` text, on their longest common subsequence.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+	let old: Vec<&str> = old.lines().collect();
+	let new: Vec<&str> = new.lines().collect();
+	let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+	for i in (0..old.len()).rev() {
+		for j in (0..new.len()).rev() {
+			common[i][j] = if old[i] == new[j] {
+				common[i + 1][j + 1] + 1
+			} else {
+				common[i + 1][j].max(common[i][j + 1])
+			};
+		}
+	}
+	let mut diff = vec![];
+	let (mut i, mut j) = (0, 0);
+	while i < old.len() && j < new.len() {
+		if old[i] == new[j] {
+			diff.push(DiffLine::Same(old[i].into()));
+			i += 1;
+			j += 1;
+		} else if common[i + 1][j] >= common[i][j + 1] {
+			diff.push(DiffLine::Removed(old[i].into()));
+			i += 1;
+		} else {
+			diff.push(DiffLine::Added(new[j].into()));
+			j += 1;
+		}
+	}
+	diff.extend(old[i..].iter().map(|line| DiffLine::Removed((*line).into())));
+	diff.extend(new[j..].iter().map(|line| DiffLine::Added((*line).into())));
+	diff
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct RevisionActionData {
+	proposal_address: Address,
+	revision: u64,
+	name: String,
+	content: String,
+}
+
+fn get_revisions(
+	store: &dyn Store,
+	proposal_address: &Address,
+) -> ProtocolLoveResult<Vec<ProposalRevision>> {
+	let mut revisions: Vec<ProposalRevision> =
+		t("get_revisions: ", get_links_and_load_type(
+			store,
+			proposal_address,
+			"proposal->proposal_revision",
+			None,
+		))?;
+	revisions.sort_by_key(|proposal_revision| proposal_revision.revision);
+	Ok(revisions)
+}
+
+fn link_proposal_revision(
+	store: &dyn Store,
+	proposal_revision: &ProposalRevision,
+) -> ProtocolLoveResult<Address> {
+	let revision_entry = Entry::App("proposal_revision".into(), proposal_revision.into());
+	let revision_address = t("link_proposal_revision: ", store.commit_entry(&revision_entry))?;
+	t("link_proposal_revision: proposal->proposal_revision: ", store.link_entries(
+		&proposal_revision.proposal_address,
+		&revision_address,
+		"proposal->proposal_revision",
+		"",
+	))?;
+	Ok(revision_address)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{DiffLine, diff_lines};
+
+	#[test]
+	fn diff_lines_keeps_the_common_lines() {
+		assert_eq!(diff_lines("a\nb\nc", "a\nc\nd"), vec![
+			DiffLine::Same("a".into()),
+			DiffLine::Removed("b".into()),
+			DiffLine::Same("c".into()),
+			DiffLine::Added("d".into()),
+		]);
+	}
+
+	#[test]
+	fn diff_lines_of_a_new_text() {
+		assert_eq!(diff_lines("", "a"), vec![DiffLine::Added("a".into())]);
+		assert_eq!(diff_lines("a", "a"), vec![DiffLine::Same("a".into())]);
+	}
+}
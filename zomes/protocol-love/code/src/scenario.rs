@@ -0,0 +1,3019 @@
+//! Scenarios mirroring `test/index.js` & `test/http.js`,
+//! run with several simulated agents over a shared [MemoryStore](../memory_store/struct.MemoryStore.html).
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use serde_json::Value;
+use crate::action::{
+	Action, ActionOp, ActionStatus, ActionStrategy, ActionsPayload, get_actions,
+};
+use crate::amendment::{
+	AmendmentParams, AmendmentPayload, AmendmentStatus, accept_amendment, propose_amendment,
+	reject_amendment,
+};
+use crate::charter::{Charter, CharterPayload, amend_charter, get_charter, get_charter_history};
+use crate::collective::{
+	Collective, CollectivePayload, CollectivePeoplePayload, CreateCollectiveParams, add_collective_person,
+	create_collective, get_collective, get_collective_people, set_collective_name,
+};
+use crate::conviction::{
+	ConvictionPayload, ConvictionStake, ConvictionStakePayload, fund_conviction_proposal,
+	get_proposal_conviction, stake_conviction,
+};
+use crate::critical_action::{
+	VetoParams, enact_proposal, execute_critical_action, get_critical_actions, set_critical_action_policy,
+	veto_critical_action,
+};
+use crate::delegation::{
+	Delegation, DelegationParams, DelegationPayload, DelegationsPayload, delegate_vote, get_delegations,
+	revoke_delegation,
+};
+use crate::dissolution::{DissolutionPayload, dissolve_collective, get_dissolution};
+use crate::election::{
+	ElectionParams, NominationParams, RoleParams, close_election, create_role, get_role,
+	nominate_candidate, open_election_voting, start_election, start_expired_elections,
+};
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::federation::{add_member_collective, get_member_collectives, join_federation};
+use crate::ledger::{Ledger, LedgerPayload, deposit_ledger_funds, get_ledger};
+use crate::memory_store::MemoryStore;
+use crate::message::{
+	MessageHistoryPayload, MessageParams, MessagePayload, ThreadParams, ThreadPayload,
+	edit_message, get_message_history, get_thread, post_message,
+};
+use crate::objection::{
+	ObjectionKind, ObjectionParams, ObjectionPayload, ProposalObjectionsPayload,
+	ResolveObjectionParams, get_proposal_objections, raise_objection, resolve_objection,
+};
+use crate::person::{Person, PersonParams, PersonPayload, PersonStatus, create_person, get_person};
+use crate::proposal::{
+	BallotMode, ExpiredProposalsPayload, Proposal, ProposalParams, ProposalPayload,
+	ProposalStatus, ProposalWindow, TallyMethod, close_expired_proposals, close_proposal,
+	create_proposal, get_proposal, open_proposal_voting, start_proposal_discussion,
+	start_proposal_reveal, update_proposal_entry, validate_proposal_entry,
+};
+use crate::revision::{
+	DiffLine, ProposalDiffPayload, ProposalRevisionsPayload, ReviseProposalParams,
+	get_proposal_diff, get_proposal_revisions, revise_proposal,
+};
+use crate::signal::{ProtocolLoveSignal, receive_signal};
+use crate::sortition::{
+	SortitionDraw, SortitionParams, SortitionPayload, SortitionSeed, SortitionSeedParams, announce_sortition,
+	commit_sortition_seed, draw_sortition, get_sortition, reveal_sortition_seed, verify_sortition,
+};
+use crate::sponsor::{
+	ProposalSponsorsPayload, SponsorshipParams, set_sponsor_threshold, sponsor_proposal,
+	unsponsor_proposal,
+};
+use crate::store::Store;
+use crate::sub_collective::{
+	SubCollectiveParams, create_sub_collective, escalate_action, get_collective_tree,
+	get_escalated_actions,
+};
+use crate::tally::{ProposalOutcome, ProposalTally, TallyRound};
+use crate::template::{
+	ProposalTemplateParams, ProposalTemplatePayload, TemplateField, TemplateFieldType,
+	create_proposal_template, get_collective_templates,
+};
+use crate::validation::{EntryValidation, ValidationContext};
+use crate::voice_credit::{
+	VoiceCreditSpend, VoiceCreditsPayload, get_voice_credits, start_voice_credit_period,
+};
+use crate::vote::{
+	Ballot, CollectiveVoteParams, ProposalTallyPayload, Vote, VoteCommitmentPayload, VoteParams,
+	VotePayload, cast_collective_vote, cast_vote, commit_vote, get_proposal_tally, reveal_vote,
+};
+
+/// A simulated DHT shared by the [Players](struct.Player.html) of the scenario.
+pub struct Scenario {
+	dht: MemoryStore,
+}
+
+impl Scenario {
+	pub fn new() -> Self {
+		Scenario {
+			dht: MemoryStore::new(Address::from("scenario")),
+		}
+	}
+
+	/// Returns a [Player](struct.Player.html) with its own agent, sharing the DHT of the scenario.
+	pub fn player(&self, name: &str) -> Player {
+		Player {
+			store: self.dht.for_agent(Address::from(format!("agent:{}", name))),
+		}
+	}
+}
+
+/// A simulated agent calling the api.
+pub struct Player {
+	pub store: MemoryStore,
+}
+
+impl Player {
+	pub fn agent_address(&self) -> Address {
+		self.store.agent_address()
+	}
+
+	pub fn create_person(&self, name: &str) -> ProtocolLoveResult<PersonPayload> {
+		self.create_person_for_agent(name, self.agent_address())
+	}
+
+	pub fn create_person_for_agent(
+		&self,
+		name: &str,
+		agent_address: Address,
+	) -> ProtocolLoveResult<PersonPayload> {
+		create_person(&self.store, PersonParams {
+			name: name.into(),
+			..PersonParams::for_agent(agent_address)
+		})
+	}
+
+	pub fn get_person(&self, person_address: &Address) -> ProtocolLoveResult<PersonPayload> {
+		get_person(&self.store, person_address.clone())
+	}
+
+	pub fn create_collective(
+		&self,
+		name: &str,
+		admin_address: Option<Address>,
+	) -> ProtocolLoveResult<CollectivePayload> {
+		create_collective(&self.store, CreateCollectiveParams {
+			name: name.into(),
+			admin_address,
+			description: None,
+			purpose: None,
+			tags: None,
+			links: None,
+			avatar: None,
+		})
+	}
+
+	pub fn get_collective(&self, collective_address: &Address) -> ProtocolLoveResult<CollectivePayload> {
+		get_collective(&self.store, collective_address.clone())
+	}
+
+	pub fn set_collective_name(
+		&self,
+		collective_address: &Address,
+		name: &str,
+	) -> ProtocolLoveResult<CollectivePayload> {
+		set_collective_name(&self.store, collective_address.clone(), name.into())
+	}
+
+	pub fn get_collective_people(
+		&self,
+		collective_address: &Address,
+	) -> ProtocolLoveResult<CollectivePeoplePayload> {
+		get_collective_people(&self.store, collective_address.clone())
+	}
+
+	pub fn add_collective_person(
+		&self,
+		collective_address: &Address,
+		person_address: &Address,
+	) -> ProtocolLoveResult<CollectivePeoplePayload> {
+		add_collective_person(&self.store, collective_address.clone(), person_address.clone())
+	}
+
+	pub fn get_actions(&self, collective_address: &Address) -> ProtocolLoveResult<ActionsPayload> {
+		get_actions(&self.store, collective_address.clone())
+	}
+
+	pub fn create_proposal(
+		&self,
+		collective_address: &Address,
+		author_address: &Address,
+		ballot_mode: BallotMode,
+	) -> ProtocolLoveResult<ProposalPayload> {
+		create_proposal(&self.store, ProposalParams {
+			collective_address: collective_address.clone(),
+			author_address: author_address.clone(),
+			name: "Plant a garden".into(),
+			content: "Plant a garden in the commons".into(),
+			ballot_mode: Some(ballot_mode),
+			tally_method: None,
+			options: None,
+			tags: None,
+			requested_funds: None,
+			status: None,
+			co_author_addresses: None,
+			discussion_window: None,
+			voting_window: None,
+			federated_proposal_address: None,
+			template_address: None,
+			template_values: None,
+		})
+	}
+
+	pub fn create_multi_option_proposal(
+		&self,
+		collective_address: &Address,
+		author_address: &Address,
+		tally_method: TallyMethod,
+		options: &[&str],
+	) -> ProtocolLoveResult<ProposalPayload> {
+		create_proposal(&self.store, ProposalParams {
+			collective_address: collective_address.clone(),
+			author_address: author_address.clone(),
+			name: "Elect a steward".into(),
+			content: "Elect the steward of the garden".into(),
+			ballot_mode: None,
+			tally_method: Some(tally_method),
+			options: Some(options.iter().map(|option| option.to_string()).collect()),
+			tags: None,
+			requested_funds: None,
+			status: None,
+			co_author_addresses: None,
+			discussion_window: None,
+			voting_window: None,
+			federated_proposal_address: None,
+			template_address: None,
+			template_values: None,
+		})
+	}
+
+	pub fn start_proposal_reveal(&self, proposal_address: &Address) -> ProtocolLoveResult<ProposalPayload> {
+		start_proposal_reveal(&self.store, proposal_address.clone(), self.store.now())
+	}
+
+	pub fn close_proposal(&self, proposal_address: &Address) -> ProtocolLoveResult<ProposalTallyPayload> {
+		close_proposal(&self.store, proposal_address.clone(), self.store.now())
+	}
+
+	pub fn cast_vote(
+		&self,
+		proposal_address: &Address,
+		person_address: &Address,
+		ballot: Ballot,
+	) -> ProtocolLoveResult<VotePayload> {
+		cast_vote(&self.store, vote_params(proposal_address, person_address, ballot, None))
+	}
+
+	pub fn commit_vote(
+		&self,
+		proposal_address: &Address,
+		person_address: &Address,
+		ballot: Ballot,
+		salt: &str,
+	) -> ProtocolLoveResult<VoteCommitmentPayload> {
+		commit_vote(&self.store, vote_params(proposal_address, person_address, ballot, Some(salt)))
+	}
+
+	pub fn reveal_vote(
+		&self,
+		proposal_address: &Address,
+		person_address: &Address,
+		ballot: Ballot,
+		salt: &str,
+	) -> ProtocolLoveResult<VotePayload> {
+		reveal_vote(&self.store, vote_params(proposal_address, person_address, ballot, Some(salt)))
+	}
+
+	pub fn get_proposal_tally(&self, proposal_address: &Address) -> ProtocolLoveResult<ProposalTallyPayload> {
+		get_proposal_tally(&self.store, proposal_address.clone())
+	}
+
+	pub fn delegate_vote(
+		&self,
+		collective_address: &Address,
+		delegator_address: &Address,
+		delegate_address: &Address,
+		tag: Option<&str>,
+	) -> ProtocolLoveResult<DelegationPayload> {
+		delegate_vote(&self.store, DelegationParams {
+			collective_address: collective_address.clone(),
+			delegator_address: delegator_address.clone(),
+			delegate_address: Some(delegate_address.clone()),
+			tag: tag.map(|tag| tag.into()),
+		})
+	}
+
+	pub fn revoke_delegation(
+		&self,
+		collective_address: &Address,
+		delegator_address: &Address,
+		tag: Option<&str>,
+	) -> ProtocolLoveResult<DelegationPayload> {
+		revoke_delegation(&self.store, DelegationParams {
+			collective_address: collective_address.clone(),
+			delegator_address: delegator_address.clone(),
+			delegate_address: None,
+			tag: tag.map(|tag| tag.into()),
+		})
+	}
+
+	pub fn get_delegations(&self, collective_address: &Address) -> ProtocolLoveResult<DelegationsPayload> {
+		get_delegations(&self.store, collective_address.clone())
+	}
+
+	pub fn start_voice_credit_period(
+		&self,
+		collective_address: &Address,
+		voice_credits_per_period: Option<u64>,
+	) -> ProtocolLoveResult<CollectivePayload> {
+		start_voice_credit_period(&self.store, collective_address.clone(), voice_credits_per_period)
+	}
+
+	pub fn get_voice_credits(
+		&self,
+		collective_address: &Address,
+		person_address: &Address,
+	) -> ProtocolLoveResult<VoiceCreditsPayload> {
+		get_voice_credits(&self.store, collective_address.clone(), person_address.clone())
+	}
+
+	pub fn get_ledger(&self, collective_address: &Address) -> ProtocolLoveResult<LedgerPayload> {
+		get_ledger(&self.store, collective_address.clone())
+	}
+
+	pub fn deposit_ledger_funds(
+		&self,
+		collective_address: &Address,
+		amount: u64,
+	) -> ProtocolLoveResult<LedgerPayload> {
+		deposit_ledger_funds(&self.store, collective_address.clone(), amount)
+	}
+
+	pub fn create_conviction_proposal(
+		&self,
+		collective_address: &Address,
+		author_address: &Address,
+		requested_funds: u64,
+	) -> ProtocolLoveResult<ProposalPayload> {
+		create_proposal(&self.store, ProposalParams {
+			collective_address: collective_address.clone(),
+			author_address: author_address.clone(),
+			name: "Buy a greenhouse".into(),
+			content: "Fund a greenhouse for the garden".into(),
+			ballot_mode: None,
+			tally_method: Some(TallyMethod::Conviction),
+			options: None,
+			tags: None,
+			requested_funds: Some(requested_funds),
+			status: None,
+			co_author_addresses: None,
+			discussion_window: None,
+			voting_window: None,
+			federated_proposal_address: None,
+			template_address: None,
+			template_values: None,
+		})
+	}
+
+	pub fn start_proposal_discussion(
+		&self,
+		proposal_address: &Address,
+	) -> ProtocolLoveResult<ProposalPayload> {
+		start_proposal_discussion(&self.store, proposal_address.clone())
+	}
+
+	pub fn open_proposal_voting(&self, proposal_address: &Address) -> ProtocolLoveResult<ProposalPayload> {
+		open_proposal_voting(&self.store, proposal_address.clone(), self.store.now())
+	}
+
+	pub fn revise_proposal(
+		&self,
+		proposal_address: &Address,
+		reviser_address: &Address,
+		content: &str,
+	) -> ProtocolLoveResult<ProposalPayload> {
+		revise_proposal(&self.store, ReviseProposalParams {
+			proposal_address: proposal_address.clone(),
+			reviser_address: reviser_address.clone(),
+			name: None,
+			content: Some(content.into()),
+		})
+	}
+
+	pub fn get_proposal_revisions(
+		&self,
+		proposal_address: &Address,
+	) -> ProtocolLoveResult<ProposalRevisionsPayload> {
+		get_proposal_revisions(&self.store, proposal_address.clone())
+	}
+
+	pub fn get_proposal_diff(
+		&self,
+		proposal_address: &Address,
+		from_revision: u64,
+		to_revision: u64,
+	) -> ProtocolLoveResult<ProposalDiffPayload> {
+		get_proposal_diff(&self.store, proposal_address.clone(), from_revision, to_revision)
+	}
+
+	pub fn propose_amendment(
+		&self,
+		proposal_address: &Address,
+		person_address: &Address,
+		name: Option<&str>,
+		content: Option<&str>,
+	) -> ProtocolLoveResult<AmendmentPayload> {
+		propose_amendment(&self.store, AmendmentParams {
+			proposal_address: proposal_address.clone(),
+			person_address: person_address.clone(),
+			name: name.map(|name| name.into()),
+			content: content.map(|content| content.into()),
+			reason: "Make it better".into(),
+		})
+	}
+
+	pub fn accept_amendment(
+		&self,
+		amendment_address: &Address,
+		author_address: &Address,
+	) -> ProtocolLoveResult<AmendmentPayload> {
+		accept_amendment(&self.store, amendment_address.clone(), author_address.clone())
+	}
+
+	pub fn reject_amendment(
+		&self,
+		amendment_address: &Address,
+		author_address: &Address,
+	) -> ProtocolLoveResult<AmendmentPayload> {
+		reject_amendment(&self.store, amendment_address.clone(), author_address.clone())
+	}
+
+	pub fn raise_objection(
+		&self,
+		proposal_address: &Address,
+		person_address: &Address,
+		kind: ObjectionKind,
+		reason: &str,
+	) -> ProtocolLoveResult<ObjectionPayload> {
+		raise_objection(&self.store, ObjectionParams {
+			proposal_address: proposal_address.clone(),
+			person_address: person_address.clone(),
+			kind,
+			reason: reason.into(),
+		})
+	}
+
+	pub fn resolve_objection(
+		&self,
+		objection_address: &Address,
+		content: Option<&str>,
+	) -> ProtocolLoveResult<ObjectionPayload> {
+		resolve_objection(&self.store, ResolveObjectionParams {
+			objection_address: objection_address.clone(),
+			content: content.map(|content| content.into()),
+		})
+	}
+
+	pub fn get_proposal_objections(
+		&self,
+		proposal_address: &Address,
+	) -> ProtocolLoveResult<ProposalObjectionsPayload> {
+		get_proposal_objections(&self.store, proposal_address.clone())
+	}
+
+	/// Stakes at the current time of the store.
+	pub fn stake_conviction(
+		&self,
+		proposal_address: &Address,
+		person_address: &Address,
+		amount: u64,
+		timestamp: u64,
+	) -> ProtocolLoveResult<ConvictionStakePayload> {
+		self.store.set_now(timestamp);
+		stake_conviction(&self.store, ConvictionStake {
+			proposal_address: proposal_address.clone(),
+			person_address: person_address.clone(),
+			amount,
+			timestamp,
+		})
+	}
+
+	pub fn get_proposal_conviction(
+		&self,
+		proposal_address: &Address,
+		timestamp: u64,
+	) -> ProtocolLoveResult<ConvictionPayload> {
+		get_proposal_conviction(&self.store, proposal_address.clone(), timestamp)
+	}
+
+	/// Funds at the current time of the store.
+	pub fn fund_conviction_proposal(
+		&self,
+		proposal_address: &Address,
+		timestamp: u64,
+	) -> ProtocolLoveResult<ConvictionPayload> {
+		self.store.set_now(timestamp);
+		fund_conviction_proposal(&self.store, proposal_address.clone(), timestamp)
+	}
+
+	/// Sponsors at the current time of the store.
+	pub fn sponsor_proposal(
+		&self,
+		proposal_address: &Address,
+		person_address: &Address,
+		timestamp: u64,
+	) -> ProtocolLoveResult<ProposalSponsorsPayload> {
+		self.store.set_now(timestamp);
+		sponsor_proposal(&self.store, sponsorship_params(proposal_address, person_address, timestamp))
+	}
+
+	/// Withdraws the sponsorship at the current time of the store.
+	pub fn unsponsor_proposal(
+		&self,
+		proposal_address: &Address,
+		person_address: &Address,
+		timestamp: u64,
+	) -> ProtocolLoveResult<ProposalSponsorsPayload> {
+		self.store.set_now(timestamp);
+		unsponsor_proposal(&self.store, sponsorship_params(proposal_address, person_address, timestamp))
+	}
+
+	/// Closes at the current time of the store.
+	pub fn close_expired_proposals(
+		&self,
+		collective_address: &Address,
+		person_address: &Address,
+		timestamp: u64,
+	) -> ProtocolLoveResult<ExpiredProposalsPayload> {
+		self.store.set_now(timestamp);
+		close_expired_proposals(&self.store, collective_address.clone(), person_address.clone(), timestamp)
+	}
+
+	/// Posts at the current time of the store.
+	pub fn post_message(
+		&self,
+		thread_address: &Address,
+		parent_address: Option<&Address>,
+		author_address: &Address,
+		content: &str,
+		timestamp: u64,
+	) -> ProtocolLoveResult<MessagePayload> {
+		self.store.set_now(timestamp);
+		post_message(&self.store, MessageParams {
+			thread_address: thread_address.clone(),
+			parent_address: parent_address.cloned(),
+			author_address: author_address.clone(),
+			content: content.into(),
+			timestamp,
+		})
+	}
+
+	/// Edits at the current time of the store.
+	pub fn edit_message(
+		&self,
+		message_address: &Address,
+		content: &str,
+		timestamp: u64,
+	) -> ProtocolLoveResult<MessagePayload> {
+		self.store.set_now(timestamp);
+		edit_message(&self.store, message_address.clone(), content.into(), timestamp)
+	}
+
+	pub fn get_thread(
+		&self,
+		thread_address: &Address,
+		parent_address: Option<&Address>,
+		offset: Option<u64>,
+		limit: Option<u64>,
+	) -> ProtocolLoveResult<ThreadPayload> {
+		get_thread(&self.store, ThreadParams {
+			thread_address: thread_address.clone(),
+			parent_address: parent_address.cloned(),
+			offset,
+			limit,
+		})
+	}
+
+	pub fn get_message_history(
+		&self,
+		message_address: &Address,
+	) -> ProtocolLoveResult<MessageHistoryPayload> {
+		get_message_history(&self.store, message_address.clone())
+	}
+}
+
+fn vote_params(
+	proposal_address: &Address,
+	person_address: &Address,
+	ballot: Ballot,
+	salt: Option<&str>,
+) -> VoteParams {
+	VoteParams {
+		proposal_address: proposal_address.clone(),
+		person_address: person_address.clone(),
+		ballot,
+		salt: salt.map(|salt| salt.into()),
+	}
+}
+
+fn sponsorship_params(
+	proposal_address: &Address,
+	person_address: &Address,
+	timestamp: u64,
+) -> SponsorshipParams {
+	SponsorshipParams {
+		proposal_address: proposal_address.clone(),
+		person_address: person_address.clone(),
+		timestamp,
+	}
+}
+
+/// Creates a person for the `player` & adds the person to the collective.
+fn join_collective(alice: &Player, player: &Player, name: &str, collective_address: &Address) -> Address {
+	let PersonPayload { person_address, .. } = player.create_person(name).unwrap();
+	alice.add_collective_person(collective_address, &person_address).unwrap();
+	person_address
+}
+
+/// A collective administered by alice with bob as a member.
+fn create_collective_with_member(
+	alice: &Player,
+	bob: &Player,
+) -> (Address, Address, Address) {
+	let PersonPayload { person_address: alice_address, .. } = alice.create_person("Alice").unwrap();
+	let PersonPayload { person_address: bob_address, .. } = bob.create_person("Bob").unwrap();
+	let CollectivePayload { collective_address, .. } =
+		alice.create_collective("Flower of Life Collective", Some(alice_address.clone())).unwrap();
+	alice.add_collective_person(&collective_address, &bob_address).unwrap();
+	(collective_address, alice_address, bob_address)
+}
+
+fn action_ops(actions: &[Action]) -> Vec<ActionOp> {
+	actions.iter().map(|action| action.op.clone()).collect()
+}
+
+fn assert_error(result: ProtocolLoveResult<impl std::fmt::Debug>, code: &str, message: &str) {
+	let error: ProtocolLoveError = result.unwrap_err();
+	assert_eq!(error.code(), code);
+	assert!(
+		error.message().ends_with(message),
+		"{:?} should end with {:?}", error.message(), message);
+}
+
+#[test]
+fn create_collective_get_collective() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let carol = scenario.player("carol");
+	let PersonPayload { person_address, person } = alice.create_person("Jane").unwrap();
+	assert_eq!(person.agent_address, alice.agent_address());
+	assert_eq!(alice.get_person(&person_address).unwrap().person.name, "Jane");
+	let CollectivePayload { collective_address, collective } =
+		alice.create_collective("Flower of Life Collective", Some(person_address.clone()))
+			.unwrap();
+	assert_eq!(collective.admin_address, Some(person_address.clone()));
+	let collective_people: Vec<Person> =
+		alice.get_collective_people(&collective_address).unwrap().collective_people;
+	assert_eq!(collective_people.len(), 1);
+	assert_eq!(collective_people[0].agent_address, alice.agent_address());
+	assert_eq!(
+		action_ops(&alice.get_actions(&collective_address).unwrap().actions),
+		vec![ActionOp::CreateCollective, ActionOp::SetCollectiveName, ActionOp::AddCollectivePerson]);
+	let renamed =
+		alice.set_collective_name(&collective_address, "Renamed Collective").unwrap();
+	assert_eq!(renamed.collective_address, collective_address);
+	assert_ne!(renamed.collective.name, collective.name);
+	assert_eq!(
+		carol.get_collective(&collective_address).unwrap().collective.name,
+		"Renamed Collective");
+	assert_eq!(
+		action_ops(&carol.get_actions(&collective_address).unwrap().actions),
+		vec![
+			ActionOp::CreateCollective,
+			ActionOp::SetCollectiveName,
+			ActionOp::AddCollectivePerson,
+			ActionOp::SetCollectiveName,
+		]);
+}
+
+#[test]
+fn collective_can_only_be_modified_by_the_admin() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let CollectivePayload { collective_address, .. } =
+		alice.create_collective("Flower of Life Collective", None).unwrap();
+	assert_error(
+		bob.set_collective_name(&collective_address, "Bob's Collective"),
+		"UNAUTHORIZED",
+		"Collective can only be modified by the admin");
+	let PersonPayload { person_address: bob_address, .. } = bob.create_person("Bob").unwrap();
+	assert_error(
+		bob.store.link_entries(&collective_address, &bob_address, "collective->person", "member"),
+		"UNAUTHORIZED",
+		"Only the admin can link to the collective");
+	assert_eq!(
+		bob.get_collective(&collective_address).unwrap().collective.name,
+		"Flower of Life Collective");
+	assert_eq!(bob.get_actions(&collective_address).unwrap().actions.len(), 3);
+}
+
+#[test]
+fn collective_must_be_created_with_same_agent_as_the_given_person() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let PersonPayload { person_address, .. } = alice.create_person("Jane").unwrap();
+	assert_error(
+		bob.create_collective("Bob's Collective", Some(person_address)),
+		"UNAUTHORIZED",
+		"Collective must be created with same agent as the given person");
+}
+
+#[test]
+fn person_representing_agent_must_be_created_by_agent() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	assert_error(
+		bob.create_person_for_agent("Jane", alice.agent_address()),
+		"UNAUTHORIZED",
+		"Person representing agent must be created by agent");
+}
+
+#[test]
+fn person_name_is_too_long() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let result = alice.create_person(&"J".repeat(65));
+	match result.clone() {
+		Err(ProtocolLoveError::ValidationFailed { field, .. }) =>
+			assert_eq!(field, Some("name".into())),
+		result => panic!("{:?} should be a validation failure", result),
+	}
+	assert_error(result, "VALIDATION_FAILED", "Name is too long");
+}
+
+#[test]
+fn person_can_only_update_by_oneself() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let PersonPayload { person_address, person } = alice.create_person("Jane").unwrap();
+	let person_entry = Entry::App("person".into(), Person {
+		name: "Not Jane".into(),
+		..person
+	}.into());
+	assert_error(
+		bob.store.update_entry(person_entry.clone(), &person_address),
+		"UNAUTHORIZED",
+		"Person can only update by oneself");
+	alice.store.update_entry(person_entry, &person_address).unwrap();
+	assert_eq!(bob.get_person(&person_address).unwrap().person.name, "Not Jane");
+}
+
+#[test]
+fn collective_changes_emit_signals() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let CollectivePayload { collective_address, .. } =
+		alice.create_collective("Flower of Life Collective", None).unwrap();
+	alice.set_collective_name(&collective_address, "Renamed Collective").unwrap();
+	let signal_names: Vec<String> =
+		alice.store.signals().into_iter().map(|(name, _)| name).collect();
+	assert_eq!(signal_names, vec![
+		"action_committed",
+		"action_committed",
+		"action_committed",
+		"person_joined",
+		"action_committed",
+	]);
+	let (_, payload) = alice.store.signals().pop().unwrap();
+	match ProtocolLoveSignal::try_from(payload).unwrap() {
+		ProtocolLoveSignal::ActionCommitted { collective_address: signal_collective_address, action, .. } => {
+			assert_eq!(signal_collective_address, collective_address);
+			assert_eq!(action.op, ActionOp::SetCollectiveName);
+		}
+		signal => panic!("{:?} should be action_committed", signal),
+	}
+	assert!(bob.store.signals().is_empty());
+
+	let carol = scenario.player("carol");
+	join_collective(&alice, &bob, "Bob", &collective_address);
+	alice.set_collective_name(&collective_address, "Flower of Life Collective").unwrap();
+	let signal_names: Vec<String> =
+		bob.store.signals().into_iter().map(|(name, _)| name).collect();
+	assert_eq!(signal_names, vec!["action_committed", "person_joined", "action_committed"]);
+	assert_eq!(alice.store.signals().len(), 8);
+	assert!(carol.store.signals().is_empty());
+	assert_error(
+		receive_signal(&bob.store, &carol.agent_address(), alice.store.signals().pop().unwrap().1),
+		"UNAUTHORIZED",
+		"Only people in the collective can send signals");
+}
+
+#[test]
+fn open_ballot_tallies_the_latest_vote_of_each_person() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let (collective_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let ProposalPayload { proposal_address, .. } =
+		alice.create_proposal(&collective_address, &alice_address, BallotMode::Open).unwrap();
+	alice.cast_vote(&proposal_address, &alice_address, Ballot::Yes).unwrap();
+	bob.cast_vote(&proposal_address, &bob_address, Ballot::Yes).unwrap();
+	let VotePayload { vote_address, .. } =
+		bob.cast_vote(&proposal_address, &bob_address, Ballot::No).unwrap();
+	assert_error(
+		alice.cast_vote(&proposal_address, &bob_address, Ballot::Yes),
+		"UNAUTHORIZED",
+		"Person does not represent the agent");
+	assert_error(
+		alice.store.link_entries(&proposal_address, &vote_address, "proposal->vote", ""),
+		"UNAUTHORIZED",
+		"Vote must be cast by the agent of the person");
+	let ProposalPayload { proposal_address: other_proposal_address, .. } =
+		alice.create_proposal(&collective_address, &alice_address, BallotMode::CommitReveal).unwrap();
+	assert_error(
+		bob.store.link_entries(&other_proposal_address, &vote_address, "proposal->vote", ""),
+		"VALIDATION_FAILED",
+		"Vote is not on the proposal");
+	assert_eq!(
+		bob.get_proposal_tally(&proposal_address).unwrap().tally,
+		ProposalTally { yes: 1, no: 1, abstain: 0, ..ProposalTally::default() });
+	assert_error(
+		bob.close_proposal(&proposal_address),
+		"UNAUTHORIZED",
+		"Only the author can change the proposal");
+	alice.close_proposal(&proposal_address).unwrap();
+	assert_error(
+		bob.cast_vote(&proposal_address, &bob_address, Ballot::Yes),
+		"CONFLICT",
+		"Proposal is not in voting");
+}
+
+#[test]
+fn secret_ballot_counts_only_reveals_matching_the_commitment() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let carol = scenario.player("carol");
+	let (collective_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let PersonPayload { person_address: carol_address, .. } = carol.create_person("Carol").unwrap();
+	let ProposalPayload { proposal_address, .. } =
+		alice.create_proposal(&collective_address, &alice_address, BallotMode::CommitReveal).unwrap();
+	assert_error(
+		bob.cast_vote(&proposal_address, &bob_address, Ballot::Yes),
+		"CONFLICT",
+		"Secret ballots are cast with commit_vote");
+	assert_error(
+		carol.commit_vote(&proposal_address, &carol_address, Ballot::Yes, "carol-salt"),
+		"UNAUTHORIZED",
+		"Only people in the collective can vote");
+	alice.commit_vote(&proposal_address, &alice_address, Ballot::No, "alice-salt").unwrap();
+	bob.commit_vote(&proposal_address, &bob_address, Ballot::No, "bob-salt").unwrap();
+	bob.commit_vote(&proposal_address, &bob_address, Ballot::Yes, "bob-salt").unwrap();
+	assert_error(
+		bob.reveal_vote(&proposal_address, &bob_address, Ballot::Yes, "bob-salt"),
+		"CONFLICT",
+		"Proposal is not revealing votes");
+	assert_error(
+		bob.get_proposal_tally(&proposal_address),
+		"CONFLICT",
+		"Secret ballots are tallied once revealing");
+	let ProposalPayload { proposal, .. } = alice.start_proposal_reveal(&proposal_address).unwrap();
+	assert_eq!(proposal.status, ProposalStatus::Revealing);
+	assert_error(
+		bob.commit_vote(&proposal_address, &bob_address, Ballot::No, "bob-salt"),
+		"CONFLICT",
+		"Proposal is not in voting");
+	assert_error(
+		bob.reveal_vote(&proposal_address, &bob_address, Ballot::No, "bob-salt"),
+		"VALIDATION_FAILED",
+		"Revealed vote does not match the commitment");
+	assert_error(
+		bob.reveal_vote(&proposal_address, &bob_address, Ballot::Yes, "other-salt"),
+		"VALIDATION_FAILED",
+		"Revealed vote does not match the commitment");
+	bob.reveal_vote(&proposal_address, &bob_address, Ballot::Yes, "bob-salt").unwrap();
+	assert_eq!(
+		carol.get_proposal_tally(&proposal_address).unwrap().tally,
+		ProposalTally { yes: 1, no: 0, abstain: 0, ..ProposalTally::default() });
+	let ProposalTallyPayload { tally, .. } = alice.close_proposal(&proposal_address).unwrap();
+	assert_eq!(tally, ProposalTally { yes: 1, no: 0, abstain: 0, ..ProposalTally::default() });
+	assert_eq!(
+		action_ops(&carol.get_actions(&collective_address).unwrap().actions)[3..].to_vec(),
+		vec![
+			ActionOp::AddCollectivePerson,
+			ActionOp::CreateProposal,
+			ActionOp::CommitVote,
+			ActionOp::CommitVote,
+			ActionOp::CommitVote,
+			ActionOp::SetProposalStatus,
+			ActionOp::RevealVote,
+			ActionOp::CloseProposal,
+		]);
+}
+
+#[test]
+fn ranked_choice_proposal_returns_the_rounds_of_the_tally() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let (collective_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	assert_error(
+		alice.create_multi_option_proposal(
+			&collective_address, &alice_address, TallyMethod::RankedChoice, &["Jane"]),
+		"VALIDATION_FAILED",
+		"Multi-option proposals need at least two options");
+	let ProposalPayload { proposal_address, .. } =
+		alice.create_multi_option_proposal(
+			&collective_address,
+			&alice_address,
+			TallyMethod::RankedChoice,
+			&["Jane", "John", "Joan"],
+		).unwrap();
+	assert_error(
+		bob.cast_vote(&proposal_address, &bob_address, Ballot::Yes),
+		"VALIDATION_FAILED",
+		"Ballot does not match the RankedChoice tally method");
+	assert_error(
+		bob.cast_vote(&proposal_address, &bob_address, Ballot::Rank(vec![1, 3])),
+		"VALIDATION_FAILED",
+		"Ballot refers to an unknown option");
+	assert_error(
+		bob.cast_vote(&proposal_address, &bob_address, Ballot::Rank(vec![1, 1])),
+		"VALIDATION_FAILED",
+		"Ballot refers to an option more than once");
+	alice.cast_vote(&proposal_address, &alice_address, Ballot::Rank(vec![2, 0])).unwrap();
+	bob.cast_vote(&proposal_address, &bob_address, Ballot::Rank(vec![0, 1])).unwrap();
+	let ProposalTallyPayload { tally, .. } = alice.close_proposal(&proposal_address).unwrap();
+	assert_eq!(tally.rounds, vec![
+		TallyRound { scores: vec![1, 0, 1], eliminated: vec![1] },
+		TallyRound { scores: vec![1, 0, 1], eliminated: vec![] },
+	]);
+	assert_eq!(tally.winners, vec![0, 2]);
+}
+
+#[test]
+fn quadratic_votes_spend_voice_credits_of_the_period() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let (collective_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	assert_error(
+		bob.start_voice_credit_period(&collective_address, Some(10)),
+		"UNAUTHORIZED",
+		"Only the admin can start a voice credit period");
+	let CollectivePayload { collective, .. } =
+		alice.start_voice_credit_period(&collective_address, Some(10)).unwrap();
+	assert_eq!(collective.voice_credit_period, 1);
+	let ProposalPayload { proposal_address, .. } =
+		alice.create_multi_option_proposal(
+			&collective_address, &alice_address, TallyMethod::Quadratic, &["Garden", "Library"],
+		).unwrap();
+	let ProposalPayload { proposal_address: other_proposal_address, .. } =
+		alice.create_multi_option_proposal(
+			&collective_address, &alice_address, TallyMethod::Quadratic, &["Pond", "Orchard"],
+		).unwrap();
+	assert_error(
+		bob.cast_vote(&proposal_address, &bob_address, Ballot::Quadratic(vec![1])),
+		"VALIDATION_FAILED",
+		"Quadratic ballot must have votes for each option");
+	bob.cast_vote(&proposal_address, &bob_address, Ballot::Quadratic(vec![3, 1])).unwrap();
+	assert_eq!(bob.get_voice_credits(&collective_address, &bob_address).unwrap().remaining, 0);
+	assert_error(
+		bob.cast_vote(&proposal_address, &bob_address, Ballot::Quadratic(vec![3, 2])),
+		"VALIDATION_FAILED",
+		"Not enough voice credits");
+	bob.cast_vote(&proposal_address, &bob_address, Ballot::Quadratic(vec![2, 2])).unwrap();
+	bob.cast_vote(&other_proposal_address, &bob_address, Ballot::Quadratic(vec![1, 1])).unwrap();
+	assert_error(
+		bob.cast_vote(&other_proposal_address, &bob_address, Ballot::Quadratic(vec![2, 0])),
+		"VALIDATION_FAILED",
+		"Not enough voice credits");
+	let VoiceCreditsPayload { period, budget, spent, remaining, .. } =
+		bob.get_voice_credits(&collective_address, &bob_address).unwrap();
+	assert_eq!((period, budget, spent, remaining), (1, 10, 10, 0));
+
+	let LedgerPayload { ledger_address, .. } = bob.get_ledger(&collective_address).unwrap();
+	let spend = VoiceCreditSpend {
+		ledger_address: ledger_address.clone(),
+		collective_address: collective_address.clone(),
+		proposal_address: other_proposal_address.clone(),
+		person_address: bob_address.clone(),
+		vote_address: other_proposal_address.clone(),
+		ballot: Ballot::Quadratic(vec![1, 0]),
+		vote_version: 2,
+		period: 1,
+		credits: 0,
+	};
+	let spend_entry = |spend: &VoiceCreditSpend| Entry::App("voice_credit_spend".into(), spend.into());
+	assert_error(
+		bob.store.commit_entry(&spend_entry(&spend)),
+		"VALIDATION_FAILED",
+		"Credits must be the cost of the ballot");
+	assert_error(
+		bob.store.commit_entry(&spend_entry(&VoiceCreditSpend { credits: 1, ..spend.clone() })),
+		"VALIDATION_FAILED",
+		"Voice credits must pay the vote of the ballot");
+	let vote_address = bob.store.entry_address(&Entry::App("vote".into(), (&Vote {
+		proposal_address: other_proposal_address.clone(),
+		person_address: bob_address.clone(),
+		ballot: Ballot::Quadratic(vec![1, 0]),
+		salt: None,
+		decision_address: None,
+		version: 2,
+	}).into())).unwrap();
+	let spend_address = bob.store.commit_entry(&spend_entry(&VoiceCreditSpend {
+		credits: 1,
+		vote_address,
+		..spend
+	})).unwrap();
+	assert_error(
+		bob.store.link_entries(
+			&ledger_address, &spend_address, "ledger->voice_credit_spend",
+			&format!("voice_credits:1:{}", alice_address)),
+		"VALIDATION_FAILED",
+		"Voice credit spend must be tagged with its period & person");
+	let bob_tag = format!("voice_credits:1:{}", bob_address);
+	assert_error(
+		alice.store.link_entries(&ledger_address, &spend_address, "ledger->voice_credit_spend", &bob_tag),
+		"UNAUTHORIZED",
+		"Voice credits must be spent by the agent of the person");
+	bob.store.link_entries(&ledger_address, &spend_address, "ledger->voice_credit_spend", &bob_tag)
+		.unwrap();
+	assert_eq!(bob.get_voice_credits(&collective_address, &bob_address).unwrap().remaining, 1);
+	assert_eq!(
+		bob.get_proposal_tally(&other_proposal_address).unwrap().tally.rounds[0].scores,
+		vec![0, 0]);
+
+	alice.cast_vote(&proposal_address, &alice_address, Ballot::Quadratic(vec![0, 3])).unwrap();
+	let carol = scenario.player("carol");
+	let PersonPayload { person_address: carol_address, .. } = create_person(&carol.store, PersonParams {
+		name: "Carol".into(),
+		agent_address: carol.agent_address(),
+		status: PersonStatus::Inactive,
+	}).unwrap();
+	alice.add_collective_person(&collective_address, &carol_address).unwrap();
+	assert_eq!(carol.get_voice_credits(&collective_address, &carol_address).unwrap().budget, 0);
+	assert_error(
+		carol.cast_vote(&proposal_address, &carol_address, Ballot::Quadratic(vec![1, 0])),
+		"UNAUTHORIZED",
+		"Only active people have voice credits");
+	let ProposalTallyPayload { tally, .. } = alice.close_proposal(&proposal_address).unwrap();
+	assert_eq!(tally.rounds[0].scores, vec![2, 5]);
+	assert_eq!(tally.winners, vec![1]);
+	alice.start_voice_credit_period(&collective_address, None).unwrap();
+	assert_eq!(bob.get_voice_credits(&collective_address, &bob_address).unwrap().remaining, 10);
+}
+
+#[test]
+fn delegated_votes_follow_the_delegation_chain() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let carol = scenario.player("carol");
+	let dave = scenario.player("dave");
+	let (collective_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let carol_address = join_collective(&alice, &carol, "Carol", &collective_address);
+	let dave_address = join_collective(&alice, &dave, "Dave", &collective_address);
+	carol.delegate_vote(&collective_address, &carol_address, &bob_address, None).unwrap();
+	dave.delegate_vote(&collective_address, &dave_address, &carol_address, None).unwrap();
+	assert_error(
+		bob.delegate_vote(&collective_address, &bob_address, &dave_address, None),
+		"CONFLICT",
+		"Delegation would create a cycle");
+	let delegation_entry = |delegate_address: &Address| Entry::App("delegation".into(), (&Delegation {
+		collective_address: collective_address.clone(),
+		delegator_address: bob_address.clone(),
+		delegate_address: Some(delegate_address.clone()),
+		tag: None,
+		version: 1,
+	}).into());
+	assert_error(
+		bob.store.commit_entry(&delegation_entry(&dave_address)),
+		"CONFLICT",
+		"Delegation would create a cycle");
+	let unlinked_address = bob.store.commit_entry(&delegation_entry(&alice_address)).unwrap();
+	assert_error(
+		alice.store.link_entries(
+			&collective_address,
+			&unlinked_address,
+			"collective->delegation",
+			&bob_address.to_string(),
+		),
+		"UNAUTHORIZED",
+		"Delegation must be linked by the agent of the delegator");
+	assert_error(
+		bob.store.link_entries(
+			&collective_address,
+			&unlinked_address,
+			"collective->delegation",
+			&carol_address.to_string(),
+		),
+		"VALIDATION_FAILED",
+		"Delegation must be tagged with its delegator");
+	assert_error(
+		bob.delegate_vote(&collective_address, &carol_address, &dave_address, None),
+		"UNAUTHORIZED",
+		"Person does not represent the agent");
+	let ProposalPayload { proposal_address, .. } =
+		alice.create_proposal(&collective_address, &alice_address, BallotMode::Open).unwrap();
+	bob.cast_vote(&proposal_address, &bob_address, Ballot::Yes).unwrap();
+	alice.cast_vote(&proposal_address, &alice_address, Ballot::No).unwrap();
+	assert_eq!(
+		alice.get_proposal_tally(&proposal_address).unwrap().tally,
+		ProposalTally { yes: 3, no: 1, delegated: 2, ..ProposalTally::default() });
+	carol.cast_vote(&proposal_address, &carol_address, Ballot::No).unwrap();
+	assert_eq!(
+		alice.get_proposal_tally(&proposal_address).unwrap().tally,
+		ProposalTally { yes: 1, no: 3, delegated: 1, ..ProposalTally::default() });
+
+	dave.delegate_vote(&collective_address, &dave_address, &alice_address, Some("budget")).unwrap();
+	let ProposalPayload { proposal_address: budget_proposal_address, .. } =
+		create_proposal(&alice.store, ProposalParams {
+			collective_address: collective_address.clone(),
+			author_address: alice_address.clone(),
+			name: "Buy seeds".into(),
+			content: "Spend the budget on seeds".into(),
+			ballot_mode: None,
+			tally_method: None,
+			options: None,
+			tags: Some(vec!["budget".into()]),
+			requested_funds: None,
+			status: None,
+			co_author_addresses: None,
+			discussion_window: None,
+			voting_window: None,
+			federated_proposal_address: None,
+			template_address: None,
+			template_values: None,
+		}).unwrap();
+	bob.cast_vote(&budget_proposal_address, &bob_address, Ballot::Yes).unwrap();
+	alice.cast_vote(&budget_proposal_address, &alice_address, Ballot::No).unwrap();
+	assert_eq!(
+		alice.get_proposal_tally(&budget_proposal_address).unwrap().tally,
+		ProposalTally { yes: 2, no: 2, delegated: 2, ..ProposalTally::default() });
+	assert_eq!(alice.get_delegations(&collective_address).unwrap().delegations.len(), 3);
+
+	dave.revoke_delegation(&collective_address, &dave_address, Some("budget")).unwrap();
+	assert_error(
+		dave.revoke_delegation(&collective_address, &dave_address, Some("budget")),
+		"CONFLICT",
+		"No delegation to revoke");
+	assert_eq!(
+		alice.get_proposal_tally(&budget_proposal_address).unwrap().tally,
+		ProposalTally { yes: 3, no: 1, delegated: 2, ..ProposalTally::default() });
+	assert_eq!(alice.get_delegations(&collective_address).unwrap().delegations.len(), 2);
+	let delegation_ops: Vec<ActionOp> =
+		action_ops(&alice.get_actions(&collective_address).unwrap().actions)
+			.into_iter()
+			.filter(|op| op == &ActionOp::DelegateVote || op == &ActionOp::RevokeDelegation)
+			.collect();
+	assert_eq!(delegation_ops, vec![
+		ActionOp::DelegateVote,
+		ActionOp::DelegateVote,
+		ActionOp::DelegateVote,
+		ActionOp::RevokeDelegation,
+	]);
+}
+
+#[test]
+fn repeated_votes_and_delegations_are_committed_as_new_versions() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let carol = scenario.player("carol");
+	let (collective_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let carol_address = join_collective(&alice, &carol, "Carol", &collective_address);
+	let ProposalPayload { proposal_address, .. } =
+		alice.create_proposal(&collective_address, &alice_address, BallotMode::Open).unwrap();
+	let first = bob.cast_vote(&proposal_address, &bob_address, Ballot::Yes).unwrap();
+	let second = bob.cast_vote(&proposal_address, &bob_address, Ballot::No).unwrap();
+	let third = bob.cast_vote(&proposal_address, &bob_address, Ballot::Yes).unwrap();
+	assert_eq!((first.vote.version, second.vote.version, third.vote.version), (1, 2, 3));
+	assert_ne!(first.vote_address, third.vote_address);
+	assert_eq!(
+		bob.store.get_links(&proposal_address, "proposal->vote", None).unwrap()[0],
+		third.vote_address);
+	assert_eq!(
+		alice.get_proposal_tally(&proposal_address).unwrap().tally,
+		ProposalTally { yes: 1, no: 0, abstain: 0, ..ProposalTally::default() });
+
+	let to_bob = carol.delegate_vote(&collective_address, &carol_address, &bob_address, None).unwrap();
+	carol.delegate_vote(&collective_address, &carol_address, &alice_address, None).unwrap();
+	carol.revoke_delegation(&collective_address, &carol_address, None).unwrap();
+	let to_bob_again =
+		carol.delegate_vote(&collective_address, &carol_address, &bob_address, None).unwrap();
+	assert_eq!((to_bob.delegation.version, to_bob_again.delegation.version), (1, 4));
+	assert_ne!(to_bob.delegation_address, to_bob_again.delegation_address);
+	let delegations = alice.get_delegations(&collective_address).unwrap().delegations;
+	assert_eq!(delegations, vec![to_bob_again.delegation]);
+}
+
+#[test]
+fn conviction_proposal_is_funded_once_its_conviction_reaches_the_threshold() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let (collective_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	assert_error(
+		bob.deposit_ledger_funds(&collective_address, 1000),
+		"UNAUTHORIZED",
+		"Only the admin can deposit funds");
+	alice.deposit_ledger_funds(&collective_address, 1000).unwrap();
+	assert_error(
+		alice.create_conviction_proposal(&collective_address, &alice_address, 0),
+		"VALIDATION_FAILED",
+		"Conviction proposals must request funds");
+	let ProposalPayload { proposal_address, .. } =
+		alice.create_conviction_proposal(&collective_address, &alice_address, 100).unwrap();
+	let ProposalPayload { proposal_address: greedy_proposal_address, .. } =
+		bob.create_conviction_proposal(&collective_address, &bob_address, 300).unwrap();
+	assert_error(
+		bob.cast_vote(&proposal_address, &bob_address, Ballot::Yes),
+		"VALIDATION_FAILED",
+		"Ballot does not match the Conviction tally method");
+
+	let start = 1_600_000_000;
+	let hour = 3600;
+	alice.stake_conviction(&proposal_address, &alice_address, 100, start).unwrap();
+	bob.stake_conviction(&proposal_address, &bob_address, 100, start).unwrap();
+	assert_error(
+		bob.stake_conviction(&greedy_proposal_address, &bob_address, 1, start),
+		"VALIDATION_FAILED",
+		"Not enough stake");
+	assert_error(
+		stake_conviction(&bob.store, ConvictionStake {
+			proposal_address: proposal_address.clone(),
+			person_address: bob_address.clone(),
+			amount: 100,
+			timestamp: start + hour,
+		}),
+		"VALIDATION_FAILED",
+		"Timestamp is too far from the time of the commit");
+	let conviction = alice.get_proposal_conviction(&proposal_address, start + 10 * hour).unwrap();
+	assert_eq!((conviction.staked, conviction.conviction), (200, 1910));
+	assert_eq!(conviction.threshold, Some(5000));
+	assert_error(
+		alice.fund_conviction_proposal(&proposal_address, start + 10 * hour),
+		"CONFLICT",
+		"Conviction is below the threshold");
+	assert_error(
+		bob.fund_conviction_proposal(&greedy_proposal_address, start + 10 * hour),
+		"CONFLICT",
+		"Ledger cannot fund the proposal");
+
+	let conviction = bob.fund_conviction_proposal(&proposal_address, start + 40 * hour).unwrap();
+	assert_eq!(conviction.conviction, 6608);
+	let proposal = get_proposal(&alice.store, proposal_address.clone()).unwrap().proposal;
+	assert_eq!(proposal.status, ProposalStatus::Closed);
+	assert_eq!(proposal.funded_at, Some(start + 40 * hour));
+	let LedgerPayload { ledger_address, ledger } = alice.get_ledger(&collective_address).unwrap();
+	assert_eq!(ledger.funds, 900);
+	let ledger_entry = |funds: u64, proposal_address: Option<Address>| Entry::App(
+		"ledger".into(),
+		Ledger { funds, proposal_address, ..ledger.clone() }.into(),
+	);
+	assert_error(
+		bob.store.update_entry(ledger_entry(800, Some(proposal_address.clone())), &ledger_address),
+		"CONFLICT",
+		"Proposal is already funded");
+	assert_error(
+		bob.store.update_entry(ledger_entry(5000, None), &ledger_address),
+		"UNAUTHORIZED",
+		"Only the admin can deposit funds");
+	assert_error(
+		bob.store.update_entry(ledger_entry(0, None), &ledger_address),
+		"UNAUTHORIZED",
+		"Funds can only be debited by a proposal");
+	assert_error(
+		bob.fund_conviction_proposal(&proposal_address, start + 41 * hour),
+		"CONFLICT",
+		"Proposal is not in voting");
+	bob.stake_conviction(&greedy_proposal_address, &bob_address, 100, start + 41 * hour).unwrap();
+	let conviction_ops: Vec<ActionOp> =
+		action_ops(&alice.get_actions(&collective_address).unwrap().actions)
+			.into_iter()
+			.filter(|op| match op {
+				ActionOp::DepositLedgerFunds | ActionOp::StakeConviction | ActionOp::FundProposal =>
+					true,
+				_ => false,
+			})
+			.collect();
+	assert_eq!(conviction_ops, vec![
+		ActionOp::DepositLedgerFunds,
+		ActionOp::StakeConviction,
+		ActionOp::StakeConviction,
+		ActionOp::FundProposal,
+		ActionOp::StakeConviction,
+	]);
+}
+
+#[test]
+fn consent_proposal_passes_once_objections_are_resolved() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let carol = scenario.player("carol");
+	let (collective_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let PersonPayload { person_address: carol_address, .. } = carol.create_person("Carol").unwrap();
+	let ProposalPayload { proposal_address, .. } =
+		alice.create_multi_option_proposal(&collective_address, &alice_address, TallyMethod::Consent, &[])
+			.unwrap();
+	assert_error(
+		bob.cast_vote(&proposal_address, &bob_address, Ballot::Yes),
+		"VALIDATION_FAILED",
+		"Ballot does not match the Consent tally method");
+	assert_error(
+		carol.raise_objection(&proposal_address, &carol_address, ObjectionKind::Objection, "No"),
+		"UNAUTHORIZED",
+		"Only people in the collective can object");
+	assert_error(
+		bob.raise_objection(&proposal_address, &bob_address, ObjectionKind::Objection, " "),
+		"VALIDATION_FAILED",
+		"Reason must be between 1 & 1000 characters");
+	let ObjectionPayload { objection_address, .. } = bob.raise_objection(
+		&proposal_address,
+		&bob_address,
+		ObjectionKind::Objection,
+		"The garden needs a water supply",
+	).unwrap();
+	bob.raise_objection(&proposal_address, &bob_address, ObjectionKind::Concern, "Slugs").unwrap();
+	assert_error(
+		alice.close_proposal(&proposal_address),
+		"CONFLICT",
+		"Proposal has unresolved objections");
+	assert_error(
+		bob.resolve_objection(&objection_address, None),
+		"UNAUTHORIZED",
+		"Only the author can resolve an objection");
+
+	alice.resolve_objection(
+		&objection_address,
+		Some("Plant a garden in the commons, watered from the well"),
+	).unwrap();
+	assert_error(
+		alice.resolve_objection(&objection_address, None),
+		"CONFLICT",
+		"Objection is already resolved");
+	let objections = bob.get_proposal_objections(&proposal_address).unwrap().objections;
+	assert_eq!(
+		objections.iter().map(|payload| payload.objection.resolved).collect::<Vec<bool>>(),
+		vec![false, true]);
+	assert_eq!(
+		get_proposal(&bob.store, proposal_address.clone()).unwrap().proposal.content,
+		"Plant a garden in the commons, watered from the well");
+	assert_eq!(
+		alice.close_proposal(&proposal_address).unwrap().tally,
+		ProposalTally { objections: 0, concerns: 1, ..ProposalTally::default() });
+	assert_error(
+		bob.raise_objection(&proposal_address, &bob_address, ObjectionKind::Objection, "Too late"),
+		"CONFLICT",
+		"Proposal is not in voting");
+	let consent_ops: Vec<ActionOp> =
+		action_ops(&alice.get_actions(&collective_address).unwrap().actions)
+			.into_iter()
+			.skip_while(|op| op != &ActionOp::CreateProposal)
+			.collect();
+	assert_eq!(consent_ops, vec![
+		ActionOp::CreateProposal,
+		ActionOp::RaiseObjection,
+		ActionOp::RaiseObjection,
+		ActionOp::ReviseProposal,
+		ActionOp::ResolveObjection,
+		ActionOp::CloseProposal,
+	]);
+}
+
+#[test]
+fn proposal_is_revised_and_amended_until_voting_opens() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let carol = scenario.player("carol");
+	let (collective_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let carol_address = join_collective(&alice, &carol, "Carol", &collective_address);
+	let ProposalPayload { proposal_address, proposal } =
+		create_proposal(&alice.store, ProposalParams {
+			collective_address: collective_address.clone(),
+			author_address: alice_address.clone(),
+			name: "Plant a garden".into(),
+			content: "Plant a garden".into(),
+			ballot_mode: None,
+			tally_method: None,
+			options: None,
+			tags: None,
+			requested_funds: None,
+			status: Some(ProposalStatus::Draft),
+			co_author_addresses: Some(vec![bob_address.clone()]),
+			discussion_window: None,
+			voting_window: None,
+			federated_proposal_address: None,
+			template_address: None,
+			template_values: None,
+		}).unwrap();
+	assert_eq!(proposal.co_author_addresses, vec![bob_address.clone()]);
+	assert_error(
+		bob.cast_vote(&proposal_address, &bob_address, Ballot::Yes),
+		"CONFLICT",
+		"Proposal is not in voting");
+
+	let revised = bob.revise_proposal(&proposal_address, &bob_address, "Plant a garden\nIn the commons")
+		.unwrap()
+		.proposal;
+	assert_eq!(revised.revision, 1);
+	assert_error(
+		carol.revise_proposal(&proposal_address, &carol_address, "Pave the garden"),
+		"UNAUTHORIZED",
+		"Only the authors can revise the proposal");
+	assert_error(
+		carol.propose_amendment(&proposal_address, &carol_address, None, Some("Pave the garden")),
+		"CONFLICT",
+		"Proposal is not in discussion");
+
+	alice.start_proposal_discussion(&proposal_address).unwrap();
+	let AmendmentPayload { amendment_address, .. } = carol.propose_amendment(
+		&proposal_address,
+		&carol_address,
+		None,
+		Some("Plant a garden\nIn the commons\nWith a pond"),
+	).unwrap();
+	let AmendmentPayload { amendment_address: rejected_address, .. } =
+		carol.propose_amendment(&proposal_address, &carol_address, Some("Pave a car park"), None)
+			.unwrap();
+	assert_error(
+		carol.accept_amendment(&amendment_address, &carol_address),
+		"UNAUTHORIZED",
+		"Only the authors can decide on an amendment");
+	let accepted = bob.accept_amendment(&amendment_address, &bob_address).unwrap();
+	assert_eq!(accepted.amendment.status, AmendmentStatus::Accepted);
+	alice.reject_amendment(&rejected_address, &alice_address).unwrap();
+	assert_error(
+		alice.accept_amendment(&rejected_address, &alice_address),
+		"CONFLICT",
+		"Amendment is already decided");
+
+	let revisions = carol.get_proposal_revisions(&proposal_address).unwrap().revisions;
+	assert_eq!(
+		revisions.iter().map(|revision| revision.revision).collect::<Vec<u64>>(),
+		vec![0, 1, 2]);
+	assert_eq!(revisions[1].reviser_address, bob_address);
+	assert_eq!(revisions[2].amendment_address, Some(amendment_address));
+	let diff = carol.get_proposal_diff(&proposal_address, 0, 2).unwrap();
+	assert_eq!(diff.name, vec![DiffLine::Same("Plant a garden".into())]);
+	assert_eq!(diff.content, vec![
+		DiffLine::Same("Plant a garden".into()),
+		DiffLine::Added("In the commons".into()),
+		DiffLine::Added("With a pond".into()),
+	]);
+	assert_error(
+		carol.get_proposal_diff(&proposal_address, 0, 3),
+		"VALIDATION_FAILED",
+		"Unknown revision");
+
+	alice.open_proposal_voting(&proposal_address).unwrap();
+	assert_error(
+		alice.revise_proposal(&proposal_address, &alice_address, "Plant a forest"),
+		"CONFLICT",
+		"Proposal content is frozen once voting opens");
+	bob.cast_vote(&proposal_address, &bob_address, Ballot::Yes).unwrap();
+	let revision_ops: Vec<ActionOp> =
+		action_ops(&alice.get_actions(&collective_address).unwrap().actions)
+			.into_iter()
+			.skip_while(|op| op != &ActionOp::CreateProposal)
+			.collect();
+	assert_eq!(revision_ops, vec![
+		ActionOp::CreateProposal,
+		ActionOp::ReviseProposal,
+		ActionOp::SetProposalStatus,
+		ActionOp::ProposeAmendment,
+		ActionOp::ProposeAmendment,
+		ActionOp::ReviseProposal,
+		ActionOp::AcceptAmendment,
+		ActionOp::RejectAmendment,
+		ActionOp::SetProposalStatus,
+		ActionOp::CastVote,
+	]);
+}
+
+#[test]
+fn proposal_discussion_threads_page_replies_and_keep_edit_history() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let carol = scenario.player("carol");
+	let (collective_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let PersonPayload { person_address: carol_address, .. } = carol.create_person("Carol").unwrap();
+	let ProposalPayload { proposal_address, .. } =
+		alice.create_proposal(&collective_address, &alice_address, BallotMode::Open).unwrap();
+	let MessagePayload { message_address, .. } =
+		alice.post_message(&proposal_address, None, &alice_address, "Where should it go?", 100)
+			.unwrap();
+	let MessagePayload { message_address: reply_address, .. } =
+		bob.post_message(&proposal_address, Some(&message_address), &bob_address, "By the well", 110)
+			.unwrap();
+	bob.post_message(&proposal_address, None, &bob_address, "Who waters it?", 120).unwrap();
+	bob.post_message(&proposal_address, None, &bob_address, "I can help", 130).unwrap();
+	alice.post_message(&collective_address, None, &alice_address, "Welcome Bob", 140).unwrap();
+	assert_error(
+		carol.post_message(&proposal_address, None, &carol_address, "Pave it", 150),
+		"UNAUTHORIZED",
+		"Only people in the collective can post messages");
+	assert_error(
+		bob.post_message(&collective_address, Some(&message_address), &bob_address, "Off topic", 150),
+		"VALIDATION_FAILED",
+		"Reply must be in the thread of the parent");
+	assert_error(
+		bob.post_message(&proposal_address, None, &bob_address, " ", 150),
+		"VALIDATION_FAILED",
+		"Content must be between 1 & 5000 characters");
+
+	let thread = carol.get_thread(&proposal_address, None, Some(1), Some(1)).unwrap();
+	assert_eq!(thread.total, 3);
+	assert_eq!(
+		thread.messages.iter().map(|payload| payload.message.content.as_str()).collect::<Vec<&str>>(),
+		vec!["Who waters it?"]);
+	let thread = carol.get_thread(&proposal_address, None, None, None).unwrap();
+	assert_eq!(thread.messages[0].message_address, message_address);
+	assert_eq!(thread.messages[0].reply_count, 1);
+	let replies = carol.get_thread(&proposal_address, Some(&message_address), None, None).unwrap();
+	assert_eq!(replies.messages[0].message_address, reply_address);
+	assert_eq!(carol.get_thread(&collective_address, None, None, None).unwrap().total, 1);
+	assert_error(
+		carol.get_thread(&proposal_address, None, None, Some(101)),
+		"VALIDATION_FAILED",
+		"Limit must be between 1 & 100");
+
+	assert_error(
+		alice.edit_message(&reply_address, "By the road", 200),
+		"UNAUTHORIZED",
+		"Only the author can edit the message");
+	bob.edit_message(&reply_address, "By the well, in the shade", 200).unwrap();
+	let reply = carol.get_thread(&proposal_address, Some(&message_address), None, None)
+		.unwrap()
+		.messages
+		.remove(0)
+		.message;
+	assert_eq!(reply.content, "By the well, in the shade");
+	assert_eq!(reply.edited_at, Some(200));
+	let edits = carol.get_message_history(&reply_address).unwrap().edits;
+	assert_eq!(edits.len(), 1);
+	assert_eq!(edits[0].prev_content, "By the well");
+
+	let (_, payload) = bob.store.signals().into_iter()
+		.rev()
+		.find(|(name, _)| name == "message_posted")
+		.unwrap();
+	match ProtocolLoveSignal::try_from(payload).unwrap() {
+		ProtocolLoveSignal::MessagePosted { thread_address, message, .. } => {
+			assert_eq!(thread_address, collective_address);
+			assert_eq!(message.content, "Welcome Bob");
+		}
+		signal => panic!("{:?} should be message_posted", signal),
+	}
+	let actions = alice.get_actions(&collective_address).unwrap().actions;
+	let message_ops: Vec<ActionOp> = action_ops(&actions)
+		.into_iter()
+		.skip_while(|op| op != &ActionOp::PostMessage)
+		.collect();
+	assert_eq!(message_ops, vec![
+		ActionOp::PostMessage,
+		ActionOp::PostMessage,
+		ActionOp::PostMessage,
+		ActionOp::PostMessage,
+		ActionOp::PostMessage,
+		ActionOp::EditMessage,
+	]);
+	assert!(actions.iter()
+		.filter(|action| action.op == ActionOp::PostMessage)
+		.all(|action| action.strategy == ActionStrategy::NewDiscussionMessage));
+}
+
+#[test]
+fn expired_proposals_are_closed_after_their_voting_window() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let carol = scenario.player("carol");
+	let (collective_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let PersonPayload { person_address: carol_address, .. } = carol.create_person("Carol").unwrap();
+	let proposal_params = ProposalParams {
+		collective_address: collective_address.clone(),
+		author_address: alice_address.clone(),
+		name: "Plant a garden".into(),
+		content: "Plant a garden in the commons".into(),
+		ballot_mode: None,
+		tally_method: None,
+		options: None,
+		tags: None,
+		requested_funds: None,
+		status: None,
+		co_author_addresses: None,
+		discussion_window: None,
+		voting_window: Some(ProposalWindow { opens_at: Some(100), closes_at: Some(200) }),
+		federated_proposal_address: None,
+		template_address: None,
+		template_values: None,
+	};
+	assert_error(
+		create_proposal(&alice.store, ProposalParams {
+			voting_window: Some(ProposalWindow { opens_at: Some(200), closes_at: Some(100) }),
+			..proposal_params.clone()
+		}),
+		"VALIDATION_FAILED",
+		"Window must open before it closes");
+	assert_error(
+		create_proposal(&alice.store, ProposalParams {
+			discussion_window: Some(ProposalWindow { opens_at: None, closes_at: Some(150) }),
+			..proposal_params.clone()
+		}),
+		"VALIDATION_FAILED",
+		"Voting must open after the discussion closes");
+	let ProposalPayload { proposal_address: draft_address, .. } =
+		create_proposal(&alice.store, ProposalParams {
+			status: Some(ProposalStatus::Draft),
+			..proposal_params.clone()
+		}).unwrap();
+	assert_error(
+		alice.open_proposal_voting(&draft_address),
+		"CONFLICT",
+		"Voting opens at 100");
+	let ProposalPayload { proposal_address, .. } =
+		create_proposal(&alice.store, proposal_params).unwrap();
+	assert_error(
+		bob.cast_vote(&proposal_address, &bob_address, Ballot::Yes),
+		"CONFLICT",
+		"Vote is cast outside the voting window");
+
+	bob.store.set_now(150);
+	bob.cast_vote(&proposal_address, &bob_address, Ballot::Yes).unwrap();
+	alice.cast_vote(&proposal_address, &alice_address, Ballot::Yes).unwrap();
+	assert_error(
+		alice.close_proposal(&proposal_address),
+		"CONFLICT",
+		"Voting is open until 200");
+	let proposal = get_proposal(&alice.store, proposal_address.clone()).unwrap().proposal;
+	assert_error(
+		update_proposal_entry(&alice.store, &proposal_address, &Proposal {
+			status: ProposalStatus::Closed,
+			outcome: Some(ProposalOutcome::Passed),
+			..proposal
+		}),
+		"CONFLICT",
+		"Voting is open until 200");
+	assert!(bob.close_expired_proposals(&collective_address, &bob_address, 150).unwrap()
+		.proposals
+		.is_empty());
+	assert_error(
+		carol.close_expired_proposals(&collective_address, &carol_address, 250),
+		"UNAUTHORIZED",
+		"Only people in the collective can close expired proposals");
+	assert_error(
+		bob.cast_vote(&proposal_address, &bob_address, Ballot::No),
+		"CONFLICT",
+		"Vote is cast outside the voting window");
+
+	let expired = bob.close_expired_proposals(&collective_address, &bob_address, 250).unwrap();
+	assert_eq!(expired.proposals.len(), 1);
+	assert_eq!(expired.proposals[0].proposal_address, proposal_address);
+	assert_eq!(expired.proposals[0].tally.yes, 2);
+	assert_eq!(expired.proposals[0].outcome, ProposalOutcome::Passed);
+	let proposal = get_proposal(&bob.store, proposal_address.clone()).unwrap().proposal;
+	assert_eq!(proposal.status, ProposalStatus::Closed);
+	assert_eq!(proposal.closed_at, Some(250));
+	assert!(bob.close_expired_proposals(&collective_address, &bob_address, 300).unwrap()
+		.proposals
+		.is_empty());
+	assert_eq!(
+		action_ops(&alice.get_actions(&collective_address).unwrap().actions).last(),
+		Some(&ActionOp::CloseExpiredProposal));
+}
+
+#[test]
+fn proposal_changes_follow_its_lifecycle() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let (collective_address, alice_address, _bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let proposal_params = ProposalParams {
+		collective_address: collective_address.clone(),
+		author_address: alice_address.clone(),
+		name: " ".into(),
+		content: "Plant a garden in the commons".into(),
+		ballot_mode: None,
+		tally_method: None,
+		options: None,
+		tags: None,
+		requested_funds: None,
+		status: None,
+		co_author_addresses: None,
+		discussion_window: None,
+		voting_window: None,
+		federated_proposal_address: None,
+		template_address: None,
+		template_values: None,
+	};
+	assert_error(
+		create_proposal(&alice.store, proposal_params.clone()),
+		"VALIDATION_FAILED",
+		"Name is required");
+	assert_error(
+		create_proposal(&alice.store, ProposalParams {
+			name: "Plant".repeat(30),
+			..proposal_params.clone()
+		}),
+		"VALIDATION_FAILED",
+		"Name is too long");
+	let carol = scenario.player("carol");
+	let PersonPayload { person_address: carol_address, .. } = create_person(&carol.store, PersonParams {
+		name: "Carol".into(),
+		agent_address: carol.agent_address(),
+		status: PersonStatus::Inactive,
+	}).unwrap();
+	alice.add_collective_person(&collective_address, &carol_address).unwrap();
+	assert_error(
+		create_proposal(&carol.store, ProposalParams {
+			author_address: carol_address,
+			name: "Plant a garden".into(),
+			..proposal_params.clone()
+		}),
+		"UNAUTHORIZED",
+		"Author must be an active person");
+	let ProposalPayload { proposal_address, proposal } =
+		create_proposal(&alice.store, ProposalParams {
+			name: "Plant a garden".into(),
+			..proposal_params
+		}).unwrap();
+	assert_error(
+		validate_proposal_entry(
+			&alice.store,
+			EntryValidation::Create {
+				entry: Proposal { options: vec!["Garden".into(), "Pond".into()], ..proposal.clone() },
+			},
+			&ValidationContext { sources: vec![alice.agent_address()], timestamp: 0 },
+		),
+		"VALIDATION_FAILED",
+		"Majority proposals have no options");
+
+	assert_error(
+		update_proposal_entry(&alice.store, &proposal_address, &Proposal {
+			tally_method: TallyMethod::Consent,
+			..proposal.clone()
+		}),
+		"UNAUTHORIZED",
+		"Only the name, content & status of a proposal can be changed");
+	assert_error(
+		update_proposal_entry(&alice.store, &proposal_address, &Proposal {
+			status: ProposalStatus::Draft,
+			..proposal.clone()
+		}),
+		"CONFLICT",
+		"Proposal cannot move from Voting to Draft");
+	assert_error(
+		update_proposal_entry(&bob.store, &proposal_address, &Proposal {
+			status: ProposalStatus::Closed,
+			..proposal.clone()
+		}),
+		"UNAUTHORIZED",
+		"Only the author can change the status of the proposal");
+	assert_error(
+		validate_proposal_entry(
+			&alice.store,
+			EntryValidation::Delete { old_entry: proposal.clone() },
+			&ValidationContext { sources: vec![alice.agent_address()], timestamp: 0 },
+		),
+		"CONFLICT",
+		"Proposal cannot be deleted");
+
+	alice.close_proposal(&proposal_address).unwrap();
+	assert_error(
+		update_proposal_entry(&alice.store, &proposal_address, &Proposal {
+			status: ProposalStatus::Closed,
+			content: "Pave the garden".into(),
+			..proposal
+		}),
+		"CONFLICT",
+		"Closed proposal cannot be changed");
+}
+
+#[test]
+fn proposal_is_created_from_a_template_of_the_collective() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let (collective_address, _alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let template_params = ProposalTemplateParams {
+		collective_address: collective_address.clone(),
+		name: "Change name".into(),
+		description: None,
+		fields: vec![
+			TemplateField { name: "name".into(), field_type: TemplateFieldType::Text, required: true },
+			TemplateField { name: "reason".into(), field_type: TemplateFieldType::Text, required: false },
+		],
+		name_template: "Rename to {name}".into(),
+		content_template: "Rename the collective to {name}. {reason}".into(),
+		action_op: Some(ActionOp::SetCollectiveName),
+		ballot_mode: None,
+		tally_method: None,
+		options: None,
+		tags: Some(vec!["governance".into()]),
+	};
+	assert_error(
+		create_proposal_template(&bob.store, template_params.clone()),
+		"UNAUTHORIZED",
+		"Only the admin can register proposal templates");
+	let ProposalTemplatePayload { template_address, .. } =
+		create_proposal_template(&alice.store, template_params).unwrap();
+	let templates = get_collective_templates(&bob.store, collective_address.clone()).unwrap().templates;
+	assert_eq!(templates.len(), 1);
+	assert_eq!(templates[0].template_address, template_address);
+
+	let template_proposal = |values: Vec<(&str, Value)>| create_proposal(&bob.store, ProposalParams {
+		collective_address: collective_address.clone(),
+		author_address: bob_address.clone(),
+		name: "".into(),
+		content: "".into(),
+		ballot_mode: None,
+		tally_method: None,
+		options: None,
+		tags: None,
+		requested_funds: None,
+		status: None,
+		co_author_addresses: None,
+		discussion_window: None,
+		voting_window: None,
+		federated_proposal_address: None,
+		template_address: Some(template_address.clone()),
+		template_values: Some(values.into_iter()
+			.map(|(name, value)| (name.to_string(), value))
+			.collect::<BTreeMap<String, Value>>()),
+	});
+	assert_error(
+		template_proposal(vec![("reason", Value::from("Shorter"))]),
+		"VALIDATION_FAILED",
+		"Template field name is required");
+	assert_error(
+		template_proposal(vec![("name", Value::from(42))]),
+		"VALIDATION_FAILED",
+		"Template field name must be Text");
+	assert_error(
+		template_proposal(vec![("name", Value::from("Garden")), ("color", Value::from("green"))]),
+		"VALIDATION_FAILED",
+		"Unknown template field color");
+
+	let ProposalPayload { proposal, .. } =
+		template_proposal(vec![("name", Value::from("Garden Collective"))]).unwrap();
+	assert_eq!(proposal.name, "Rename to Garden Collective");
+	assert_eq!(proposal.content, "Rename the collective to Garden Collective. ");
+	assert_eq!(proposal.tally_method, TallyMethod::Majority);
+	assert_eq!(proposal.tags, vec!["governance".to_string()]);
+	let template = proposal.template.unwrap();
+	assert_eq!(template.template_address, template_address);
+	assert_eq!(template.action_op, Some(ActionOp::SetCollectiveName));
+	assert_eq!(template.values.get("name"), Some(&Value::from("Garden Collective")));
+}
+
+#[test]
+fn sponsored_proposal_opens_voting_once_it_reaches_the_threshold() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let carol = scenario.player("carol");
+	let (collective_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let carol_address = join_collective(&alice, &carol, "Carol", &collective_address);
+	assert_error(
+		set_sponsor_threshold(&bob.store, collective_address.clone(), 2),
+		"UNAUTHORIZED",
+		"Only the admin can set the sponsor threshold");
+	set_sponsor_threshold(&alice.store, collective_address.clone(), 2).unwrap();
+	let ProposalPayload { proposal_address, proposal } =
+		alice.create_proposal(&collective_address, &alice_address, BallotMode::Open).unwrap();
+	assert_eq!(proposal.status, ProposalStatus::Draft);
+	assert_error(
+		alice.open_proposal_voting(&proposal_address),
+		"CONFLICT",
+		"Proposal needs 2 sponsors before voting");
+	assert_error(
+		alice.sponsor_proposal(&proposal_address, &alice_address, 10),
+		"VALIDATION_FAILED",
+		"Authors cannot sponsor their proposal");
+
+	let sponsors = bob.sponsor_proposal(&proposal_address, &bob_address, 10).unwrap();
+	assert_eq!(sponsors.sponsor_addresses, vec![bob_address.clone()]);
+	assert_eq!(sponsors.status, ProposalStatus::Draft);
+	assert_error(
+		bob.sponsor_proposal(&proposal_address, &bob_address, 11),
+		"CONFLICT",
+		"Proposal is already sponsored");
+	assert!(bob.unsponsor_proposal(&proposal_address, &bob_address, 12).unwrap()
+		.sponsor_addresses
+		.is_empty());
+	assert_error(
+		carol.unsponsor_proposal(&proposal_address, &carol_address, 13),
+		"CONFLICT",
+		"Proposal is not sponsored");
+	bob.sponsor_proposal(&proposal_address, &bob_address, 14).unwrap();
+	let sponsors = carol.sponsor_proposal(&proposal_address, &carol_address, 15).unwrap();
+	assert_eq!(sponsors.sponsor_addresses, vec![carol_address.clone(), bob_address.clone()]);
+	assert_eq!(sponsors.status, ProposalStatus::Voting);
+	assert_error(
+		bob.unsponsor_proposal(&proposal_address, &bob_address, 16),
+		"CONFLICT",
+		"Proposal is not gathering sponsors");
+	bob.cast_vote(&proposal_address, &bob_address, Ballot::Yes).unwrap();
+	let sponsor_ops: Vec<ActionOp> =
+		action_ops(&alice.get_actions(&collective_address).unwrap().actions)
+			.into_iter()
+			.skip_while(|op| op != &ActionOp::CreateProposal)
+			.collect();
+	assert_eq!(sponsor_ops, vec![
+		ActionOp::CreateProposal,
+		ActionOp::SponsorProposal,
+		ActionOp::UnsponsorProposal,
+		ActionOp::SponsorProposal,
+		ActionOp::SponsorProposal,
+		ActionOp::SetProposalStatus,
+		ActionOp::CastVote,
+	]);
+}
+
+#[test]
+fn charter_is_amended_by_passed_charter_proposals() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let (collective_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let ProposalTemplatePayload { template_address, .. } =
+		create_proposal_template(&alice.store, ProposalTemplateParams {
+			collective_address: collective_address.clone(),
+			name: "Amend the charter".into(),
+			description: None,
+			fields: vec![
+				TemplateField { name: "charter".into(), field_type: TemplateFieldType::Text, required: true },
+			],
+			name_template: "Amend the charter".into(),
+			content_template: "{charter}".into(),
+			action_op: Some(ActionOp::AmendCharter),
+			ballot_mode: None,
+			tally_method: None,
+			options: None,
+			tags: None,
+		}).unwrap();
+	let closed_charter_proposal = |charter: &str, ballot: Ballot| {
+		let ProposalPayload { proposal_address, .. } = create_proposal(&alice.store, ProposalParams {
+			collective_address: collective_address.clone(),
+			author_address: alice_address.clone(),
+			name: "".into(),
+			content: "".into(),
+			ballot_mode: None,
+			tally_method: None,
+			options: None,
+			tags: None,
+			requested_funds: None,
+			status: None,
+			co_author_addresses: None,
+			discussion_window: None,
+			voting_window: None,
+			federated_proposal_address: None,
+			template_address: Some(template_address.clone()),
+			template_values: Some(vec![("charter".to_string(), Value::from(charter))]
+				.into_iter()
+				.collect::<BTreeMap<String, Value>>()),
+		}).unwrap();
+		alice.cast_vote(&proposal_address, &alice_address, ballot.clone()).unwrap();
+		bob.cast_vote(&proposal_address, &bob_address, ballot).unwrap();
+		alice.close_proposal(&proposal_address).unwrap();
+		proposal_address
+	};
+	assert!(get_charter(&bob.store, collective_address.clone(), None).unwrap().charter.is_none());
+
+	alice.store.set_now(100);
+	let first_address = closed_charter_proposal("Tend the garden together", Ballot::Yes);
+	assert_error(
+		amend_charter(&bob.store, first_address.clone(), 100),
+		"UNAUTHORIZED",
+		"Only the admin can amend the charter");
+	let CharterPayload { charter, .. } = amend_charter(&alice.store, first_address.clone(), 100).unwrap();
+	let charter = charter.unwrap();
+	assert_eq!(charter.version, 1);
+	assert_eq!(charter.content, "Tend the garden together");
+	assert_eq!(charter.proposal_address, first_address);
+	assert_error(
+		amend_charter(&alice.store, first_address.clone(), 101),
+		"CONFLICT",
+		"Proposal is already enacted");
+
+	let rejected_address = closed_charter_proposal("Pave the garden", Ballot::No);
+	assert_error(
+		amend_charter(&alice.store, rejected_address, 102),
+		"CONFLICT",
+		"Proposal has not passed");
+	let ProposalPayload { proposal_address: garden_address, .. } =
+		alice.create_proposal(&collective_address, &alice_address, BallotMode::Open).unwrap();
+	alice.cast_vote(&garden_address, &alice_address, Ballot::Yes).unwrap();
+	alice.close_proposal(&garden_address).unwrap();
+	assert_error(
+		amend_charter(&alice.store, garden_address, 103),
+		"VALIDATION_FAILED",
+		"Proposal does not amend the charter of the collective");
+
+	alice.store.set_now(200);
+	let second_address = closed_charter_proposal("Tend & share the garden together", Ballot::Yes);
+	let charter = amend_charter(&alice.store, second_address, 200).unwrap().charter.unwrap();
+	assert_eq!(charter.version, 2);
+	assert_eq!(
+		get_charter(&bob.store, collective_address.clone(), None).unwrap().charter,
+		Some(charter.clone()));
+	assert_eq!(
+		get_charter(&bob.store, collective_address.clone(), Some(150)).unwrap()
+			.charter
+			.map(|charter| charter.content),
+		Some("Tend the garden together".to_string()));
+	assert!(get_charter(&bob.store, collective_address.clone(), Some(50)).unwrap().charter.is_none());
+	let versions = get_charter_history(&bob.store, collective_address.clone()).unwrap().versions;
+	assert_eq!(
+		versions.iter().map(|charter| charter.version).collect::<Vec<u64>>(),
+		vec![1, 2]);
+	assert_eq!(
+		action_ops(&alice.get_actions(&collective_address).unwrap().actions)
+			.into_iter()
+			.filter(|op| op == &ActionOp::AmendCharter)
+			.count(),
+		2);
+
+	let charter_address =
+		bob.store.get_links(&collective_address, "collective->charter", None).unwrap()[0].clone();
+	let charter_entry = |version: u64, proposal_address: &Address| Entry::App("charter".into(), Charter {
+		version,
+		content: "Tend the garden together".into(),
+		proposal_address: proposal_address.clone(),
+		amended_at: 200,
+		..charter.clone()
+	}.into());
+	assert_error(
+		alice.store.update_entry(charter_entry(3, &first_address), &charter_address),
+		"CONFLICT",
+		"Proposal is already enacted");
+	let forged_address = alice.store.commit_entry(&charter_entry(1, &first_address)).unwrap();
+	assert_error(
+		alice.store.link_entries(&collective_address, &forged_address, "collective->charter", ""),
+		"CONFLICT",
+		"Collective already has a charter");
+}
+
+#[test]
+fn sub_collectives_inherit_policies_constrain_people_and_escalate_actions() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let dave = scenario.player("dave");
+	let (collective_address, _alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let PersonPayload { person_address: dave_address, .. } = dave.create_person("Dave").unwrap();
+	set_sponsor_threshold(&alice.store, collective_address.clone(), 1).unwrap();
+	let sub_collective_params = |name: &str, inherit_policies: Option<bool>| SubCollectiveParams {
+		parent_address: collective_address.clone(),
+		collective: CreateCollectiveParams {
+			name: name.into(),
+			admin_address: None,
+			description: None,
+			purpose: None,
+			tags: None,
+			links: None,
+			avatar: None,
+		},
+		inherit_policies,
+	};
+	assert_error(
+		create_sub_collective(&bob.store, sub_collective_params("Garden Circle", None)),
+		"UNAUTHORIZED",
+		"Only the admin can create child collectives");
+	let CollectivePayload { collective_address: garden_address, collective: garden } =
+		create_sub_collective(&alice.store, sub_collective_params("Garden Circle", None)).unwrap();
+	assert_eq!(garden.parent_address, Some(collective_address.clone()));
+	assert!(garden.inherit_policies);
+	let CollectivePayload { collective_address: kitchen_address, .. } =
+		create_sub_collective(&alice.store, sub_collective_params("Kitchen Circle", Some(false))).unwrap();
+
+	assert_error(
+		alice.add_collective_person(&garden_address, &dave_address),
+		"VALIDATION_FAILED",
+		"Person must be in the parent collective");
+	assert_error(
+		alice.store.link_entries(&garden_address, &dave_address, "collective->person", "member"),
+		"VALIDATION_FAILED",
+		"Target of the collective->person link does not belong to the collective");
+	assert_error(
+		alice.store.link_entries(&garden_address, &kitchen_address, "collective->child_collective", ""),
+		"VALIDATION_FAILED",
+		"Target of the collective->child_collective link does not belong to the collective");
+	alice.add_collective_person(&garden_address, &bob_address).unwrap();
+	alice.add_collective_person(&kitchen_address, &bob_address).unwrap();
+	assert_eq!(
+		bob.create_proposal(&garden_address, &bob_address, BallotMode::Open).unwrap().proposal.status,
+		ProposalStatus::Draft);
+	assert_eq!(
+		bob.create_proposal(&kitchen_address, &bob_address, BallotMode::Open).unwrap().proposal.status,
+		ProposalStatus::Voting);
+	assert_error(
+		set_sponsor_threshold(&alice.store, garden_address.clone(), 2),
+		"CONFLICT",
+		"Collective inherits the policies of its parent");
+
+	let tree = get_collective_tree(&bob.store, collective_address.clone()).unwrap();
+	assert_eq!(
+		tree.children.iter().map(|child| child.collective_address.clone()).collect::<Vec<Address>>(),
+		vec![garden_address.clone(), kitchen_address.clone()]);
+	assert!(tree.children[0].children.is_empty());
+
+	let action_address =
+		alice.store.get_links(&garden_address, "collective->action", None).unwrap()[0].clone();
+	assert_error(
+		escalate_action(&bob.store, garden_address.clone(), action_address.clone()),
+		"UNAUTHORIZED",
+		"Only the admin can escalate actions");
+	assert_error(
+		escalate_action(&alice.store, collective_address.clone(), action_address.clone()),
+		"CONFLICT",
+		"Collective has no parent");
+	let escalated_actions =
+		escalate_action(&alice.store, garden_address.clone(), action_address.clone()).unwrap()
+			.escalated_actions;
+	assert_eq!(escalated_actions.len(), 1);
+	assert_eq!(escalated_actions[0].collective_address, garden_address);
+	assert_eq!(escalated_actions[0].action.op, ActionOp::CreateProposal);
+	assert_error(
+		escalate_action(&alice.store, garden_address.clone(), action_address),
+		"CONFLICT",
+		"Action is already escalated");
+	assert_eq!(
+		get_escalated_actions(&bob.store, collective_address.clone()).unwrap().escalated_actions,
+		escalated_actions);
+	let parent_ops = action_ops(&alice.get_actions(&collective_address).unwrap().actions);
+	assert_eq!(
+		parent_ops.iter().filter(|op| *op == &ActionOp::AddChildCollective).count(),
+		2);
+	assert_eq!(parent_ops.last(), Some(&ActionOp::EscalateAction));
+}
+
+#[test]
+fn member_collective_votes_with_the_outcome_of_its_own_decision() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let carol = scenario.player("carol");
+	let dave = scenario.player("dave");
+	let (federation_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let PersonPayload { person_address: carol_address, .. } = carol.create_person("Carol").unwrap();
+	let CollectivePayload { collective_address: coop_address, .. } =
+		carol.create_collective("Garden Co-op", Some(carol_address.clone())).unwrap();
+	let dave_address = join_collective(&carol, &dave, "Dave", &coop_address);
+	assert_error(
+		add_member_collective(&bob.store, federation_address.clone(), coop_address.clone()),
+		"UNAUTHORIZED",
+		"Only the admin can add member collectives");
+	assert_error(
+		add_member_collective(&alice.store, federation_address.clone(), coop_address.clone()),
+		"CONFLICT",
+		"Member collective has not joined the federation");
+	assert_error(
+		alice.store.link_entries(&federation_address, &coop_address, "collective->member_collective", ""),
+		"UNAUTHORIZED",
+		"Member collective has not joined the federation");
+	assert_error(
+		join_federation(&dave.store, coop_address.clone(), federation_address.clone()),
+		"UNAUTHORIZED",
+		"Only the admin can join a federation");
+	join_federation(&carol.store, coop_address.clone(), federation_address.clone()).unwrap();
+	let member_collectives =
+		add_member_collective(&alice.store, federation_address.clone(), coop_address.clone()).unwrap()
+			.member_collectives;
+	assert_eq!(member_collectives.len(), 1);
+	assert_eq!(member_collectives[0].collective_address, coop_address);
+	assert_eq!(
+		get_member_collectives(&bob.store, federation_address.clone()).unwrap().member_collectives.len(),
+		1);
+
+	let ProposalPayload { proposal_address, .. } =
+		alice.create_proposal(&federation_address, &alice_address, BallotMode::Open).unwrap();
+	let ProposalPayload { proposal_address: unrelated_decision_address, .. } =
+		carol.create_proposal(&coop_address, &carol_address, BallotMode::Open).unwrap();
+	let ProposalPayload { proposal_address: decision_address, .. } =
+		create_proposal(&carol.store, ProposalParams {
+			collective_address: coop_address.clone(),
+			author_address: carol_address.clone(),
+			name: "Federation garden vote".into(),
+			content: "Decide the vote of the co-op on the federation proposal".into(),
+			ballot_mode: None,
+			tally_method: None,
+			options: None,
+			tags: None,
+			requested_funds: None,
+			status: None,
+			co_author_addresses: None,
+			discussion_window: None,
+			voting_window: None,
+			federated_proposal_address: Some(proposal_address.clone()),
+			template_address: None,
+			template_values: None,
+		}).unwrap();
+	let collective_vote_params = |decision_address: &Address| CollectiveVoteParams {
+		proposal_address: proposal_address.clone(),
+		member_collective_address: coop_address.clone(),
+		decision_address: decision_address.clone(),
+	};
+	assert_error(
+		cast_collective_vote(&carol.store, collective_vote_params(&decision_address)),
+		"CONFLICT",
+		"Decision of the member collective is not closed");
+	carol.cast_vote(&decision_address, &carol_address, Ballot::Yes).unwrap();
+	dave.cast_vote(&decision_address, &dave_address, Ballot::Yes).unwrap();
+	carol.close_proposal(&decision_address).unwrap();
+	carol.cast_vote(&unrelated_decision_address, &carol_address, Ballot::Yes).unwrap();
+	carol.close_proposal(&unrelated_decision_address).unwrap();
+	assert_error(
+		cast_collective_vote(&carol.store, collective_vote_params(&unrelated_decision_address)),
+		"VALIDATION_FAILED",
+		"Decision is not on the proposal of the federation");
+	assert_error(
+		cast_collective_vote(&dave.store, collective_vote_params(&decision_address)),
+		"UNAUTHORIZED",
+		"Vote of a member collective must be cast by its admin");
+	let VotePayload { vote, .. } =
+		cast_collective_vote(&carol.store, collective_vote_params(&decision_address)).unwrap();
+	assert_eq!(vote.person_address, coop_address);
+	assert_eq!(vote.ballot, Ballot::Yes);
+
+	alice.cast_vote(&proposal_address, &alice_address, Ballot::No).unwrap();
+	bob.cast_vote(&proposal_address, &bob_address, Ballot::Yes).unwrap();
+	assert_eq!(
+		alice.get_proposal_tally(&proposal_address).unwrap().tally,
+		ProposalTally { yes: 2, no: 1, abstain: 0, ..ProposalTally::default() });
+	assert_eq!(
+		alice.close_proposal(&proposal_address).unwrap().tally,
+		ProposalTally { yes: 2, no: 1, abstain: 0, ..ProposalTally::default() });
+	assert_eq!(
+		get_proposal(&alice.store, proposal_address.clone()).unwrap().proposal.outcome,
+		Some(ProposalOutcome::Passed));
+}
+
+#[test]
+fn elected_role_is_held_for_a_term_within_the_term_limit() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let carol = scenario.player("carol");
+	let dave = scenario.player("dave");
+	let (collective_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let carol_address = join_collective(&alice, &carol, "Carol", &collective_address);
+	let PersonPayload { person_address: dave_address, .. } = dave.create_person("Dave").unwrap();
+	let role_params = RoleParams {
+		collective_address: collective_address.clone(),
+		name: "Treasurer".into(),
+		description: None,
+		term_length: 100,
+		term_limit: Some(1),
+	};
+	assert_error(
+		create_role(&bob.store, role_params.clone()),
+		"UNAUTHORIZED",
+		"Only the admin can create roles");
+	let role_address = create_role(&alice.store, role_params).unwrap().role_address;
+
+	alice.store.set_now(1000);
+	let election_params = |timestamp: u64| ElectionParams {
+		role_address: role_address.clone(),
+		person_address: bob_address.clone(),
+		timestamp,
+	};
+	let election_address = start_election(&bob.store, election_params(1000)).unwrap().election_address;
+	assert_error(
+		start_election(&bob.store, election_params(1000)),
+		"CONFLICT",
+		"Election is already in progress");
+	let nomination_params = |nominator_address: &Address, candidate_address: &Address| NominationParams {
+		election_address: election_address.clone(),
+		nominator_address: nominator_address.clone(),
+		candidate_address: candidate_address.clone(),
+	};
+	nominate_candidate(&bob.store, nomination_params(&bob_address, &bob_address)).unwrap();
+	assert_error(
+		nominate_candidate(&carol.store, nomination_params(&carol_address, &dave_address)),
+		"VALIDATION_FAILED",
+		"Candidates must be people in the collective");
+	assert_error(
+		nominate_candidate(&carol.store, nomination_params(&carol_address, &bob_address)),
+		"CONFLICT",
+		"Person is already a candidate");
+	nominate_candidate(&carol.store, nomination_params(&carol_address, &carol_address)).unwrap();
+	let election = open_election_voting(&alice.store, election_address.clone(), alice_address.clone())
+		.unwrap()
+		.election;
+	assert_eq!(election.candidate_addresses, vec![bob_address.clone(), carol_address.clone()]);
+	let proposal_address = election.proposal_address.unwrap();
+	let proposal = get_proposal(&bob.store, proposal_address.clone()).unwrap().proposal;
+	assert_eq!(proposal.options, vec!["Bob".to_string(), "Carol".to_string()]);
+	assert_eq!(proposal.tally_method, TallyMethod::RankedChoice);
+	assert_error(
+		nominate_candidate(&alice.store, nomination_params(&alice_address, &alice_address)),
+		"CONFLICT",
+		"Nominations are closed");
+
+	alice.cast_vote(&proposal_address, &alice_address, Ballot::Rank(vec![0, 1])).unwrap();
+	bob.cast_vote(&proposal_address, &bob_address, Ballot::Rank(vec![0, 1])).unwrap();
+	carol.cast_vote(&proposal_address, &carol_address, Ballot::Rank(vec![1, 0])).unwrap();
+	let holder = close_election(&alice.store, election_address, 1000).unwrap().holder.unwrap();
+	assert_eq!(holder.person_address, bob_address);
+	assert_eq!((holder.term, holder.ends_at), (1, 1100));
+	assert_eq!(
+		get_role(&carol.store, role_address.clone(), 1050).unwrap().holder.map(|holder| holder.person_address),
+		Some(bob_address.clone()));
+	assert_error(
+		start_election(&bob.store, election_params(1050)),
+		"CONFLICT",
+		"Role term has not expired");
+
+	alice.store.set_now(1100);
+	let elections =
+		start_expired_elections(&carol.store, collective_address.clone(), carol_address.clone(), 1100)
+			.unwrap()
+			.elections;
+	assert_eq!(elections.len(), 1);
+	assert_eq!(elections[0].election.term, 2);
+	let role = get_role(&carol.store, role_address, 1100).unwrap();
+	assert!(role.holder.is_none());
+	assert_eq!(role.election_address, Some(elections[0].election_address.clone()));
+	assert_error(
+		nominate_candidate(&bob.store, NominationParams {
+			election_address: elections[0].election_address.clone(),
+			nominator_address: bob_address.clone(),
+			candidate_address: bob_address.clone(),
+		}),
+		"VALIDATION_FAILED",
+		"Person has reached the term limit of the role");
+	assert!(start_expired_elections(&carol.store, collective_address.clone(), carol_address.clone(), 1101)
+		.unwrap()
+		.elections
+		.is_empty());
+
+	let secretary_address = create_role(&alice.store, RoleParams {
+		collective_address,
+		name: "Secretary".into(),
+		description: None,
+		term_length: 100,
+		term_limit: None,
+	}).unwrap().role_address;
+	assert_error(
+		bob.store.link_entries(&secretary_address, &elections[0].election_address, "role->election", ""),
+		"VALIDATION_FAILED",
+		"Election is not of the role");
+	let term_address =
+		bob.store.entry_address(&Entry::App("role_term".into(), (&holder).into())).unwrap();
+	assert_error(
+		bob.store.link_entries(&secretary_address, &term_address, "role->role_term", ""),
+		"VALIDATION_FAILED",
+		"Term is not of the role");
+	let secretary_params = |timestamp: u64| ElectionParams {
+		role_address: secretary_address.clone(),
+		person_address: carol_address.clone(),
+		timestamp,
+	};
+	let election_address = start_election(&carol.store, secretary_params(1100)).unwrap().election_address;
+	assert_error(
+		open_election_voting(&alice.store, election_address.clone(), alice_address.clone()),
+		"VALIDATION_FAILED",
+		"Election needs at least two candidates");
+	let role = close_election(&alice.store, election_address.clone(), 1110).unwrap();
+	assert!(role.holder.is_none());
+	assert!(role.election_address.is_none());
+	assert_error(
+		nominate_candidate(&carol.store, NominationParams {
+			election_address,
+			nominator_address: carol_address.clone(),
+			candidate_address: carol_address.clone(),
+		}),
+		"CONFLICT",
+		"Nominations are closed");
+	assert_error(
+		start_election(&carol.store, secretary_params(1100)),
+		"VALIDATION_FAILED",
+		"Election must start after the previous election");
+	let election_address = start_election(&carol.store, secretary_params(1120)).unwrap().election_address;
+	assert_eq!(get_role(&bob.store, secretary_address.clone(), 1120).unwrap().election_address,
+		Some(election_address.clone()));
+	nominate_candidate(&carol.store, NominationParams {
+		election_address: election_address.clone(),
+		nominator_address: carol_address.clone(),
+		candidate_address: carol_address.clone(),
+	}).unwrap();
+	let holder = close_election(&bob.store, election_address, 1130).unwrap().holder.unwrap();
+	assert_eq!(holder.person_address, carol_address);
+	assert_eq!((holder.term, holder.ends_at), (1, 1230));
+}
+
+#[test]
+fn sortition_draws_its_pool_verifiably_from_secrets_committed_by_its_people() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let carol = scenario.player("carol");
+	let dave = scenario.player("dave");
+	let erin = scenario.player("erin");
+	let frank = scenario.player("frank");
+	let (collective_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let carol_address = join_collective(&alice, &carol, "Carol", &collective_address);
+	let frank_address = join_collective(&alice, &frank, "Frank", &collective_address);
+	let PersonPayload { person_address: erin_address, .. } = create_person(&erin.store, PersonParams {
+		name: "Erin".into(),
+		agent_address: erin.agent_address(),
+		status: PersonStatus::Inactive,
+	}).unwrap();
+	alice.add_collective_person(&collective_address, &erin_address).unwrap();
+	alice.store.set_now(1000);
+	let sortition_params = |name: &str, announced_at: u64| SortitionParams {
+		collective_address: collective_address.clone(),
+		name: name.into(),
+		size: 2,
+		commit_until: announced_at + 100,
+		reveal_until: announced_at + 200,
+		timestamp: announced_at,
+	};
+	assert_error(
+		announce_sortition(&bob.store, sortition_params("Citizens' Panel", 1000)),
+		"UNAUTHORIZED",
+		"Only the admin can announce sortitions");
+	let SortitionPayload { sortition_address, sortition, .. } =
+		announce_sortition(&alice.store, sortition_params("Citizens' Panel", 1000)).unwrap();
+	let mut pool = vec![alice_address.clone(), bob_address.clone(), carol_address.clone(), frank_address];
+	pool.sort();
+	assert_eq!(sortition.pool, pool);
+	let dave_address = join_collective(&alice, &dave, "Dave", &collective_address);
+
+	let seed_params = |person_address: &Address, secret: &str, timestamp: u64| SortitionSeedParams {
+		sortition_address: sortition_address.clone(),
+		person_address: person_address.clone(),
+		secret: secret.into(),
+		timestamp,
+	};
+	assert_error(
+		commit_sortition_seed(&dave.store, seed_params(&dave_address, "late", 1000)),
+		"UNAUTHORIZED",
+		"Only people of the pool seed the draw");
+	let alice_seed = commit_sortition_seed(&alice.store, seed_params(&alice_address, "acorn", 1000)).unwrap();
+	assert_eq!(alice_seed.seed.secret, None);
+	commit_sortition_seed(&bob.store, seed_params(&bob_address, "birch", 1000)).unwrap();
+	commit_sortition_seed(&carol.store, seed_params(&carol_address, "cedar", 1000)).unwrap();
+	assert_error(
+		commit_sortition_seed(&bob.store, seed_params(&bob_address, "beech", 1000)),
+		"CONFLICT",
+		"Seed is already committed");
+	assert_error(
+		reveal_sortition_seed(&alice.store, seed_params(&alice_address, "acorn", 1000)),
+		"CONFLICT",
+		"Seeds are revealed from 1100 until 1200");
+
+	alice.store.set_now(1100);
+	assert_error(
+		reveal_sortition_seed(&alice.store, seed_params(&alice_address, "oak", 1100)),
+		"VALIDATION_FAILED",
+		"Secret does not match the commitment");
+	assert_error(
+		carol.store.update_entry(
+			Entry::App("sortition_seed".into(), SortitionSeed {
+				secret: Some("acorn".into()),
+				revealed_at: Some(1100),
+				..alice_seed.seed.clone()
+			}.into()),
+			&alice_seed.seed_address,
+		),
+		"UNAUTHORIZED",
+		"Seed must be committed & revealed by the person");
+	reveal_sortition_seed(&alice.store, seed_params(&alice_address, "acorn", 1100)).unwrap();
+	reveal_sortition_seed(&bob.store, seed_params(&bob_address, "birch", 1100)).unwrap();
+	assert_error(
+		draw_sortition(&bob.store, sortition_address.clone(), 1100),
+		"CONFLICT",
+		"Seeds are revealed until 1200");
+	reveal_sortition_seed(&carol.store, seed_params(&carol_address, "cedar", 1100)).unwrap();
+
+	alice.store.set_now(1200);
+	let draw = draw_sortition(&bob.store, sortition_address.clone(), 1200).unwrap().draw.unwrap();
+	assert_eq!(draw.pool, pool);
+	assert_eq!(draw.seed_addresses.len(), 3);
+	assert_eq!(draw.selected_addresses.len(), 2);
+	assert!(draw.selected_addresses.iter().all(|person_address| pool.contains(person_address)));
+	assert_ne!(draw.selected_addresses[0], draw.selected_addresses[1]);
+	assert_error(
+		draw_sortition(&alice.store, sortition_address.clone(), 1200),
+		"CONFLICT",
+		"Sortition is already drawn");
+	assert_eq!(get_sortition(&carol.store, sortition_address.clone()).unwrap().draw, Some(draw.clone()));
+	let verification = verify_sortition(&carol.store, sortition_address).unwrap();
+	assert!(verification.verified);
+	assert_eq!(verification.draw, draw);
+	assert_eq!(
+		action_ops(&alice.get_actions(&collective_address).unwrap().actions).last(),
+		Some(&ActionOp::DrawSortition));
+
+	let draw_entry = |draw: SortitionDraw| Entry::App("sortition_draw".into(), draw.into());
+	assert_error(
+		alice.store.commit_entry(&draw_entry(SortitionDraw {
+			pool: pool[..2].to_vec(),
+			selected_addresses: pool[..2].to_vec(),
+			..draw.clone()
+		})),
+		"CONFLICT",
+		"Sortition is already drawn");
+
+	let jury_address =
+		announce_sortition(&alice.store, sortition_params("Jury", 1200)).unwrap().sortition_address;
+	let jury_seed_params = |person_address: &Address, secret: &str, timestamp: u64| SortitionSeedParams {
+		sortition_address: jury_address.clone(),
+		..seed_params(person_address, secret, timestamp)
+	};
+	for (player, person_address) in &[(&alice, &alice_address), (&bob, &bob_address), (&dave, &dave_address)] {
+		commit_sortition_seed(&player.store, jury_seed_params(person_address, "pine", 1200)).unwrap();
+	}
+	alice.store.set_now(1300);
+	for (player, person_address) in &[(&alice, &alice_address), (&bob, &bob_address)] {
+		reveal_sortition_seed(&player.store, jury_seed_params(person_address, "pine", 1300)).unwrap();
+	}
+	alice.store.set_now(1400);
+	assert_error(
+		reveal_sortition_seed(&dave.store, jury_seed_params(&dave_address, "pine", 1400)),
+		"CONFLICT",
+		"Seeds are revealed from 1300 until 1400");
+	assert_error(
+		draw_sortition(&alice.store, jury_address.clone(), 1400),
+		"CONFLICT",
+		"Sortition needs 1 more revealed seeds to be drawn");
+	assert_error(
+		alice.store.commit_entry(&draw_entry(SortitionDraw {
+			sortition_address: jury_address.clone(),
+			drawn_at: 1400,
+			..draw
+		})),
+		"CONFLICT",
+		"Sortition needs 1 more revealed seeds to be drawn");
+	assert_error(
+		verify_sortition(&carol.store, jury_address),
+		"CONFLICT",
+		"Sortition is not drawn");
+}
+
+#[test]
+fn critical_action_cools_off_before_execution_and_can_be_vetoed() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let carol = scenario.player("carol");
+	let (collective_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let carol_address = join_collective(&alice, &carol, "Carol", &collective_address);
+	let role_address = create_role(&alice.store, RoleParams {
+		collective_address: collective_address.clone(),
+		name: "Guardian".into(),
+		description: None,
+		term_length: 10000,
+		term_limit: None,
+	}).unwrap().role_address;
+	alice.store.set_now(1000);
+	let election_address = start_election(&carol.store, ElectionParams {
+		role_address: role_address.clone(),
+		person_address: carol_address.clone(),
+		timestamp: 1000,
+	}).unwrap().election_address;
+	nominate_candidate(&carol.store, NominationParams {
+		election_address: election_address.clone(),
+		nominator_address: carol_address.clone(),
+		candidate_address: carol_address.clone(),
+	}).unwrap();
+	let proposal_address = open_election_voting(&alice.store, election_address.clone(), alice_address.clone())
+		.unwrap()
+		.election
+		.proposal_address
+		.unwrap();
+	carol.cast_vote(&proposal_address, &carol_address, Ballot::Rank(vec![0])).unwrap();
+	close_election(&alice.store, election_address, 1000).unwrap();
+
+	let ProposalTemplatePayload { template_address: policy_template_address, .. } =
+		create_proposal_template(&alice.store, ProposalTemplateParams {
+			collective_address: collective_address.clone(),
+			name: "Set the critical action policy".into(),
+			description: None,
+			fields: vec![
+				TemplateField {
+					name: "critical_action_ops".into(),
+					field_type: TemplateFieldType::List,
+					required: true,
+				},
+				TemplateField {
+					name: "cooling_off_period".into(),
+					field_type: TemplateFieldType::Number,
+					required: true,
+				},
+				TemplateField {
+					name: "veto_role_addresses".into(),
+					field_type: TemplateFieldType::List,
+					required: true,
+				},
+			],
+			name_template: "Cool off for {cooling_off_period} seconds".into(),
+			content_template: "{critical_action_ops}".into(),
+			action_op: Some(ActionOp::SetCriticalActionPolicy),
+			ballot_mode: None,
+			tally_method: None,
+			options: None,
+			tags: None,
+		}).unwrap();
+	let policy_proposal = |cooling_off_period: u64, veto_role_addresses: Vec<String>| {
+		let ProposalPayload { proposal_address, .. } = create_proposal(&alice.store, ProposalParams {
+			collective_address: collective_address.clone(),
+			author_address: alice_address.clone(),
+			name: "".into(),
+			content: "".into(),
+			ballot_mode: None,
+			tally_method: None,
+			options: None,
+			tags: None,
+			requested_funds: None,
+			status: None,
+			co_author_addresses: None,
+			discussion_window: None,
+			voting_window: None,
+			federated_proposal_address: None,
+			template_address: Some(policy_template_address.clone()),
+			template_values: Some(vec![
+				("critical_action_ops".to_string(), Value::from(vec!["AmendCharter", "FundProposal"])),
+				("cooling_off_period".to_string(), Value::from(cooling_off_period)),
+				("veto_role_addresses".to_string(), Value::from(veto_role_addresses)),
+			]
+				.into_iter()
+				.collect::<BTreeMap<String, Value>>()),
+		}).unwrap();
+		proposal_address
+	};
+	let pass = |proposal_address: &Address| {
+		alice.cast_vote(proposal_address, &alice_address, Ballot::Yes).unwrap();
+		bob.cast_vote(proposal_address, &bob_address, Ballot::Yes).unwrap();
+		alice.close_proposal(proposal_address).unwrap();
+	};
+	let first_policy_address = policy_proposal(100, vec![role_address.to_string()]);
+	assert_error(
+		set_critical_action_policy(&alice.store, first_policy_address.clone()),
+		"CONFLICT",
+		"Proposal has not passed");
+	pass(&first_policy_address);
+	assert_error(
+		set_critical_action_policy(&bob.store, first_policy_address.clone()),
+		"UNAUTHORIZED",
+		"Only the admin can set the critical action policy");
+	let collective = set_critical_action_policy(&alice.store, first_policy_address.clone()).unwrap().collective;
+	assert_eq!(collective.critical_action_ops, vec![ActionOp::AmendCharter, ActionOp::FundProposal]);
+	assert_eq!(collective.cooling_off_period, 100);
+	assert_eq!(collective.policy_proposal_address, Some(first_policy_address.clone()));
+	assert_error(
+		set_critical_action_policy(&alice.store, first_policy_address.clone()),
+		"CONFLICT",
+		"Policy is already set by the proposal");
+	assert_error(
+		alice.store.update_entry(
+			Entry::App("collective".into(), Collective { cooling_off_period: 0, ..collective.clone() }.into()),
+			&collective_address,
+		),
+		"VALIDATION_FAILED",
+		"Policy must be the policy of the proposal");
+	let ProposalTemplatePayload { template_address, .. } =
+		create_proposal_template(&alice.store, ProposalTemplateParams {
+			collective_address: collective_address.clone(),
+			name: "Amend the charter".into(),
+			description: None,
+			fields: vec![
+				TemplateField { name: "charter".into(), field_type: TemplateFieldType::Text, required: true },
+			],
+			name_template: "Amend the charter".into(),
+			content_template: "{charter}".into(),
+			action_op: Some(ActionOp::AmendCharter),
+			ballot_mode: None,
+			tally_method: None,
+			options: None,
+			tags: None,
+		}).unwrap();
+	let passed_charter_proposal = |charter: &str| {
+		let ProposalPayload { proposal_address, .. } = create_proposal(&alice.store, ProposalParams {
+			collective_address: collective_address.clone(),
+			author_address: alice_address.clone(),
+			name: "".into(),
+			content: "".into(),
+			ballot_mode: None,
+			tally_method: None,
+			options: None,
+			tags: None,
+			requested_funds: None,
+			status: None,
+			co_author_addresses: None,
+			discussion_window: None,
+			voting_window: None,
+			federated_proposal_address: None,
+			template_address: Some(template_address.clone()),
+			template_values: Some(vec![("charter".to_string(), Value::from(charter))]
+				.into_iter()
+				.collect::<BTreeMap<String, Value>>()),
+		}).unwrap();
+		alice.cast_vote(&proposal_address, &alice_address, Ballot::Yes).unwrap();
+		bob.cast_vote(&proposal_address, &bob_address, Ballot::Yes).unwrap();
+		alice.close_proposal(&proposal_address).unwrap();
+		proposal_address
+	};
+
+	alice.store.set_now(2000);
+	let first_address = passed_charter_proposal("Tend the garden together");
+	assert_error(
+		amend_charter(&alice.store, first_address.clone(), 2000),
+		"CONFLICT",
+		"Critical action is not executed");
+	let enacted = enact_proposal(&bob.store, first_address.clone(), 2000).unwrap();
+	assert_eq!(enacted.action.status, ActionStatus::Open);
+	let action_entry = |status: ActionStatus| Entry::App("action".into(), Action {
+		status,
+		..enacted.action.clone()
+	}.into());
+	assert_error(
+		bob.store.update_entry(action_entry(ActionStatus::Executed), &enacted.action_address),
+		"CONFLICT",
+		"Critical action is not executed");
+	assert_error(
+		bob.store.update_entry(action_entry(ActionStatus::Vetoed), &enacted.action_address),
+		"UNAUTHORIZED",
+		"Action must be vetoed by its critical action");
+	let critical_action_address = enacted.critical_action_address.unwrap();
+	let critical_action = enacted.critical_action.unwrap();
+	assert_eq!(critical_action.executable_at, 2100);
+	assert_eq!(critical_action.policy.veto_role_addresses, vec![role_address.clone()]);
+	assert_error(
+		enact_proposal(&bob.store, first_address.clone(), 2000),
+		"CONFLICT",
+		"Proposal is already enacted");
+	assert_error(
+		execute_critical_action(&bob.store, critical_action_address.clone(), 2050),
+		"CONFLICT",
+		"Action is cooling off until 2100");
+	alice.store.set_now(2100);
+	let executed = execute_critical_action(&bob.store, critical_action_address.clone(), 2100).unwrap();
+	assert_eq!(executed.action.status, ActionStatus::Executed);
+	assert_error(
+		execute_critical_action(&bob.store, critical_action_address, 2100),
+		"CONFLICT",
+		"Action is already executed");
+	assert_eq!(
+		amend_charter(&alice.store, first_address, 2100).unwrap().charter.unwrap().content,
+		"Tend the garden together");
+
+	alice.store.set_now(2200);
+	let second_address = passed_charter_proposal("Pave the garden");
+	assert_error(
+		bob.store.link_entries(&second_address, &enacted.action_address, "proposal->action", ""),
+		"VALIDATION_FAILED",
+		"Action is not enacted from the proposal");
+	let critical_action_address =
+		enact_proposal(&alice.store, second_address.clone(), 2200).unwrap().critical_action_address.unwrap();
+	let veto_params = |person_address: &Address| VetoParams {
+		critical_action_address: critical_action_address.clone(),
+		person_address: person_address.clone(),
+		role_address: role_address.clone(),
+		reason: Some("The garden feeds us".into()),
+		timestamp: 2200,
+	};
+	assert_error(
+		veto_critical_action(&bob.store, veto_params(&bob_address)),
+		"UNAUTHORIZED",
+		"Only the holder of the role can veto");
+	let second_policy_address = policy_proposal(50, vec![]);
+	pass(&second_policy_address);
+	let collective = set_critical_action_policy(&alice.store, second_policy_address).unwrap().collective;
+	assert!(collective.veto_role_addresses.is_empty());
+	assert_error(
+		alice.store.update_entry(
+			Entry::App("collective".into(), Collective {
+				cooling_off_period: 100,
+				veto_role_addresses: vec![role_address.clone()],
+				policy_proposal_address: Some(first_policy_address),
+				..collective
+			}.into()),
+			&collective_address,
+		),
+		"CONFLICT",
+		"Policy is already set by the proposal");
+	let vetoed = veto_critical_action(&carol.store, veto_params(&carol_address)).unwrap();
+	assert_eq!(vetoed.action.status, ActionStatus::Vetoed);
+	assert_eq!(vetoed.critical_action.veto.unwrap().person_address, carol_address);
+	assert_error(
+		veto_critical_action(&carol.store, veto_params(&carol_address)),
+		"CONFLICT",
+		"Action is already vetoed");
+	alice.store.set_now(2300);
+	assert_error(
+		execute_critical_action(&bob.store, critical_action_address.clone(), 2300),
+		"CONFLICT",
+		"Action is vetoed");
+	assert_error(
+		amend_charter(&alice.store, second_address, 2300),
+		"CONFLICT",
+		"Critical action is vetoed");
+	assert_eq!(
+		get_critical_actions(&carol.store, collective_address.clone()).unwrap().critical_actions.len(),
+		2);
+	assert_eq!(
+		action_ops(&alice.get_actions(&collective_address).unwrap().actions).last(),
+		Some(&ActionOp::VetoAction));
+
+	alice.deposit_ledger_funds(&collective_address, 1000).unwrap();
+	let ProposalPayload { proposal_address: funding_address, .. } =
+		alice.create_conviction_proposal(&collective_address, &alice_address, 100).unwrap();
+	let start = 1_600_000_000;
+	let hour = 3600;
+	alice.stake_conviction(&funding_address, &alice_address, 100, start).unwrap();
+	bob.stake_conviction(&funding_address, &bob_address, 100, start).unwrap();
+	carol.stake_conviction(&funding_address, &carol_address, 100, start).unwrap();
+	assert_error(
+		bob.fund_conviction_proposal(&funding_address, start + 40 * hour),
+		"CONFLICT",
+		"Critical action is not executed");
+	let funding_action = enact_proposal(&bob.store, funding_address.clone(), start + 40 * hour).unwrap();
+	assert_eq!(funding_action.action.op, ActionOp::FundProposal);
+	let critical_action = funding_action.critical_action.unwrap();
+	assert_eq!(critical_action.executable_at, start + 40 * hour + 50);
+	assert_error(
+		bob.fund_conviction_proposal(&funding_address, start + 40 * hour),
+		"CONFLICT",
+		"Critical action is not executed");
+	alice.store.set_now(start + 40 * hour + 50);
+	execute_critical_action(&bob.store, funding_action.critical_action_address.unwrap(), start + 40 * hour + 50)
+		.unwrap();
+	bob.fund_conviction_proposal(&funding_address, start + 40 * hour + 50).unwrap();
+	assert_eq!(alice.get_ledger(&collective_address).unwrap().ledger.funds, 900);
+}
+
+#[test]
+fn dissolved_collective_settles_its_ledger_and_is_archived() {
+	let scenario = Scenario::new();
+	let alice = scenario.player("alice");
+	let bob = scenario.player("bob");
+	let carol = scenario.player("carol");
+	let dave = scenario.player("dave");
+	let (collective_address, alice_address, bob_address) =
+		create_collective_with_member(&alice, &bob);
+	let carol_address = join_collective(&alice, &carol, "Carol", &collective_address);
+	let CollectivePayload { collective_address: garden_address, .. } =
+		create_sub_collective(&alice.store, SubCollectiveParams {
+			parent_address: collective_address.clone(),
+			collective: CreateCollectiveParams {
+				name: "Garden Circle".into(),
+				admin_address: None,
+				description: None,
+				purpose: None,
+				tags: None,
+				links: None,
+				avatar: None,
+			},
+			inherit_policies: None,
+		}).unwrap();
+	alice.add_collective_person(&garden_address, &alice_address).unwrap();
+	alice.add_collective_person(&garden_address, &bob_address).unwrap();
+	alice.deposit_ledger_funds(&collective_address, 100).unwrap();
+	alice.deposit_ledger_funds(&garden_address, 10).unwrap();
+	let closed_dissolution_proposal = |collective_address: &Address, reason: &str, ballot: Ballot| {
+		let ProposalTemplatePayload { template_address, .. } =
+			create_proposal_template(&alice.store, ProposalTemplateParams {
+				collective_address: collective_address.clone(),
+				name: "Dissolve the collective".into(),
+				description: None,
+				fields: vec![
+					TemplateField { name: "reason".into(), field_type: TemplateFieldType::Text, required: true },
+				],
+				name_template: "Dissolve the collective".into(),
+				content_template: "{reason}".into(),
+				action_op: Some(ActionOp::DissolveCollective),
+				ballot_mode: None,
+				tally_method: None,
+				options: None,
+				tags: None,
+			}).unwrap();
+		let ProposalPayload { proposal_address, .. } = create_proposal(&alice.store, ProposalParams {
+			collective_address: collective_address.clone(),
+			author_address: alice_address.clone(),
+			name: "".into(),
+			content: "".into(),
+			ballot_mode: None,
+			tally_method: None,
+			options: None,
+			tags: None,
+			requested_funds: None,
+			status: None,
+			co_author_addresses: None,
+			discussion_window: None,
+			voting_window: None,
+			federated_proposal_address: None,
+			template_address: Some(template_address),
+			template_values: Some(vec![("reason".to_string(), Value::from(reason))]
+				.into_iter()
+				.collect::<BTreeMap<String, Value>>()),
+		}).unwrap();
+		alice.cast_vote(&proposal_address, &alice_address, ballot.clone()).unwrap();
+		bob.cast_vote(&proposal_address, &bob_address, ballot).unwrap();
+		alice.close_proposal(&proposal_address).unwrap();
+		proposal_address
+	};
+
+	alice.store.set_now(1000);
+	let garden_proposal_address =
+		closed_dissolution_proposal(&garden_address, "The garden is harvested", Ballot::Yes);
+	let dissolution = dissolve_collective(&alice.store, garden_proposal_address.clone(), 1000).unwrap()
+		.dissolution
+		.unwrap();
+	assert_eq!(dissolution.settlements.len(), 1);
+	assert_eq!(dissolution.settlements[0].recipient_address, collective_address);
+	assert_eq!(dissolution.settlements[0].amount, 10);
+	assert_eq!(alice.get_ledger(&garden_address).unwrap().ledger.funds, 0);
+	assert_eq!(alice.get_ledger(&collective_address).unwrap().ledger.funds, 110);
+	assert_eq!(
+		get_dissolution(&bob.store, garden_address.clone()).unwrap().dissolution,
+		Some(dissolution));
+	let LedgerPayload { ledger_address, ledger } = alice.get_ledger(&collective_address).unwrap();
+	let settlement_entry = Entry::App("ledger".into(), Ledger {
+		funds: 120,
+		proposal_address: Some(garden_proposal_address),
+		..ledger
+	}.into());
+	assert_error(
+		bob.store.update_entry(settlement_entry.clone(), &ledger_address),
+		"UNAUTHORIZED",
+		"Only the admin of the dissolved collective settles its funds");
+	assert_error(
+		alice.store.update_entry(settlement_entry, &ledger_address),
+		"CONFLICT",
+		"Collective is already settled");
+
+	let rejected_address = closed_dissolution_proposal(&collective_address, "Stay together", Ballot::No);
+	assert_error(
+		dissolve_collective(&alice.store, rejected_address, 1000),
+		"CONFLICT",
+		"Proposal has not passed");
+	let proposal_address = closed_dissolution_proposal(&collective_address, "Our work is done", Ballot::Yes);
+	assert_error(
+		dissolve_collective(&bob.store, proposal_address.clone(), 1000),
+		"UNAUTHORIZED",
+		"Only the admin can dissolve the collective");
+	let DissolutionPayload { collective, dissolution, .. } =
+		dissolve_collective(&alice.store, proposal_address.clone(), 1000).unwrap();
+	assert_eq!(collective.archived_at, Some(1000));
+	let dissolution = dissolution.unwrap();
+	let mut recipient_addresses = dissolution.settlements.iter()
+		.map(|settlement| settlement.recipient_address.clone())
+		.collect::<Vec<Address>>();
+	recipient_addresses.sort();
+	let mut people_addresses = vec![alice_address.clone(), bob_address.clone(), carol_address];
+	people_addresses.sort();
+	assert_eq!(recipient_addresses, people_addresses);
+	assert_eq!(
+		dissolution.settlements.iter().map(|settlement| settlement.amount).collect::<Vec<u64>>(),
+		vec![37, 37, 36]);
+	assert_eq!(alice.get_ledger(&collective_address).unwrap().ledger.funds, 0);
+
+	assert_error(
+		dissolve_collective(&alice.store, proposal_address, 1000),
+		"CONFLICT",
+		"Collective is already dissolved");
+	assert_error(
+		alice.create_proposal(&collective_address, &alice_address, BallotMode::Open),
+		"CONFLICT",
+		"Collective is archived");
+	assert_error(
+		alice.deposit_ledger_funds(&collective_address, 5),
+		"CONFLICT",
+		"Collective is archived");
+	assert_error(
+		alice.set_collective_name(&collective_address, "Flower of Death Collective"),
+		"CONFLICT",
+		"Collective is archived");
+	let PersonPayload { person_address: dave_address, .. } = dave.create_person("Dave").unwrap();
+	assert_error(
+		alice.add_collective_person(&collective_address, &dave_address),
+		"CONFLICT",
+		"Collective is archived");
+	assert_eq!(
+		get_dissolution(&carol.store, collective_address.clone()).unwrap().dissolution,
+		Some(dissolution));
+	assert_eq!(
+		action_ops(&carol.get_actions(&collective_address).unwrap().actions).last(),
+		Some(&ActionOp::DissolveCollective));
+	assert_eq!(carol.get_collective_people(&collective_address).unwrap().collective_people.len(), 3);
+}
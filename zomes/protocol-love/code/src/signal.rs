@@ -0,0 +1,130 @@
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use std::convert::TryFrom;
+use crate::action::Action;
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::message::Message;
+use crate::person::Person;
+use crate::proposal::Proposal;
+use crate::store::{Store, get_links_and_load_type};
+use crate::utils::t;
+
+/// A signal emitted to the UI when the state of a [Collective](struct.Collective.html) changes,
+/// so the UI can subscribe instead of polling.
+///
+/// The signal reaches the UI of every person of the collective, through the `receive` callback of
+/// their zome.
+///
+/// Emitted with the [name](enum.ProtocolLoveSignal.html#method.name) of the signal
+/// & the signal as the payload, tagged by `type`.
+///
+/// # Examples
+///
+/// ```
+/// {
+/// 	"type": "action_committed",
+/// 	"collective_address": "Qm...",
+/// 	"action_address": "Qm...",
+/// 	"action": {"op": "SetCollectiveName", "status": "Executed", "data": "{\"name\":\"Renamed\"}", ...}
+/// }
+/// ```
+///
+/// The schema of the `action.data` for each op is documented in [ActionOp](enum.ActionOp.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProtocolLoveSignal {
+	/// An [Action](struct.Action.html) is committed to the collective.
+	ActionCommitted {
+		collective_address: Address,
+		action_address: Address,
+		action: Action,
+	},
+	/// A [Person](struct.Person.html) joined the collective.
+	PersonJoined {
+		collective_address: Address,
+		person_address: Address,
+	},
+	/// A [Proposal](struct.Proposal.html) is opened.
+	ProposalOpened {
+		proposal_address: Address,
+		proposal: Proposal,
+	},
+	/// A [Vote](struct.Vote.html) or [VoteCommitment](struct.VoteCommitment.html) is cast.
+	///
+	/// The ballot is not included, so secret ballots stay hidden.
+	VoteCast {
+		collective_address: Address,
+		proposal_address: Address,
+		person_address: Address,
+	},
+	/// A [Message](struct.Message.html) is posted in the thread of a proposal or collective.
+	MessagePosted {
+		collective_address: Address,
+		thread_address: Address,
+		message_address: Address,
+		message: Message,
+	},
+}
+
+impl ProtocolLoveSignal {
+	/// Name of the signal, matching the `type` of the payload.
+	pub fn name(&self) -> &'static str {
+		match self {
+			ProtocolLoveSignal::ActionCommitted { .. } => "action_committed",
+			ProtocolLoveSignal::PersonJoined { .. } => "person_joined",
+			ProtocolLoveSignal::ProposalOpened { .. } => "proposal_opened",
+			ProtocolLoveSignal::VoteCast { .. } => "vote_cast",
+			ProtocolLoveSignal::MessagePosted { .. } => "message_posted",
+		}
+	}
+
+	/// Address of the [Collective](struct.Collective.html) whose state changed.
+	pub fn collective_address(&self) -> &Address {
+		match self {
+			ProtocolLoveSignal::ActionCommitted { collective_address, .. }
+			| ProtocolLoveSignal::PersonJoined { collective_address, .. }
+			| ProtocolLoveSignal::VoteCast { collective_address, .. }
+			| ProtocolLoveSignal::MessagePosted { collective_address, .. } => collective_address,
+			ProtocolLoveSignal::ProposalOpened { proposal, .. } => &proposal.collective_address,
+		}
+	}
+}
+
+/// Emits the `signal` to the UI of the agent & sends it to the other people of the collective.
+///
+/// A person who cannot be reached, e.g. while offline, misses the signal.
+pub fn emit_signal(store: &dyn Store, signal: ProtocolLoveSignal) -> ProtocolLoveResult<()> {
+	let name = signal.name();
+	t("emit_signal: ", store.emit_signal(name, signal.clone().into()))?;
+	let agent_address = store.agent_address();
+	let people = t("emit_signal: ", get_collective_people(store, signal.collective_address()))?;
+	for person in people.iter().filter(|person| person.agent_address != agent_address) {
+		let _ = store.send_signal(&person.agent_address, signal.clone().into());
+	}
+	Ok(())
+}
+
+/// Emits a signal sent by the agent at `from_address` to the UI of the agent, when the sender is
+/// a person of the collective of the signal.
+pub fn receive_signal(
+	store: &dyn Store,
+	from_address: &Address,
+	payload: JsonString,
+) -> ProtocolLoveResult<()> {
+	let signal = ProtocolLoveSignal::try_from(payload).map_err(|_|
+		ProtocolLoveError::validation_failed("payload", "receive_signal: Payload is not a signal"))?;
+	let people = t("receive_signal: ", get_collective_people(store, signal.collective_address()))?;
+	if !people.iter().any(|person| &person.agent_address == from_address) {
+		return Err(ProtocolLoveError::unauthorized(
+			"receive_signal: Only people in the collective can send signals"));
+	}
+	let name = signal.name();
+	t("receive_signal: ", store.emit_signal(name, signal.into()))
+}
+
+fn get_collective_people(store: &dyn Store, collective_address: &Address) -> ProtocolLoveResult<Vec<Person>> {
+	get_links_and_load_type(store, collective_address, "collective->person", None)
+}
@@ -0,0 +1,801 @@
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use hdk::holochain_core_types::dna::entry_types::Sharing;
+use hdk::prelude::ValidatingEntryType;
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::action::{ActionOp, create_root_action};
+use crate::collective::{Collective, is_collective_admin, is_collective_person};
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::person::{Person, PersonStatus, get_agent_person};
+use crate::store::{Store, HdkStore, get_as_type, get_initial_as_type, get_links_and_load_type};
+use crate::utils::t;
+use crate::validation::{
+	EntryValidation, LinkValidation, ValidationContext, from_hdk_link_validation_data,
+	from_hdk_validation_data, validate_app_link, validate_timestamp,
+};
+
+/// Least number of revealed [SortitionSeeds](struct.SortitionSeed.html) seeding the draw of a
+/// [Sortition](struct.Sortition.html).
+pub const SORTITION_SEED_REVEALS: usize = 3;
+
+/// A draw by lot of `size` people from the pool of a [Collective](struct.Collective.html), e.g.
+/// a jury or a citizens' panel.
+///
+/// The pool is fixed to the active people of the collective at the announcement. The draw is
+/// seeded by secrets the people of the pool commit to until `commit_until` & reveal until
+/// `reveal_until`, so no one knows the seed before the last reveal & a person can only withhold
+/// their secret, not choose it.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct Sortition {
+	pub collective_address: Address,
+	pub name: String,
+	/// Number of people to draw.
+	pub size: u64,
+	/// Time the sortition was announced in seconds since the unix epoch.
+	pub announced_at: u64,
+	/// Addresses of the active [People](struct.Person.html) of the collective at the
+	/// announcement, sorted.
+	#[serde(default)]
+	pub pool: Vec<Address>,
+	/// End of the commitment of seeds in seconds since the unix epoch.
+	#[serde(default)]
+	pub commit_until: u64,
+	/// End of the reveal of seeds in seconds since the unix epoch. The sortition is drawn after.
+	#[serde(default)]
+	pub reveal_until: u64,
+}
+
+/// The commitment of a [Person](struct.Person.html) of the pool to a secret seeding the draw of
+/// a [Sortition](struct.Sortition.html), updated with the secret once revealed.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct SortitionSeed {
+	pub sortition_address: Address,
+	pub person_address: Address,
+	/// Hash of the sortition, the person & the secret.
+	pub commitment: Address,
+	/// Time of the commitment in seconds since the unix epoch.
+	pub committed_at: u64,
+	pub secret: Option<String>,
+	/// Time of the reveal in seconds since the unix epoch.
+	pub revealed_at: Option<u64>,
+}
+
+/// The draw of a [Sortition](struct.Sortition.html), also recorded as an
+/// [ActionOp::DrawSortition](enum.ActionOp.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct SortitionDraw {
+	pub sortition_address: Address,
+	/// Addresses of the revealed [SortitionSeeds](struct.SortitionSeed.html), sorted.
+	pub seed_addresses: Vec<Address>,
+	/// Hash of the sortition & the revealed secrets.
+	pub seed: Address,
+	/// Addresses of the [People](struct.Person.html) of the pool of the sortition.
+	pub pool: Vec<Address>,
+	/// Addresses of the drawn [People](struct.Person.html), in the order of their lots.
+	pub selected_addresses: Vec<Address>,
+	/// Time of the draw in seconds since the unix epoch.
+	pub drawn_at: u64,
+}
+
+/// Api params for [announce_sortition](fn.announce_sortition.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct SortitionParams {
+	pub collective_address: Address,
+	pub name: String,
+	pub size: u64,
+	pub commit_until: u64,
+	pub reveal_until: u64,
+	pub timestamp: u64,
+}
+
+/// Api params for [commit_sortition_seed](fn.commit_sortition_seed.html) &
+/// [reveal_sortition_seed](fn.reveal_sortition_seed.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct SortitionSeedParams {
+	pub sortition_address: Address,
+	pub person_address: Address,
+	/// Random secret, kept until revealed.
+	pub secret: String,
+	pub timestamp: u64,
+}
+
+/// Api payload of a [Sortition](struct.Sortition.html) & its draw, once drawn.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct SortitionPayload {
+	pub sortition_address: Address,
+	pub sortition: Sortition,
+	pub draw: Option<SortitionDraw>,
+}
+
+/// Api payload of a [SortitionSeed](struct.SortitionSeed.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct SortitionSeedPayload {
+	pub seed_address: Address,
+	pub seed: SortitionSeed,
+}
+
+/// Api payload of [verify_sortition](fn.verify_sortition.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct SortitionVerificationPayload {
+	pub sortition_address: Address,
+	pub draw: SortitionDraw,
+	/// Does the draw follow from its revealed seeds?
+	pub verified: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct AnnounceSortitionActionData {
+	sortition_address: Address,
+	sortition: Sortition,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct SortitionSeedActionData {
+	seed_address: Address,
+	seed: SortitionSeed,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct SortitionSecret {
+	sortition_address: Address,
+	person_address: Address,
+	secret: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct SortitionSecrets {
+	sortition_address: Address,
+	secrets: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct SortitionLot {
+	seed: Address,
+	person_address: Address,
+}
+
+/// Returns a Holochain entry definition for a sortition.
+pub fn sortition_def() -> ValidatingEntryType {
+	entry!(
+		name: "sortition",
+		description: "A protocol.love draw by lot of people in a collective",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<Sortition>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_sortition_entry(&HdkStore, validation, &context)?)
+		},
+		links: [
+			to!(
+				"sortition_draw",
+				link_type: "sortition->sortition_draw",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			),
+			to!(
+				"sortition_seed",
+				link_type: "sortition->sortition_seed",
+				validation_package: || {
+					hdk::ValidationPackageDefinition::Entry
+				},
+				validation: |validation_data: hdk::LinkValidationData| {
+					let (link, context) = from_hdk_link_validation_data(validation_data);
+					Ok(validate_app_link(&HdkStore, &link, &context)?)
+				}
+			)
+		]
+	)
+}
+
+/// Returns a Holochain entry definition for the seed of a sortition.
+pub fn sortition_seed_def() -> ValidatingEntryType {
+	entry!(
+		name: "sortition_seed",
+		description: "A protocol.love commitment to a secret seeding the draw of a sortition",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<SortitionSeed>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_sortition_seed_entry(&HdkStore, validation, &context)?)
+		}
+	)
+}
+
+/// Returns a Holochain entry definition for the draw of a sortition.
+pub fn sortition_draw_def() -> ValidatingEntryType {
+	entry!(
+		name: "sortition_draw",
+		description: "The protocol.love draw of a sortition",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<SortitionDraw>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_sortition_draw_entry(&HdkStore, validation, &context)?)
+		}
+	)
+}
+
+/// Validation rules of a [Sortition](struct.Sortition.html) entry.
+///
+/// The pool is fixed at the announcement to sorted people of the collective.
+pub fn validate_sortition_entry(
+	store: &dyn Store,
+	validation: EntryValidation<Sortition>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let collective: Collective = t("validation error: sortition: fetch collective: ",
+				get_as_type(store, &entry.collective_address))?;
+			let admin_agent_address = match &collective.admin_address {
+				Some(admin_address) => {
+					let admin: Person = t("validation error: sortition: fetch admin: ",
+						get_as_type(store, admin_address))?;
+					Some(admin.agent_address)
+				}
+				None => None,
+			};
+			match admin_agent_address {
+				Some(agent_address) if context.sources.contains(&agent_address) => {}
+				_ => return Err(ProtocolLoveError::unauthorized("Only the admin can announce sortitions")),
+			}
+			if entry.name.trim().is_empty() || entry.name.len() > 64 {
+				return Err(ProtocolLoveError::validation_failed(
+					"name", "Name must be between 1 & 64 characters"));
+			}
+			if entry.size == 0 {
+				return Err(ProtocolLoveError::validation_failed("size", "Size must be positive"));
+			}
+			if entry.commit_until <= entry.announced_at {
+				return Err(ProtocolLoveError::validation_failed(
+					"commit_until", "Commitments must close after the announcement"));
+			}
+			if entry.reveal_until <= entry.commit_until {
+				return Err(ProtocolLoveError::validation_failed(
+					"reveal_until", "Seeds must be revealed after the commitments close"));
+			}
+			if !entry.pool.windows(2).all(|pair| pair[0] < pair[1]) {
+				return Err(ProtocolLoveError::validation_failed("pool", "Pool must be sorted"));
+			}
+			for person_address in &entry.pool {
+				if !is_collective_person(store, &entry.collective_address, person_address)? {
+					return Err(ProtocolLoveError::validation_failed(
+						"pool", "Pool must be people of the collective"));
+				}
+			}
+			if (entry.pool.len() as u64) < entry.size {
+				return Err(ProtocolLoveError::validation_failed(
+					"size", "Pool has fewer people than the size of the sortition"));
+			}
+			validate_timestamp("announced_at", entry.announced_at, context)
+		}
+		EntryValidation::Modify { .. } =>
+			Err(ProtocolLoveError::unauthorized("Sortition cannot be modified")),
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Sortition cannot be deleted")),
+	}
+}
+
+/// Validation rules of a [SortitionSeed](struct.SortitionSeed.html) entry.
+///
+/// A person of the pool commits to a secret until `commit_until` & reveals it until
+/// `reveal_until`, as the only modification of the seed.
+pub fn validate_sortition_seed_entry(
+	store: &dyn Store,
+	validation: EntryValidation<SortitionSeed>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let sortition = validate_seed_person(store, &entry, context)?;
+			if !sortition.pool.contains(&entry.person_address) {
+				return Err(ProtocolLoveError::unauthorized("Only people of the pool seed the draw"));
+			}
+			if entry.committed_at >= sortition.commit_until {
+				return Err(ProtocolLoveError::conflict("Commitments are closed"));
+			}
+			if entry.secret.is_some() || entry.revealed_at.is_some() {
+				return Err(ProtocolLoveError::validation_failed(
+					"secret", "Secret cannot be revealed when committed"));
+			}
+			validate_timestamp("committed_at", entry.committed_at, context)
+		}
+		EntryValidation::Modify { new_entry, old_entry } => {
+			if old_entry.secret.is_some() {
+				return Err(ProtocolLoveError::conflict("Seed is already revealed"));
+			}
+			let (secret, revealed_at) = match (&new_entry.secret, new_entry.revealed_at) {
+				(Some(secret), Some(revealed_at)) => (secret, revealed_at),
+				_ => return Err(ProtocolLoveError::unauthorized("Seed can only be modified by its reveal")),
+			};
+			if new_entry != (SortitionSeed {
+				secret: Some(secret.clone()),
+				revealed_at: Some(revealed_at),
+				..old_entry.clone()
+			}) {
+				return Err(ProtocolLoveError::unauthorized("Seed can only be modified by its reveal"));
+			}
+			let sortition = validate_seed_person(store, &new_entry, context)?;
+			if revealed_at < sortition.commit_until || revealed_at >= sortition.reveal_until {
+				return Err(ProtocolLoveError::conflict(&format!(
+					"Seeds are revealed from {} until {}", sortition.commit_until, sortition.reveal_until)));
+			}
+			if seed_commitment(store, &new_entry.sortition_address, &new_entry.person_address, secret)?
+				!= new_entry.commitment {
+				return Err(ProtocolLoveError::validation_failed(
+					"secret", "Secret does not match the commitment"));
+			}
+			validate_timestamp("revealed_at", revealed_at, context)
+		}
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Sortition seed cannot be deleted")),
+	}
+}
+
+/// Validates the seed is committed or revealed by its person & returns its sortition.
+fn validate_seed_person(
+	store: &dyn Store,
+	seed: &SortitionSeed,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<Sortition> {
+	let sortition: Sortition = t("validation error: sortition_seed: fetch sortition: ",
+		get_as_type(store, &seed.sortition_address))?;
+	let person: Person = t("validation error: sortition_seed: fetch person: ",
+		get_as_type(store, &seed.person_address))?;
+	if !context.sources.contains(&person.agent_address) {
+		return Err(ProtocolLoveError::unauthorized("Seed must be committed & revealed by the person"));
+	}
+	Ok(sortition)
+}
+
+/// Validation rules of a [SortitionDraw](struct.SortitionDraw.html) entry.
+///
+/// The sortition is drawn once after `reveal_until`, seeded by the revealed secrets & drawing
+/// from the pool fixed at the announcement.
+pub fn validate_sortition_draw_entry(
+	store: &dyn Store,
+	validation: EntryValidation<SortitionDraw>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let sortition: Sortition = t("validation error: sortition_draw: fetch sortition: ",
+				get_as_type(store, &entry.sortition_address))?;
+			if find_sortition_draw(store, &entry.sortition_address)?.is_some() {
+				return Err(ProtocolLoveError::conflict("Sortition is already drawn"));
+			}
+			let draw = compute_sortition_draw(store, &entry.sortition_address, &sortition, entry.drawn_at)?;
+			if entry.seed_addresses != draw.seed_addresses {
+				return Err(ProtocolLoveError::validation_failed(
+					"seed_addresses", "Draw must be seeded by the revealed seeds"));
+			}
+			if entry.seed != draw.seed {
+				return Err(ProtocolLoveError::validation_failed(
+					"seed", "Seed must hash the revealed secrets"));
+			}
+			if entry.pool != draw.pool {
+				return Err(ProtocolLoveError::validation_failed(
+					"pool", "Pool must be the pool of the sortition"));
+			}
+			if entry.selected_addresses != draw.selected_addresses {
+				return Err(ProtocolLoveError::validation_failed(
+					"selected_addresses", "Selected people must hold the lowest lots"));
+			}
+			validate_timestamp("drawn_at", entry.drawn_at, context)
+		}
+		EntryValidation::Modify { .. } =>
+			Err(ProtocolLoveError::unauthorized("Sortition draw cannot be modified")),
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Sortition draw cannot be deleted")),
+	}
+}
+
+/// Validates the target of a "sortition->sortition_draw" link is the draw of the sortition.
+pub fn validate_sortition_draw_link(
+	store: &dyn Store,
+	link: &LinkValidation,
+	_context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let draw: SortitionDraw = t("validation error: sortition->sortition_draw: fetch draw: ",
+		get_as_type(store, &link.target))?;
+	if draw.sortition_address != link.base {
+		return Err(ProtocolLoveError::validation_failed("target", "Draw is not of the sortition"));
+	}
+	Ok(())
+}
+
+/// Validates the target of a "sortition->sortition_seed" link is the only seed of its person in
+/// the sortition.
+pub fn validate_sortition_seed_link(
+	store: &dyn Store,
+	link: &LinkValidation,
+	_context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let seed: SortitionSeed = t("validation error: sortition->sortition_seed: fetch seed: ",
+		get_initial_as_type(store, &link.target))?;
+	if seed.sortition_address != link.base {
+		return Err(ProtocolLoveError::validation_failed("target", "Seed is not of the sortition"));
+	}
+	let is_committed = get_sortition_seeds(store, &link.base)?
+		.into_iter()
+		.any(|(seed_address, other_seed)|
+			seed_address != link.target && other_seed.person_address == seed.person_address);
+	if is_committed {
+		return Err(ProtocolLoveError::conflict("Seed is already committed"));
+	}
+	Ok(())
+}
+
+/// Api for the admin to announce a [Sortition](struct.Sortition.html) of the collective, drawing
+/// from its active people.
+///
+/// The people of the pool commit to secrets with
+/// [commit_sortition_seed](fn.commit_sortition_seed.html) until `commit_until`, reveal them with
+/// [reveal_sortition_seed](fn.reveal_sortition_seed.html) until `reveal_until` & the sortition
+/// is drawn after with [draw_sortition](fn.draw_sortition.html).
+pub fn announce_sortition(
+	store: &dyn Store,
+	sortition_params: SortitionParams,
+) -> ProtocolLoveResult<SortitionPayload> {
+	let collective: Collective =
+		t("announce_sortition: ", get_as_type(store, &sortition_params.collective_address))?;
+	if !t("announce_sortition: ", is_collective_admin(store, &collective))? {
+		return Err(ProtocolLoveError::unauthorized(
+			"announce_sortition: Only the admin can announce sortitions"));
+	}
+	let mut pool = vec![];
+	for person_address in t("announce_sortition: ", store.get_links(
+		&sortition_params.collective_address,
+		"collective->person",
+		None,
+	))? {
+		let person: Person = t("announce_sortition: ", get_as_type(store, &person_address))?;
+		if let PersonStatus::Active = person.status {
+			pool.push(person_address);
+		}
+	}
+	pool.sort();
+	if (pool.len() as u64) < sortition_params.size {
+		return Err(ProtocolLoveError::conflict(
+			"announce_sortition: Collective has fewer active people than the size of the sortition"));
+	}
+	let sortition = Sortition {
+		collective_address: sortition_params.collective_address,
+		name: sortition_params.name,
+		size: sortition_params.size,
+		announced_at: sortition_params.timestamp,
+		pool,
+		commit_until: sortition_params.commit_until,
+		reveal_until: sortition_params.reveal_until,
+	};
+	let sortition_entry = Entry::App("sortition".into(), (&sortition).into());
+	let sortition_address = t("announce_sortition: ", store.commit_entry(&sortition_entry))?;
+	t("announce_sortition: ", create_root_action(
+		store,
+		&sortition.collective_address,
+		ActionOp::AnnounceSortition,
+		AnnounceSortitionActionData {
+			sortition_address: sortition_address.clone(),
+			sortition: sortition.clone(),
+		}.into(),
+		serde_json::value::Value::Null.into(),
+		"announce_sortition",
+	))?;
+	Ok(SortitionPayload {
+		sortition_address,
+		sortition,
+		draw: None,
+	})
+}
+
+/// Api for a person of the pool to commit to the secret seeding the draw of the
+/// [Sortition](struct.Sortition.html). Only the hash of the secret is committed.
+pub fn commit_sortition_seed(
+	store: &dyn Store,
+	seed_params: SortitionSeedParams,
+) -> ProtocolLoveResult<SortitionSeedPayload> {
+	let SortitionSeedParams {
+		sortition_address,
+		person_address,
+		secret,
+		timestamp,
+	} = seed_params;
+	let sortition: Sortition = t("commit_sortition_seed: ", get_as_type(store, &sortition_address))?;
+	t("commit_sortition_seed: ", get_agent_person(store, &person_address))?;
+	if t("commit_sortition_seed: ", find_person_seed(store, &sortition_address, &person_address))?.is_some() {
+		return Err(ProtocolLoveError::conflict("commit_sortition_seed: Seed is already committed"));
+	}
+	let seed = SortitionSeed {
+		sortition_address: sortition_address.clone(),
+		person_address: person_address.clone(),
+		commitment: t("commit_sortition_seed: ",
+			seed_commitment(store, &sortition_address, &person_address, &secret))?,
+		committed_at: timestamp,
+		secret: None,
+		revealed_at: None,
+	};
+	let seed_address = t("commit_sortition_seed: ",
+		store.commit_entry(&Entry::App("sortition_seed".into(), (&seed).into())))?;
+	t("commit_sortition_seed: sortition->sortition_seed: ", store.link_entries(
+		&sortition_address,
+		&seed_address,
+		"sortition->sortition_seed",
+		"",
+	))?;
+	t("commit_sortition_seed: ", create_root_action(
+		store,
+		&sortition.collective_address,
+		ActionOp::CommitSortitionSeed,
+		SortitionSeedActionData {
+			seed_address: seed_address.clone(),
+			seed: seed.clone(),
+		}.into(),
+		serde_json::value::Value::Null.into(),
+		"commit_sortition_seed",
+	))?;
+	Ok(SortitionSeedPayload {
+		seed_address,
+		seed,
+	})
+}
+
+/// Api for a person of the pool to reveal the secret committed with
+/// [commit_sortition_seed](fn.commit_sortition_seed.html).
+pub fn reveal_sortition_seed(
+	store: &dyn Store,
+	seed_params: SortitionSeedParams,
+) -> ProtocolLoveResult<SortitionSeedPayload> {
+	let SortitionSeedParams {
+		sortition_address,
+		person_address,
+		secret,
+		timestamp,
+	} = seed_params;
+	let sortition: Sortition = t("reveal_sortition_seed: ", get_as_type(store, &sortition_address))?;
+	t("reveal_sortition_seed: ", get_agent_person(store, &person_address))?;
+	let (seed_address, saved_seed) =
+		match t("reveal_sortition_seed: ", find_person_seed(store, &sortition_address, &person_address))? {
+			Some(seed) => seed,
+			None => return Err(ProtocolLoveError::conflict("reveal_sortition_seed: Seed is not committed")),
+		};
+	let seed = SortitionSeed {
+		secret: Some(secret),
+		revealed_at: Some(timestamp),
+		..saved_seed
+	};
+	t("reveal_sortition_seed: ", store.update_entry(
+		Entry::App("sortition_seed".into(), (&seed).into()),
+		&seed_address,
+	))?;
+	t("reveal_sortition_seed: ", create_root_action(
+		store,
+		&sortition.collective_address,
+		ActionOp::RevealSortitionSeed,
+		SortitionSeedActionData {
+			seed_address: seed_address.clone(),
+			seed: seed.clone(),
+		}.into(),
+		serde_json::value::Value::Null.into(),
+		"reveal_sortition_seed",
+	))?;
+	Ok(SortitionSeedPayload {
+		seed_address,
+		seed,
+	})
+}
+
+/// Api to draw the [Sortition](struct.Sortition.html) at `sortition_address` at the
+/// `timestamp`, committing the [SortitionDraw](struct.SortitionDraw.html) & recording it as an
+/// [ActionOp::DrawSortition](enum.ActionOp.html).
+///
+/// Each person of the pool gets a lot, the hash of the seed & the person. The people with the
+/// lowest lots are drawn.
+pub fn draw_sortition(
+	store: &dyn Store,
+	sortition_address: Address,
+	timestamp: u64,
+) -> ProtocolLoveResult<SortitionPayload> {
+	let sortition: Sortition = t("draw_sortition: ", get_as_type(store, &sortition_address))?;
+	if t("draw_sortition: ", find_sortition_draw(store, &sortition_address))?.is_some() {
+		return Err(ProtocolLoveError::conflict("draw_sortition: Sortition is already drawn"));
+	}
+	let draw = t("draw_sortition: ", compute_sortition_draw(store, &sortition_address, &sortition, timestamp))?;
+	let draw_entry = Entry::App("sortition_draw".into(), (&draw).into());
+	let draw_address = t("draw_sortition: ", store.commit_entry(&draw_entry))?;
+	t("draw_sortition: sortition->sortition_draw: ", store.link_entries(
+		&sortition_address,
+		&draw_address,
+		"sortition->sortition_draw",
+		"",
+	))?;
+	t("draw_sortition: ", create_root_action(
+		store,
+		&sortition.collective_address,
+		ActionOp::DrawSortition,
+		draw.clone().into(),
+		serde_json::value::Value::Null.into(),
+		"draw_sortition",
+	))?;
+	Ok(SortitionPayload {
+		sortition_address,
+		sortition,
+		draw: Some(draw),
+	})
+}
+
+/// Api to get a [Sortition](struct.Sortition.html) & its draw.
+pub fn get_sortition(
+	store: &dyn Store,
+	sortition_address: Address,
+) -> ProtocolLoveResult<SortitionPayload> {
+	let sortition: Sortition = t("get_sortition: ", get_as_type(store, &sortition_address))?;
+	let draw = t("get_sortition: ", find_sortition_draw(store, &sortition_address))?;
+	Ok(SortitionPayload {
+		sortition_address,
+		sortition,
+		draw,
+	})
+}
+
+/// Api to re-verify the draw of the [Sortition](struct.Sortition.html) at `sortition_address`.
+///
+/// The draw is verified when its seed & selection follow from the seeds revealed before
+/// `reveal_until` & the pool fixed at the announcement.
+pub fn verify_sortition(
+	store: &dyn Store,
+	sortition_address: Address,
+) -> ProtocolLoveResult<SortitionVerificationPayload> {
+	let sortition: Sortition = t("verify_sortition: ", get_as_type(store, &sortition_address))?;
+	let draw = match t("verify_sortition: ", find_sortition_draw(store, &sortition_address))? {
+		Some(draw) => draw,
+		None => return Err(ProtocolLoveError::conflict("verify_sortition: Sortition is not drawn")),
+	};
+	let verified = t("verify_sortition: ",
+		compute_sortition_draw(store, &sortition_address, &sortition, draw.drawn_at))? == draw;
+	Ok(SortitionVerificationPayload {
+		sortition_address,
+		draw,
+		verified,
+	})
+}
+
+/// Draws the sortition at the `timestamp` from its revealed seeds & its pool.
+fn compute_sortition_draw(
+	store: &dyn Store,
+	sortition_address: &Address,
+	sortition: &Sortition,
+	timestamp: u64,
+) -> ProtocolLoveResult<SortitionDraw> {
+	if timestamp < sortition.reveal_until {
+		return Err(ProtocolLoveError::conflict(&format!(
+			"Seeds are revealed until {}", sortition.reveal_until)));
+	}
+	let mut seeds: Vec<(Address, String)> = t("compute_sortition_draw: ",
+		get_sortition_seeds(store, sortition_address))?
+		.into_iter()
+		.filter_map(|(seed_address, seed)| match (seed.secret, seed.revealed_at) {
+			(Some(secret), Some(revealed_at)) if revealed_at < sortition.reveal_until =>
+				Some((seed_address, secret)),
+			_ => None,
+		})
+		.collect();
+	if seeds.len() < SORTITION_SEED_REVEALS {
+		return Err(ProtocolLoveError::conflict(&format!(
+			"Sortition needs {} more revealed seeds to be drawn",
+			SORTITION_SEED_REVEALS - seeds.len())));
+	}
+	seeds.sort();
+	let (seed_addresses, secrets): (Vec<Address>, Vec<String>) = seeds.into_iter().unzip();
+	let seed = t("compute_sortition_draw: ", store.entry_address(&Entry::App(
+		"sortition".into(),
+		SortitionSecrets {
+			sortition_address: sortition_address.clone(),
+			secrets,
+		}.into(),
+	)))?;
+	let selected_addresses =
+		t("compute_sortition_draw: ", draw_lots(store, &seed, &sortition.pool, sortition.size))?;
+	Ok(SortitionDraw {
+		sortition_address: sortition_address.clone(),
+		seed_addresses,
+		seed,
+		pool: sortition.pool.clone(),
+		selected_addresses,
+		drawn_at: timestamp,
+	})
+}
+
+/// Hash of the sortition, the person & the secret, committed by the person.
+fn seed_commitment(
+	store: &dyn Store,
+	sortition_address: &Address,
+	person_address: &Address,
+	secret: &str,
+) -> ProtocolLoveResult<Address> {
+	let sortition_secret = SortitionSecret {
+		sortition_address: sortition_address.clone(),
+		person_address: person_address.clone(),
+		secret: secret.to_string(),
+	};
+	t("seed_commitment: ", store.entry_address(&Entry::App("sortition".into(), sortition_secret.into())))
+}
+
+/// Draws the `size` people of the `pool` with the lowest lots for the `seed`.
+///
+/// Lots are base58 hashes of the same length, so they sort in the order of their values.
+fn draw_lots(
+	store: &dyn Store,
+	seed: &Address,
+	pool: &[Address],
+	size: u64,
+) -> ProtocolLoveResult<Vec<Address>> {
+	let mut lots = pool.iter()
+		.map(|person_address| {
+			let lot = SortitionLot {
+				seed: seed.clone(),
+				person_address: person_address.clone(),
+			};
+			let lot_address =
+				t("draw_lots: ", store.entry_address(&Entry::App("sortition".into(), lot.into())))?;
+			Ok((lot_address, person_address.clone()))
+		})
+		.collect::<ProtocolLoveResult<Vec<(Address, Address)>>>()?;
+	lots.sort();
+	Ok(lots.into_iter()
+		.take(size as usize)
+		.map(|(_, person_address)| person_address)
+		.collect())
+}
+
+/// Reads the addresses & latest versions of the [SortitionSeeds](struct.SortitionSeed.html) of
+/// the sortition.
+fn get_sortition_seeds(
+	store: &dyn Store,
+	sortition_address: &Address,
+) -> ProtocolLoveResult<Vec<(Address, SortitionSeed)>> {
+	let seed_addresses =
+		t("get_sortition_seeds: ", store.get_links(sortition_address, "sortition->sortition_seed", None))?;
+	let mut seeds = vec![];
+	for seed_address in seed_addresses {
+		let seed: SortitionSeed = t("get_sortition_seeds: ", get_as_type(store, &seed_address))?;
+		if &seed.sortition_address == sortition_address {
+			seeds.push((seed_address, seed));
+		}
+	}
+	Ok(seeds)
+}
+
+/// Gets the address & latest version of the seed of the person in the sortition.
+fn find_person_seed(
+	store: &dyn Store,
+	sortition_address: &Address,
+	person_address: &Address,
+) -> ProtocolLoveResult<Option<(Address, SortitionSeed)>> {
+	Ok(get_sortition_seeds(store, sortition_address)?
+		.into_iter()
+		.find(|(_, seed)| &seed.person_address == person_address))
+}
+
+/// Gets the [SortitionDraw](struct.SortitionDraw.html) linked from the sortition.
+fn find_sortition_draw(
+	store: &dyn Store,
+	sortition_address: &Address,
+) -> ProtocolLoveResult<Option<SortitionDraw>> {
+	let draws: Vec<SortitionDraw> = t("find_sortition_draw: ",
+		get_links_and_load_type(store, sortition_address, "sortition->sortition_draw", None))?;
+	Ok(draws.into_iter().next())
+}
@@ -0,0 +1,332 @@
+use std::collections::HashSet;
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use hdk::holochain_core_types::dna::entry_types::Sharing;
+use hdk::prelude::ValidatingEntryType;
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::action::{ActionOp, create_root_action};
+use crate::collective::{
+	Collective, CollectivePayload, get_policy_collective, is_collective_admin, is_collective_person,
+	update_collective_entry,
+};
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::person::{Person, get_agent_person};
+use crate::proposal::{Proposal, ProposalStatus, set_proposal_status};
+use crate::store::{Store, HdkStore, get_as_type, get_links_and_load_type};
+use crate::utils::t;
+use crate::validation::{
+	EntryValidation, LinkValidation, ValidationContext, from_hdk_validation_data, validate_timestamp,
+};
+
+/// A [Person](struct.Person.html) sponsoring a [Proposal](struct.Proposal.html) in draft or
+/// discussion, or withdrawing their sponsorship.
+///
+/// The latest sponsorship of a person on a proposal replaces the former.
+/// Once the sponsors reach the `sponsor_threshold` of the [Collective](struct.Collective.html),
+/// voting opens.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct Sponsorship {
+	pub proposal_address: Address,
+	/// Address of the [Person](struct.Person.html) sponsoring the proposal.
+	pub person_address: Address,
+	/// `false` when the person withdraws their sponsorship.
+	pub sponsoring: bool,
+	/// Time of the sponsorship in seconds since the unix epoch.
+	pub timestamp: u64,
+}
+
+/// Api params for [sponsor_proposal](fn.sponsor_proposal.html) &
+/// [unsponsor_proposal](fn.unsponsor_proposal.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct SponsorshipParams {
+	pub proposal_address: Address,
+	pub person_address: Address,
+	pub timestamp: u64,
+}
+
+/// Api payload of the sponsors of a [Proposal](struct.Proposal.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ProposalSponsorsPayload {
+	pub proposal_address: Address,
+	/// Addresses of the [People](struct.Person.html) sponsoring the proposal.
+	pub sponsor_addresses: Vec<Address>,
+	/// Sponsors needed for voting to open.
+	pub sponsor_threshold: u64,
+	pub status: ProposalStatus,
+}
+
+/// Returns a Holochain entry definition for a sponsorship.
+pub fn sponsorship_def() -> ValidatingEntryType {
+	entry!(
+		name: "sponsorship",
+		description: "A protocol.love sponsorship of a proposal",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<Sponsorship>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_sponsorship_entry(&HdkStore, validation, &context)?)
+		}
+	)
+}
+
+/// Validation rules of a [Sponsorship](struct.Sponsorship.html) entry.
+///
+/// People of the collective other than the authors sponsor a proposal in draft or discussion.
+pub fn validate_sponsorship_entry(
+	store: &dyn Store,
+	validation: EntryValidation<Sponsorship>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let person: Person = t("validation error: sponsorship: fetch person: ",
+				get_as_type(store, &entry.person_address))?;
+			if !context.sources.contains(&person.agent_address) {
+				return Err(ProtocolLoveError::unauthorized(
+					"Sponsorship must be given by the agent of the person"));
+			}
+			let proposal: Proposal = t("validation error: sponsorship: fetch proposal: ",
+				get_as_type(store, &entry.proposal_address))?;
+			if !is_collective_person(store, &proposal.collective_address, &entry.person_address)? {
+				return Err(ProtocolLoveError::unauthorized(
+					"Only people in the collective can sponsor"));
+			}
+			if entry.person_address == proposal.author_address
+				|| proposal.co_author_addresses.contains(&entry.person_address) {
+				return Err(ProtocolLoveError::validation_failed(
+					"person_address", "Authors cannot sponsor their proposal"));
+			}
+			match proposal.status {
+				ProposalStatus::Draft | ProposalStatus::Discussion => {}
+				_ => return Err(ProtocolLoveError::conflict("Proposal is not gathering sponsors")),
+			}
+			validate_timestamp("timestamp", entry.timestamp, context)
+		}
+		EntryValidation::Modify { .. } =>
+			Err(ProtocolLoveError::unauthorized("Sponsorship cannot be modified")),
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Sponsorship cannot be deleted")),
+	}
+}
+
+/// Validation rules of a `proposal->sponsorship` link: the sponsorship is of the base proposal &
+/// is linked by the agent of the sponsor.
+pub fn validate_proposal_sponsorship_link(
+	store: &dyn Store,
+	link: &LinkValidation,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let sponsorship: Sponsorship = t("validation error: proposal->sponsorship: fetch sponsorship: ",
+		get_as_type(store, &link.target))?;
+	if sponsorship.proposal_address != link.base {
+		return Err(ProtocolLoveError::validation_failed(
+			"target", "Sponsorship is not of the proposal"));
+	}
+	let person: Person = t("validation error: proposal->sponsorship: fetch person: ",
+		get_as_type(store, &sponsorship.person_address))?;
+	if !context.sources.contains(&person.agent_address) {
+		return Err(ProtocolLoveError::unauthorized(
+			"Sponsorship must be linked by the agent of the sponsor"));
+	}
+	Ok(())
+}
+
+/// Api for a [Person](struct.Person.html) of the [Collective](struct.Collective.html) to sponsor a
+/// [Proposal](struct.Proposal.html) in draft or discussion.
+///
+/// Voting opens once the sponsors reach the `sponsor_threshold` of the collective within the voting
+/// window of the proposal.
+pub fn sponsor_proposal(
+	store: &dyn Store,
+	sponsorship_params: SponsorshipParams,
+) -> ProtocolLoveResult<ProposalSponsorsPayload> {
+	t("sponsor_proposal: ", get_agent_person(store, &sponsorship_params.person_address))?;
+	let sponsor_addresses =
+		t("sponsor_proposal: ", get_sponsor_addresses(store, &sponsorship_params.proposal_address))?;
+	if sponsor_addresses.contains(&sponsorship_params.person_address) {
+		return Err(ProtocolLoveError::conflict("sponsor_proposal: Proposal is already sponsored"));
+	}
+	t("sponsor_proposal: ", commit_sponsorship(store, sponsorship_params, true))
+}
+
+/// Api for a [Person](struct.Person.html) to withdraw their sponsorship of a
+/// [Proposal](struct.Proposal.html) still gathering sponsors.
+pub fn unsponsor_proposal(
+	store: &dyn Store,
+	sponsorship_params: SponsorshipParams,
+) -> ProtocolLoveResult<ProposalSponsorsPayload> {
+	t("unsponsor_proposal: ", get_agent_person(store, &sponsorship_params.person_address))?;
+	let sponsor_addresses =
+		t("unsponsor_proposal: ", get_sponsor_addresses(store, &sponsorship_params.proposal_address))?;
+	if !sponsor_addresses.contains(&sponsorship_params.person_address) {
+		return Err(ProtocolLoveError::conflict("unsponsor_proposal: Proposal is not sponsored"));
+	}
+	t("unsponsor_proposal: ", commit_sponsorship(store, sponsorship_params, false))
+}
+
+/// Api to get the sponsors of a [Proposal](struct.Proposal.html).
+pub fn get_proposal_sponsors(
+	store: &dyn Store,
+	proposal_address: Address,
+) -> ProtocolLoveResult<ProposalSponsorsPayload> {
+	let proposal: Proposal = t("get_proposal_sponsors: ", get_as_type(store, &proposal_address))?;
+	let collective: Collective =
+		t("get_proposal_sponsors: ", get_as_type(store, &proposal.collective_address))?;
+	let policy_collective = t("get_proposal_sponsors: ", get_policy_collective(store, &collective))?;
+	let sponsor_addresses =
+		t("get_proposal_sponsors: ", get_sponsor_addresses(store, &proposal_address))?;
+	Ok(ProposalSponsorsPayload {
+		proposal_address,
+		sponsor_addresses,
+		sponsor_threshold: policy_collective.sponsor_threshold,
+		status: proposal.status,
+	})
+}
+
+/// Api for the admin to set the number of sponsors a [Proposal](struct.Proposal.html) of the
+/// [Collective](struct.Collective.html) needs before voting opens. `0` requires no sponsors.
+pub fn set_sponsor_threshold(
+	store: &dyn Store,
+	collective_address: Address,
+	sponsor_threshold: u64,
+) -> ProtocolLoveResult<CollectivePayload> {
+	let saved_collective: Collective =
+		t("set_sponsor_threshold: ", get_as_type(store, &collective_address))?;
+	if !t("set_sponsor_threshold: ", is_collective_admin(store, &saved_collective))? {
+		return Err(ProtocolLoveError::unauthorized(
+			"set_sponsor_threshold: Only the admin can set the sponsor threshold"));
+	}
+	if saved_collective.inherit_policies {
+		return Err(ProtocolLoveError::conflict(
+			"set_sponsor_threshold: Collective inherits the policies of its parent"));
+	}
+	let collective = Collective {
+		sponsor_threshold,
+		..saved_collective.clone()
+	};
+	t("set_sponsor_threshold: ", update_collective_entry(store, &collective_address, &collective))?;
+	t("set_sponsor_threshold: ", create_root_action(
+		store,
+		&collective_address,
+		ActionOp::SetSponsorThreshold,
+		SponsorThresholdActionData {
+			sponsor_threshold,
+		}.into(),
+		SponsorThresholdActionData {
+			sponsor_threshold: saved_collective.sponsor_threshold,
+		}.into(),
+		"set_sponsor_threshold",
+	))?;
+	Ok(CollectivePayload {
+		collective_address,
+		collective,
+	})
+}
+
+/// Gets the addresses of the people whose latest sponsorship of the proposal is active,
+/// the most recent first.
+pub fn get_sponsor_addresses(
+	store: &dyn Store,
+	proposal_address: &Address,
+) -> ProtocolLoveResult<Vec<Address>> {
+	let sponsorships: Vec<Sponsorship> =
+		t("get_sponsor_addresses: ", get_links_and_load_type(
+			store,
+			proposal_address,
+			"proposal->sponsorship",
+			None,
+		))?;
+	let mut person_addresses = HashSet::new();
+	Ok(sponsorships.into_iter()
+		.filter(|sponsorship| &sponsorship.proposal_address == proposal_address)
+		.filter(|sponsorship| person_addresses.insert(sponsorship.person_address.clone()))
+		.filter(|sponsorship| sponsorship.sponsoring)
+		.map(|sponsorship| sponsorship.person_address)
+		.collect())
+}
+
+/// Validates the proposal at the `proposal_address` reached the sponsor threshold of its
+/// collective. Returns `false` when the collective requires no sponsors.
+pub fn validate_proposal_sponsors(
+	store: &dyn Store,
+	proposal_address: &Address,
+	proposal: &Proposal,
+) -> ProtocolLoveResult<bool> {
+	let collective: Collective = t("validation error: proposal: fetch collective: ",
+		get_as_type(store, &proposal.collective_address))?;
+	let sponsor_threshold = get_policy_collective(store, &collective)?.sponsor_threshold;
+	if sponsor_threshold == 0 {
+		return Ok(false);
+	}
+	let sponsor_count = get_sponsor_addresses(store, proposal_address)?.len() as u64;
+	if sponsor_count < sponsor_threshold {
+		return Err(ProtocolLoveError::conflict(
+			&format!("Proposal needs {} sponsors before voting", sponsor_threshold)));
+	}
+	Ok(true)
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct SponsorThresholdActionData {
+	sponsor_threshold: u64,
+}
+
+/// Commits the sponsorship & opens voting once the sponsors reach the threshold.
+fn commit_sponsorship(
+	store: &dyn Store,
+	sponsorship_params: SponsorshipParams,
+	sponsoring: bool,
+) -> ProtocolLoveResult<ProposalSponsorsPayload> {
+	let proposal_address = sponsorship_params.proposal_address;
+	let proposal: Proposal = t("commit_sponsorship: ", get_as_type(store, &proposal_address))?;
+	let sponsorship = Sponsorship {
+		proposal_address: proposal_address.clone(),
+		person_address: sponsorship_params.person_address,
+		sponsoring,
+		timestamp: sponsorship_params.timestamp,
+	};
+	let sponsorship_entry = Entry::App("sponsorship".into(), (&sponsorship).into());
+	let sponsorship_address = t("commit_sponsorship: ", store.commit_entry(&sponsorship_entry))?;
+	t("commit_sponsorship: proposal->sponsorship: ", store.link_entries(
+		&proposal_address,
+		&sponsorship_address,
+		"proposal->sponsorship",
+		"",
+	))?;
+	t("commit_sponsorship: ", create_root_action(
+		store,
+		&proposal.collective_address,
+		if sponsoring { ActionOp::SponsorProposal } else { ActionOp::UnsponsorProposal },
+		sponsorship.into(),
+		serde_json::value::Value::Null.into(),
+		"commit_sponsorship",
+	))?;
+	let collective: Collective =
+		t("commit_sponsorship: ", get_as_type(store, &proposal.collective_address))?;
+	let sponsor_threshold = t("commit_sponsorship: ", get_policy_collective(store, &collective))?
+		.sponsor_threshold;
+	let sponsor_addresses = t("commit_sponsorship: ", get_sponsor_addresses(store, &proposal_address))?;
+	let status = if sponsor_threshold > 0
+		&& sponsor_addresses.len() as u64 >= sponsor_threshold
+		&& proposal.voting_window.contains(sponsorship_params.timestamp) {
+		t("commit_sponsorship: ", set_proposal_status(
+			store,
+			&proposal_address,
+			proposal,
+			ProposalStatus::Voting,
+		))?.status
+	} else {
+		proposal.status
+	};
+	Ok(ProposalSponsorsPayload {
+		proposal_address,
+		sponsor_addresses,
+		sponsor_threshold,
+		status,
+	})
+}
@@ -0,0 +1,218 @@
+use std::convert::TryFrom;
+use hdk::holochain_json_api::json::JsonString;
+use holochain_wasm_utils::api_serialization::get_entry::{
+	GetEntryOptions, GetEntryResultType, StatusRequestKind,
+};
+use holochain_wasm_utils::api_serialization::get_links::GetLinksOptions;
+use holochain_wasm_utils::holochain_core_types::entry::{AppEntryValue, Entry};
+use holochain_wasm_utils::holochain_core_types::link::LinkMatch;
+use holochain_wasm_utils::holochain_core_types::network::query::SortOrder;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+
+/// Storage of entries & links used by the domain logic.
+///
+/// [HdkStore](struct.HdkStore.html) is used by the zome.
+/// [MemoryStore](struct.MemoryStore.html) runs the domain logic without a conductor.
+pub trait Store {
+	/// Address of the agent performing the calls.
+	fn agent_address(&self) -> Address;
+	/// Returns the address of the `entry` without committing it.
+	fn entry_address(&self, entry: &Entry) -> ProtocolLoveResult<Address>;
+	/// Commits the `entry` & returns its address.
+	fn commit_entry(&self, entry: &Entry) -> ProtocolLoveResult<Address>;
+	/// Updates the entry at `address` with `entry` & returns the address of the new entry.
+	fn update_entry(&self, entry: Entry, address: &Address) -> ProtocolLoveResult<Address>;
+	/// Links `base` to `target` with the `link_type` & `tag`.
+	fn link_entries(
+		&self,
+		base: &Address,
+		target: &Address,
+		link_type: &str,
+		tag: &str,
+	) -> ProtocolLoveResult<Address>;
+	/// Gets the latest version of the entry at `address`.
+	fn get_entry(&self, address: &Address) -> ProtocolLoveResult<Option<Entry>>;
+	/// Gets the versions of the entry at `address`, from the version at `address` to the latest.
+	fn get_entry_history(&self, address: &Address) -> ProtocolLoveResult<Vec<Entry>>;
+	/// Gets the address of the initial version of the entry, following its history back from
+	/// the version at `address`.
+	fn get_initial_address(&self, address: &Address) -> ProtocolLoveResult<Address>;
+	/// Emits a signal with the `name` & `payload` to the UI of the agent.
+	fn emit_signal(&self, name: &str, payload: JsonString) -> ProtocolLoveResult<()>;
+	/// Sends the `payload` of a signal to the agent at `agent_address`, whose zome emits it to its
+	/// UI in [receive_signal](fn.receive_signal.html).
+	fn send_signal(&self, agent_address: &Address, payload: JsonString) -> ProtocolLoveResult<()>;
+	/// Gets the target addresses of the `link_type` links from `base`, optionally matching the `tag`.
+	///
+	/// The links are ordered by the time of their commit, the most recent first. Links committed
+	/// at the same time have no defined order, so callers needing a total order sort by a field
+	/// of the target entries.
+	fn get_links(
+		&self,
+		base: &Address,
+		link_type: &str,
+		tag: Option<&str>,
+	) -> ProtocolLoveResult<Vec<Address>>;
+}
+
+/// Time in milliseconds to wait for an agent to receive a signal.
+pub const SIGNAL_SEND_TIMEOUT_MS: usize = 5000;
+
+/// [Store](trait.Store.html) backed by the Holochain zome api.
+pub struct HdkStore;
+
+impl Store for HdkStore {
+	fn agent_address(&self) -> Address {
+		hdk::AGENT_ADDRESS.clone()
+	}
+
+	fn entry_address(&self, entry: &Entry) -> ProtocolLoveResult<Address> {
+		Ok(hdk::entry_address(entry)?)
+	}
+
+	fn commit_entry(&self, entry: &Entry) -> ProtocolLoveResult<Address> {
+		Ok(hdk::commit_entry(entry)?)
+	}
+
+	fn update_entry(&self, entry: Entry, address: &Address) -> ProtocolLoveResult<Address> {
+		Ok(hdk::update_entry(entry, address)?)
+	}
+
+	fn link_entries(
+		&self,
+		base: &Address,
+		target: &Address,
+		link_type: &str,
+		tag: &str,
+	) -> ProtocolLoveResult<Address> {
+		Ok(hdk::link_entries(base, target, link_type, tag)?)
+	}
+
+	fn get_entry(&self, address: &Address) -> ProtocolLoveResult<Option<Entry>> {
+		Ok(hdk::get_entry(address)?)
+	}
+
+	fn get_entry_history(&self, address: &Address) -> ProtocolLoveResult<Vec<Entry>> {
+		Ok(match hdk::get_entry_history(address)? {
+			Some(history) => history.items.into_iter().filter_map(|item| item.entry).collect(),
+			None => vec![],
+		})
+	}
+
+	fn get_initial_address(&self, address: &Address) -> ProtocolLoveResult<Address> {
+		let mut version_address = address.clone();
+		loop {
+			let entry_result = hdk::get_entry_result(&version_address, GetEntryOptions {
+				status_request: StatusRequestKind::Initial,
+				headers: true,
+				..GetEntryOptions::default()
+			})?;
+			let replaced_address = match entry_result.result {
+				GetEntryResultType::Single(item) => item.headers.iter()
+					.find_map(|header| header.link_update_delete()),
+				GetEntryResultType::All(_) => None,
+			};
+			match replaced_address {
+				Some(replaced_address) => version_address = replaced_address,
+				None => return Ok(version_address),
+			}
+		}
+	}
+
+	fn emit_signal(&self, name: &str, payload: JsonString) -> ProtocolLoveResult<()> {
+		Ok(hdk::emit_signal(name, payload)?)
+	}
+
+	fn send_signal(&self, agent_address: &Address, payload: JsonString) -> ProtocolLoveResult<()> {
+		hdk::send(agent_address.clone(), payload.to_string(), SIGNAL_SEND_TIMEOUT_MS.into())?;
+		Ok(())
+	}
+
+	fn get_links(
+		&self,
+		base: &Address,
+		link_type: &str,
+		tag: Option<&str>,
+	) -> ProtocolLoveResult<Vec<Address>> {
+		let links = hdk::get_links_with_options(
+			base,
+			LinkMatch::Exactly(link_type),
+			match tag {
+				Some(tag) => LinkMatch::Exactly(tag),
+				None => LinkMatch::Any,
+			},
+			GetLinksOptions {
+				headers: true,
+				sort_order: Some(SortOrder::Descending),
+				..GetLinksOptions::default()
+			},
+		)?;
+		// The conductor does not guarantee the order, so the links are sorted by the time of
+		// the earliest header of their target. Entries replacing a former entry of the same
+		// content, like votes & delegations, carry a version to get their own address & header.
+		let mut timed_links = links.links().into_iter()
+			.map(|link| (link.headers.iter().map(|header| *header.timestamp()).min(), link.address))
+			.collect::<Vec<_>>();
+		timed_links.sort_by(|(time, _), (other_time, _)| other_time.cmp(time));
+		Ok(timed_links.into_iter().map(|(_, address)| address).collect())
+	}
+}
+
+/// Commits the `entry` unless an entry with the same content is already stored.
+///
+/// Makes the creation of content addressed entries safe to retry.
+pub fn commit_entry_if_missing(store: &dyn Store, entry: &Entry) -> ProtocolLoveResult<Address> {
+	let address = store.entry_address(entry)?;
+	match store.get_entry(&address)? {
+		Some(_) => Ok(address),
+		None => store.commit_entry(entry),
+	}
+}
+
+/// Gets the entry at `address` from the `store` as the type `R`.
+///
+/// Returns [ProtocolLoveError::NotFound](enum.ProtocolLoveError.html) with the `address`
+/// when the entry is missing.
+pub fn get_as_type<R: TryFrom<AppEntryValue>>(
+	store: &dyn Store,
+	address: &Address,
+) -> ProtocolLoveResult<R> {
+	entry_as_type(store.get_entry(address)?, address)
+}
+
+/// Gets the version of the entry committed at `address` as the type `R`, ignoring its updates.
+pub fn get_initial_as_type<R: TryFrom<AppEntryValue>>(
+	store: &dyn Store,
+	address: &Address,
+) -> ProtocolLoveResult<R> {
+	entry_as_type(store.get_entry_history(address)?.into_iter().next(), address)
+}
+
+fn entry_as_type<R: TryFrom<AppEntryValue>>(
+	entry: Option<Entry>,
+	address: &Address,
+) -> ProtocolLoveResult<R> {
+	match entry {
+		Some(Entry::App(_, entry_value)) =>
+			R::try_from(entry_value).map_err(|_|
+				ProtocolLoveError::invariant_broken(
+					&format!("Could not convert entry at {}", address))),
+		Some(_) => Err(ProtocolLoveError::invariant_broken(
+			&format!("Entry at {} is not an app entry", address))),
+		None => Err(ProtocolLoveError::not_found(address)),
+	}
+}
+
+/// Gets the targets of the `link_type` links from `base` as the type `R`.
+pub fn get_links_and_load_type<R: TryFrom<AppEntryValue>>(
+	store: &dyn Store,
+	base: &Address,
+	link_type: &str,
+	tag: Option<&str>,
+) -> ProtocolLoveResult<Vec<R>> {
+	store.get_links(base, link_type, tag)?
+		.iter()
+		.map(|address| get_as_type(store, address))
+		.collect()
+}
@@ -0,0 +1,221 @@
+use std::convert::TryFrom;
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::action::{Action, ActionOp, create_root_action, get_actions};
+use crate::collective::{
+	Collective, CollectivePayload, CreateCollectiveParams, initialise_collective,
+	is_collective_admin, is_collective_person,
+};
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::store::{Store, commit_entry_if_missing, get_as_type};
+use crate::utils::t;
+
+/// Api params to create a child [Collective](struct.Collective.html), e.g. a circle or a
+/// working group, of the collective at `parent_address`.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct SubCollectiveParams {
+	pub parent_address: Address,
+	/// The `admin_address` defaults to the admin of the parent.
+	pub collective: CreateCollectiveParams,
+	/// Use the decision policies of the parent instead of its own. Defaults to `true`.
+	pub inherit_policies: Option<bool>,
+}
+
+/// Api payload of a [Collective](struct.Collective.html) & its descendants.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct CollectiveTreePayload {
+	pub collective_address: Address,
+	pub collective: Collective,
+	/// Child collectives, oldest first.
+	pub children: Vec<CollectiveTreePayload>,
+}
+
+/// An [Action](struct.Action.html) of a child collective escalated to its parent.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct EscalatedAction {
+	/// The child collective of the action.
+	pub collective_address: Address,
+	pub action_address: Address,
+	pub action: Action,
+}
+
+/// Api payload of the [EscalatedActions](struct.EscalatedAction.html) of a
+/// [Collective](struct.Collective.html), oldest first.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct EscalatedActionsPayload {
+	pub collective_address: Address,
+	pub escalated_actions: Vec<EscalatedAction>,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct AddChildCollectiveActionData {
+	collective_address: Address,
+	collective: Collective,
+}
+
+/// Api for the admin of the parent to create a child [Collective](struct.Collective.html).
+///
+/// The people of the child collective must be people in the parent.
+pub fn create_sub_collective(
+	store: &dyn Store,
+	sub_collective_params: SubCollectiveParams,
+) -> ProtocolLoveResult<CollectivePayload> {
+	let SubCollectiveParams {
+		parent_address,
+		collective: collective_params,
+		inherit_policies,
+	} = sub_collective_params;
+	let parent: Collective = t("create_sub_collective: ", get_as_type(store, &parent_address))?;
+	if !t("create_sub_collective: ", is_collective_admin(store, &parent))? {
+		return Err(ProtocolLoveError::unauthorized(
+			"create_sub_collective: Only the admin can create child collectives"));
+	}
+	let admin_address = match collective_params.admin_address.clone().or(parent.admin_address) {
+		Some(admin_address) => admin_address,
+		None => return Err(ProtocolLoveError::invariant_broken(
+			"create_sub_collective: Collective has no admin")),
+	};
+	if !t("create_sub_collective: ", is_collective_person(store, &parent_address, &admin_address))? {
+		return Err(ProtocolLoveError::validation_failed(
+			"admin_address", "create_sub_collective: Admin must be a person in the parent collective"));
+	}
+	let collective_draft: Collective = collective_params.into();
+	let collective = Collective {
+		admin_address: Some(admin_address),
+		parent_address: Some(parent_address.clone()),
+		inherit_policies: inherit_policies.unwrap_or(true),
+		..collective_draft
+	};
+	let collective_entry = Entry::App("collective".into(), (&collective).into());
+	let collective_address =
+		t("create_sub_collective: ", commit_entry_if_missing(store, &collective_entry))?;
+	let payload = t("create_sub_collective: ", initialise_collective(
+		store,
+		collective_address.clone(),
+	))?;
+	let child_addresses = t("create_sub_collective: ", store.get_links(
+		&parent_address,
+		"collective->child_collective",
+		None,
+	))?;
+	if !child_addresses.contains(&collective_address) {
+		t("create_sub_collective: collective->child_collective: ", store.link_entries(
+			&parent_address,
+			&collective_address,
+			"collective->child_collective",
+			"",
+		))?;
+		t("create_sub_collective: ", create_root_action(
+			store,
+			&parent_address,
+			ActionOp::AddChildCollective,
+			AddChildCollectiveActionData {
+				collective_address,
+				collective,
+			}.into(),
+			serde_json::value::Value::Null.into(),
+			"create_sub_collective",
+		))?;
+	}
+	Ok(payload)
+}
+
+/// Api to get the [Collective](struct.Collective.html) at `collective_address` with its
+/// descendants.
+pub fn get_collective_tree(
+	store: &dyn Store,
+	collective_address: Address,
+) -> ProtocolLoveResult<CollectiveTreePayload> {
+	let collective: Collective = t("get_collective_tree: ", get_as_type(store, &collective_address))?;
+	let mut child_addresses = t("get_collective_tree: ", store.get_links(
+		&collective_address,
+		"collective->child_collective",
+		None,
+	))?;
+	child_addresses.reverse();
+	let children = child_addresses.into_iter()
+		.map(|child_address| get_collective_tree(store, child_address))
+		.collect::<ProtocolLoveResult<Vec<CollectiveTreePayload>>>()?;
+	Ok(CollectiveTreePayload {
+		collective_address,
+		collective,
+		children,
+	})
+}
+
+/// Api for the admin of a child collective to escalate its [Action](struct.Action.html) at
+/// `action_address` to the parent, recording an
+/// [ActionOp::EscalateAction](enum.ActionOp.html) in the action log of the parent.
+pub fn escalate_action(
+	store: &dyn Store,
+	collective_address: Address,
+	action_address: Address,
+) -> ProtocolLoveResult<EscalatedActionsPayload> {
+	let collective: Collective = t("escalate_action: ", get_as_type(store, &collective_address))?;
+	let parent_address = match collective.parent_address.clone() {
+		Some(parent_address) => parent_address,
+		None => return Err(ProtocolLoveError::conflict("escalate_action: Collective has no parent")),
+	};
+	if !t("escalate_action: ", is_collective_admin(store, &collective))? {
+		return Err(ProtocolLoveError::unauthorized(
+			"escalate_action: Only the admin can escalate actions"));
+	}
+	let action_addresses =
+		t("escalate_action: ", store.get_links(&collective_address, "collective->action", None))?;
+	if !action_addresses.contains(&action_address) {
+		return Err(ProtocolLoveError::validation_failed(
+			"action_address", "escalate_action: Action is not in the collective"));
+	}
+	let escalated_actions = t("escalate_action: ", get_parent_escalated_actions(store, &parent_address))?;
+	if escalated_actions.iter().any(|escalated_action| escalated_action.action_address == action_address) {
+		return Err(ProtocolLoveError::conflict("escalate_action: Action is already escalated"));
+	}
+	let action: Action = t("escalate_action: ", get_as_type(store, &action_address))?;
+	t("escalate_action: ", create_root_action(
+		store,
+		&parent_address,
+		ActionOp::EscalateAction,
+		EscalatedAction {
+			collective_address,
+			action_address,
+			action,
+		}.into(),
+		serde_json::value::Value::Null.into(),
+		"escalate_action",
+	))?;
+	get_escalated_actions(store, parent_address)
+}
+
+/// Api to get the [Actions](struct.Action.html) escalated to the
+/// [Collective](struct.Collective.html) by its children, oldest first.
+pub fn get_escalated_actions(
+	store: &dyn Store,
+	collective_address: Address,
+) -> ProtocolLoveResult<EscalatedActionsPayload> {
+	let escalated_actions =
+		t("get_escalated_actions: ", get_parent_escalated_actions(store, &collective_address))?;
+	Ok(EscalatedActionsPayload {
+		collective_address,
+		escalated_actions,
+	})
+}
+
+/// Reads the [ActionOp::EscalateAction](enum.ActionOp.html) actions of the collective,
+/// oldest first.
+fn get_parent_escalated_actions(
+	store: &dyn Store,
+	collective_address: &Address,
+) -> ProtocolLoveResult<Vec<EscalatedAction>> {
+	t("get_parent_escalated_actions: ", get_actions(store, collective_address.clone()))?
+		.actions
+		.into_iter()
+		.filter(|action| action.op == ActionOp::EscalateAction)
+		.map(|action| EscalatedAction::try_from(action.data).map_err(|_|
+			ProtocolLoveError::invariant_broken(
+				"get_parent_escalated_actions: Could not read the escalated action")))
+		.collect()
+}
@@ -0,0 +1,377 @@
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use crate::objection::{Objection, ObjectionKind};
+use crate::proposal::TallyMethod;
+use crate::vote::Ballot;
+
+/// The tally of a [Proposal](struct.Proposal.html), computed with its
+/// [TallyMethod](enum.TallyMethod.html).
+///
+/// Options are referred to by their index in the `options` of the proposal.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq, Default)]
+pub struct ProposalTally {
+	/// Count of [Ballot::Yes](enum.Ballot.html) with [TallyMethod::Majority](enum.TallyMethod.html).
+	pub yes: u64,
+	/// Count of [Ballot::No](enum.Ballot.html) with [TallyMethod::Majority](enum.TallyMethod.html).
+	pub no: u64,
+	/// Count of [Ballot::Abstain](enum.Ballot.html) with [TallyMethod::Majority](enum.TallyMethod.html).
+	pub abstain: u64,
+	/// Count of the ballots cast by delegates on behalf of the people who did not vote.
+	#[serde(default)]
+	pub delegated: u64,
+	/// Round by round breakdown of a multi-option tally.
+	#[serde(default)]
+	pub rounds: Vec<TallyRound>,
+	/// Indexes of the winning options. Holds several options on a tie.
+	#[serde(default)]
+	pub winners: Vec<usize>,
+	/// With [TallyMethod::Schulze](enum.TallyMethod.html), `pairwise[i][j]` is the number of
+	/// voters preferring option `i` over option `j`.
+	#[serde(default)]
+	pub pairwise: Vec<Vec<u64>>,
+	/// With [TallyMethod::Schulze](enum.TallyMethod.html), `strongest_paths[i][j]` is the
+	/// strength of the strongest path from option `i` to option `j`.
+	#[serde(default)]
+	pub strongest_paths: Vec<Vec<u64>>,
+	/// Count of the unresolved objections with [TallyMethod::Consent](enum.TallyMethod.html).
+	#[serde(default)]
+	pub objections: u64,
+	/// Count of the concerns with [TallyMethod::Consent](enum.TallyMethod.html).
+	#[serde(default)]
+	pub concerns: u64,
+}
+
+/// The outcome of a closed [Proposal](struct.Proposal.html), read from its final tally.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub enum ProposalOutcome {
+	/// The proposal passed, or a single option won.
+	Passed,
+	/// The proposal was rejected, or no option won.
+	Rejected,
+	/// Several options won with the same score.
+	Tied,
+}
+
+/// A round of a multi-option tally.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq, Default)]
+pub struct TallyRound {
+	/// Score of each option in the round, indexed like the options of the proposal.
+	pub scores: Vec<u64>,
+	/// Indexes of the options eliminated at the end of the round.
+	pub eliminated: Vec<usize>,
+}
+
+/// Counts the yes, no & abstain ballots.
+pub fn tally_majority(ballots: &[Ballot]) -> ProposalTally {
+	let mut tally = ProposalTally::default();
+	for ballot in ballots {
+		match ballot {
+			Ballot::Yes => tally.yes += 1,
+			Ballot::No => tally.no += 1,
+			Ballot::Abstain => tally.abstain += 1,
+			_ => {}
+		}
+	}
+	tally
+}
+
+/// Each approved option scores one point. The options with the most approvals win.
+pub fn tally_approval(option_count: usize, ballots: &[Ballot]) -> ProposalTally {
+	let mut scores = vec![0; option_count];
+	for ballot in ballots {
+		if let Ballot::Approve(options) = ballot {
+			for option in options {
+				scores[*option] += 1;
+			}
+		}
+	}
+	single_round_tally(scores)
+}
+
+/// Instant-runoff: the first preferences among the remaining options are counted each round.
+///
+/// An option with a majority of the counted ballots wins. Otherwise the options with the
+/// fewest votes are eliminated & their ballots move to the next preference.
+pub fn tally_ranked_choice(option_count: usize, ballots: &[Ballot]) -> ProposalTally {
+	let rankings = rankings(ballots);
+	let mut remaining: Vec<usize> = (0..option_count).collect();
+	let mut rounds = vec![];
+	let mut winners = vec![];
+	while !remaining.is_empty() {
+		let mut scores = vec![0; option_count];
+		for ranking in &rankings {
+			if let Some(option) = ranking.iter().find(|option| remaining.contains(option)) {
+				scores[*option] += 1;
+			}
+		}
+		let counted: u64 = scores.iter().sum();
+		let max_score = remaining.iter().map(|option| scores[*option]).max().unwrap_or(0);
+		let min_score = remaining.iter().map(|option| scores[*option]).min().unwrap_or(0);
+		if max_score * 2 > counted || max_score == min_score {
+			winners = remaining.iter()
+				.filter(|option| scores[**option] == max_score)
+				.cloned()
+				.collect();
+			rounds.push(TallyRound { scores, eliminated: vec![] });
+			break;
+		}
+		let eliminated: Vec<usize> = remaining.iter()
+			.filter(|option| scores[**option] == min_score)
+			.cloned()
+			.collect();
+		remaining.retain(|option| !eliminated.contains(option));
+		rounds.push(TallyRound { scores, eliminated });
+	}
+	ProposalTally {
+		rounds,
+		winners,
+		..ProposalTally::default()
+	}
+}
+
+/// Each vote on an option scores one point. The options with the most votes win.
+///
+/// The quadratic cost of the votes is paid with voice credits when the ballot is cast.
+pub fn tally_quadratic(option_count: usize, ballots: &[Ballot]) -> ProposalTally {
+	let mut scores = vec![0; option_count];
+	for ballot in ballots {
+		if let Ballot::Quadratic(votes) = ballot {
+			for (option, votes) in votes.iter().enumerate() {
+				scores[option] += votes;
+			}
+		}
+	}
+	single_round_tally(scores)
+}
+
+/// Borda count: with `n` options, an option ranked at position `i` scores `n - 1 - i` points.
+/// Unranked options score no points.
+pub fn tally_borda(option_count: usize, ballots: &[Ballot]) -> ProposalTally {
+	let mut scores = vec![0; option_count];
+	for ranking in rankings(ballots) {
+		for (position, option) in ranking.iter().enumerate() {
+			scores[*option] += (option_count - 1 - position) as u64;
+		}
+	}
+	single_round_tally(scores)
+}
+
+/// Schulze method: the options beating or tying every other option on the strongest
+/// paths of pairwise preferences win.
+///
+/// Ranked options are preferred over unranked options. The round scores are the number
+/// of options each option beats.
+pub fn tally_schulze(option_count: usize, ballots: &[Ballot]) -> ProposalTally {
+	let mut pairwise = vec![vec![0; option_count]; option_count];
+	for ranking in rankings(ballots) {
+		for (position, preferred) in ranking.iter().enumerate() {
+			for other in 0..option_count {
+				if other != *preferred && !ranking[..position].contains(&other) {
+					pairwise[*preferred][other] += 1;
+				}
+			}
+		}
+	}
+	let mut strongest_paths = vec![vec![0; option_count]; option_count];
+	for i in 0..option_count {
+		for j in 0..option_count {
+			if i != j && pairwise[i][j] > pairwise[j][i] {
+				strongest_paths[i][j] = pairwise[i][j];
+			}
+		}
+	}
+	for k in 0..option_count {
+		for i in 0..option_count {
+			for j in 0..option_count {
+				if i != j && i != k && j != k {
+					let through_k = strongest_paths[i][k].min(strongest_paths[k][j]);
+					if through_k > strongest_paths[i][j] {
+						strongest_paths[i][j] = through_k;
+					}
+				}
+			}
+		}
+	}
+	let scores: Vec<u64> = (0..option_count)
+		.map(|i| (0..option_count)
+			.filter(|j| strongest_paths[i][*j] > strongest_paths[*j][i])
+			.count() as u64)
+		.collect();
+	let winners = (0..option_count)
+		.filter(|i| (0..option_count).all(|j| strongest_paths[*i][j] >= strongest_paths[j][*i]))
+		.collect();
+	ProposalTally {
+		rounds: vec![TallyRound { scores, eliminated: vec![] }],
+		winners,
+		pairwise,
+		strongest_paths,
+		..ProposalTally::default()
+	}
+}
+
+/// Counts the unresolved objections blocking the consent & the concerns.
+pub fn tally_consent(objections: &[Objection]) -> ProposalTally {
+	let mut tally = ProposalTally::default();
+	for objection in objections {
+		match objection.kind {
+			ObjectionKind::Objection if !objection.resolved => tally.objections += 1,
+			ObjectionKind::Objection => {}
+			ObjectionKind::Concern => tally.concerns += 1,
+		}
+	}
+	tally
+}
+
+/// Reads the outcome of the `tally` counted with the `tally_method`.
+///
+/// A majority passes with more yes than no. A consent passes without unresolved objections.
+/// A conviction proposal is only passed by funding it.
+pub fn tally_outcome(tally_method: &TallyMethod, tally: &ProposalTally) -> ProposalOutcome {
+	match tally_method {
+		TallyMethod::Majority if tally.yes > tally.no => ProposalOutcome::Passed,
+		TallyMethod::Majority | TallyMethod::Conviction => ProposalOutcome::Rejected,
+		TallyMethod::Consent if tally.objections == 0 => ProposalOutcome::Passed,
+		TallyMethod::Consent => ProposalOutcome::Rejected,
+		_ => match tally.winners.len() {
+			0 => ProposalOutcome::Rejected,
+			1 => ProposalOutcome::Passed,
+			_ => ProposalOutcome::Tied,
+		},
+	}
+}
+
+fn single_round_tally(scores: Vec<u64>) -> ProposalTally {
+	let max_score = scores.iter().cloned().max().unwrap_or(0);
+	let winners = (0..scores.len()).filter(|option| scores[*option] == max_score).collect();
+	ProposalTally {
+		rounds: vec![TallyRound { scores, eliminated: vec![] }],
+		winners,
+		..ProposalTally::default()
+	}
+}
+
+fn rankings(ballots: &[Ballot]) -> Vec<&Vec<usize>> {
+	ballots.iter()
+		.filter_map(|ballot| match ballot {
+			Ballot::Rank(options) => Some(options),
+			_ => None,
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::vote::Ballot;
+	use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+	use crate::objection::{Objection, ObjectionKind};
+	use crate::proposal::TallyMethod;
+	use super::{
+		ProposalOutcome, ProposalTally, TallyRound, tally_approval, tally_borda, tally_consent,
+		tally_majority, tally_outcome, tally_quadratic, tally_ranked_choice, tally_schulze,
+	};
+
+	fn rank(count: usize, options: &[usize]) -> Vec<Ballot> {
+		vec![Ballot::Rank(options.to_vec()); count]
+	}
+
+	#[test]
+	fn approval_counts_each_approved_option() {
+		let tally = tally_approval(3, &[
+			Ballot::Approve(vec![0, 1]),
+			Ballot::Approve(vec![1]),
+			Ballot::Approve(vec![2, 1]),
+		]);
+		assert_eq!(tally.rounds, vec![TallyRound { scores: vec![1, 3, 1], eliminated: vec![] }]);
+		assert_eq!(tally.winners, vec![1]);
+	}
+
+	#[test]
+	fn ranked_choice_transfers_eliminated_ballots() {
+		let ballots = [rank(4, &[0, 1]), rank(3, &[1, 0]), rank(2, &[2, 1])].concat();
+		let tally = tally_ranked_choice(3, &ballots);
+		assert_eq!(tally.rounds, vec![
+			TallyRound { scores: vec![4, 3, 2], eliminated: vec![2] },
+			TallyRound { scores: vec![4, 5, 0], eliminated: vec![] },
+		]);
+		assert_eq!(tally.winners, vec![1]);
+	}
+
+	#[test]
+	fn ranked_choice_returns_a_tie() {
+		let ballots = [rank(2, &[0]), rank(2, &[1])].concat();
+		assert_eq!(tally_ranked_choice(2, &ballots).winners, vec![0, 1]);
+	}
+
+	#[test]
+	fn quadratic_sums_the_votes_on_each_option() {
+		let tally = tally_quadratic(2, &[
+			Ballot::Quadratic(vec![3, 0]),
+			Ballot::Quadratic(vec![1, 2]),
+			Ballot::Quadratic(vec![0, 1]),
+		]);
+		assert_eq!(tally.rounds[0].scores, vec![4, 3]);
+		assert_eq!(tally.winners, vec![0]);
+	}
+
+	#[test]
+	fn borda_scores_positions() {
+		let ballots = [rank(3, &[0, 1, 2]), rank(2, &[1, 2, 0])].concat();
+		let tally = tally_borda(3, &ballots);
+		assert_eq!(tally.rounds[0].scores, vec![6, 7, 2]);
+		assert_eq!(tally.winners, vec![1]);
+	}
+
+	#[test]
+	fn consent_counts_unresolved_objections_and_concerns() {
+		let objection = |kind: ObjectionKind, resolved: bool| Objection {
+			proposal_address: Address::from("proposal"),
+			person_address: Address::from("person"),
+			kind,
+			reason: "Too expensive".into(),
+			resolved,
+		};
+		let tally = tally_consent(&[
+			objection(ObjectionKind::Objection, true),
+			objection(ObjectionKind::Objection, false),
+			objection(ObjectionKind::Concern, false),
+			objection(ObjectionKind::Concern, true),
+		]);
+		assert_eq!((tally.objections, tally.concerns), (1, 2));
+	}
+
+	#[test]
+	fn outcome_is_read_from_the_tally_of_the_method() {
+		let tally = tally_majority(&[Ballot::Yes, Ballot::No, Ballot::Abstain]);
+		assert_eq!(tally_outcome(&TallyMethod::Majority, &tally), ProposalOutcome::Rejected);
+		let tally = tally_majority(&[Ballot::Yes, Ballot::Yes, Ballot::No]);
+		assert_eq!(tally_outcome(&TallyMethod::Majority, &tally), ProposalOutcome::Passed);
+		let tally = tally_approval(3, &[Ballot::Approve(vec![0, 1])]);
+		assert_eq!(tally_outcome(&TallyMethod::Approval, &tally), ProposalOutcome::Tied);
+		let tally = tally_approval(3, &[Ballot::Approve(vec![0, 1]), Ballot::Approve(vec![1])]);
+		assert_eq!(tally_outcome(&TallyMethod::Approval, &tally), ProposalOutcome::Passed);
+		let tally = ProposalTally { objections: 1, ..ProposalTally::default() };
+		assert_eq!(tally_outcome(&TallyMethod::Consent, &tally), ProposalOutcome::Rejected);
+	}
+
+	#[test]
+	fn schulze_resolves_a_cycle_with_strongest_paths() {
+		// Wikipedia example with options A, B, C, D & E.
+		let ballots = [
+			rank(5, &[0, 2, 1, 4, 3]),
+			rank(5, &[0, 3, 4, 2, 1]),
+			rank(8, &[1, 4, 3, 0, 2]),
+			rank(3, &[2, 0, 1, 4, 3]),
+			rank(7, &[2, 0, 4, 1, 3]),
+			rank(2, &[2, 1, 0, 3, 4]),
+			rank(7, &[3, 2, 4, 1, 0]),
+			rank(8, &[4, 1, 0, 3, 2]),
+		].concat();
+		let tally = tally_schulze(5, &ballots);
+		assert_eq!(tally.pairwise[0][1], 20);
+		assert_eq!(tally.strongest_paths[4], vec![25, 28, 28, 31, 0]);
+		assert_eq!(tally.rounds[0].scores, vec![3, 1, 2, 0, 4]);
+		assert_eq!(tally.winners, vec![4]);
+	}
+}
@@ -0,0 +1,388 @@
+use std::collections::BTreeMap;
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use hdk::holochain_core_types::dna::entry_types::Sharing;
+use hdk::prelude::ValidatingEntryType;
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use serde_json::Value;
+use crate::action::{ActionOp, create_root_action};
+use crate::collective::{Collective, is_collective_admin};
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::person::Person;
+use crate::proposal::{BallotMode, ProposalParams, TallyMethod};
+use crate::store::{Store, HdkStore, get_as_type};
+use crate::utils::t;
+use crate::validation::{EntryValidation, ValidationContext, from_hdk_validation_data};
+
+/// A template registered by a [Collective](struct.Collective.html) for a recurring kind of
+/// [Proposal](struct.Proposal.html), e.g. admitting a member or approving a budget.
+///
+/// The name & content of the proposal are rendered from the typed values of the `fields`,
+/// replacing each `{field}` placeholder of the `name_template` & `content_template`.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct ProposalTemplate {
+	pub collective_address: Address,
+	/// Name of the template.
+	pub name: String,
+	pub description: String,
+	/// Fields filled in when creating a proposal from the template.
+	pub fields: Vec<TemplateField>,
+	/// Name of the proposal with `{field}` placeholders.
+	pub name_template: String,
+	/// Content of the proposal with `{field}` placeholders.
+	pub content_template: String,
+	/// Op of the action the proposal decides on. The values of the fields are its `data`.
+	pub action_op: Option<ActionOp>,
+	/// Default [BallotMode](enum.BallotMode.html) of the proposals.
+	pub ballot_mode: BallotMode,
+	/// Default [TallyMethod](enum.TallyMethod.html) of the proposals.
+	pub tally_method: TallyMethod,
+	/// Default options of the proposals.
+	pub options: Vec<String>,
+	/// Default tags of the proposals.
+	pub tags: Vec<String>,
+}
+
+/// A typed field of a [ProposalTemplate](struct.ProposalTemplate.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct TemplateField {
+	pub name: String,
+	pub field_type: TemplateFieldType,
+	/// Whether a value is required to create a proposal.
+	pub required: bool,
+}
+
+/// The type of the value of a [TemplateField](struct.TemplateField.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub enum TemplateFieldType {
+	/// A JSON string.
+	Text,
+	/// A JSON unsigned integer.
+	Number,
+	/// The address of an existing entry, e.g. a [Person](struct.Person.html).
+	Address,
+	/// A JSON array of strings, e.g. the ops of a
+	/// [CriticalActionPolicy](struct.CriticalActionPolicy.html).
+	List,
+}
+
+/// The [ProposalTemplate](struct.ProposalTemplate.html) a [Proposal](struct.Proposal.html) is
+/// created from, with the values of its fields.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct ProposalTemplateValues {
+	pub template_address: Address,
+	/// Values of the fields of the template, by field name.
+	pub values: BTreeMap<String, Value>,
+	/// Op of the action the proposal decides on, from the template.
+	pub action_op: Option<ActionOp>,
+}
+
+/// Api params for [create_proposal_template](fn.create_proposal_template.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ProposalTemplateParams {
+	pub collective_address: Address,
+	pub name: String,
+	pub description: Option<String>,
+	pub fields: Vec<TemplateField>,
+	pub name_template: String,
+	pub content_template: String,
+	pub action_op: Option<ActionOp>,
+	/// Optional ballot mode defaults to [BallotMode::Open](enum.BallotMode.html).
+	pub ballot_mode: Option<BallotMode>,
+	/// Optional tally method defaults to [TallyMethod::Majority](enum.TallyMethod.html).
+	pub tally_method: Option<TallyMethod>,
+	pub options: Option<Vec<String>>,
+	pub tags: Option<Vec<String>>,
+}
+
+/// Api payload containing the `template_address` & [template](struct.ProposalTemplate.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ProposalTemplatePayload {
+	pub template_address: Address,
+	pub template: ProposalTemplate,
+}
+
+/// Api payload of the [ProposalTemplates](struct.ProposalTemplate.html) of a
+/// [Collective](struct.Collective.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct CollectiveTemplatesPayload {
+	pub collective_address: Address,
+	pub templates: Vec<ProposalTemplatePayload>,
+}
+
+/// Returns a Holochain entry definition for a proposal template.
+pub fn proposal_template_def() -> ValidatingEntryType {
+	entry!(
+		name: "proposal_template",
+		description: "A protocol.love template of a recurring proposal",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<ProposalTemplate>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_proposal_template_entry(&HdkStore, validation, &context)?)
+		}
+	)
+}
+
+/// Validation rules of a [ProposalTemplate](struct.ProposalTemplate.html) entry.
+///
+/// Only the admin of the collective registers templates. A template is never changed, so the
+/// proposals created from it keep their meaning.
+pub fn validate_proposal_template_entry(
+	store: &dyn Store,
+	validation: EntryValidation<ProposalTemplate>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let collective: Collective = t("validation error: proposal_template: fetch collective: ",
+				get_as_type(store, &entry.collective_address))?;
+			let admin_agent_address = match &collective.admin_address {
+				Some(admin_address) => {
+					let admin: Person = t("validation error: proposal_template: fetch admin: ",
+						get_as_type(store, admin_address))?;
+					Some(admin.agent_address)
+				}
+				None => None,
+			};
+			match admin_agent_address {
+				Some(agent_address) if context.sources.contains(&agent_address) => {}
+				_ => return Err(ProtocolLoveError::unauthorized(
+					"Only the admin can register proposal templates")),
+			}
+			if entry.name.trim().is_empty() || entry.name.len() > 64 {
+				return Err(ProtocolLoveError::validation_failed(
+					"name", "Name must be between 1 & 64 characters"));
+			}
+			let mut field_names: Vec<&str> = vec![];
+			for field in &entry.fields {
+				if field.name.trim().is_empty() || field_names.contains(&field.name.as_str()) {
+					return Err(ProtocolLoveError::validation_failed(
+						"fields", "Fields must have distinct names"));
+				}
+				field_names.push(&field.name);
+			}
+			Ok(())
+		}
+		EntryValidation::Modify { .. } =>
+			Err(ProtocolLoveError::unauthorized("Proposal template cannot be modified")),
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Proposal template cannot be deleted")),
+	}
+}
+
+/// Validates the `values` are known fields of the `template`, of the field type,
+/// & the required fields have a value.
+pub fn validate_template_values(
+	store: &dyn Store,
+	template: &ProposalTemplate,
+	values: &BTreeMap<String, Value>,
+) -> ProtocolLoveResult<()> {
+	for name in values.keys() {
+		if !template.fields.iter().any(|field| &field.name == name) {
+			return Err(ProtocolLoveError::validation_failed(
+				"template_values", &format!("Unknown template field {}", name)));
+		}
+	}
+	for field in &template.fields {
+		let value = match values.get(&field.name) {
+			Some(Value::Null) | None if field.required =>
+				return Err(ProtocolLoveError::validation_failed(
+					"template_values", &format!("Template field {} is required", field.name))),
+			Some(Value::Null) | None => continue,
+			Some(value) => value,
+		};
+		let is_typed = match (&field.field_type, value) {
+			(TemplateFieldType::Text, Value::String(_)) => true,
+			(TemplateFieldType::Number, Value::Number(number)) => number.is_u64(),
+			(TemplateFieldType::Address, Value::String(address)) =>
+				store.get_entry(&Address::from(address.as_str()))?.is_some(),
+			(TemplateFieldType::List, Value::Array(values)) => values.iter().all(Value::is_string),
+			_ => false,
+		};
+		if !is_typed {
+			return Err(ProtocolLoveError::validation_failed(
+				"template_values",
+				&format!("Template field {} must be {:?}", field.name, field.field_type)));
+		}
+	}
+	Ok(())
+}
+
+/// Replaces each `{field}` placeholder of the `text` with the value of the field.
+/// Fields without a value are replaced with an empty string.
+pub fn render_template(
+	text: &str,
+	fields: &[TemplateField],
+	values: &BTreeMap<String, Value>,
+) -> String {
+	fields.iter().fold(text.to_string(), |rendered, field| {
+		let value = match values.get(&field.name) {
+			Some(Value::String(value)) => value.clone(),
+			Some(Value::Null) | None => "".to_string(),
+			Some(value) => value.to_string(),
+		};
+		rendered.replace(&format!("{{{}}}", field.name), &value)
+	})
+}
+
+/// Fills in the [ProposalParams](struct.ProposalParams.html) from the template at the
+/// `template_address` of the params: renders the name & content from the typed
+/// `template_values` & defaults the decision policy to the one of the template.
+///
+/// Content given along a template is appended to the rendered content.
+pub fn apply_proposal_template(
+	store: &dyn Store,
+	proposal_params: ProposalParams,
+) -> ProtocolLoveResult<(ProposalParams, Option<ProposalTemplateValues>)> {
+	let template_address = match &proposal_params.template_address {
+		Some(template_address) => template_address.clone(),
+		None if proposal_params.template_values.is_some() =>
+			return Err(ProtocolLoveError::validation_failed(
+				"template_values", "apply_proposal_template: Template values need a template")),
+		None => return Ok((proposal_params, None)),
+	};
+	let template: ProposalTemplate =
+		t("apply_proposal_template: ", get_as_type(store, &template_address))?;
+	if template.collective_address != proposal_params.collective_address {
+		return Err(ProtocolLoveError::validation_failed(
+			"template_address", "apply_proposal_template: Template is not registered in the collective"));
+	}
+	let values = proposal_params.template_values.clone().unwrap_or_default();
+	t("apply_proposal_template: ", validate_template_values(store, &template, &values))?;
+	let name = if proposal_params.name.trim().is_empty() {
+		render_template(&template.name_template, &template.fields, &values)
+	} else {
+		proposal_params.name.clone()
+	};
+	let rendered_content = render_template(&template.content_template, &template.fields, &values);
+	let content = if proposal_params.content.trim().is_empty() {
+		rendered_content
+	} else {
+		format!("{}\n\n{}", rendered_content, proposal_params.content)
+	};
+	let options = match proposal_params.options.clone() {
+		None if !template.options.is_empty() => Some(template.options.clone()),
+		options => options,
+	};
+	let tags = match proposal_params.tags.clone() {
+		None if !template.tags.is_empty() => Some(template.tags.clone()),
+		tags => tags,
+	};
+	Ok((ProposalParams {
+		name,
+		content,
+		ballot_mode: proposal_params.ballot_mode.clone().or(Some(template.ballot_mode)),
+		tally_method: proposal_params.tally_method.clone().or(Some(template.tally_method)),
+		options,
+		tags,
+		..proposal_params
+	}, Some(ProposalTemplateValues {
+		template_address,
+		values,
+		action_op: template.action_op,
+	})))
+}
+
+/// Api for the admin to register a [ProposalTemplate](struct.ProposalTemplate.html) in the
+/// [Collective](struct.Collective.html).
+pub fn create_proposal_template(
+	store: &dyn Store,
+	template_params: ProposalTemplateParams,
+) -> ProtocolLoveResult<ProposalTemplatePayload> {
+	let collective: Collective =
+		t("create_proposal_template: ", get_as_type(store, &template_params.collective_address))?;
+	if !t("create_proposal_template: ", is_collective_admin(store, &collective))? {
+		return Err(ProtocolLoveError::unauthorized(
+			"create_proposal_template: Only the admin can register proposal templates"));
+	}
+	let template = ProposalTemplate {
+		collective_address: template_params.collective_address,
+		name: template_params.name,
+		description: template_params.description.unwrap_or_default(),
+		fields: template_params.fields,
+		name_template: template_params.name_template,
+		content_template: template_params.content_template,
+		action_op: template_params.action_op,
+		ballot_mode: template_params.ballot_mode.unwrap_or(BallotMode::Open),
+		tally_method: template_params.tally_method.unwrap_or_default(),
+		options: template_params.options.unwrap_or_default(),
+		tags: template_params.tags.unwrap_or_default(),
+	};
+	let template_entry = Entry::App("proposal_template".into(), (&template).into());
+	let template_address = t("create_proposal_template: ", store.commit_entry(&template_entry))?;
+	t("create_proposal_template: collective->proposal_template: ", store.link_entries(
+		&template.collective_address,
+		&template_address,
+		"collective->proposal_template",
+		"",
+	))?;
+	let template_payload = ProposalTemplatePayload {
+		template_address,
+		template,
+	};
+	t("create_proposal_template: ", create_root_action(
+		store,
+		&template_payload.template.collective_address,
+		ActionOp::CreateProposalTemplate,
+		template_payload.clone().into(),
+		serde_json::value::Value::Null.into(),
+		"create_proposal_template",
+	))?;
+	Ok(template_payload)
+}
+
+/// Api to get the [ProposalTemplates](struct.ProposalTemplate.html) of a
+/// [Collective](struct.Collective.html), the oldest first.
+pub fn get_collective_templates(
+	store: &dyn Store,
+	collective_address: Address,
+) -> ProtocolLoveResult<CollectiveTemplatesPayload> {
+	let mut template_addresses = t("get_collective_templates: ", store.get_links(
+		&collective_address,
+		"collective->proposal_template",
+		None,
+	))?;
+	template_addresses.reverse();
+	let templates: Vec<ProposalTemplate> =
+		t("get_collective_templates: ", template_addresses.iter()
+			.map(|template_address| get_as_type(store, template_address))
+			.collect::<ProtocolLoveResult<Vec<ProposalTemplate>>>())?;
+	Ok(CollectiveTemplatesPayload {
+		collective_address,
+		templates: template_addresses.into_iter()
+			.zip(templates)
+			.map(|(template_address, template)| ProposalTemplatePayload {
+				template_address,
+				template,
+			})
+			.collect(),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeMap;
+	use serde_json::Value;
+	use super::{TemplateField, TemplateFieldType, render_template};
+
+	#[test]
+	fn render_template_replaces_the_placeholders_of_the_fields() {
+		let fields = vec![
+			TemplateField { name: "name".into(), field_type: TemplateFieldType::Text, required: true },
+			TemplateField { name: "amount".into(), field_type: TemplateFieldType::Number, required: false },
+			TemplateField { name: "note".into(), field_type: TemplateFieldType::Text, required: false },
+		];
+		let mut values = BTreeMap::new();
+		values.insert("name".to_string(), Value::from("Garden"));
+		values.insert("amount".to_string(), Value::from(120));
+		assert_eq!(
+			render_template("Fund {name} with {amount}{note} ({unknown})", &fields, &values),
+			"Fund Garden with 120 ({unknown})");
+	}
+}
@@ -0,0 +1,242 @@
+use std::convert::TryFrom;
+use chrono::DateTime;
+use hdk::{EntryValidationData, LinkValidationData, ValidationData};
+use holochain_wasm_utils::holochain_core_types::entry::AppEntryValue;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::store::Store;
+
+/// An operation on an entry being validated.
+///
+/// Independent of Holochain so the validation rules can run against any [Store](trait.Store.html).
+pub enum EntryValidation<T> {
+	Create {
+		entry: T,
+	},
+	Modify {
+		new_entry: T,
+		old_entry: T,
+	},
+	Delete {
+		old_entry: T,
+	},
+}
+
+impl<T> EntryValidation<T> {
+	/// Converts the entries into the type `R`.
+	pub fn try_map<R: TryFrom<T>>(self) -> ProtocolLoveResult<EntryValidation<R>> {
+		self.try_map_with(|entry| R::try_from(entry).map_err(|_|
+			ProtocolLoveError::invariant_broken("Could not convert the entry being validated")))
+	}
+
+	/// Converts the entries with the `convert` function.
+	pub fn try_map_with<R, F: Fn(T) -> ProtocolLoveResult<R>>(
+		self,
+		convert: F,
+	) -> ProtocolLoveResult<EntryValidation<R>> {
+		Ok(match self {
+			EntryValidation::Create { entry } =>
+				EntryValidation::Create { entry: convert(entry)? },
+			EntryValidation::Modify { new_entry, old_entry } =>
+				EntryValidation::Modify {
+					new_entry: convert(new_entry)?,
+					old_entry: convert(old_entry)?,
+				},
+			EntryValidation::Delete { old_entry } =>
+				EntryValidation::Delete { old_entry: convert(old_entry)? },
+		})
+	}
+}
+
+/// A link being added or removed, validated like an [EntryValidation](enum.EntryValidation.html).
+pub struct LinkValidation {
+	pub base: Address,
+	pub target: Address,
+	pub link_type: String,
+	pub tag: String,
+}
+
+/// Maximum difference in seconds between a timestamp in an entry & the time of the commit.
+pub const MAX_CLOCK_DRIFT_SECS: u64 = 300;
+
+/// Context of an [EntryValidation](enum.EntryValidation.html).
+pub struct ValidationContext {
+	/// Agents who signed the entry.
+	pub sources: Vec<Address>,
+	/// Time of the commit in seconds since the unix epoch, from the header of the entry.
+	pub timestamp: u64,
+}
+
+/// Validates the `timestamp` of the `field` is within
+/// [MAX_CLOCK_DRIFT_SECS](constant.MAX_CLOCK_DRIFT_SECS.html) of the commit.
+pub fn validate_timestamp(
+	field: &str,
+	timestamp: u64,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let drift =
+		if timestamp > context.timestamp { timestamp - context.timestamp }
+		else { context.timestamp - timestamp };
+	if drift > MAX_CLOCK_DRIFT_SECS {
+		return Err(ProtocolLoveError::validation_failed(
+			field, "Timestamp is too far from the time of the commit"));
+	}
+	Ok(())
+}
+
+/// Converts the `hdk::EntryValidationData` into an [EntryValidation](enum.EntryValidation.html)
+/// & [ValidationContext](struct.ValidationContext.html).
+pub fn from_hdk_validation_data<T>(
+	validation_data: EntryValidationData<T>
+) -> (EntryValidation<T>, ValidationContext) {
+	match validation_data {
+		EntryValidationData::Create { entry, validation_data } => (
+			EntryValidation::Create { entry },
+			hdk_validation_context(&validation_data),
+		),
+		EntryValidationData::Modify { new_entry, old_entry, validation_data, .. } => (
+			EntryValidation::Modify { new_entry, old_entry },
+			hdk_validation_context(&validation_data),
+		),
+		EntryValidationData::Delete { old_entry, validation_data, .. } => (
+			EntryValidation::Delete { old_entry },
+			hdk_validation_context(&validation_data),
+		),
+	}
+}
+
+/// Converts the `hdk::LinkValidationData` into a [LinkValidation](struct.LinkValidation.html)
+/// & [ValidationContext](struct.ValidationContext.html).
+pub fn from_hdk_link_validation_data(
+	validation_data: LinkValidationData
+) -> (LinkValidation, ValidationContext) {
+	let (link_data, validation_data) = match validation_data {
+		LinkValidationData::LinkAdd { link, validation_data } => (link, validation_data),
+		LinkValidationData::LinkRemove { link, validation_data } => (link, validation_data),
+	};
+	let link = link_data.link();
+	(
+		LinkValidation {
+			base: link.base().clone(),
+			target: link.target().clone(),
+			link_type: link.link_type().clone(),
+			tag: link.tag().clone(),
+		},
+		hdk_validation_context(&validation_data),
+	)
+}
+
+fn hdk_validation_context(validation_data: &ValidationData) -> ValidationContext {
+	let header_time = validation_data.package.chain_header.timestamp().to_string();
+	ValidationContext {
+		sources: validation_data.sources(),
+		timestamp: DateTime::parse_from_rfc3339(&header_time)
+			.map(|time| time.timestamp() as u64)
+			.unwrap_or_default(),
+	}
+}
+
+/// Runs the validation rules of the app entry with the `entry_type`.
+///
+/// Used by the [MemoryStore](struct.MemoryStore.html) to validate like a conductor.
+pub fn validate_app_entry(
+	store: &dyn Store,
+	entry_type: &str,
+	validation: EntryValidation<AppEntryValue>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match entry_type {
+		"action" =>
+			crate::action::validate_action_entry(store, validation.try_map()?, context),
+		"amendment" =>
+			crate::amendment::validate_amendment_entry(store, validation.try_map()?, context),
+		"charter" =>
+			crate::charter::validate_charter_entry(store, validation.try_map()?, context),
+		"collective" =>
+			crate::collective::validate_collective_entry(store, validation.try_map()?, context),
+		"conviction_stake" =>
+			crate::conviction::validate_conviction_stake_entry(store, validation.try_map()?, context),
+		"critical_action" =>
+			crate::critical_action::validate_critical_action_entry(store, validation.try_map()?, context),
+		"delegation" =>
+			crate::delegation::validate_delegation_entry(store, validation.try_map()?, context),
+		"election" =>
+			crate::election::validate_election_entry(store, validation.try_map()?, context),
+		"ledger" =>
+			crate::ledger::validate_ledger_entry(store, validation.try_map()?, context),
+		"message" =>
+			crate::message::validate_message_entry(store, validation.try_map()?, context),
+		"message_edit" =>
+			crate::message::validate_message_edit_entry(store, validation.try_map()?, context),
+		"objection" =>
+			crate::objection::validate_objection_entry(store, validation.try_map()?, context),
+		"person" =>
+			crate::person::validate_person_entry(validation.try_map()?, context),
+		"proposal" =>
+			crate::proposal::validate_proposal_entry(store, validation.try_map()?, context),
+		"proposal_revision" =>
+			crate::revision::validate_proposal_revision_entry(store, validation.try_map()?, context),
+		"proposal_template" =>
+			crate::template::validate_proposal_template_entry(store, validation.try_map()?, context),
+		"role" =>
+			crate::election::validate_role_entry(store, validation.try_map()?, context),
+		"role_term" =>
+			crate::election::validate_role_term_entry(store, validation.try_map()?, context),
+		"sortition" =>
+			crate::sortition::validate_sortition_entry(store, validation.try_map()?, context),
+		"sortition_draw" =>
+			crate::sortition::validate_sortition_draw_entry(store, validation.try_map()?, context),
+		"sortition_seed" =>
+			crate::sortition::validate_sortition_seed_entry(store, validation.try_map()?, context),
+		"sponsorship" =>
+			crate::sponsor::validate_sponsorship_entry(store, validation.try_map()?, context),
+		"vote" =>
+			crate::vote::validate_vote_entry(store, validation.try_map()?, context),
+		"vote_commitment" =>
+			crate::vote::validate_vote_commitment_entry(store, validation.try_map()?, context),
+		"voice_credit_spend" =>
+			crate::voice_credit::validate_voice_credit_spend_entry(store, validation.try_map()?, context),
+		_ => Ok(()),
+	}
+}
+
+/// Runs the validation rules of the `link` with the `link_type`. Links from an archived
+/// collective are rejected.
+///
+/// Used by the [MemoryStore](struct.MemoryStore.html) to validate like a conductor.
+pub fn validate_app_link(
+	store: &dyn Store,
+	link: &LinkValidation,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	if link.link_type.starts_with("collective->") {
+		return crate::collective::validate_collective_link(store, link, context);
+	}
+	match link.link_type.as_str() {
+		"action->critical_action" =>
+			crate::critical_action::validate_action_critical_action_link(store, link, context),
+		"ledger->voice_credit_spend" =>
+			crate::voice_credit::validate_ledger_voice_credit_spend_link(store, link, context),
+		"proposal->action" =>
+			crate::critical_action::validate_proposal_action_link(store, link, context),
+		"proposal->conviction_stake" =>
+			crate::conviction::validate_proposal_conviction_stake_link(store, link, context),
+		"proposal->objection" =>
+			crate::objection::validate_proposal_objection_link(store, link, context),
+		"proposal->sponsorship" =>
+			crate::sponsor::validate_proposal_sponsorship_link(store, link, context),
+		"proposal->vote" =>
+			crate::vote::validate_proposal_vote_link(store, link, context),
+		"proposal->vote_commitment" =>
+			crate::vote::validate_proposal_vote_commitment_link(store, link, context),
+		"role->election" =>
+			crate::election::validate_role_election_link(store, link, context),
+		"role->role_term" =>
+			crate::election::validate_role_role_term_link(store, link, context),
+		"sortition->sortition_draw" =>
+			crate::sortition::validate_sortition_draw_link(store, link, context),
+		"sortition->sortition_seed" =>
+			crate::sortition::validate_sortition_seed_link(store, link, context),
+		_ => Ok(()),
+	}
+}
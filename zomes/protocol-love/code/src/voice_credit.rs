@@ -0,0 +1,428 @@
+use std::collections::HashSet;
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use hdk::holochain_core_types::dna::entry_types::Sharing;
+use hdk::prelude::ValidatingEntryType;
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::action::{ActionOp, create_root_action};
+use crate::collective::{
+	Collective, CollectivePayload, get_policy_collective, is_collective_admin, is_collective_person,
+	update_collective_entry,
+};
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::ledger::get_collective_ledger;
+use crate::person::{Person, PersonStatus};
+use crate::proposal::{Proposal, ProposalStatus, TallyMethod};
+use crate::store::{Store, HdkStore, get_as_type, get_links_and_load_type};
+use crate::utils::t;
+use crate::validation::{
+	EntryValidation, LinkValidation, ValidationContext, from_hdk_validation_data,
+};
+use crate::vote::{Ballot, Vote, vote_entry_address};
+
+/// Voice credits spent by a [Person](struct.Person.html) on a quadratic [Vote](struct.Vote.html),
+/// recorded on the primary [Ledger](struct.Ledger.html) of the [Collective](struct.Collective.html).
+///
+/// Casting `n` votes on an option costs `n²` credits. A later spend on the same
+/// [Proposal](struct.Proposal.html) replaces the former, as only the latest vote is counted.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct VoiceCreditSpend {
+	pub ledger_address: Address,
+	pub collective_address: Address,
+	pub proposal_address: Address,
+	/// Address of the spending [Person](struct.Person.html).
+	pub person_address: Address,
+	/// Address of the [Vote](struct.Vote.html) paid with the credits.
+	pub vote_address: Address,
+	/// Ballot of the paid vote.
+	pub ballot: Ballot,
+	/// Version of the paid vote.
+	pub vote_version: u64,
+	/// Voice credit period of the proposal.
+	pub period: u64,
+	pub credits: u64,
+}
+
+/// Api payload of the voice credits of a [Person](struct.Person.html) in the current period.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct VoiceCreditsPayload {
+	pub collective_address: Address,
+	pub person_address: Address,
+	pub period: u64,
+	pub budget: u64,
+	pub spent: u64,
+	pub remaining: u64,
+}
+
+/// Returns a Holochain entry definition for a voice credit spend.
+pub fn voice_credit_spend_def() -> ValidatingEntryType {
+	entry!(
+		name: "voice_credit_spend",
+		description: "Voice credits spent on a protocol.love quadratic vote",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<VoiceCreditSpend>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_voice_credit_spend_entry(&HdkStore, validation, &context)?)
+		}
+	)
+}
+
+/// Validation rules of a [VoiceCreditSpend](struct.VoiceCreditSpend.html) entry.
+///
+/// The credits are the cost of the ballot of the paid vote, & the credits spent in the period,
+/// including this spend, must fit the budget of the collective.
+pub fn validate_voice_credit_spend_entry(
+	store: &dyn Store,
+	validation: EntryValidation<VoiceCreditSpend>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let person: Person = t("validation error: voice_credit_spend: fetch person: ",
+				get_as_type(store, &entry.person_address))?;
+			if !context.sources.contains(&person.agent_address) {
+				return Err(ProtocolLoveError::unauthorized(
+					"Voice credits must be spent by the agent of the person"));
+			}
+			if !is_collective_person(store, &entry.collective_address, &entry.person_address)? {
+				return Err(ProtocolLoveError::unauthorized(
+					"Only people in the collective have voice credits"));
+			}
+			if let PersonStatus::Inactive = person.status {
+				return Err(ProtocolLoveError::unauthorized("Only active people have voice credits"));
+			}
+			let proposal: Proposal = t("validation error: voice_credit_spend: fetch proposal: ",
+				get_as_type(store, &entry.proposal_address))?;
+			if proposal.collective_address != entry.collective_address
+				|| proposal.voice_credit_period != entry.period {
+				return Err(ProtocolLoveError::validation_failed(
+					"proposal_address", "Voice credits must be spent in the period of the proposal"));
+			}
+			if proposal.tally_method != TallyMethod::Quadratic {
+				return Err(ProtocolLoveError::conflict("Proposal does not use quadratic voting"));
+			}
+			if proposal.status != ProposalStatus::Voting {
+				return Err(ProtocolLoveError::conflict("Proposal is not in voting"));
+			}
+			if entry.ledger_address != get_collective_ledger(store, &entry.collective_address)? {
+				return Err(ProtocolLoveError::validation_failed(
+					"ledger_address", "Voice credits must be spent on the primary ledger"));
+			}
+			if entry.credits != voice_credit_cost(&entry.ballot) {
+				return Err(ProtocolLoveError::validation_failed(
+					"credits", "Credits must be the cost of the ballot"));
+			}
+			if entry.vote_address != vote_entry_address(store, &paid_vote(&entry))? {
+				return Err(ProtocolLoveError::validation_failed(
+					"vote_address", "Voice credits must pay the vote of the ballot"));
+			}
+			validate_voice_credit_budget(store, &entry, &person)
+		}
+		EntryValidation::Modify { .. } =>
+			Err(ProtocolLoveError::unauthorized("Voice credit spend cannot be modified")),
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Voice credit spend cannot be deleted")),
+	}
+}
+
+/// Validation rules of a `ledger->voice_credit_spend` link: the spend is on the base ledger,
+/// tagged with its period & person, linked by the agent of the person & fits the budget along
+/// with the spends already linked.
+pub fn validate_ledger_voice_credit_spend_link(
+	store: &dyn Store,
+	link: &LinkValidation,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let spend: VoiceCreditSpend =
+		t("validation error: ledger->voice_credit_spend: fetch voice credit spend: ",
+			get_as_type(store, &link.target))?;
+	if spend.ledger_address != link.base {
+		return Err(ProtocolLoveError::validation_failed(
+			"target", "Voice credit spend is not on the ledger"));
+	}
+	if link.tag != voice_credit_tag(spend.period, &spend.person_address) {
+		return Err(ProtocolLoveError::validation_failed(
+			"tag", "Voice credit spend must be tagged with its period & person"));
+	}
+	let person: Person = t("validation error: ledger->voice_credit_spend: fetch person: ",
+		get_as_type(store, &spend.person_address))?;
+	if !context.sources.contains(&person.agent_address) {
+		return Err(ProtocolLoveError::unauthorized(
+			"Voice credits must be spent by the agent of the person"));
+	}
+	validate_voice_credit_budget(store, &spend, &person)
+}
+
+/// Address of the [Vote](struct.Vote.html) paid by the latest
+/// [VoiceCreditSpend](struct.VoiceCreditSpend.html) of the person on the proposal. A quadratic
+/// vote is only counted while its spend is the latest.
+pub fn get_paid_vote_address(
+	store: &dyn Store,
+	proposal: &Proposal,
+	proposal_address: &Address,
+	person_address: &Address,
+) -> ProtocolLoveResult<Option<Address>> {
+	let ledger_address = get_collective_ledger(store, &proposal.collective_address)?;
+	let spends = get_voice_credit_spends(
+		store,
+		&ledger_address,
+		proposal.voice_credit_period,
+		person_address,
+	)?;
+	Ok(spends.into_iter()
+		.find(|spend| &spend.proposal_address == proposal_address)
+		.map(|spend| spend.vote_address))
+}
+
+/// Validates a quadratic [Vote](struct.Vote.html) at `vote_address` is paid with a
+/// [VoiceCreditSpend](struct.VoiceCreditSpend.html) of its cost.
+pub fn validate_vote_paid(
+	store: &dyn Store,
+	proposal: &Proposal,
+	vote: &Vote,
+	vote_address: &Address,
+) -> ProtocolLoveResult<()> {
+	let ledger_address = get_collective_ledger(store, &proposal.collective_address)?;
+	let credits = voice_credit_cost(&vote.ballot);
+	let spends = get_voice_credit_spends(
+		store,
+		&ledger_address,
+		proposal.voice_credit_period,
+		&vote.person_address,
+	)?;
+	if spends.iter().any(|spend|
+		&spend.vote_address == vote_address && spend.credits == credits) {
+		Ok(())
+	} else {
+		Err(ProtocolLoveError::validation_failed(
+			"ballot", "Quadratic vote must spend its voice credits"))
+	}
+}
+
+/// The voice credits a [Ballot](enum.Ballot.html) costs. Casting `n` votes on an option costs `n²`.
+pub fn voice_credit_cost(ballot: &Ballot) -> u64 {
+	match ballot {
+		Ballot::Quadratic(votes) => votes.iter()
+			.map(|votes| votes.saturating_mul(*votes))
+			.fold(0, u64::saturating_add),
+		_ => 0,
+	}
+}
+
+/// Commits & links the [VoiceCreditSpend](struct.VoiceCreditSpend.html) paying for the `vote`.
+pub fn spend_voice_credits(
+	store: &dyn Store,
+	proposal: &Proposal,
+	vote: &Vote,
+	vote_address: &Address,
+) -> ProtocolLoveResult<Address> {
+	let person: Person = t("spend_voice_credits: ", get_as_type(store, &vote.person_address))?;
+	if let PersonStatus::Inactive = person.status {
+		return Err(ProtocolLoveError::unauthorized(
+			"spend_voice_credits: Only active people have voice credits"));
+	}
+	let ledger_address =
+		t("spend_voice_credits: ", get_collective_ledger(store, &proposal.collective_address))?;
+	let spend = VoiceCreditSpend {
+		ledger_address,
+		collective_address: proposal.collective_address.clone(),
+		proposal_address: vote.proposal_address.clone(),
+		person_address: vote.person_address.clone(),
+		vote_address: vote_address.clone(),
+		ballot: vote.ballot.clone(),
+		vote_version: vote.version,
+		period: proposal.voice_credit_period,
+		credits: voice_credit_cost(&vote.ballot),
+	};
+	let spend_entry = Entry::App("voice_credit_spend".into(), (&spend).into());
+	let spend_address = t("spend_voice_credits: ", store.commit_entry(&spend_entry))?;
+	t("spend_voice_credits: ledger->voice_credit_spend: ", store.link_entries(
+		&spend.ledger_address,
+		&spend_address,
+		"ledger->voice_credit_spend",
+		&voice_credit_tag(spend.period, &spend.person_address),
+	))?;
+	Ok(spend_address)
+}
+
+/// Api to get the voice credits of a [Person](struct.Person.html) in the current period of the
+/// [Collective](struct.Collective.html).
+pub fn get_voice_credits(
+	store: &dyn Store,
+	collective_address: Address,
+	person_address: Address,
+) -> ProtocolLoveResult<VoiceCreditsPayload> {
+	let collective: Collective = t("get_voice_credits: ", get_as_type(store, &collective_address))?;
+	let person: Person = t("get_voice_credits: ", get_as_type(store, &person_address))?;
+	let ledger_address = t("get_voice_credits: ", get_collective_ledger(store, &collective_address))?;
+	let spent = t("get_voice_credits: ", get_voice_credits_spent(
+		store,
+		&ledger_address,
+		collective.voice_credit_period,
+		&person_address,
+		None,
+	))?;
+	let budget = t("get_voice_credits: ", voice_credit_budget(store, &collective, &person))?;
+	Ok(VoiceCreditsPayload {
+		collective_address,
+		person_address,
+		period: collective.voice_credit_period,
+		budget,
+		spent,
+		remaining: budget.saturating_sub(spent),
+	})
+}
+
+/// Api for the admin to start a new voice credit period, optionally changing the
+/// `voice_credits_per_period`. Every active person receives a new budget.
+///
+/// Proposals keep the period they are created in.
+pub fn start_voice_credit_period(
+	store: &dyn Store,
+	collective_address: Address,
+	voice_credits_per_period: Option<u64>,
+) -> ProtocolLoveResult<CollectivePayload> {
+	let saved_collective: Collective =
+		t("start_voice_credit_period: ", get_as_type(store, &collective_address))?;
+	if !t("start_voice_credit_period: ", is_collective_admin(store, &saved_collective))? {
+		return Err(ProtocolLoveError::unauthorized(
+			"start_voice_credit_period: Only the admin can start a voice credit period"));
+	}
+	if saved_collective.inherit_policies && voice_credits_per_period.is_some() {
+		return Err(ProtocolLoveError::conflict(
+			"start_voice_credit_period: Collective inherits the policies of its parent"));
+	}
+	let collective = Collective {
+		voice_credits_per_period:
+			voice_credits_per_period.unwrap_or(saved_collective.voice_credits_per_period),
+		voice_credit_period: saved_collective.voice_credit_period + 1,
+		..saved_collective.clone()
+	};
+	t("start_voice_credit_period: ", update_collective_entry(
+		store,
+		&collective_address,
+		&collective,
+	))?;
+	t("start_voice_credit_period: ", create_root_action(
+		store,
+		&collective_address,
+		ActionOp::StartVoiceCreditPeriod,
+		StartVoiceCreditPeriodActionData {
+			voice_credit_period: collective.voice_credit_period,
+			voice_credits_per_period: collective.voice_credits_per_period,
+		}.into(),
+		StartVoiceCreditPeriodActionData {
+			voice_credit_period: saved_collective.voice_credit_period,
+			voice_credits_per_period: saved_collective.voice_credits_per_period,
+		}.into(),
+		"start_voice_credit_period",
+	))?;
+	Ok(CollectivePayload {
+		collective_address,
+		collective,
+	})
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct StartVoiceCreditPeriodActionData {
+	voice_credit_period: u64,
+	voice_credits_per_period: u64,
+}
+
+/// The voice credits the `person` receives each period. Inactive people receive none.
+fn voice_credit_budget(
+	store: &dyn Store,
+	collective: &Collective,
+	person: &Person,
+) -> ProtocolLoveResult<u64> {
+	match person.status {
+		PersonStatus::Active => Ok(get_policy_collective(store, collective)?.voice_credits_per_period),
+		PersonStatus::Inactive => Ok(0),
+	}
+}
+
+/// Validates the credits spent in the period by the person, replacing their former spend on the
+/// proposal with the `spend`, fit the budget of the collective.
+fn validate_voice_credit_budget(
+	store: &dyn Store,
+	spend: &VoiceCreditSpend,
+	person: &Person,
+) -> ProtocolLoveResult<()> {
+	let collective: Collective = t("validation error: voice_credit_spend: fetch collective: ",
+		get_as_type(store, &spend.collective_address))?;
+	let spent = get_voice_credits_spent(
+		store,
+		&spend.ledger_address,
+		spend.period,
+		&spend.person_address,
+		Some(&spend.proposal_address),
+	)?;
+	let budget = voice_credit_budget(store, &collective, person)?;
+	if spent.saturating_add(spend.credits) > budget {
+		return Err(ProtocolLoveError::validation_failed("credits", "Not enough voice credits"));
+	}
+	Ok(())
+}
+
+/// The open [Vote](struct.Vote.html) paid by the `spend`.
+fn paid_vote(spend: &VoiceCreditSpend) -> Vote {
+	Vote {
+		proposal_address: spend.proposal_address.clone(),
+		person_address: spend.person_address.clone(),
+		ballot: spend.ballot.clone(),
+		salt: None,
+		decision_address: None,
+		version: spend.vote_version,
+	}
+}
+
+/// Sums the latest spend on each proposal, skipping the `excluded_proposal_address`.
+fn get_voice_credits_spent(
+	store: &dyn Store,
+	ledger_address: &Address,
+	period: u64,
+	person_address: &Address,
+	excluded_proposal_address: Option<&Address>,
+) -> ProtocolLoveResult<u64> {
+	let spends = get_voice_credit_spends(store, ledger_address, period, person_address)?;
+	let mut proposal_addresses = HashSet::new();
+	let mut spent = 0;
+	for spend in spends {
+		if Some(&spend.proposal_address) == excluded_proposal_address
+			|| !proposal_addresses.insert(spend.proposal_address.clone()) {
+			continue;
+		}
+		spent += spend.credits;
+	}
+	Ok(spent)
+}
+
+/// Gets the spends of the person in the period on the ledger, newest first.
+fn get_voice_credit_spends(
+	store: &dyn Store,
+	ledger_address: &Address,
+	period: u64,
+	person_address: &Address,
+) -> ProtocolLoveResult<Vec<VoiceCreditSpend>> {
+	let spends: Vec<VoiceCreditSpend> = t("get_voice_credit_spends: ", get_links_and_load_type(
+		store,
+		ledger_address,
+		"ledger->voice_credit_spend",
+		Some(&voice_credit_tag(period, person_address)),
+	))?;
+	Ok(spends.into_iter()
+		.filter(|spend| &spend.ledger_address == ledger_address
+			&& spend.period == period
+			&& &spend.person_address == person_address)
+		.collect())
+}
+
+fn voice_credit_tag(period: u64, person_address: &Address) -> String {
+	format!("voice_credits:{}:{}", period, person_address)
+}
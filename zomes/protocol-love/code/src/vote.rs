@@ -0,0 +1,726 @@
+use std::collections::HashMap;
+use hdk::holochain_json_api::{
+	json::JsonString,
+	error::JsonError,
+};
+use hdk::holochain_core_types::dna::entry_types::Sharing;
+use hdk::prelude::ValidatingEntryType;
+use holochain_wasm_utils::holochain_core_types::entry::Entry;
+use holochain_wasm_utils::holochain_persistence_api::cas::content::Address;
+use crate::action::{ActionOp, create_root_action};
+use crate::collective::is_collective_person;
+use crate::delegation::get_delegated_ballots;
+use crate::error::{ProtocolLoveError, ProtocolLoveResult};
+use crate::federation::{decision_ballot, validate_collective_voter};
+use crate::objection::{Objection, get_objections};
+use crate::person::{Person, get_agent_person};
+use crate::proposal::{BallotMode, Proposal, ProposalStatus, TallyMethod};
+use crate::signal::{ProtocolLoveSignal, emit_signal};
+use crate::store::{Store, HdkStore, get_as_type, get_links_and_load_type};
+use crate::tally::{
+	ProposalTally, tally_approval, tally_borda, tally_consent, tally_majority, tally_quadratic,
+	tally_ranked_choice, tally_schulze,
+};
+use crate::voice_credit::{get_paid_vote_address, spend_voice_credits, validate_vote_paid};
+use crate::utils::t;
+use crate::validation::{
+	EntryValidation, LinkValidation, ValidationContext, from_hdk_validation_data,
+};
+
+/// The choice of a voter on a [Proposal](struct.Proposal.html).
+///
+/// The kind of ballot must match the [TallyMethod](enum.TallyMethod.html) of the proposal.
+/// Options are referred to by their index in the `options` of the proposal.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+pub enum Ballot {
+	Yes,
+	No,
+	Abstain,
+	/// Indexes of the approved options, with [TallyMethod::Approval](enum.TallyMethod.html).
+	Approve(Vec<usize>),
+	/// Indexes of the ranked options, most preferred first. Unranked options are least preferred.
+	Rank(Vec<usize>),
+	/// Votes on each option, with [TallyMethod::Quadratic](enum.TallyMethod.html).
+	Quadratic(Vec<u64>),
+}
+
+/// A vote cast by a [Person](struct.Person.html) on a [Proposal](struct.Proposal.html).
+///
+/// In a federation, a member [Collective](struct.Collective.html) votes with the outcome of its
+/// own decision. See [cast_collective_vote](fn.cast_collective_vote.html).
+///
+/// With [BallotMode::CommitReveal](enum.BallotMode.html), the vote is the reveal of a
+/// [VoteCommitment](struct.VoteCommitment.html) & holds the salt hiding the ballot.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct Vote {
+	pub proposal_address: Address,
+	/// Address of the voting [Person](struct.Person.html), or of the voting member
+	/// [Collective](struct.Collective.html).
+	pub person_address: Address,
+	pub ballot: Ballot,
+	/// Random salt of a commit-reveal vote.
+	pub salt: Option<String>,
+	/// The closed [Proposal](struct.Proposal.html) deciding the vote of a member collective.
+	#[serde(default)]
+	pub decision_address: Option<Address>,
+	/// Number of the open votes of the voter on the proposal, including this one. A vote
+	/// repeating an earlier ballot gets its own address, so it is ordered by its own commit.
+	/// `0` for commit-reveal votes, told apart by their salt.
+	#[serde(default)]
+	pub version: u64,
+}
+
+/// The commitment to a secret [Vote](struct.Vote.html) on a [Proposal](struct.Proposal.html).
+///
+/// The `vote_address` is the address of the salted vote entry, so the ballot stays hidden
+/// until the vote is revealed.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct VoteCommitment {
+	pub proposal_address: Address,
+	/// Address of the voting [Person](struct.Person.html).
+	pub person_address: Address,
+	/// Address of the [Vote](struct.Vote.html) to be revealed.
+	pub vote_address: Address,
+}
+
+/// Api params for [cast_vote](fn.cast_vote.html), [commit_vote](fn.commit_vote.html)
+/// & [reveal_vote](fn.reveal_vote.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct VoteParams {
+	pub proposal_address: Address,
+	/// Address of the voting [Person](struct.Person.html).
+	pub person_address: Address,
+	pub ballot: Ballot,
+	/// Random salt, required by [commit_vote](fn.commit_vote.html) &
+	/// [reveal_vote](fn.reveal_vote.html). Must be kept by the voter until the reveal.
+	pub salt: Option<String>,
+}
+
+/// Api params for [cast_collective_vote](fn.cast_collective_vote.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct CollectiveVoteParams {
+	pub proposal_address: Address,
+	pub member_collective_address: Address,
+	/// Address of the closed [Proposal](struct.Proposal.html) of the member collective.
+	pub decision_address: Address,
+}
+
+/// Api payload containing the `vote_address` & [vote](struct.Vote.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct VotePayload {
+	pub vote_address: Address,
+	pub vote: Vote,
+}
+
+/// Api payload containing the `vote_commitment_address` & [vote_commitment](struct.VoteCommitment.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct VoteCommitmentPayload {
+	pub vote_commitment_address: Address,
+	pub vote_commitment: VoteCommitment,
+}
+
+/// Api payload containing the `proposal_address` & [tally](struct.ProposalTally.html).
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ProposalTallyPayload {
+	pub proposal_address: Address,
+	pub tally: ProposalTally,
+}
+
+/// Returns a Holochain entry definition for a vote.
+pub fn vote_def() -> ValidatingEntryType {
+	entry!(
+		name: "vote",
+		description: "A protocol.love vote on a proposal",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<Vote>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_vote_entry(&HdkStore, validation, &context)?)
+		}
+	)
+}
+
+/// Returns a Holochain entry definition for a vote commitment.
+pub fn vote_commitment_def() -> ValidatingEntryType {
+	entry!(
+		name: "vote_commitment",
+		description: "A protocol.love commitment to a secret vote",
+		sharing: Sharing::Public,
+		validation_package: || {
+			hdk::ValidationPackageDefinition::Entry
+		},
+		validation: | validation_data: hdk::EntryValidationData<VoteCommitment>| {
+			let (validation, context) = from_hdk_validation_data(validation_data);
+			Ok(validate_vote_commitment_entry(&HdkStore, validation, &context)?)
+		}
+	)
+}
+
+/// Validation rules of a [Vote](struct.Vote.html) entry.
+pub fn validate_vote_entry(
+	store: &dyn Store,
+	validation: EntryValidation<Vote>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let proposal = match &entry.decision_address {
+				Some(decision_address) =>
+					validate_collective_voter(store, &entry, decision_address, context)?,
+				None => validate_voter(store, &entry.proposal_address, &entry.person_address, context)?,
+			};
+			validate_ballot(&proposal, &entry.ballot)?;
+			if proposal.tally_method == TallyMethod::Quadratic {
+				validate_vote_paid(store, &proposal, &entry, &vote_entry_address(store, &entry)?)?;
+			}
+			match proposal.ballot_mode {
+				BallotMode::Open => {
+					if proposal.status != ProposalStatus::Voting {
+						return Err(ProtocolLoveError::conflict("Proposal is not in voting"));
+					}
+					validate_voting_window(&proposal, context)?;
+					if entry.salt.is_some() {
+						return Err(ProtocolLoveError::validation_failed(
+							"salt", "Open votes are not salted"));
+					}
+				}
+				BallotMode::CommitReveal => {
+					if proposal.status != ProposalStatus::Revealing {
+						return Err(ProtocolLoveError::conflict("Proposal is not revealing votes"));
+					}
+					if entry.salt.is_none() {
+						return Err(ProtocolLoveError::validation_failed(
+							"salt", "Revealed votes must be salted"));
+					}
+					let vote_address = vote_entry_address(store, &entry)?;
+					let vote_commitment = latest_vote_commitment(
+						store,
+						&entry.proposal_address,
+						&entry.person_address,
+					)?;
+					match vote_commitment {
+						Some(vote_commitment) if vote_commitment.vote_address == vote_address => {}
+						Some(_) => return Err(ProtocolLoveError::validation_failed(
+							"ballot", "Revealed vote does not match the commitment")),
+						None => return Err(ProtocolLoveError::validation_failed(
+							"person_address", "Revealed vote has no commitment")),
+					}
+				}
+			}
+			Ok(())
+		}
+		EntryValidation::Modify { .. } =>
+			Err(ProtocolLoveError::unauthorized("Vote cannot be modified")),
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Vote cannot be deleted")),
+	}
+}
+
+/// Validation rules of a [VoteCommitment](struct.VoteCommitment.html) entry.
+pub fn validate_vote_commitment_entry(
+	store: &dyn Store,
+	validation: EntryValidation<VoteCommitment>,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	match validation {
+		EntryValidation::Create { entry } => {
+			let proposal =
+				validate_voter(store, &entry.proposal_address, &entry.person_address, context)?;
+			if proposal.ballot_mode != BallotMode::CommitReveal {
+				return Err(ProtocolLoveError::conflict("Proposal does not use secret ballots"));
+			}
+			if proposal.status != ProposalStatus::Voting {
+				return Err(ProtocolLoveError::conflict("Proposal is not in voting"));
+			}
+			validate_voting_window(&proposal, context)
+		}
+		EntryValidation::Modify { .. } =>
+			Err(ProtocolLoveError::unauthorized("Vote commitment cannot be modified")),
+		EntryValidation::Delete { .. } =>
+			Err(ProtocolLoveError::conflict("Vote commitment cannot be deleted")),
+	}
+}
+
+/// Validation rules of a `proposal->vote` link: the vote is on the base proposal & is linked
+/// by its voter.
+pub fn validate_proposal_vote_link(
+	store: &dyn Store,
+	link: &LinkValidation,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let vote: Vote =
+		t("validation error: proposal->vote: fetch vote: ", get_as_type(store, &link.target))?;
+	if vote.proposal_address != link.base {
+		return Err(ProtocolLoveError::validation_failed("target", "Vote is not on the proposal"));
+	}
+	match &vote.decision_address {
+		Some(decision_address) =>
+			validate_collective_voter(store, &vote, decision_address, context)?,
+		None => validate_voter(store, &vote.proposal_address, &vote.person_address, context)?,
+	};
+	Ok(())
+}
+
+/// Validation rules of a `proposal->vote_commitment` link: the commitment is on the base
+/// proposal & is linked by its voter.
+pub fn validate_proposal_vote_commitment_link(
+	store: &dyn Store,
+	link: &LinkValidation,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<()> {
+	let vote_commitment: VoteCommitment =
+		t("validation error: proposal->vote_commitment: fetch vote commitment: ",
+			get_as_type(store, &link.target))?;
+	if vote_commitment.proposal_address != link.base {
+		return Err(ProtocolLoveError::validation_failed(
+			"target", "Vote commitment is not on the proposal"));
+	}
+	validate_voter(
+		store,
+		&vote_commitment.proposal_address,
+		&vote_commitment.person_address,
+		context,
+	)?;
+	Ok(())
+}
+
+/// Validates the ballot is cast in the voting window of the proposal.
+fn validate_voting_window(proposal: &Proposal, context: &ValidationContext) -> ProtocolLoveResult<()> {
+	if !proposal.voting_window.contains(context.timestamp) {
+		return Err(ProtocolLoveError::conflict("Vote is cast outside the voting window"));
+	}
+	Ok(())
+}
+
+/// Validates the voter is the agent & a person in the collective of the proposal.
+fn validate_voter(
+	store: &dyn Store,
+	proposal_address: &Address,
+	person_address: &Address,
+	context: &ValidationContext,
+) -> ProtocolLoveResult<Proposal> {
+	let proposal: Proposal =
+		t("validation error: vote: fetch proposal: ", get_as_type(store, proposal_address))?;
+	let person: Person =
+		t("validation error: vote: fetch person: ", get_as_type(store, person_address))?;
+	if !context.sources.contains(&person.agent_address) {
+		return Err(ProtocolLoveError::unauthorized("Vote must be cast by the agent of the person"));
+	}
+	if !is_collective_person(store, &proposal.collective_address, person_address)? {
+		return Err(ProtocolLoveError::unauthorized("Only people in the collective can vote"));
+	}
+	Ok(proposal)
+}
+
+/// Validates the kind of the `ballot` matches the [TallyMethod](enum.TallyMethod.html) of the
+/// `proposal` & the ballot refers to distinct options of the proposal.
+pub fn validate_ballot(proposal: &Proposal, ballot: &Ballot) -> ProtocolLoveResult<()> {
+	let options = match (&proposal.tally_method, ballot) {
+		(TallyMethod::Majority, Ballot::Yes)
+		| (TallyMethod::Majority, Ballot::No)
+		| (TallyMethod::Majority, Ballot::Abstain) => return Ok(()),
+		(TallyMethod::Approval, Ballot::Approve(options)) => options,
+		(TallyMethod::RankedChoice, Ballot::Rank(options))
+		| (TallyMethod::Borda, Ballot::Rank(options))
+		| (TallyMethod::Schulze, Ballot::Rank(options)) => options,
+		(TallyMethod::Quadratic, Ballot::Quadratic(votes)) =>
+			return if votes.len() == proposal.options.len() {
+				Ok(())
+			} else {
+				Err(ProtocolLoveError::validation_failed(
+					"ballot", "Quadratic ballot must have votes for each option"))
+			},
+		(tally_method, _) => return Err(ProtocolLoveError::validation_failed(
+			"ballot", &format!("Ballot does not match the {:?} tally method", tally_method))),
+	};
+	for (index, option) in options.iter().enumerate() {
+		if *option >= proposal.options.len() {
+			return Err(ProtocolLoveError::validation_failed(
+				"ballot", "Ballot refers to an unknown option"));
+		}
+		if options[..index].contains(option) {
+			return Err(ProtocolLoveError::validation_failed(
+				"ballot", "Ballot refers to an option more than once"));
+		}
+	}
+	Ok(())
+}
+
+/// Api to cast an open [Vote](struct.Vote.html) on a [BallotMode::Open](enum.BallotMode.html)
+/// [Proposal](struct.Proposal.html).
+pub fn cast_vote(store: &dyn Store, vote_params: VoteParams) -> ProtocolLoveResult<VotePayload> {
+	let proposal: Proposal =
+		t("cast_vote: ", get_as_type(store, &vote_params.proposal_address))?;
+	if proposal.ballot_mode != BallotMode::Open {
+		return Err(ProtocolLoveError::conflict(
+			"cast_vote: Secret ballots are cast with commit_vote"));
+	}
+	t("cast_vote: ", get_agent_person(store, &vote_params.person_address))?;
+	let version = t("cast_vote: ", next_vote_version(
+		store,
+		&vote_params.proposal_address,
+		&vote_params.person_address,
+	))?;
+	let vote = Vote {
+		proposal_address: vote_params.proposal_address,
+		person_address: vote_params.person_address,
+		ballot: vote_params.ballot,
+		salt: None,
+		decision_address: None,
+		version,
+	};
+	if proposal.tally_method == TallyMethod::Quadratic {
+		let vote_address = t("cast_vote: ", vote_entry_address(store, &vote))?;
+		t("cast_vote: ", spend_voice_credits(store, &proposal, &vote, &vote_address))?;
+	}
+	let vote_address = t("cast_vote: ", commit_vote_entry(store, &vote))?;
+	t("cast_vote: ", create_root_action(
+		store,
+		&proposal.collective_address,
+		ActionOp::CastVote,
+		BallotActionData {
+			proposal_address: vote.proposal_address.clone(),
+			person_address: vote.person_address.clone(),
+			ballot: vote.ballot.clone(),
+		}.into(),
+		serde_json::value::Value::Null.into(),
+		"cast_vote",
+	))?;
+	t("cast_vote: ", emit_signal(store, ProtocolLoveSignal::VoteCast {
+		collective_address: proposal.collective_address,
+		proposal_address: vote.proposal_address.clone(),
+		person_address: vote.person_address.clone(),
+	}))?;
+	Ok(VotePayload {
+		vote_address,
+		vote,
+	})
+}
+
+/// Api for the admin of a member [Collective](struct.Collective.html) to cast its
+/// [Vote](struct.Vote.html) on a proposal of the federation.
+///
+/// The ballot follows the outcome of the closed decision of the member collective: `Yes` when
+/// passed, `No` when rejected & `Abstain` when tied. A later vote replaces the former.
+pub fn cast_collective_vote(
+	store: &dyn Store,
+	collective_vote_params: CollectiveVoteParams,
+) -> ProtocolLoveResult<VotePayload> {
+	let proposal: Proposal =
+		t("cast_collective_vote: ", get_as_type(store, &collective_vote_params.proposal_address))?;
+	let decision: Proposal =
+		t("cast_collective_vote: ", get_as_type(store, &collective_vote_params.decision_address))?;
+	let ballot = match &decision.outcome {
+		Some(outcome) => decision_ballot(outcome),
+		None => return Err(ProtocolLoveError::conflict(
+			"cast_collective_vote: Decision of the member collective is not closed")),
+	};
+	let version = t("cast_collective_vote: ", next_vote_version(
+		store,
+		&collective_vote_params.proposal_address,
+		&collective_vote_params.member_collective_address,
+	))?;
+	let vote = Vote {
+		proposal_address: collective_vote_params.proposal_address,
+		person_address: collective_vote_params.member_collective_address,
+		ballot,
+		salt: None,
+		decision_address: Some(collective_vote_params.decision_address),
+		version,
+	};
+	let vote_address = t("cast_collective_vote: ", commit_vote_entry(store, &vote))?;
+	t("cast_collective_vote: ", create_root_action(
+		store,
+		&proposal.collective_address,
+		ActionOp::CastVote,
+		BallotActionData {
+			proposal_address: vote.proposal_address.clone(),
+			person_address: vote.person_address.clone(),
+			ballot: vote.ballot.clone(),
+		}.into(),
+		serde_json::value::Value::Null.into(),
+		"cast_collective_vote",
+	))?;
+	t("cast_collective_vote: ", emit_signal(store, ProtocolLoveSignal::VoteCast {
+		collective_address: proposal.collective_address,
+		proposal_address: vote.proposal_address.clone(),
+		person_address: vote.person_address.clone(),
+	}))?;
+	Ok(VotePayload {
+		vote_address,
+		vote,
+	})
+}
+
+/// Api to commit a secret [Vote](struct.Vote.html) on a
+/// [BallotMode::CommitReveal](enum.BallotMode.html) [Proposal](struct.Proposal.html).
+///
+/// Only the address of the salted vote is committed. A later commitment replaces the former.
+pub fn commit_vote(
+	store: &dyn Store,
+	vote_params: VoteParams,
+) -> ProtocolLoveResult<VoteCommitmentPayload> {
+	let proposal: Proposal =
+		t("commit_vote: ", get_as_type(store, &vote_params.proposal_address))?;
+	t("commit_vote: ", get_agent_person(store, &vote_params.person_address))?;
+	let vote = salted_vote(vote_params)?;
+	let vote_commitment = VoteCommitment {
+		proposal_address: vote.proposal_address.clone(),
+		person_address: vote.person_address.clone(),
+		vote_address: t("commit_vote: ", vote_entry_address(store, &vote))?,
+	};
+	let vote_commitment_entry =
+		Entry::App("vote_commitment".into(), (&vote_commitment).into());
+	let vote_commitment_address =
+		t("commit_vote: ", store.commit_entry(&vote_commitment_entry))?;
+	t("commit_vote: proposal->vote_commitment: ", store.link_entries(
+		&vote_commitment.proposal_address,
+		&vote_commitment_address,
+		"proposal->vote_commitment",
+		"",
+	))?;
+	t("commit_vote: ", create_root_action(
+		store,
+		&proposal.collective_address,
+		ActionOp::CommitVote,
+		(&vote_commitment).into(),
+		serde_json::value::Value::Null.into(),
+		"commit_vote",
+	))?;
+	t("commit_vote: ", emit_signal(store, ProtocolLoveSignal::VoteCast {
+		collective_address: proposal.collective_address,
+		proposal_address: vote_commitment.proposal_address.clone(),
+		person_address: vote_commitment.person_address.clone(),
+	}))?;
+	Ok(VoteCommitmentPayload {
+		vote_commitment_address,
+		vote_commitment,
+	})
+}
+
+/// Api to reveal a committed secret [Vote](struct.Vote.html) with the same ballot & salt.
+pub fn reveal_vote(store: &dyn Store, vote_params: VoteParams) -> ProtocolLoveResult<VotePayload> {
+	let proposal: Proposal =
+		t("reveal_vote: ", get_as_type(store, &vote_params.proposal_address))?;
+	t("reveal_vote: ", get_agent_person(store, &vote_params.person_address))?;
+	let vote = salted_vote(vote_params)?;
+	let vote_address = t("reveal_vote: ", commit_vote_entry(store, &vote))?;
+	t("reveal_vote: ", create_root_action(
+		store,
+		&proposal.collective_address,
+		ActionOp::RevealVote,
+		BallotActionData {
+			proposal_address: vote.proposal_address.clone(),
+			person_address: vote.person_address.clone(),
+			ballot: vote.ballot.clone(),
+		}.into(),
+		serde_json::value::Value::Null.into(),
+		"reveal_vote",
+	))?;
+	Ok(VotePayload {
+		vote_address,
+		vote,
+	})
+}
+
+/// Api to get the current [ProposalTally](struct.ProposalTally.html) of a
+/// [Proposal](struct.Proposal.html).
+///
+/// The tally of a commit-reveal proposal is only available once revealing.
+pub fn get_proposal_tally(
+	store: &dyn Store,
+	proposal_address: Address,
+) -> ProtocolLoveResult<ProposalTallyPayload> {
+	let proposal: Proposal =
+		t("get_proposal_tally: ", get_as_type(store, &proposal_address))?;
+	if proposal.ballot_mode == BallotMode::CommitReveal
+		&& proposal.status == ProposalStatus::Voting {
+		return Err(ProtocolLoveError::conflict(
+			"get_proposal_tally: Secret ballots are tallied once revealing"));
+	}
+	let tally = t("get_proposal_tally: ", tally_proposal(store, &proposal_address, &proposal))?;
+	Ok(ProposalTallyPayload {
+		proposal_address,
+		tally,
+	})
+}
+
+/// Tallies the latest [Vote](struct.Vote.html) of each voter with the
+/// [TallyMethod](enum.TallyMethod.html) of the proposal.
+///
+/// With [BallotMode::CommitReveal](enum.BallotMode.html), a vote only counts when it matches
+/// the latest [VoteCommitment](struct.VoteCommitment.html) of the voter.
+///
+/// People who did not vote are represented by their [Delegation](struct.Delegation.html),
+/// except with [TallyMethod::Quadratic](enum.TallyMethod.html) as voice credits are personal.
+///
+/// A [TallyMethod::Consent](enum.TallyMethod.html) proposal counts its
+/// [Objections](struct.Objection.html) instead of votes.
+pub fn tally_proposal(
+	store: &dyn Store,
+	proposal_address: &Address,
+	proposal: &Proposal,
+) -> ProtocolLoveResult<ProposalTally> {
+	let votes = get_counted_votes(store, proposal_address, proposal)?;
+	let delegated_ballots = match proposal.tally_method {
+		TallyMethod::Quadratic | TallyMethod::Conviction | TallyMethod::Consent => vec![],
+		_ => get_delegated_ballots(store, proposal, &votes)?,
+	};
+	let delegated = delegated_ballots.len() as u64;
+	let ballots: Vec<Ballot> = votes.into_iter()
+		.map(|vote| vote.ballot)
+		.chain(delegated_ballots)
+		.collect();
+	let option_count = proposal.options.len();
+	let tally = match proposal.tally_method {
+		TallyMethod::Majority => tally_majority(&ballots),
+		TallyMethod::Approval => tally_approval(option_count, &ballots),
+		TallyMethod::RankedChoice => tally_ranked_choice(option_count, &ballots),
+		TallyMethod::Borda => tally_borda(option_count, &ballots),
+		TallyMethod::Schulze => tally_schulze(option_count, &ballots),
+		TallyMethod::Quadratic => tally_quadratic(option_count, &ballots),
+		TallyMethod::Conviction => ProposalTally::default(),
+		TallyMethod::Consent => {
+			let objections: Vec<Objection> = get_objections(store, proposal_address)?.into_iter()
+				.map(|objection_payload| objection_payload.objection)
+				.collect();
+			tally_consent(&objections)
+		}
+	};
+	Ok(ProposalTally {
+		delegated,
+		..tally
+	})
+}
+
+/// Gets the latest valid [Vote](struct.Vote.html) of each voter on the proposal.
+pub fn get_counted_votes(
+	store: &dyn Store,
+	proposal_address: &Address,
+	proposal: &Proposal,
+) -> ProtocolLoveResult<Vec<Vote>> {
+	let votes: Vec<Vote> =
+		t("get_counted_votes: ", get_links_and_load_type(
+			store,
+			proposal_address,
+			"proposal->vote",
+			None,
+		))?;
+	let votes = votes.into_iter().filter(|vote| &vote.proposal_address == proposal_address);
+	let vote_commitment_addresses: Option<HashMap<Address, Address>> = match proposal.ballot_mode {
+		BallotMode::Open => None,
+		BallotMode::CommitReveal => {
+			let vote_commitments: Vec<VoteCommitment> =
+				t("get_counted_votes: ", get_links_and_load_type(
+					store,
+					proposal_address,
+					"proposal->vote_commitment",
+					None,
+				))?;
+			let mut vote_commitment_addresses = HashMap::new();
+			for vote_commitment in vote_commitments.into_iter()
+				.filter(|vote_commitment| &vote_commitment.proposal_address == proposal_address) {
+				vote_commitment_addresses.entry(vote_commitment.person_address)
+					.or_insert(vote_commitment.vote_address);
+			}
+			Some(vote_commitment_addresses)
+		}
+	};
+	let mut counted_votes: HashMap<Address, Vote> = HashMap::new();
+	for vote in votes {
+		if counted_votes.contains_key(&vote.person_address) {
+			continue;
+		}
+		if let Some(vote_commitment_addresses) = &vote_commitment_addresses {
+			let vote_address = vote_entry_address(store, &vote)?;
+			if vote_commitment_addresses.get(&vote.person_address) != Some(&vote_address) {
+				continue;
+			}
+		}
+		if proposal.tally_method == TallyMethod::Quadratic
+			&& get_paid_vote_address(store, proposal, proposal_address, &vote.person_address)?
+				!= Some(vote_entry_address(store, &vote)?) {
+			continue;
+		}
+		counted_votes.insert(vote.person_address.clone(), vote);
+	}
+	Ok(counted_votes.into_iter().map(|(_, vote)| vote).collect())
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+struct BallotActionData {
+	proposal_address: Address,
+	person_address: Address,
+	ballot: Ballot,
+}
+
+fn salted_vote(vote_params: VoteParams) -> ProtocolLoveResult<Vote> {
+	match vote_params.salt {
+		Some(ref salt) if !salt.is_empty() => Ok(Vote {
+			proposal_address: vote_params.proposal_address,
+			person_address: vote_params.person_address,
+			ballot: vote_params.ballot,
+			salt: vote_params.salt,
+			decision_address: None,
+			version: 0,
+		}),
+		_ => Err(ProtocolLoveError::validation_failed("salt", "Secret ballots must be salted")),
+	}
+}
+
+/// The version of the next open vote of the voter on the proposal.
+fn next_vote_version(
+	store: &dyn Store,
+	proposal_address: &Address,
+	person_address: &Address,
+) -> ProtocolLoveResult<u64> {
+	let votes: Vec<Vote> =
+		t("next_vote_version: ", get_links_and_load_type(
+			store,
+			proposal_address,
+			"proposal->vote",
+			None,
+		))?;
+	Ok(votes.into_iter()
+		.filter(|vote|
+			&vote.proposal_address == proposal_address && &vote.person_address == person_address)
+		.map(|vote| vote.version)
+		.max()
+		.unwrap_or(0) + 1)
+}
+
+/// Address of the `vote` entry, committed or not.
+pub fn vote_entry_address(store: &dyn Store, vote: &Vote) -> ProtocolLoveResult<Address> {
+	store.entry_address(&Entry::App("vote".into(), vote.into()))
+}
+
+fn latest_vote_commitment(
+	store: &dyn Store,
+	proposal_address: &Address,
+	person_address: &Address,
+) -> ProtocolLoveResult<Option<VoteCommitment>> {
+	let vote_commitments: Vec<VoteCommitment> =
+		t("latest_vote_commitment: ", get_links_and_load_type(
+			store,
+			proposal_address,
+			"proposal->vote_commitment",
+			None,
+		))?;
+	Ok(vote_commitments.into_iter()
+		.find(|vote_commitment| &vote_commitment.proposal_address == proposal_address
+			&& &vote_commitment.person_address == person_address))
+}
+
+fn commit_vote_entry(store: &dyn Store, vote: &Vote) -> ProtocolLoveResult<Address> {
+	let vote_entry = Entry::App("vote".into(), vote.into());
+	let vote_address = t("commit_vote_entry: ", store.commit_entry(&vote_entry))?;
+	t("commit_vote_entry: proposal->vote: ", store.link_entries(
+		&vote.proposal_address,
+		&vote_address,
+		"proposal->vote",
+		"",
+	))?;
+	Ok(vote_address)
+}